@@ -1,6 +1,6 @@
 use std::fs::File;
 use std::io::BufReader;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::Context as _;
 use bumpalo::Bump;
@@ -13,8 +13,10 @@ use okane_core::syntax::Separation;
 use okane_core::syntax::display::DisplayContext;
 use okane_core::syntax::plain::LedgerEntry;
 use okane_core::{load, report};
+use path_slash::PathExt as _;
 
 use crate::build::CLAP_LONG_VERSION;
+use crate::diagnostic::ErrorFormat;
 use crate::format;
 use crate::import;
 use crate::ui;
@@ -27,6 +29,13 @@ pub struct InvalidFlagError(String);
 #[clap(about, version, author, long_version = CLAP_LONG_VERSION)]
 #[command(infer_subcommands = true)]
 pub struct Cli {
+    /// Format of the errors and warnings.
+    ///
+    /// `json` prints one JSON object per line, with the file path, the line / column ranges,
+    /// the severity, the message and the labels.
+    #[arg(long, global = true, value_enum, default_value_t)]
+    error_format: ErrorFormat,
+
     #[clap(subcommand)]
     command: Command,
 }
@@ -36,11 +45,15 @@ impl Cli {
         self.command.validate()
     }
 
+    pub fn error_format(&self) -> ErrorFormat {
+        self.error_format
+    }
+
     pub fn run<W>(self, w: &mut W) -> anyhow::Result<()>
     where
         W: std::io::Write,
     {
-        self.command.run(w)
+        self.command.run(w, self.error_format)
     }
 }
 
@@ -50,6 +63,8 @@ pub enum Command {
     Import(ImportCmd),
     /// Format the given file (in future it'll work without file arg)
     Format(FormatCmd),
+    /// Checks the given file, and reports all errors found.
+    Check(CheckCmd),
     /// List all accounts in the file.
     Accounts(AccountsCmd),
     /// List all tags in the file.
@@ -58,6 +73,8 @@ pub enum Command {
     Balance(BalanceCmd),
     /// Gives register report.
     Register(RegisterCmd),
+    /// Gives budget report, comparing actual amounts against periodic transactions.
+    Budget(BudgetCmd),
     /// Open an interactive terminal UI showing the balance report.
     Ui(UiCmd),
     /// Primitive is a set of commands which are primitive and suitable for debugging.
@@ -69,24 +86,27 @@ impl Command {
         match self {
             Command::Import(cmd) => cmd.validate(),
             Command::Balance(cmd) => cmd.validate(),
+            Command::Budget(cmd) => cmd.validate(),
             Command::Ui(cmd) => cmd.validate(),
             _ => Ok(()),
         }
     }
 
-    pub fn run<W>(self, w: &mut W) -> anyhow::Result<()>
+    pub fn run<W>(self, w: &mut W, error_format: ErrorFormat) -> anyhow::Result<()>
     where
         W: std::io::Write,
     {
         match self {
             Command::Import(cmd) => cmd.run(w),
             Command::Format(cmd) => cmd.run(w),
+            Command::Check(cmd) => cmd.run(w, error_format),
             Command::Accounts(cmd) => cmd.run(w),
             Command::Tags(cmd) => cmd.run(w),
-            Command::Balance(cmd) => cmd.run(w),
-            Command::Register(cmd) => cmd.run(w),
+            Command::Balance(cmd) => cmd.run(w, error_format),
+            Command::Register(cmd) => cmd.run(w, error_format),
+            Command::Budget(cmd) => cmd.run(w, error_format),
             Command::Ui(cmd) => cmd.run(),
-            Command::Primitive(cmd) => cmd.run(w),
+            Command::Primitive(cmd) => cmd.run(w, error_format),
         }
     }
 }
@@ -223,11 +243,11 @@ pub struct Primitives {
 }
 
 impl Primitives {
-    fn run<W>(self, w: &mut W) -> anyhow::Result<()>
+    fn run<W>(self, w: &mut W, error_format: ErrorFormat) -> anyhow::Result<()>
     where
         W: std::io::Write,
     {
-        self.command.run(w)
+        self.command.run(w, error_format)
     }
 }
 
@@ -239,21 +259,78 @@ enum PrimitiveCmd {
     Flatten(FlattenCmd),
     /// Evaluates the given value under given condition.
     Eval(EvalCmd),
+    /// Prints the tree of the files included from the given one ledger file,
+    /// with glob patterns expanded.
+    Includes(IncludesCmd),
 }
 
 impl PrimitiveCmd {
-    fn run<W>(self, w: &mut W) -> anyhow::Result<()>
+    fn run<W>(self, w: &mut W, error_format: ErrorFormat) -> anyhow::Result<()>
     where
         W: std::io::Write,
     {
         match self {
             PrimitiveCmd::Format(cmd) => cmd.run(w),
             PrimitiveCmd::Flatten(cmd) => cmd.run(w),
-            PrimitiveCmd::Eval(cmd) => cmd.run(w),
+            PrimitiveCmd::Eval(cmd) => cmd.run(w, error_format),
+            PrimitiveCmd::Includes(cmd) => cmd.run(w),
         }
     }
 }
 
+#[derive(Args, Debug)]
+struct IncludesCmd {
+    pub source: PathBuf,
+}
+
+impl IncludesCmd {
+    pub fn run<W>(self, w: &mut W) -> anyhow::Result<()>
+    where
+        W: std::io::Write,
+    {
+        // Only one source is accepted, so there's exactly one tree.
+        for tree in load::new_loader(self.source).include_trees()? {
+            // Paths are shown relative to the directory of the given file, to keep them short.
+            let base = tree.path.parent().unwrap_or(Path::new("")).to_owned();
+            write_include_tree(w, &base, &tree, 0)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_include_tree<W>(
+    w: &mut W,
+    base: &Path,
+    tree: &load::IncludeTree,
+    depth: usize,
+) -> std::io::Result<()>
+where
+    W: std::io::Write,
+{
+    // Uses slash even on Windows, to match how include directives are written.
+    let path = tree.path.strip_prefix(base).unwrap_or(&tree.path);
+    writeln!(
+        w,
+        "{:indent$}{}",
+        "",
+        path.to_slash_lossy(),
+        indent = depth * 4
+    )?;
+    for include in &tree.includes {
+        writeln!(
+            w,
+            "{:indent$}include {}",
+            "",
+            include.pattern,
+            indent = depth * 4 + 2
+        )?;
+        for file in &include.files {
+            write_include_tree(w, base, file, depth + 1)?;
+        }
+    }
+    Ok(())
+}
+
 #[derive(Args, Debug)]
 struct FlattenCmd {
     pub source: PathBuf,
@@ -275,10 +352,11 @@ impl FlattenCmd {
             Write(#[from] std::io::Error),
         }
 
-        // TODO: Pick DisplayContext from load results.
-        let ctx = DisplayContext::default();
+        // Amounts are printed with the commodity format declared so far,
+        // including the ones in the previously included files.
+        let mut ctx = DisplayContext::default();
         let mut prev_path: Option<PathBuf> = None;
-        load::new_loader(self.source).load(
+        load::new_loader(self.source).load_parallel(
             |path, _ctx, entry: &LedgerEntry| -> Result<(), FlattenError> {
                 let file_changed = prev_path.as_deref().is_some_and(|prev| prev != path);
                 if file_changed {
@@ -289,6 +367,7 @@ impl FlattenCmd {
                         writeln!(w)?;
                     }
                 }
+                ctx.apply_statement(&entry.statement);
                 write!(w, "{}", ctx.as_display(entry))?;
                 Ok(())
             },
@@ -315,17 +394,15 @@ struct EvalCmd {
 }
 
 impl EvalCmd {
-    pub fn run<W>(self, w: &mut W) -> anyhow::Result<()>
+    pub fn run<W>(self, w: &mut W, error_format: ErrorFormat) -> anyhow::Result<()>
     where
         W: std::io::Write,
     {
         let arena = Bump::new();
         let mut ctx = report::ReportContext::new(&arena);
-        let mut ledger = report::process(
-            &mut ctx,
-            load::new_loader(self.source),
-            &self.eval_options.to_process_options(),
-        )?;
+        let mut ledger = self
+            .eval_options
+            .process(&mut ctx, vec![self.source], error_format)?;
         let mut expression: String = '('.to_string();
         for term in &self.expression {
             expression.push_str(term);
@@ -345,9 +422,75 @@ impl EvalCmd {
     }
 }
 
+#[derive(Args, Debug)]
+pub struct CheckCmd {
+    #[command(flatten)]
+    strictness: StrictnessOptions,
+
+    /// Path to the Ledger file.
+    source: PathBuf,
+}
+
+impl CheckCmd {
+    pub fn run<W>(self, w: &mut W, error_format: ErrorFormat) -> anyhow::Result<()>
+    where
+        W: std::io::Write,
+    {
+        let arena = Bump::new();
+        let mut ctx = report::ReportContext::new(&arena);
+        let options = report::ProcessOptions {
+            strictness: self.strictness.to_strictness(),
+            keep_going: true,
+            ..report::ProcessOptions::default()
+        };
+        let ledger = report::process(&mut ctx, load::new_loader(self.source), &options)?;
+        let mut stderr = std::io::stderr().lock();
+        for warning in ledger.warnings() {
+            error_format.write_report_error(&mut stderr, warning)?;
+        }
+        let errors = ledger.errors();
+        for (i, err) in errors.iter().enumerate() {
+            if i != 0 && error_format == ErrorFormat::Human {
+                writeln!(w)?;
+            }
+            error_format.write_report_error(w, err)?;
+        }
+        if !errors.is_empty() {
+            anyhow::bail!("found {} error(s) in the ledger", errors.len());
+        }
+        Ok(())
+    }
+}
+
+/// Ledger files given with `-f/--file`, as an alternative to the positional argument.
+#[derive(Args, Debug)]
+struct SourceOptions {
+    /// Path to the Ledger file, can be repeated to read multiple files in order.
+    ///
+    /// `-` reads the standard input, where `include` is resolved from the working directory.
+    #[arg(short = 'f', long = "file", value_name = "FILE")]
+    files: Vec<PathBuf>,
+}
+
+impl SourceOptions {
+    /// Returns the files to load, either the positional `source` or `-f/--file`.
+    fn into_sources(self, source: Option<PathBuf>) -> Vec<PathBuf> {
+        if self.files.is_empty() {
+            vec![source.expect("required by clap without --file")]
+        } else {
+            self.files
+        }
+    }
+}
+
 #[derive(Args, Debug)]
 pub struct AccountsCmd {
-    pub source: PathBuf,
+    #[command(flatten)]
+    source_options: SourceOptions,
+
+    /// Path to the Ledger file, `-` to read the standard input.
+    #[arg(required_unless_present = "files", conflicts_with = "files")]
+    source: Option<PathBuf>,
 }
 
 impl AccountsCmd {
@@ -357,7 +500,8 @@ impl AccountsCmd {
     {
         let arena = Bump::new();
         let mut ctx = report::ReportContext::new(&arena);
-        let accounts = report::accounts(&mut ctx, load::new_loader(self.source))?;
+        let sources = self.source_options.into_sources(self.source);
+        let accounts = report::accounts(&mut ctx, load::new_loader_from_sources(sources))?;
         for acc in accounts.iter() {
             writeln!(w, "{}", acc.as_str())?;
         }
@@ -367,8 +511,12 @@ impl AccountsCmd {
 
 #[derive(Args, Debug)]
 pub struct TagsCmd {
-    /// Path to the Ledger file.
-    pub source: PathBuf,
+    #[command(flatten)]
+    source_options: SourceOptions,
+
+    /// Path to the Ledger file, `-` to read the standard input.
+    #[arg(required_unless_present = "files", conflicts_with = "files")]
+    source: Option<PathBuf>,
 
     /// Print each tag as `key: value`, instead of the key alone.
     ///
@@ -387,7 +535,10 @@ impl TagsCmd {
         } else {
             report::TagQuery::KeysOnly
         };
-        let tags = report::tags(load::new_loader(self.source), query)?;
+        let arena = Bump::new();
+        let mut ctx = report::ReportContext::new(&arena);
+        let sources = self.source_options.into_sources(self.source);
+        let tags = report::tags(&mut ctx, load::new_loader_from_sources(sources), query)?;
         for tag in tags {
             match tag.value {
                 None => writeln!(w, "{}", tag.key),
@@ -404,8 +555,17 @@ pub struct BalanceCmd {
     #[command(flatten)]
     eval_options: EvalOptions,
 
-    /// Path to the Ledger file.
-    source: PathBuf,
+    /// Only reports real postings, excluding virtual postings
+    /// written as `(Account)` or `[Account]`.
+    #[arg(long, default_value_t)]
+    real: bool,
+
+    #[command(flatten)]
+    source_options: SourceOptions,
+
+    /// Path to the Ledger file, `-` to read the standard input.
+    #[arg(required_unless_present = "files", conflicts_with = "files")]
+    source: Option<PathBuf>,
 
     /// [Optional] Accounts to report the balance.
     ///
@@ -423,17 +583,14 @@ impl BalanceCmd {
         self.eval_options.validate()?;
         Ok(())
     }
-    pub fn run<W>(self, w: &mut W) -> anyhow::Result<()>
+    pub fn run<W>(self, w: &mut W, error_format: ErrorFormat) -> anyhow::Result<()>
     where
         W: std::io::Write,
     {
         let arena = Bump::new();
         let mut ctx = report::ReportContext::new(&arena);
-        let mut ledger = report::process(
-            &mut ctx,
-            load::new_loader(self.source),
-            &self.eval_options.to_process_options(),
-        )?;
+        let sources = self.source_options.into_sources(self.source);
+        let mut ledger = self.eval_options.process(&mut ctx, sources, error_format)?;
         let account = self
             .eval_options
             .create_account_filter(&ctx, self.account.as_slice())
@@ -442,6 +599,8 @@ impl BalanceCmd {
             account,
             conversion: self.eval_options.to_conversion(&ctx)?,
             date_range: self.eval_options.to_date_range()?,
+            real: self.real,
+            effective: self.eval_options.effective,
         };
         for (account, amount) in ledger.balance(&ctx, &query)?.into_owned().into_vec() {
             writeln!(
@@ -460,13 +619,25 @@ pub struct UiCmd {
     #[command(flatten)]
     eval_options: EvalOptions,
 
+    #[command(flatten)]
+    source_options: SourceOptions,
+
     /// Path to the Ledger file.
-    source: PathBuf,
+    #[arg(required_unless_present = "files", conflicts_with = "files")]
+    source: Option<PathBuf>,
 }
 
 impl UiCmd {
     fn validate(&self) -> Result<(), InvalidFlagError> {
         self.eval_options.validate()?;
+        let stdin = Path::new(load::STDIN_PATH);
+        if self.source.as_deref() == Some(stdin)
+            || self.source_options.files.iter().any(|f| f == stdin)
+        {
+            return Err(InvalidFlagError(
+                "ui cannot read the standard input, as it re-reads the files on reload".to_string(),
+            ));
+        }
         Ok(())
     }
 
@@ -474,13 +645,18 @@ impl UiCmd {
         // All report data is built inside `run_ui`: its session loop resets
         // the arena on reload (`r` / `F5`), which requires that nothing out
         // here borrows it.
+        let sources = self.source_options.into_sources(self.source);
+        let title = sources
+            .iter()
+            .map(|source| source.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
         let config = ui::report::SessionConfig::new(
-            load::new_loader(self.source.clone()),
+            load::new_loader_from_sources(sources),
             self.eval_options.to_query_options()?,
         );
         let mut arena = Bump::new();
-        ui::report::run_ui(&mut arena, self.source.display().to_string(), &config)
-            .context("failed to run TUI")
+        ui::report::run_ui(&mut arena, title, &config).context("failed to run TUI")
     }
 }
 
@@ -515,8 +691,17 @@ pub struct RegisterCmd {
     #[arg(long, value_enum, default_value_t)]
     sort: SortKey,
 
-    /// Path to the Ledger file.
-    source: PathBuf,
+    /// Only reports real postings, excluding virtual postings
+    /// written as `(Account)` or `[Account]`.
+    #[arg(long, default_value_t)]
+    real: bool,
+
+    #[command(flatten)]
+    source_options: SourceOptions,
+
+    /// Path to the Ledger file, `-` to read the standard input.
+    #[arg(required_unless_present = "files", conflicts_with = "files")]
+    source: Option<PathBuf>,
 
     /// [Optional] Accounts to get register.
     ///
@@ -531,17 +716,14 @@ pub struct RegisterCmd {
 }
 
 impl RegisterCmd {
-    pub fn run<W>(self, w: &mut W) -> anyhow::Result<()>
+    pub fn run<W>(self, w: &mut W, error_format: ErrorFormat) -> anyhow::Result<()>
     where
         W: std::io::Write,
     {
         let arena = Bump::new();
         let mut ctx = report::ReportContext::new(&arena);
-        let mut ledger = report::process(
-            &mut ctx,
-            load::new_loader(self.source),
-            &self.eval_options.to_process_options(),
-        )?;
+        let sources = self.source_options.into_sources(self.source);
+        let mut ledger = self.eval_options.process(&mut ctx, sources, error_format)?;
         let query = query::RegisterQuery {
             account: self
                 .eval_options
@@ -550,6 +732,8 @@ impl RegisterCmd {
             date_range: self.eval_options.to_date_range()?,
             conversion: self.eval_options.to_conversion(&ctx)?,
             sort: self.sort.into(),
+            real: self.real,
+            effective: self.eval_options.effective,
         };
         let mut entries = ledger.register_entries(&ctx, &query)?;
         while let Some(entry) = entries.next()? {
@@ -567,6 +751,67 @@ impl RegisterCmd {
     }
 }
 
+#[derive(Args, Debug)]
+pub struct BudgetCmd {
+    #[command(flatten)]
+    eval_options: EvalOptions,
+
+    /// Path to the Ledger file.
+    source: PathBuf,
+
+    /// [Optional] Budgeted accounts to report.
+    ///
+    /// By default, each pattern is an unanchored regex matched against the account name.
+    /// Use `--account-filter` to change the matching logic.
+    ///
+    /// If none are set, show all budgeted accounts.
+    account: Vec<String>,
+}
+
+impl BudgetCmd {
+    fn validate(&self) -> Result<(), InvalidFlagError> {
+        self.eval_options.validate()?;
+        Ok(())
+    }
+
+    pub fn run<W>(self, w: &mut W, error_format: ErrorFormat) -> anyhow::Result<()>
+    where
+        W: std::io::Write,
+    {
+        let arena = Bump::new();
+        let mut ctx = report::ReportContext::new(&arena);
+        let mut ledger = self
+            .eval_options
+            .process(&mut ctx, vec![self.source], error_format)?;
+        let query = query::BudgetQuery {
+            account: self
+                .eval_options
+                .create_account_filter(&ctx, self.account.as_slice())
+                .context("failed to create regex for account filter")?,
+            conversion: self.eval_options.to_conversion(&ctx)?,
+            date_range: self.eval_options.to_date_range()?,
+        };
+        for entry in ledger.budget(&ctx, &query)? {
+            let percent = match entry.percent() {
+                Some(percent) => format!("{}%", percent.round()),
+                None => "-".to_string(),
+            };
+            writeln!(
+                w,
+                "{} {} {} {} {} {} {}",
+                entry.start,
+                entry.end,
+                entry.account.as_str(),
+                entry.actual.as_inline_display(&ctx),
+                entry.budget.as_inline_display(&ctx),
+                entry.difference().as_inline_display(&ctx),
+                percent,
+            )?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Args, Debug)]
 pub struct EvalOptions {
     /// Path to the Price DB.
@@ -613,11 +858,58 @@ pub struct EvalOptions {
     #[arg(long, default_value_t)]
     current: bool,
 
+    /// Use the effective date instead of the primary date.
+    ///
+    /// The effective date is given as `date=effective_date` in the transaction,
+    /// or `; [=effective_date]` in the posting.
+    /// It's used for the date range, sorting, and the historical exchange rate.
+    #[arg(long, default_value_t)]
+    effective: bool,
+
     /// Controls the account filter mode.
     ///
     /// By default, `regex` mode is used.
     #[arg(long, value_enum, default_value_t)]
     account_filter: AccountFilterMode,
+
+    #[command(flatten)]
+    strictness: StrictnessOptions,
+
+    /// Path to the cache of the processed Ledger files.
+    ///
+    /// If none of the loaded files has changed since the cache is written,
+    /// the transactions are read from the cache without parsing the files.
+    /// Otherwise the files are processed as usual, and the cache is updated.
+    #[arg(long, value_name = "FILE")]
+    cache: Option<PathBuf>,
+}
+
+/// Options to control the undeclared names handling.
+#[derive(Args, Debug)]
+struct StrictnessOptions {
+    /// Warns on accounts, commodities, payees and tags used without declaration.
+    ///
+    /// Those can be declared with `account`, `commodity`, `payee` and `tag` directives.
+    #[arg(long, default_value_t)]
+    strict: bool,
+
+    /// Fails on accounts, commodities, payees and tags used without declaration.
+    ///
+    /// This takes precedence over `--strict`.
+    #[arg(long, default_value_t)]
+    pedantic: bool,
+}
+
+impl StrictnessOptions {
+    fn to_strictness(&self) -> report::Strictness {
+        if self.pedantic {
+            report::Strictness::Error
+        } else if self.strict {
+            report::Strictness::Warn
+        } else {
+            report::Strictness::Permissive
+        }
+    }
 }
 
 /// Mode of the account filter.
@@ -645,7 +937,29 @@ impl EvalOptions {
     fn to_process_options(&self) -> report::ProcessOptions {
         report::ProcessOptions {
             price_db_path: self.price_db.clone(),
+            strictness: self.strictness.to_strictness(),
+            cache_path: self.cache.clone(),
+            ..report::ProcessOptions::default()
+        }
+    }
+
+    /// Processes the given `sources` in order, and prints the warnings into stderr.
+    fn process<'ctx>(
+        &self,
+        ctx: &mut report::ReportContext<'ctx>,
+        sources: Vec<PathBuf>,
+        error_format: ErrorFormat,
+    ) -> anyhow::Result<query::Ledger<'ctx>> {
+        let ledger = report::process(
+            ctx,
+            load::new_loader_from_sources(sources),
+            &self.to_process_options(),
+        )?;
+        let mut stderr = std::io::stderr().lock();
+        for warning in ledger.warnings() {
+            error_format.write_report_error(&mut stderr, warning)?;
         }
+        Ok(ledger)
     }
 
     fn to_date_range(&self) -> anyhow::Result<query::DateRange> {
@@ -705,6 +1019,8 @@ impl EvalOptions {
             today: self.today,
             start: range.start,
             end: range.end,
+            effective: self.effective,
+            cache: self.cache.clone(),
         })
     }
 
@@ -0,0 +1,108 @@
+//! Prints errors and warnings in the format chosen with `--error-format`.
+
+use std::io::Write;
+
+use okane_core::diagnostic::Diagnostic;
+use okane_core::{load, report};
+
+/// Format of the errors and warnings.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ErrorFormat {
+    /// Human readable text with the source snippet.
+    #[default]
+    Human,
+    /// One JSON object per line, for the tools to consume.
+    Json,
+}
+
+impl ErrorFormat {
+    /// Writes the error or warning found while processing the ledger.
+    pub fn write_report_error<W>(self, w: &mut W, err: &report::ReportError) -> std::io::Result<()>
+    where
+        W: Write,
+    {
+        match self {
+            ErrorFormat::Human => writeln!(w, "{}", err),
+            ErrorFormat::Json => write_json(w, &err.to_diagnostics()),
+        }
+    }
+
+    /// Writes the error which aborted the command.
+    pub fn write_error<W>(self, w: &mut W, err: &anyhow::Error) -> std::io::Result<()>
+    where
+        W: Write,
+    {
+        match self {
+            ErrorFormat::Human => {
+                writeln!(w, "{}", err)?;
+                for cause in err.chain().skip(1) {
+                    writeln!(w, "Caused by {}", cause)?;
+                }
+                Ok(())
+            }
+            ErrorFormat::Json => {
+                let diagnostics = err
+                    .chain()
+                    .find_map(|cause| {
+                        if let Some(err) = cause.downcast_ref::<report::ReportError>() {
+                            Some(err.to_diagnostics())
+                        } else {
+                            cause
+                                .downcast_ref::<load::LoadError>()
+                                .map(|err| err.to_diagnostics())
+                        }
+                    })
+                    .unwrap_or_else(|| vec![Diagnostic::from_error(err.as_ref())]);
+                write_json(w, &diagnostics)
+            }
+        }
+    }
+}
+
+fn write_json<W>(w: &mut W, diagnostics: &[Diagnostic]) -> std::io::Result<()>
+where
+    W: Write,
+{
+    for diagnostic in diagnostics {
+        serde_json::to_writer(&mut *w, diagnostic)?;
+        writeln!(w)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("outer")]
+    struct Outer(#[source] std::io::Error);
+
+    #[test]
+    fn write_error_human_prints_causes() {
+        let err = anyhow::Error::new(Outer(std::io::Error::other("inner")));
+        let mut got = Vec::new();
+
+        ErrorFormat::Human.write_error(&mut got, &err).unwrap();
+
+        assert_eq!("outer\nCaused by inner\n", String::from_utf8(got).unwrap());
+    }
+
+    #[test]
+    fn write_error_json_prints_one_line() {
+        let err = anyhow::Error::new(Outer(std::io::Error::other("inner")));
+        let mut got = Vec::new();
+
+        ErrorFormat::Json.write_error(&mut got, &err).unwrap();
+
+        assert_eq!(
+            concat!(
+                r#"{"severity":"error","message":"outer","path":null,"labels":[],"notes":["inner"]}"#,
+                "\n"
+            ),
+            String::from_utf8(got).unwrap()
+        );
+    }
+}
@@ -76,6 +76,7 @@ impl From<OutputCommodityDetailsSpec> for okane_core::syntax::display::Commodity
         Self {
             format: value.style.map(|x| x.into()),
             min_scale: value.scale,
+            style: None,
         }
     }
 }
@@ -586,6 +586,7 @@ where
         // This amount is reformatted with DisplayContext at last.
         value: PrettyDecimal::unformatted(amount.value),
         commodity,
+        style: syntax::expr::CommodityStyle::default(),
     }
 }
 
@@ -810,6 +811,7 @@ mod tests {
         syntax::expr::Amount {
             value,
             commodity: commodity.into(),
+            style: syntax::expr::CommodityStyle::default(),
         }
         .into()
     }
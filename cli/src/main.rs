@@ -7,6 +7,7 @@
 //! As oppose to that, this library mainly provides binary specific functionalities, mainly for integration tests.
 
 mod cmd;
+mod diagnostic;
 mod format;
 mod import;
 #[cfg(test)]
@@ -22,15 +23,13 @@ use clap::Parser as _;
 fn main() {
     env_logger::init();
     let cli = cmd::Cli::parse();
+    let error_format = cli.error_format();
     if let Err(err) = cli.validate() {
-        eprintln!("{}", err);
+        let _ = error_format.write_error(&mut std::io::stderr(), &err.into());
         std::process::exit(2);
     }
     if let Err(err) = cli.run(&mut std::io::stdout().lock()) {
-        eprintln!("{}", err);
-        for cause in err.chain().skip(1) {
-            eprintln!("Caused by {}", cause);
-        }
+        let _ = error_format.write_error(&mut std::io::stderr(), &err);
         std::process::exit(1);
     }
 }
@@ -11,7 +11,9 @@
 //! everything referencing the arena — is torn down, the arena is reset, and
 //! a fresh session is built from scratch, so stale interned entries never
 //! survive a reload. Only an owned [`app::UiSnapshot`] (selection, search,
-//! screen) crosses the boundary. See [`run_ui`] for the loop.
+//! screen) crosses the boundary, along with the owned [`report::Incremental`]
+//! state, so a reload only re-books the ledger from the first changed file.
+//! See [`run_ui`] for the loop.
 
 mod app;
 mod balance;
@@ -97,6 +99,8 @@ fn session_loop<F: load::FileSystem>(
     // The query in effect. It starts as the CLI's and is replaced by the `.`
     // form; like the snapshot, it is owned data that crosses the arena reset.
     let mut options = config.options.clone();
+    // Processed state of each file, owned so it also crosses the arena reset.
+    let mut incremental = report::Incremental::new();
     let mut first = true;
     loop {
         if !first {
@@ -106,6 +110,7 @@ fn session_loop<F: load::FileSystem>(
         let built = build_session(
             &mut ctx,
             config,
+            &mut incremental,
             &options,
             source_display,
             snapshot.as_ref(),
@@ -153,15 +158,22 @@ struct SessionData<'ctx> {
 }
 
 /// Loads and processes the source into `ctx` and builds the session data,
-/// restoring the UI state from `snapshot` when given.
+/// restoring the UI state from `snapshot` when given. Files unchanged since
+/// the previous session are not book-kept again, thanks to `incremental`.
 fn build_session<'ctx, F: load::FileSystem>(
     ctx: &mut ReportContext<'ctx>,
     config: &SessionConfig<F>,
+    incremental: &mut report::Incremental,
     options: &QueryOptions,
     source_display: &str,
     snapshot: Option<&UiSnapshot>,
 ) -> anyhow::Result<SessionData<'ctx>> {
-    let mut ledger = report::process(ctx, &config.loader, &options.to_process_options())?;
+    let mut ledger = report::process_incremental(
+        ctx,
+        &config.loader,
+        &options.to_process_options(),
+        incremental,
+    )?;
     let app = build_app(ctx, &mut ledger, options, source_display, snapshot)?;
     Ok(SessionData { ledger, app })
 }
@@ -313,9 +325,26 @@ mod tests {
         ctx: &mut ReportContext<'ctx>,
         content: &str,
         snapshot: Option<&UiSnapshot>,
+    ) -> anyhow::Result<SessionData<'ctx>> {
+        build_with(ctx, content, &mut report::Incremental::new(), snapshot)
+    }
+
+    /// Same as [`build`], continuing from the sessions built with `incremental`.
+    fn build_with<'ctx>(
+        ctx: &mut ReportContext<'ctx>,
+        content: &str,
+        incremental: &mut report::Incremental,
+        snapshot: Option<&UiSnapshot>,
     ) -> anyhow::Result<SessionData<'ctx>> {
         let config = session_config(content);
-        build_session(ctx, &config, &testing::options(), "test", snapshot)
+        build_session(
+            ctx,
+            &config,
+            incremental,
+            &testing::options(),
+            "test",
+            snapshot,
+        )
     }
 
     fn account_names(app: &App<'_>) -> Vec<String> {
@@ -368,19 +397,20 @@ mod tests {
 
     /// The reason reload rebuilds from scratch: interned entries of the
     /// previous session (accounts, and likewise aliases) must not leak
-    /// into the fresh context.
+    /// into the fresh context, even with the state kept for the unchanged files.
     #[test]
     fn rebuild_drops_stale_interned_accounts() {
         let mut arena = Bump::new();
+        let mut incremental = report::Incremental::new();
         {
             let mut ctx = ReportContext::new(&arena);
-            build(&mut ctx, V1, None).unwrap();
+            build_with(&mut ctx, V1, &mut incremental, None).unwrap();
             assert!(ctx.account("Expenses:Food").is_some());
         }
         arena.reset();
 
         let mut ctx = ReportContext::new(&arena);
-        build(&mut ctx, V3, None).unwrap();
+        build_with(&mut ctx, V3, &mut incremental, None).unwrap();
         assert_eq!(ctx.account("Expenses:Food"), None);
     }
 
@@ -112,6 +112,8 @@ pub fn load_register<'ctx>(
         date_range: template.date_range,
         conversion: template.conversion,
         sort: Sort::Date,
+        real: false,
+        effective: template.effective,
     };
     let mut entries = ledger.register_entries(ctx, &query)?;
     let mut rows = Vec::new();
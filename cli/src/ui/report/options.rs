@@ -43,12 +43,21 @@ pub struct QueryOptions {
     pub start: Option<NaiveDate>,
     /// End of the date range (exclusive), as `--end`.
     pub end: Option<NaiveDate>,
+    /// Use the effective date of the postings, as `--effective`. Not editable
+    /// in the form: it is a property of how the file is written, and hence
+    /// stays as the command line states it.
+    pub effective: bool,
+    /// Path to the cache of the processed source, as `--cache`. Not editable
+    /// in the form, as it never changes the numbers.
+    pub cache: Option<PathBuf>,
 }
 
 impl QueryOptions {
     pub fn to_process_options(&self) -> ProcessOptions {
         ProcessOptions {
             price_db_path: self.price_db.clone(),
+            cache_path: self.cache.clone(),
+            ..ProcessOptions::default()
         }
     }
 
@@ -93,6 +102,7 @@ impl QueryOptions {
             template: RegisterQueryTemplate {
                 conversion,
                 date_range: self.date_range(),
+                effective: self.effective,
             },
         })
     }
@@ -127,6 +137,9 @@ impl QueryOptions {
                 format_date(self.end)
             ));
         }
+        if self.effective {
+            parts.push("effective".to_owned());
+        }
         (!parts.is_empty()).then(|| parts.join(" · "))
     }
 }
@@ -171,6 +184,7 @@ impl<'ctx> QueryState<'ctx> {
             template: RegisterQueryTemplate {
                 conversion: None,
                 date_range: options.date_range(),
+                effective: options.effective,
             },
         }
     }
@@ -183,6 +197,8 @@ impl<'ctx> QueryState<'ctx> {
             account: AccountFilter::All,
             conversion: self.template.conversion,
             date_range: self.template.date_range,
+            real: false,
+            effective: self.template.effective,
         }
     }
 }
@@ -101,6 +101,8 @@ impl RegisterRow<'_> {
 pub struct RegisterQueryTemplate<'ctx> {
     pub conversion: Option<Conversion<'ctx>>,
     pub date_range: DateRange,
+    /// Whether to use the effective date of the postings.
+    pub effective: bool,
 }
 
 /// State for the register drill-down screen.
@@ -1423,6 +1423,8 @@ mod tests {
             account: AccountFilter::All,
             conversion: None,
             date_range: DateRange::default(),
+            real: false,
+            effective: false,
         };
         let balance = ledger.balance(&ctx, &query).unwrap().into_owned();
         let tree = BalanceTree::create(&ctx, balance).unwrap().into_nodes();
@@ -1442,6 +1444,8 @@ mod tests {
             account: AccountFilter::All,
             conversion: None,
             date_range: DateRange::default(),
+            real: false,
+            effective: false,
         };
         let account = ledger
             .balance(&ctx, &query)
@@ -1476,6 +1480,8 @@ mod tests {
             account: AccountFilter::All,
             conversion: None,
             date_range: DateRange::default(),
+            real: false,
+            effective: false,
         };
         let balance = ledger.balance(&ctx, &query).unwrap().into_owned();
         let tree = BalanceTree::create(&ctx, balance).unwrap().into_nodes();
@@ -1734,6 +1740,7 @@ mod tests {
         let template = RegisterQueryTemplate {
             conversion: None,
             date_range: DateRange::default(),
+            effective: false,
         };
         let scope = super::super::register::RegisterScope::Single(account);
         let rows =
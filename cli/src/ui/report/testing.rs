@@ -21,6 +21,7 @@ pub(super) fn template<'ctx>() -> RegisterQueryTemplate<'ctx> {
     RegisterQueryTemplate {
         conversion: None,
         date_range: DateRange::default(),
+        effective: false,
     }
 }
 
@@ -34,6 +35,8 @@ pub(super) fn options() -> QueryOptions {
         today: NaiveDate::from_ymd_opt(2024, 6, 1).expect("a valid date"),
         start: None,
         end: None,
+        effective: false,
+        cache: None,
     }
 }
 
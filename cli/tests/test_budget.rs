@@ -0,0 +1,81 @@
+use std::ffi::OsStr;
+use std::io::Write;
+use std::path::PathBuf;
+
+use rstest::rstest;
+
+pub mod testing;
+
+#[ctor::ctor(unsafe)]
+fn init() {
+    env_logger::init();
+}
+
+#[rstest]
+fn budget_default(
+    #[base_dir = "../testdata/budget"]
+    #[files("*.ledger")]
+    input: PathBuf,
+) {
+    println!("test input file path: {}", input.display());
+    let mut golden_path = input.clone();
+    let filename = golden_path.file_name().unwrap().to_owned();
+    assert!(golden_path.pop());
+    golden_path.push("golden");
+    golden_path.push(filename);
+    assert!(
+        golden_path.set_extension("golden.budget.default.txt"),
+        "failed to set extension .ledger to input {}",
+        input.display()
+    );
+    log::info!("golden_path: {}", golden_path.display());
+    let golden = okane_golden::Golden::new(golden_path).unwrap();
+
+    let result = assert_cmd::Command::new(&*testing::BIN_PATH)
+        .args(["budget".as_ref(), input.as_os_str()])
+        .assert()
+        .success();
+
+    let output = result.get_output();
+    std::io::stderr().write_all(&output.stderr).unwrap();
+    let stdout = std::str::from_utf8(&output.stdout).unwrap();
+    golden.assert(stdout);
+}
+
+#[rstest]
+fn budget_date_range_with_account_filter(
+    #[base_dir = "../testdata/budget"]
+    #[files("monthly.ledger")]
+    input: PathBuf,
+) {
+    let mut golden_path = input.clone();
+    let filename = golden_path.file_name().unwrap().to_owned();
+    assert!(golden_path.pop());
+    golden_path.push("golden");
+    golden_path.push(filename);
+    assert!(
+        golden_path.set_extension("golden.budget.date_range.txt"),
+        "failed to set extension .ledger to input {}",
+        input.display()
+    );
+    log::info!("golden_path: {}", golden_path.display());
+    let golden = okane_golden::Golden::new(golden_path).unwrap();
+
+    let result = assert_cmd::Command::new(&*testing::BIN_PATH)
+        .args([
+            "budget".as_ref(),
+            input.as_os_str(),
+            OsStr::new("--start"),
+            OsStr::new("2024-02-01"),
+            OsStr::new("--end"),
+            OsStr::new("2024-03-01"),
+            OsStr::new("^Expenses"),
+        ])
+        .assert()
+        .success();
+
+    let output = result.get_output();
+    std::io::stderr().write_all(&output.stderr).unwrap();
+    let stdout = std::str::from_utf8(&output.stdout).unwrap();
+    golden.assert(stdout);
+}
@@ -0,0 +1,101 @@
+use std::path::PathBuf;
+
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+
+pub mod testing;
+
+#[ctor::ctor(unsafe)]
+fn init() {
+    env_logger::init();
+}
+
+#[rstest]
+fn check_succeeds_on_valid_file(
+    #[base_dir = "../testdata/report"]
+    #[files("*.ledger")]
+    input: PathBuf,
+) {
+    println!("test input file path: {}", input.display());
+
+    let result = assert_cmd::Command::new(&*testing::BIN_PATH)
+        .args(["check".as_ref(), input.as_os_str()])
+        .assert()
+        .success();
+
+    assert_eq!(
+        "",
+        std::str::from_utf8(&result.get_output().stdout).unwrap()
+    );
+}
+
+#[test]
+fn check_reports_all_errors() {
+    let input = testing::TESTDATA_DIR
+        .join("check")
+        .join("multiple_errors.ledger");
+
+    let result = assert_cmd::Command::new(&*testing::BIN_PATH)
+        .args(["check".as_ref(), input.as_os_str()])
+        .assert()
+        .failure();
+
+    let output = result.get_output();
+    let stdout = std::str::from_utf8(&output.stdout).unwrap();
+    let stderr = std::str::from_utf8(&output.stderr).unwrap();
+    assert!(
+        stdout.contains("transaction cannot have unbalanced postings"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stdout.contains("balance assertion off by"),
+        "stdout: {stdout}"
+    );
+    assert!(
+        stderr.contains("found 2 error(s) in the ledger"),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn check_reports_all_errors_in_json() {
+    let input = testing::TESTDATA_DIR
+        .join("check")
+        .join("multiple_errors.ledger");
+
+    let result = assert_cmd::Command::new(&*testing::BIN_PATH)
+        .args([
+            "check".as_ref(),
+            "--error-format=json".as_ref(),
+            input.as_os_str(),
+        ])
+        .assert()
+        .failure();
+
+    let output = result.get_output();
+    let stdout = std::str::from_utf8(&output.stdout).unwrap();
+    let got: Vec<(String, bool, u64)> = stdout
+        .lines()
+        .map(|line| {
+            let record: serde_json::Value = serde_json::from_str(line).unwrap();
+            (
+                record["severity"].as_str().unwrap().to_string(),
+                record["path"]
+                    .as_str()
+                    .unwrap()
+                    .ends_with("multiple_errors.ledger"),
+                record["labels"][0]["start"]["line"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            ("error".to_string(), true, 6),
+            ("error".to_string(), true, 11)
+        ],
+        got
+    );
+    let stderr: serde_json::Value =
+        serde_json::from_str(std::str::from_utf8(&output.stderr).unwrap()).unwrap();
+    assert_eq!("found 2 error(s) in the ledger", stderr["message"]);
+}
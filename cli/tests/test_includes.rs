@@ -0,0 +1,55 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+
+pub mod testing;
+
+#[ctor::ctor(unsafe)]
+fn init() {
+    env_logger::init();
+}
+
+#[test]
+fn includes_prints_tree() {
+    let input = testing::TESTDATA_DIR.join("load").join("recursive.ledger");
+
+    let result = assert_cmd::Command::new(&*testing::BIN_PATH)
+        .args(["primitive".as_ref(), "includes".as_ref(), input.as_os_str()])
+        .assert()
+        .success();
+
+    assert_eq!(
+        indoc! {"
+            recursive.ledger
+              include child1.ledger
+                child1.ledger
+                  include sub/child*.ledger
+                    sub/child2.ledger
+                      include ../child3.ledger
+                        child3.ledger
+                    sub/child4.ledger
+        "},
+        std::str::from_utf8(&result.get_output().stdout).unwrap()
+    );
+}
+
+#[test]
+fn includes_fails_on_cycle() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a.ledger"), "include b.ledger\n").unwrap();
+    std::fs::write(dir.path().join("b.ledger"), "include a.ledger\n").unwrap();
+
+    let result = assert_cmd::Command::new(&*testing::BIN_PATH)
+        .args([
+            "primitive".as_ref(),
+            "includes".as_ref(),
+            dir.path().join("a.ledger").as_os_str(),
+        ])
+        .assert()
+        .failure();
+
+    let stderr = std::str::from_utf8(&result.get_output().stderr).unwrap();
+    assert!(
+        stderr.contains("include cycle detected"),
+        "stderr: {stderr}"
+    );
+}
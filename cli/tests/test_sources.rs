@@ -0,0 +1,141 @@
+use indoc::indoc;
+use pretty_assertions::assert_eq;
+use rstest::rstest;
+
+pub mod testing;
+
+#[ctor::ctor(unsafe)]
+fn init() {
+    env_logger::init();
+}
+
+#[test]
+fn accounts_reads_stdin_with_include_from_working_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("child.ledger"), "account Expenses:Child\n").unwrap();
+
+    let result = assert_cmd::Command::new(&*testing::BIN_PATH)
+        .current_dir(dir.path())
+        .args(["accounts", "-"])
+        .write_stdin("account Assets:Stdin\ninclude child.ledger\n")
+        .assert()
+        .success();
+
+    assert_eq!(
+        indoc! {"
+            Assets:Stdin
+            Expenses:Child
+        "},
+        std::str::from_utf8(&result.get_output().stdout).unwrap()
+    );
+}
+
+#[test]
+fn balance_reads_multiple_files() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("opening.ledger"),
+        indoc! {"
+            2024/01/01 Opening
+                Assets:Bank      100 CHF
+                Equity:Opening
+        "},
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("2024.ledger"),
+        indoc! {"
+            2024/02/01 Shop
+                Expenses:Food     30 CHF
+                Assets:Bank
+        "},
+    )
+    .unwrap();
+
+    let result = assert_cmd::Command::new(&*testing::BIN_PATH)
+        .current_dir(dir.path())
+        .args(["balance", "-f", "opening.ledger", "--file", "2024.ledger"])
+        .assert()
+        .success();
+
+    assert_eq!(
+        indoc! {"
+            Assets:Bank: 70 CHF
+            Equity:Opening: -100 CHF
+            Expenses:Food: 30 CHF
+        "},
+        std::str::from_utf8(&result.get_output().stdout).unwrap()
+    );
+}
+
+#[rstest]
+#[case("balance")]
+#[case("register")]
+fn positional_conflicts_with_file(#[case] subcommand: &str) {
+    let result = assert_cmd::Command::new(&*testing::BIN_PATH)
+        .args([subcommand, "-f", "main.ledger", "Assets"])
+        .assert()
+        .failure();
+
+    let stderr = std::str::from_utf8(&result.get_output().stderr).unwrap();
+    assert!(stderr.contains("cannot be used with"), "stderr: {}", stderr);
+}
+
+#[test]
+fn ui_rejects_stdin() {
+    let result = assert_cmd::Command::new(&*testing::BIN_PATH)
+        .args(["ui", "-f", "-"])
+        .assert()
+        .failure();
+
+    let stderr = std::str::from_utf8(&result.get_output().stderr).unwrap();
+    assert!(
+        stderr.contains("ui cannot read the standard input"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn balance_with_cache_follows_file_change() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("main.ledger");
+    let balance = || {
+        let result = assert_cmd::Command::new(&*testing::BIN_PATH)
+            .current_dir(dir.path())
+            .args(["balance", "--cache", "cache/main.snapshot", "main.ledger"])
+            .assert()
+            .success();
+        String::from_utf8(result.get_output().stdout.clone()).unwrap()
+    };
+    std::fs::write(
+        &source,
+        indoc! {"
+            2024/01/01 Opening
+                Assets:Bank      100 CHF
+                Equity:Opening
+        "},
+    )
+    .unwrap();
+
+    let first = balance();
+    let second = balance();
+    std::fs::write(
+        &source,
+        indoc! {"
+            2024/01/01 Opening
+                Assets:Bank      200 CHF
+                Equity:Opening
+        "},
+    )
+    .unwrap();
+    let changed = balance();
+
+    assert_eq!(first, second);
+    assert!(first.contains("Assets:Bank: 100 CHF"), "output: {}", first);
+    assert!(
+        changed.contains("Assets:Bank: 200 CHF"),
+        "output: {}",
+        changed
+    );
+}
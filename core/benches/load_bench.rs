@@ -0,0 +1,101 @@
+use std::hint::black_box;
+use std::num::NonZeroUsize;
+use std::path::Path;
+
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use log::LevelFilter;
+use okane_core::{load::LoadError, syntax};
+use testing::{ExampleInput, FakeFileSink, FileSink, InputParams, RealFileSink};
+
+pub mod testing;
+
+/// Returns the number of threads to load the input.
+fn threads(parallel: bool) -> NonZeroUsize {
+    if parallel {
+        std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN)
+    } else {
+        NonZeroUsize::MIN
+    }
+}
+
+fn count_entries<T: FileSink>(input: &ExampleInput<T>, parallel: bool) -> usize
+where
+    T::FileSystem: Sync,
+{
+    let mut count = 0;
+    input
+        .new_loader()
+        .with_threads(threads(parallel))
+        .load_parallel(|_, _, _: &syntax::tracked::LedgerEntry| {
+            count += 1;
+            Ok::<(), LoadError>(())
+        })
+        .unwrap();
+    count
+}
+
+fn mode(parallel: bool) -> &'static str {
+    if parallel { "parallel" } else { "sequential" }
+}
+
+fn parallel_load_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parallel_load");
+    group.warm_up_time(std::time::Duration::from_secs(7));
+
+    let params = InputParams::middle();
+    let input = RealFileSink::new_example(Path::new("load_bench"), params).unwrap();
+
+    assert_eq!(count_entries(&input, false), count_entries(&input, true));
+
+    for parallel in [false, true] {
+        group.bench_with_input(
+            BenchmarkId::new(format!("on-file/{}", mode(parallel)), params),
+            &parallel,
+            |b, parallel| b.iter(|| black_box(count_entries(&input, *parallel))),
+        );
+    }
+
+    for params in InputParams::params_from_env() {
+        if let Some(samples) = params.sample_size {
+            group.sample_size(samples);
+        }
+        let input = FakeFileSink::new_example(Path::new("load_bench"), params).unwrap();
+        for parallel in [false, true] {
+            group.bench_with_input(
+                BenchmarkId::new(format!("on-memory/{}", mode(parallel)), params),
+                &parallel,
+                |b, parallel| {
+                    b.iter_batched(
+                        || input.new_loader().with_threads(threads(*parallel)),
+                        |loader| {
+                            let mut count = 0;
+                            loader
+                                .load_parallel(|_, _, _: &syntax::tracked::LedgerEntry| {
+                                    count += 1;
+                                    Ok::<(), LoadError>(())
+                                })
+                                .unwrap();
+                            black_box(count)
+                        },
+                        BatchSize::LargeInput,
+                    );
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+#[ctor::ctor(unsafe)]
+fn init() {
+    // print INFO level logs by default, unless overridden by env.
+    let mut builder = env_logger::builder();
+    builder
+        .is_test(true)
+        .filter_level(LevelFilter::Info)
+        .parse_default_env();
+    let _ = builder.try_init();
+}
+
+criterion_group!(benches, parallel_load_benchmark);
+criterion_main!(benches);
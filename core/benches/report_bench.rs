@@ -153,6 +153,8 @@ fn query_balance(c: &mut Criterion) {
                     end: Some(end),
                 },
                 conversion: None,
+                real: false,
+                effective: false,
             };
             group.bench_with_input(BenchmarkId::new(label, params), &params, |b, _params| {
                 b.iter_with_large_drop(|| {
@@ -181,6 +183,8 @@ fn query_balance(c: &mut Criterion) {
                 },
                 target: usd,
             }),
+            real: false,
+            effective: false,
         };
         group.bench_with_input(
             BenchmarkId::new("conversion-up-to-date", params),
@@ -209,6 +213,8 @@ fn query_balance(c: &mut Criterion) {
                 strategy: report::query::ConversionStrategy::Historical,
                 target: chf,
             }),
+            real: false,
+            effective: false,
         };
         group.bench_with_input(
             BenchmarkId::new("conversion-historical", params),
@@ -227,6 +233,7 @@ fn query_balance(c: &mut Criterion) {
         let mut ctx = report::ReportContext::new(&arena);
         let opts = report::ProcessOptions {
             price_db_path: Some(input.pricedbpath().to_owned()),
+            ..Default::default()
         };
         let mut ledger = report::process(&mut ctx, input.new_loader(), &opts)
             .expect("report::process must succeed");
@@ -239,6 +246,8 @@ fn query_balance(c: &mut Criterion) {
                 strategy: report::query::ConversionStrategy::Historical,
                 target: chf,
             }),
+            real: false,
+            effective: false,
         };
         group.bench_with_input(
             BenchmarkId::new("conversion-historical-pricedb", params),
@@ -402,6 +411,7 @@ fn query_register_entries(c: &mut Criterion) {
         let mut ctx = report::ReportContext::new(&arena);
         let opts = report::ProcessOptions {
             price_db_path: Some(input.pricedbpath().to_owned()),
+            ..Default::default()
         };
         let mut ledger = report::process(&mut ctx, input.new_loader(), &opts)
             .expect("report::process must succeed");
@@ -0,0 +1,182 @@
+//! Structured representation of the errors, for the tools to consume
+//! without scraping the rendered message.
+//!
+//! With `serde` feature, [`Diagnostic`] can be serialized into JSON or any other format.
+
+use std::ops::Range;
+
+/// Severity of the [`Diagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Position within a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Position {
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column number, counted in Unicode scalar values.
+    pub column: usize,
+}
+
+/// Label pointing a range of the file with a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Label {
+    /// Start of the range, inclusive.
+    pub start: Position,
+    /// End of the range, exclusive.
+    pub end: Position,
+    /// Message describing the range, can be empty.
+    pub message: String,
+    /// `true` if this is the main cause of the diagnostic,
+    /// `false` if this is just giving the context.
+    pub primary: bool,
+}
+
+/// Structured error or warning.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    /// Path of the file having the issue, if any.
+    pub path: Option<String>,
+    /// Labels within the file at `path`.
+    pub labels: Vec<Label>,
+    /// Underlying causes of the issue, from the outermost one.
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    /// Creates an error [`Diagnostic`] out of the general `error`, without any labels.
+    /// The sources of the `error` are kept as notes.
+    pub fn from_error(error: &(dyn std::error::Error + 'static)) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: error.to_string(),
+            path: None,
+            labels: Vec::new(),
+            notes: error.source().map(error_chain).unwrap_or_default(),
+        }
+    }
+}
+
+/// Returns the messages of the `error` and its sources, from the outermost one.
+pub(crate) fn error_chain(error: &(dyn std::error::Error + 'static)) -> Vec<String> {
+    std::iter::successors(Some(error), |err| err.source())
+        .map(|err| err.to_string())
+        .collect()
+}
+
+/// Snippet of the file, to compute [`Position`] from the offset within it.
+pub(crate) struct SourceText<'a> {
+    text: &'a str,
+    line_start: usize,
+}
+
+impl<'a> SourceText<'a> {
+    /// Creates a new instance, where `text` starts at the line `line_start` of the file.
+    pub(crate) fn new(text: &'a str, line_start: usize) -> Self {
+        Self { text, line_start }
+    }
+
+    /// Returns the [`Position`] of the byte `offset` in the text.
+    pub(crate) fn position(&self, offset: usize) -> Position {
+        let before = &self.text[..offset.min(self.text.len())];
+        let (line, line_head) = match before.rfind('\n') {
+            None => (self.line_start, before),
+            Some(pos) => (
+                self.line_start + before.matches('\n').count(),
+                &before[pos + 1..],
+            ),
+        };
+        Position {
+            line,
+            column: line_head.chars().count() + 1,
+        }
+    }
+
+    /// Returns the [`Label`] for the given byte `span` in the text.
+    pub(crate) fn label(&self, span: Range<usize>, message: String, primary: bool) -> Label {
+        Label {
+            start: self.position(span.start),
+            end: self.position(span.end),
+            message,
+            primary,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn source_text_position_counts_lines_and_chars() {
+        let text = SourceText::new("foo\n日本語 bar\n", 10);
+
+        assert_eq!(
+            Position {
+                line: 10,
+                column: 1
+            },
+            text.position(0)
+        );
+        assert_eq!(
+            Position {
+                line: 10,
+                column: 4
+            },
+            text.position(3)
+        );
+        assert_eq!(
+            Position {
+                line: 11,
+                column: 1
+            },
+            text.position(4)
+        );
+        assert_eq!(
+            Position {
+                line: 11,
+                column: 5
+            },
+            text.position(14)
+        );
+        assert_eq!(
+            Position {
+                line: 12,
+                column: 1
+            },
+            text.position(18)
+        );
+    }
+
+    #[test]
+    fn from_error_keeps_sources_as_notes() {
+        #[derive(Debug, thiserror::Error)]
+        #[error("outer")]
+        struct Outer(#[source] std::io::Error);
+
+        let err = Outer(std::io::Error::other("inner"));
+
+        assert_eq!(
+            Diagnostic {
+                severity: Severity::Error,
+                message: "outer".to_string(),
+                path: None,
+                labels: Vec::new(),
+                notes: vec!["inner".to_string()],
+            },
+            Diagnostic::from_error(&err)
+        );
+    }
+}
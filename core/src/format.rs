@@ -51,10 +51,11 @@ impl FormatOptions {
         }
         let mut buf = String::new();
         r.read_to_string(&mut buf)?;
-        // TODO: Grab DisplayContext externally, or from LedgerEntry.
-        let ctx = DisplayContext::default();
+        // Amounts are printed with the commodity format declared so far.
+        let mut ctx = DisplayContext::default();
         for parsed in parse_ledger(&ParseOptions::default(), &buf) {
             let (_, entry): (_, syntax::plain::LedgerEntry) = parsed?;
+            ctx.apply_statement(&entry.statement);
             write!(w, "{}", ctx.as_display(&entry))?;
         }
         Ok(())
@@ -125,7 +126,24 @@ mod tests {
             apply    tag   foo
             end  apply   tag
 
-            2021/05/14 !(#txn-1) My Grocery
+            Y  2021
+            alias  Cash =Assets:Cash	
+            apply   account  Bank	
+            end apply  account
+
+            comment
+              free  text	
+            end comment
+            test  bal
+                            10 CHF  Expenses:Grocery
+            end test
+
+            i 2021/05/14 10:00 Client:Acme   Review	
+            o 05/14 11:00:00 Client:Acme
+            i 2021/05/14 12:00:00 Internal
+            o 2021/05/14 12:30:00	 note
+
+            05/14 !(#txn-1) My Grocery
                 Expenses:Grocery\t10 CHF
                 Assets:Bank  -10 CHF
         "};
@@ -134,6 +152,140 @@ mod tests {
         assert_eq!(once, format_str(&once));
     }
 
+    #[test]
+    fn format_keeps_block_comment() {
+        let input = indoc! {"
+            comment
+            2021/05/14 Disabled
+                Expenses:Travel  $100
+            end comment
+
+            test reg Expenses
+            21-May-14 Trip      Expenses:Travel     $100     $100
+            end test
+        "};
+
+        assert_eq!(input, format_str(input));
+    }
+
+    #[test]
+    fn format_keeps_payee_and_tag_declarations() {
+        let input = indoc! {"
+            payee KFC
+                alias KENTUCKY FRIED CHICKEN
+
+            tag Receipt
+                check value =~ /pattern/
+                assert value != \"foobar\"
+
+            tag trip
+        "};
+
+        assert_eq!(input, format_str(input));
+    }
+
+    #[test]
+    fn format_keeps_default_commodity_directives() {
+        let input = indoc! {"
+            D 1,000.00 JPY
+
+            commodity USD
+                nomarket
+                default
+        "};
+
+        assert_eq!(input, format_str(input));
+    }
+
+    #[test]
+    fn format_keeps_prefix_commodity() {
+        let input = indoc! {"
+            2021/05/14 Trip
+                Expenses:Travel                             $100
+                Expenses:Travel                          € 12.50
+                Expenses:Travel                             12.5EUR
+                Assets:Bank                                -$100 = -$200
+                Assets:Bank
+        "};
+
+        assert_eq!(input, format_str(input));
+        assert_eq!(
+            format_str(input),
+            format_str(&input.replace("= -$200", "= $-200"))
+        );
+    }
+
+    #[test]
+    fn format_follows_commodity_format_style() {
+        let input = indoc! {"
+            commodity USD
+                alias $
+                format USD 1,000.00
+
+            2021/05/14 Trip
+                Expenses:Travel                               100 USD
+                Expenses:Travel                             $1000
+                Expenses:Travel                               12 EUR
+                Assets:Bank
+        "};
+        let want = indoc! {"
+            commodity USD
+                alias $
+                format USD 1,000.00
+
+            2021/05/14 Trip
+                Expenses:Travel                       USD 100.00
+                Expenses:Travel                       $ 1,000.00
+                Expenses:Travel                               12 EUR
+                Assets:Bank
+        "};
+
+        assert_eq!(want, format_str(input));
+    }
+
+    #[test]
+    fn format_keeps_virtual_postings() {
+        let input = indoc! {"
+            2021/05/14 Grocery
+                Expenses:Food                                 80 CHF
+                Assets:Bank
+                (Budget:Food)                                -80 CHF
+                [Savings:Goal]                                10 CHF
+                [Savings:Available]
+        "};
+
+        assert_eq!(input, format_str(input));
+    }
+
+    #[test]
+    fn format_quotes_commodity_only_if_required() {
+        let input = indoc! {r#"
+            commodity "VANGUARD FTSE 100"
+                alias "VUKE"
+
+            P 2021/05/14 "A1B2" 12.50 GBP
+
+            2021/05/14 Buy
+                Assets:Broker                   10 "VANGUARD FTSE 100" {7.50 "GBP"} @ 8 GBP
+                Assets:Broker                    5 "A1B2" @@ "$"100
+                Assets:Bank
+        "#};
+        let want = indoc! {r#"
+            commodity "VANGUARD FTSE 100"
+                alias VUKE
+
+            P 2021/05/14 "A1B2" 12.50 GBP
+
+            2021/05/14 Buy
+                Assets:Broker                                 10 "VANGUARD FTSE 100" {7.50 GBP} @ 8 GBP
+                Assets:Broker                                  5 "A1B2" @@ $100
+                Assets:Bank
+        "#};
+
+        assert_eq!(want, format_str(input));
+        assert_eq!(want, format_str(want));
+    }
+
     #[test]
     fn format_succeeds_transaction_without_lot_price() {
         let input = indoc! {"
@@ -168,6 +320,16 @@ mod tests {
 
             include        path/to/other.ledger
 
+            P  2021/03/01  USD\t0.91 CHF\t
+
+            =   /^Expenses:Grocery/   ; VAT split
+              Expenses:VAT  0.2
+              Assets:Bank  -0.2
+
+            ~  monthly   from 2021/03   ; budget
+              Expenses:Grocery  300 CHF
+              Assets:Bank
+
             2021/03/12 Opening Balance  ; initial balance
              Assets:Bank     = 1000 CHF
              Equity
@@ -217,6 +379,18 @@ mod tests {
 
             include path/to/other.ledger
 
+            P 2021/03/01 USD 0.91 CHF
+
+            = /^Expenses:Grocery/
+                ; VAT split
+                Expenses:VAT                                 0.2
+                Assets:Bank                                 -0.2
+
+            ~ Monthly from 2021/03/01
+                ; budget
+                Expenses:Grocery                             300 CHF
+                Assets:Bank
+
             2021/03/12 Opening Balance
                 ; initial balance
                 Assets:Bank                                          = 1000 CHF
@@ -228,7 +402,7 @@ mod tests {
                 ; Payee: My Card
                 ; My card took commission
                 ; :financial:経済:
-                Assets:Bank                                  -20 CHF = 1 CHF
+                Assets:Bank                                  -20 CHF = 1CHF
                 Expenses:Household                               = 0
                 Assets:Complex                        (-10 * 2.1 $) @ (1 $ + 1 $) = 2.5 $
                 Assets:Broker                                 -2 SPINX {100 USD} [2010/12/23] (bought before Xmas) @ 10000 USD
@@ -2,6 +2,7 @@
 //! [okane](https://crates.io/crates/okane) CLI tool functionality,
 //! withreusable components.
 
+pub mod diagnostic;
 pub mod format;
 pub mod load;
 pub mod parse;
@@ -6,22 +6,29 @@
 // without pinning their own (possibly mismatched) annotate-snippets
 // dependency.
 pub use annotate_snippets::Renderer;
+// Re-exported for [`Loader::with_default_encoding`], for the same reason as [`Renderer`].
+pub use encoding_rs::Encoding;
+
+pub(crate) mod decode;
 
 use std::{
     borrow::Cow,
     collections::HashMap,
+    num::NonZeroUsize,
     path::{self, Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::SystemTime,
 };
 
-use crate::{parse, syntax};
+use crate::{diagnostic::Diagnostic, parse, syntax};
 
 /// Error caused by [Loader::load].
 #[derive(thiserror::Error, Debug)]
 pub enum LoadError {
     #[error("failed to perform IO on file {1}")]
     IO(#[source] std::io::Error, PathBuf),
-    #[error("failed to parse file {1}")]
-    Parse(#[source] parse::ParseError, PathBuf),
+    #[error("found {} syntax error(s) in the loaded files", .0.len())]
+    Parse(#[source] ParseErrors),
     #[error("loading file path {0} doesn't have parent, maybe filesystem root is passed")]
     RootLoadingPath(PathBuf),
     #[error("invalid Unicode path is not supported: {0}")]
@@ -30,31 +37,179 @@ pub enum LoadError {
     InvalidIncludeGlob(#[from] glob::PatternError),
     #[error("failed to match glob pattern")]
     GlobFailure(#[from] glob::GlobError),
+    #[error("include cycle detected: {}", display_cycle(.0))]
+    IncludeCycle(Vec<PathBuf>),
+}
+
+/// Event given by [`Loader::load_events`], in the loading order.
+pub(crate) enum LoadEvent<'a, 'i, Deco: syntax::decoration::Decoration> {
+    /// Entering the file at `path` with the decoded `content`,
+    /// `index`-th in the visiting order, before any of its entries.
+    Enter {
+        index: usize,
+        path: &'a Path,
+        content: &'a str,
+    },
+    /// Entry of the file at `path`.
+    Entry(
+        &'a Path,
+        &'a parse::ParsedContext<'i>,
+        &'a syntax::LedgerEntry<'i, Deco>,
+    ),
+    /// `include` directive in the `index`-th file matched the canonicalized `files`,
+    /// given before loading them.
+    Include {
+        index: usize,
+        pattern: &'a str,
+        files: &'a [PathBuf],
+    },
+    /// Leaving the `index`-th file, after visiting `visits` files from it including itself.
+    Exit { index: usize, visits: usize },
+}
+
+/// Point to resume loading with [`Loader::load_events`],
+/// as the state before the file is already known.
+#[derive(Debug)]
+pub(crate) struct Resume {
+    /// Index of the file to resume from, in the visiting order.
+    pub index: usize,
+    /// Number of the files visited from each file before `index`, including itself,
+    /// as given by [`LoadEvent::Exit`].
+    pub visits: Vec<usize>,
+}
+
+/// Prints the include cycle as `a -> b -> a`.
+fn display_cycle(cycle: &[PathBuf]) -> String {
+    cycle
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+/// File in the include tree, given by [`Loader::include_tree`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct IncludeTree {
+    /// Canonicalized path of the file.
+    pub path: PathBuf,
+    /// `include` directives in the file, in the order of appearance.
+    pub includes: Vec<Include>,
+}
+
+/// `include` directive and the files resolved from it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Include {
+    /// Path as written in the directive, which can be a glob pattern.
+    pub pattern: String,
+    /// Files matching the `pattern`, in the loading order.
+    pub files: Vec<IncludeTree>,
+}
+
+impl LoadError {
+    /// Converts the error into the list of [`Diagnostic`], one for each syntax error.
+    pub fn to_diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            LoadError::Parse(errors) => errors.0.iter().map(|e| e.to_diagnostic()).collect(),
+            LoadError::IO(_, path) | LoadError::RootLoadingPath(path) => {
+                let mut diagnostic = Diagnostic::from_error(self);
+                diagnostic.path = Some(path.to_string_lossy().into_owned());
+                vec![diagnostic]
+            }
+            _ => vec![Diagnostic::from_error(self)],
+        }
+    }
+}
+
+/// Syntax errors found across the loaded files, in the order of appearance.
+#[derive(Debug)]
+pub struct ParseErrors(Vec<parse::ParseError>);
+
+impl ParseErrors {
+    /// Returns the number of the errors.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if there's no error.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the errors.
+    pub fn errors(&self) -> &[parse::ParseError] {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for ParseErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, err) in self.0.iter().enumerate() {
+            if i != 0 {
+                // Leaves a blank line between the errors.
+                write!(f, "\n\n")?;
+            }
+            write!(f, "{}", err)?;
+        }
+        Ok(())
+    }
 }
 
+impl std::error::Error for ParseErrors {}
+
+/// Path to read the ledger from the standard input, instead of the file.
+pub const STDIN_PATH: &str = "-";
+
 /// Loader is an object to keep loading a given file and may recusrively load them as `repr::LedgerEntry`,
 /// with the metadata about filename or line/column to point the error in a user friendly manner.
 pub struct Loader<F: FileSystem> {
-    source: PathBuf,
+    sources: Vec<PathBuf>,
     error_style: annotate_snippets::Renderer,
+    encoding: &'static Encoding,
+    parallel: Option<Parallel<F>>,
     filesystem: F,
 }
 
-/// Creates a new [`Loader`] instance with [`ProdFileSystem`].
+/// Parallel loading set by [`Loader::with_threads`].
+struct Parallel<F: FileSystem> {
+    threads: NonZeroUsize,
+    /// Reads the files on the `threads`, given where `F` is known to be [`Sync`].
+    read: ParallelRead<F>,
+}
+
+/// Reads the files on up to the given threads, skipping the ones paired with `true`.
+type ParallelRead<F> =
+    fn(&Loader<F>, NonZeroUsize, &[(&PathBuf, bool)]) -> Vec<Result<String, LoadError>>;
+
+/// Creates a new [`Loader`] instance with [`ProdFileSystem`],
+/// which loads the included files with all the available threads.
 pub fn new_loader(source: PathBuf) -> Loader<ProdFileSystem> {
-    Loader::new(source, ProdFileSystem)
+    new_loader_from_sources(vec![source])
+}
+
+/// Creates a new [`Loader`] instance loading all the `sources` with [`ProdFileSystem`],
+/// which loads the included files with all the available threads.
+pub fn new_loader_from_sources(sources: Vec<PathBuf>) -> Loader<ProdFileSystem> {
+    let threads = std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    Loader::from_sources(sources, ProdFileSystem).with_threads(threads)
 }
 
 impl<F: FileSystem> Loader<F> {
     /// Create a new instance of `Loader` to load the given path.
     ///
-    /// It might look weird to have the source path as a `Loader` member,
-    /// but that would give future flexibility to support loading from stdio/network without include,
-    /// or completely static one.
+    /// [`STDIN_PATH`] (`-`) reads the standard input instead,
+    /// and `include` directives in it are resolved relative to the working directory.
     pub fn new(source: PathBuf, filesystem: F) -> Self {
+        Self::from_sources(vec![source], filesystem)
+    }
+
+    /// Create a new instance of `Loader` to load the given paths in order,
+    /// as if they're concatenated into one file.
+    pub fn from_sources(sources: Vec<PathBuf>, filesystem: F) -> Self {
         Self {
-            source,
+            sources,
             error_style: annotate_snippets::Renderer::styled(),
+            encoding: encoding_rs::UTF_8,
+            parallel: None,
             filesystem,
         }
     }
@@ -62,12 +217,19 @@ impl<F: FileSystem> Loader<F> {
     /// Create a new instance with the given `renderer`.
     pub fn with_error_renderer(self, renderer: annotate_snippets::Renderer) -> Self {
         Self {
-            source: self.source,
             error_style: renderer,
-            filesystem: self.filesystem,
+            ..self
         }
     }
 
+    /// Create a new instance with the given default `encoding`, UTF-8 if not specified.
+    ///
+    /// The default is used for the files without byte order mark
+    /// nor the declaration such as `; -*- coding: shift_jis -*-` in the first two lines.
+    pub fn with_default_encoding(self, encoding: &'static Encoding) -> Self {
+        Self { encoding, ..self }
+    }
+
     /// Returns a [`annotate_snippets::Renderer`] instance.
     pub(crate) fn error_style(&self) -> &annotate_snippets::Renderer {
         &self.error_style
@@ -78,77 +240,767 @@ impl<F: FileSystem> Loader<F> {
         &self.filesystem
     }
 
+    /// Returns the paths to load, as given to the constructor.
+    pub(crate) fn sources(&self) -> &[PathBuf] {
+        &self.sources
+    }
+
+    /// Returns the default encoding of the files.
+    pub(crate) fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
+
     /// Loads [syntax::LedgerEntry] and invoke callback on every entry,
     /// recursively resolving `include` directives.
+    ///
+    /// On syntax errors, the loader keeps parsing the rest of the files
+    /// to report all of them at once as [`LoadError::Parse`].
+    /// `callback` is no longer invoked after the first syntax error.
+    ///
+    /// The files are loaded sequentially, see [`Loader::load_parallel`] to use the threads.
     pub fn load<T, E, Deco>(&self, mut callback: T) -> Result<(), E>
     where
         T: FnMut(&Path, &parse::ParsedContext<'_>, &syntax::LedgerEntry<'_, Deco>) -> Result<(), E>,
         E: std::error::Error + From<LoadError>,
         Deco: syntax::decoration::Decoration,
     {
-        let popts = parse::ParseOptions::default().with_error_style(self.error_style.clone());
-        self.load_impl(&popts, &self.source, &mut callback)
+        self.load_sources(
+            None,
+            &mut |event| match event {
+                LoadEvent::Entry(path, ctx, entry) => callback(path, ctx, entry),
+                _ => Ok(()),
+            },
+            |loader, parse_options, path, state, callback| {
+                loader.load_file(parse_options, path, state, callback)
+            },
+        )
+        .map(|_| ())
+    }
+
+    /// Same as [`Loader::load`], but reads and parses the included files
+    /// with the threads given by [`Loader::with_threads`].
+    ///
+    /// `callback` still receives the entries in the same order as [`Loader::load`].
+    pub fn load_parallel<T, E, Deco>(&self, mut callback: T) -> Result<(), E>
+    where
+        T: FnMut(&Path, &parse::ParsedContext<'_>, &syntax::LedgerEntry<'_, Deco>) -> Result<(), E>,
+        E: std::error::Error + From<LoadError>,
+        Deco: syntax::decoration::Decoration,
+        for<'i> syntax::LedgerEntry<'i, Deco>: Send,
+    {
+        self.load_tree(&mut callback).map(|_| ())
     }
 
-    fn load_impl<T, E, Deco>(
+    /// Returns the trees of the files included from each source, with glob patterns expanded.
+    ///
+    /// Like [`Loader::load`], it fails on syntax errors or the include cycle.
+    pub fn include_trees(&self) -> Result<Vec<IncludeTree>, LoadError> {
+        self.load_tree(&mut |_, _, _: &syntax::plain::LedgerEntry| Ok::<(), LoadError>(()))
+    }
+
+    /// Same as [`Loader::load_parallel`], but also returns the include trees like [`Loader::include_trees`].
+    pub(crate) fn load_tree<T, E, Deco>(&self, callback: &mut T) -> Result<Vec<IncludeTree>, E>
+    where
+        T: FnMut(&Path, &parse::ParsedContext<'_>, &syntax::LedgerEntry<'_, Deco>) -> Result<(), E>,
+        E: std::error::Error + From<LoadError>,
+        Deco: syntax::decoration::Decoration,
+        for<'i> syntax::LedgerEntry<'i, Deco>: Send,
+    {
+        self.load_events(None, &mut |event| match event {
+            LoadEvent::Entry(path, ctx, entry) => callback(path, ctx, entry),
+            _ => Ok(()),
+        })
+    }
+
+    /// Same as [`Loader::load_tree`], but gives [`LoadEvent`] to observe the visited files.
+    ///
+    /// With `resume`, the files visited before [`Resume::index`] give no events,
+    /// and the ones completely visited before it are not even read.
+    /// Their include trees are left empty in the returned trees.
+    pub(crate) fn load_events<T, E, Deco>(
+        &self,
+        resume: Option<&Resume>,
+        callback: &mut T,
+    ) -> Result<Vec<IncludeTree>, E>
+    where
+        T: FnMut(LoadEvent<'_, '_, Deco>) -> Result<(), E>,
+        E: std::error::Error + From<LoadError>,
+        Deco: syntax::decoration::Decoration,
+        for<'i> syntax::LedgerEntry<'i, Deco>: Send,
+    {
+        self.load_sources(
+            resume,
+            callback,
+            |loader, parse_options, path, state, callback| {
+                loader.load_file_parallel(parse_options, path, state, callback)
+            },
+        )
+    }
+
+    /// Loads all the sources with `load_file`, and returns their include trees.
+    fn load_sources<T, E, Deco, L>(
+        &self,
+        resume: Option<&Resume>,
+        callback: &mut T,
+        load_file: L,
+    ) -> Result<Vec<IncludeTree>, E>
+    where
+        T: FnMut(LoadEvent<'_, '_, Deco>) -> Result<(), E>,
+        E: std::error::Error + From<LoadError>,
+        Deco: syntax::decoration::Decoration,
+        L: Fn(
+            &Self,
+            &parse::ParseOptions,
+            &Path,
+            &mut LoadState<'_>,
+            &mut T,
+        ) -> Result<IncludeTree, E>,
+    {
+        let popts = parse::ParseOptions::default()
+            .with_error_style(self.error_style.clone())
+            .with_recovery(true);
+        let mut state = LoadState {
+            stack: Vec::new(),
+            errors: Vec::new(),
+            visited: 0,
+            resume,
+        };
+        let mut trees = Vec::with_capacity(self.sources.len());
+        for source in &self.sources {
+            trees.push(load_file(self, &popts, source, &mut state, callback)?);
+        }
+        if state.errors.is_empty() {
+            Ok(trees)
+        } else {
+            Err(LoadError::Parse(ParseErrors(state.errors)).into())
+        }
+    }
+
+    /// Loads the file at `path`, and returns the tree of the files included from it.
+    fn load_file<T, E, Deco>(
         &self,
         parse_options: &parse::ParseOptions,
         path: &Path,
+        state: &mut LoadState<'_>,
         callback: &mut T,
-    ) -> Result<(), E>
+    ) -> Result<IncludeTree, E>
     where
-        T: FnMut(&Path, &parse::ParsedContext<'_>, &syntax::LedgerEntry<'_, Deco>) -> Result<(), E>,
+        T: FnMut(LoadEvent<'_, '_, Deco>) -> Result<(), E>,
+        E: std::error::Error + From<LoadError>,
+        Deco: syntax::decoration::Decoration,
+    {
+        let path = canonicalize::<F>(path);
+        if let Some(tree) = state.skip(&path) {
+            return Ok(tree);
+        }
+        check_cycle(&mut state.stack, &path)?;
+        let content = self.read(&path)?;
+        let entries = parse::parse_ledger(parse_options, &content);
+        let file = ReadFile {
+            path: &path,
+            content: &content,
+        };
+        self.load_entries(parse_options, file, entries, Lazy, state, callback)
+    }
+
+    /// Same as [`Loader::load_file`], but loads the included files in parallel
+    /// if [`Loader::with_threads`] is given multiple threads.
+    fn load_file_parallel<T, E, Deco>(
+        &self,
+        parse_options: &parse::ParseOptions,
+        path: &Path,
+        state: &mut LoadState<'_>,
+        callback: &mut T,
+    ) -> Result<IncludeTree, E>
+    where
+        T: FnMut(LoadEvent<'_, '_, Deco>) -> Result<(), E>,
+        E: std::error::Error + From<LoadError>,
+        Deco: syntax::decoration::Decoration,
+        for<'i> syntax::LedgerEntry<'i, Deco>: Send,
+    {
+        let Some(parallel) = self.parallel.as_ref().filter(|p| p.threads.get() > 1) else {
+            return self.load_file(parse_options, path, state, callback);
+        };
+        let path = canonicalize::<F>(path);
+        if let Some(tree) = state.skip(&path) {
+            return Ok(tree);
+        }
+        check_cycle(&mut state.stack, &path)?;
+        let content = self.read(&path)?;
+        let entries = parse::parse_ledger(parse_options, &content).collect();
+        self.load_prefetching(
+            parallel,
+            parse_options,
+            ReadFile {
+                path: &path,
+                content: &content,
+            },
+            entries,
+            state,
+            callback,
+        )
+    }
+
+    /// Loads the file read and parsed by [`Loader::load_prefetching`].
+    fn load_prefetched<T, E, Deco>(
+        &self,
+        parallel: &Parallel<F>,
+        parse_options: &parse::ParseOptions,
+        file: PrefetchedFile<'_, Deco>,
+        state: &mut LoadState<'_>,
+        callback: &mut T,
+    ) -> Result<IncludeTree, E>
+    where
+        T: FnMut(LoadEvent<'_, '_, Deco>) -> Result<(), E>,
+        E: std::error::Error + From<LoadError>,
+        Deco: syntax::decoration::Decoration,
+        for<'i> syntax::LedgerEntry<'i, Deco>: Send,
+    {
+        if let Some(tree) = state.skip(&file.path) {
+            return Ok(tree);
+        }
+        check_cycle(&mut state.stack, &file.path)?;
+        let entries = file.entries?;
+        self.load_prefetching(
+            parallel,
+            parse_options,
+            ReadFile {
+                path: &file.path,
+                content: file.content,
+            },
+            entries,
+            state,
+            callback,
+        )
+    }
+
+    /// Loads the `entries` of the file at `path`,
+    /// after reading and parsing all the files included from it concurrently.
+    fn load_prefetching<T, E, Deco>(
+        &self,
+        parallel: &Parallel<F>,
+        parse_options: &parse::ParseOptions,
+        file: ReadFile<'_>,
+        entries: Vec<ParsedEntry<'_, Deco>>,
+        state: &mut LoadState<'_>,
+        callback: &mut T,
+    ) -> Result<IncludeTree, E>
+    where
+        T: FnMut(LoadEvent<'_, '_, Deco>) -> Result<(), E>,
         E: std::error::Error + From<LoadError>,
         Deco: syntax::decoration::Decoration,
+        for<'i> syntax::LedgerEntry<'i, Deco>: Send,
     {
-        let path: Cow<'_, Path> = F::canonicalize_path(path);
-        let content = self
-            .filesystem
-            .file_content_utf8(&path)
-            .map_err(|err| LoadError::IO(err, path.clone().into_owned()))?;
-        for parsed in parse::parse_ledger(parse_options, &content) {
-            let (ctx, entry) =
-                parsed.map_err(|e| LoadError::Parse(e, path.clone().into_owned()))?;
+        let path = file.path;
+        // Resolves the includes ahead, with the same default year as load_entries gives.
+        let mut default_year = parse_options.default_year();
+        let mut includes = Vec::new();
+        let mut include_options = Vec::new();
+        for (_, entry) in entries.iter().flatten() {
             match &entry.statement {
+                syntax::LedgerStatement::Year(year) => default_year = Some(*year),
                 syntax::LedgerStatement::Include(p) => {
-                    let include_path: PathBuf = p.0.as_ref().into();
-                    let target: String = path
-                        .as_ref()
-                        .parent()
-                        .ok_or_else(|| LoadError::RootLoadingPath(path.as_ref().to_owned()))?
-                        .join(include_path)
-                        .into_os_string()
-                        .into_string()
-                        .map_err(|x| {
-                            LoadError::InvalidUnicodePath(format!("{}", PathBuf::from(x).display()))
-                        })?;
-                    let mut paths: Vec<PathBuf> = self.filesystem.glob(&target)?;
-                    if paths.is_empty() {
-                        return Err(LoadError::IO(
-                            std::io::Error::new(
-                                std::io::ErrorKind::NotFound,
-                                format!("glob {} does not hit any files", target),
-                            ),
-                            PathBuf::from(target),
-                        )
-                        .into());
+                    includes.push(self.resolve_include(path, &p.0));
+                    include_options.push(parse_options.clone().with_default_year(default_year));
+                }
+                _ => (),
+            }
+        }
+        let targets: Vec<(PathBuf, &parse::ParseOptions)> = includes
+            .iter()
+            .zip(&include_options)
+            .flat_map(|(paths, options)| {
+                paths
+                    .iter()
+                    .flatten()
+                    .map(move |path| (canonicalize::<F>(path).into_owned(), options))
+            })
+            .collect();
+        // Files to skip are known ahead, as the ones before are skipped or not regardless of the contents.
+        // Included files are visited after the file at `path`, which is not counted yet.
+        let mut next = state.visited + 1;
+        let skipped: Vec<bool> = targets
+            .iter()
+            .map(|_| match state.skipped_visits(next) {
+                Some(visits) => {
+                    next += visits;
+                    true
+                }
+                None => {
+                    next = usize::MAX;
+                    false
+                }
+            })
+            .collect();
+        // Read errors are kept until the file is reached, to fail in the same order as the sequential loading.
+        let mut read_errors = Vec::with_capacity(targets.len());
+        let jobs: Vec<(&PathBuf, bool)> =
+            targets.iter().map(|(path, _)| path).zip(skipped).collect();
+        let contents: Vec<String> = (parallel.read)(self, parallel.threads, &jobs)
+            .into_iter()
+            .map(|content| match content {
+                Ok(content) => {
+                    read_errors.push(None);
+                    content
+                }
+                Err(e) => {
+                    read_errors.push(Some(e));
+                    String::new()
+                }
+            })
+            .collect();
+        let jobs: Vec<(&parse::ParseOptions, &str)> = targets
+            .iter()
+            .map(|(_, options)| *options)
+            .zip(contents.iter().map(String::as_str))
+            .collect();
+        let parsed: Vec<Vec<ParsedEntry<'_, Deco>>> =
+            parallel_map(parallel.threads, &jobs, |&(options, content)| {
+                parse::parse_ledger(options, content).collect()
+            });
+        let mut files = targets
+            .into_iter()
+            .zip(contents.iter())
+            .zip(read_errors)
+            .zip(parsed)
+            .map(
+                |((((path, _), content), read_error), entries)| PrefetchedFile {
+                    path,
+                    content,
+                    entries: match read_error {
+                        Some(e) => Err(e),
+                        None => Ok(entries),
+                    },
+                },
+            );
+        let prefetched: Vec<_> = includes
+            .into_iter()
+            .map(|paths| paths.map(|paths| files.by_ref().take(paths.len()).collect()))
+            .collect();
+        let includes = Prefetched {
+            parallel,
+            files: prefetched.into_iter(),
+        };
+        self.load_entries(
+            parse_options,
+            file,
+            entries.into_iter(),
+            includes,
+            state,
+            callback,
+        )
+    }
+
+    /// Gives the parsed `entries` of the `file` to the `callback`,
+    /// and loads the files from `includes` on each `include` directive.
+    fn load_entries<'i, I, S, T, E, Deco>(
+        &self,
+        parse_options: &parse::ParseOptions,
+        file: ReadFile<'_>,
+        entries: I,
+        mut includes: S,
+        state: &mut LoadState<'_>,
+        callback: &mut T,
+    ) -> Result<IncludeTree, E>
+    where
+        I: Iterator<Item = ParsedEntry<'i, Deco>>,
+        S: IncludeSource<F, Deco>,
+        T: FnMut(LoadEvent<'_, '_, Deco>) -> Result<(), E>,
+        E: std::error::Error + From<LoadError>,
+        Deco: syntax::decoration::Decoration,
+    {
+        let ReadFile { path, content } = file;
+        let mut tree = IncludeTree {
+            path: path.to_owned(),
+            includes: Vec::new(),
+        };
+        // Included files inherit the default year in effect at the `include` directive.
+        let mut default_year = parse_options.default_year();
+        state.stack.push(tree.path.clone());
+        let index = state.visited;
+        state.visited += 1;
+        if state.active() {
+            callback(LoadEvent::Enter {
+                index,
+                path,
+                content,
+            })?;
+        }
+        for parsed in entries {
+            let (ctx, entry) = match parsed {
+                Ok(parsed) => parsed,
+                Err(mut e) => {
+                    e.set_path(path);
+                    state.errors.push(e);
+                    continue;
+                }
+            };
+            // Entries after a syntax error are only parsed to find more errors.
+            let active = state.active();
+            match &entry.statement {
+                syntax::LedgerStatement::Year(year) => {
+                    default_year = Some(*year);
+                    if active {
+                        callback(LoadEvent::Entry(path, &ctx, &entry))
+                    } else {
+                        Ok(())
                     }
-                    log::debug!("glob {} hit {} files", target, paths.len());
-                    paths.sort_unstable();
-                    for path in &paths {
-                        self.load_impl(parse_options, path, callback)?;
+                }
+                syntax::LedgerStatement::Include(p) => {
+                    let parse_options = parse_options.clone().with_default_year(default_year);
+                    let files = includes.next(self, path, &p.0)?;
+                    if active {
+                        let canonicalized: Vec<PathBuf> = files
+                            .iter()
+                            .map(|file| S::path(file).into_owned())
+                            .collect();
+                        callback(LoadEvent::Include {
+                            index,
+                            pattern: &p.0,
+                            files: &canonicalized,
+                        })?;
                     }
+                    let files = files
+                        .into_iter()
+                        .map(|file| includes.load(self, &parse_options, file, state, callback))
+                        .collect::<Result<Vec<_>, E>>()?;
+                    tree.includes.push(Include {
+                        pattern: p.0.to_string(),
+                        files,
+                    });
                     Ok(())
                 }
-                _ => callback(&path, &ctx, &entry),
+                _ if active => callback(LoadEvent::Entry(path, &ctx, &entry)),
+                _ => Ok(()),
             }?;
         }
-        Ok(())
+        state.stack.pop();
+        if state.active() {
+            let visits = state.visited - index;
+            callback(LoadEvent::Exit { index, visits })?;
+        }
+        Ok(tree)
+    }
+
+    /// Returns the sorted paths matching the `include` directive in the file at `path`.
+    pub(crate) fn resolve_include(
+        &self,
+        path: &Path,
+        include: &str,
+    ) -> Result<Vec<PathBuf>, LoadError> {
+        let target: String = path
+            .parent()
+            .ok_or_else(|| LoadError::RootLoadingPath(path.to_owned()))?
+            .join(include)
+            .into_os_string()
+            .into_string()
+            .map_err(|x| {
+                LoadError::InvalidUnicodePath(format!("{}", PathBuf::from(x).display()))
+            })?;
+        let mut paths: Vec<PathBuf> = self.filesystem.glob(&target)?;
+        if paths.is_empty() {
+            return Err(LoadError::IO(
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("glob {} does not hit any files", target),
+                ),
+                PathBuf::from(target),
+            ));
+        }
+        log::debug!("glob {} hit {} files", target, paths.len());
+        paths.sort_unstable();
+        Ok(paths)
+    }
+
+    /// Reads the file at the canonicalized `path` into String.
+    pub(crate) fn read(&self, path: &Path) -> Result<String, LoadError> {
+        if path == Path::new(STDIN_PATH) {
+            self.filesystem.stdin_content()
+        } else {
+            self.filesystem.file_content(path)
+        }
+        .and_then(|content| decode::decode(content, self.encoding))
+        .map_err(|err| LoadError::IO(err, path.to_owned()))
+    }
+}
+
+impl<F: FileSystem + Sync> Loader<F> {
+    /// Create a new instance which reads and parses the included files with up to `threads` threads
+    /// in [`Loader::load_parallel`]. `1`, the default, loads the files sequentially.
+    ///
+    /// With multiple threads, all the files included from a file are loaded concurrently
+    /// before its entries are given, still the callback receives the entries
+    /// in the same order as the sequential loading.
+    pub fn with_threads(self, threads: NonZeroUsize) -> Self {
+        let parallel = Parallel {
+            threads,
+            read: Self::read_parallel,
+        };
+        Self {
+            parallel: Some(parallel),
+            ..self
+        }
+    }
+
+    /// Reads the files of `jobs` except the skipped ones on up to `threads` threads.
+    fn read_parallel(
+        &self,
+        threads: NonZeroUsize,
+        jobs: &[(&PathBuf, bool)],
+    ) -> Vec<Result<String, LoadError>> {
+        parallel_map(threads, jobs, |&(path, skipped)| {
+            if skipped {
+                Ok(String::new())
+            } else {
+                self.read(path)
+            }
+        })
+    }
+}
+
+/// State kept while [`Loader`] recursively loads the files.
+struct LoadState<'a> {
+    /// Files being loaded, to detect the include cycle.
+    stack: Vec<PathBuf>,
+    /// Syntax errors found so far.
+    errors: Vec<parse::ParseError>,
+    /// Number of the files visited so far, including the skipped ones.
+    visited: usize,
+    resume: Option<&'a Resume>,
+}
+
+impl LoadState<'_> {
+    /// Returns `true` if the events are given,
+    /// namely after reaching the file to resume from, and without syntax errors.
+    fn active(&self) -> bool {
+        self.errors.is_empty() && self.resume.is_none_or(|resume| self.visited > resume.index)
+    }
+
+    /// Returns the number of the files visited from the file `index`-th in the visiting order,
+    /// if the file is completely visited before resuming.
+    fn skipped_visits(&self, index: usize) -> Option<usize> {
+        let resume = self.resume?;
+        let visits = *resume.visits.get(index)?;
+        (index.saturating_add(visits) <= resume.index).then_some(visits)
+    }
+
+    /// Skips visiting the file at `path` if it's completely visited before resuming,
+    /// and returns its empty tree.
+    fn skip(&mut self, path: &Path) -> Option<IncludeTree> {
+        let visits = self.skipped_visits(self.visited)?;
+        self.visited += visits;
+        Some(IncludeTree {
+            path: path.to_owned(),
+            includes: Vec::new(),
+        })
+    }
+}
+
+/// Result of [`parse::parse_ledger`] for each entry.
+type ParsedEntry<'i, Deco> =
+    Result<(parse::ParsedContext<'i>, syntax::LedgerEntry<'i, Deco>), parse::ParseError>;
+
+/// Where [`Loader`] gets the files of `include` directives.
+trait IncludeSource<F: FileSystem, Deco: syntax::decoration::Decoration> {
+    /// File given for the directive.
+    type File;
+
+    /// Returns the files of the next `include` directive `pattern` in the file at `path`.
+    fn next(
+        &mut self,
+        loader: &Loader<F>,
+        path: &Path,
+        pattern: &str,
+    ) -> Result<Vec<Self::File>, LoadError>;
+
+    /// Returns the canonicalized path of the `file`.
+    fn path(file: &Self::File) -> Cow<'_, Path>;
+
+    /// Loads the `file`, and returns the tree of the files included from it.
+    fn load<T, E>(
+        &self,
+        loader: &Loader<F>,
+        parse_options: &parse::ParseOptions,
+        file: Self::File,
+        state: &mut LoadState<'_>,
+        callback: &mut T,
+    ) -> Result<IncludeTree, E>
+    where
+        T: FnMut(LoadEvent<'_, '_, Deco>) -> Result<(), E>,
+        E: std::error::Error + From<LoadError>;
+}
+
+/// Reads and parses the files when the directive is reached.
+struct Lazy;
+
+impl<F: FileSystem, Deco: syntax::decoration::Decoration> IncludeSource<F, Deco> for Lazy {
+    type File = PathBuf;
+
+    fn next(
+        &mut self,
+        loader: &Loader<F>,
+        path: &Path,
+        pattern: &str,
+    ) -> Result<Vec<PathBuf>, LoadError> {
+        loader.resolve_include(path, pattern)
+    }
+
+    fn path(file: &PathBuf) -> Cow<'_, Path> {
+        canonicalize::<F>(file)
+    }
+
+    fn load<T, E>(
+        &self,
+        loader: &Loader<F>,
+        parse_options: &parse::ParseOptions,
+        file: PathBuf,
+        state: &mut LoadState<'_>,
+        callback: &mut T,
+    ) -> Result<IncludeTree, E>
+    where
+        T: FnMut(LoadEvent<'_, '_, Deco>) -> Result<(), E>,
+        E: std::error::Error + From<LoadError>,
+    {
+        loader.load_file(parse_options, &file, state, callback)
+    }
+}
+
+/// Files already read and parsed, for each directive in order.
+struct Prefetched<'a, 'i, F: FileSystem, Deco: syntax::decoration::Decoration> {
+    parallel: &'a Parallel<F>,
+    files: std::vec::IntoIter<Result<Vec<PrefetchedFile<'i, Deco>>, LoadError>>,
+}
+
+impl<'i, F, Deco> IncludeSource<F, Deco> for Prefetched<'_, 'i, F, Deco>
+where
+    F: FileSystem,
+    Deco: syntax::decoration::Decoration,
+    for<'j> syntax::LedgerEntry<'j, Deco>: Send,
+{
+    type File = PrefetchedFile<'i, Deco>;
+
+    fn next(
+        &mut self,
+        _loader: &Loader<F>,
+        path: &Path,
+        pattern: &str,
+    ) -> Result<Vec<Self::File>, LoadError> {
+        // All include directives are prefetched, so this must not run out.
+        self.files.next().unwrap_or_else(|| {
+            Err(LoadError::IO(
+                std::io::Error::other(format!("include {pattern} is not prefetched")),
+                path.to_owned(),
+            ))
+        })
+    }
+
+    fn path(file: &Self::File) -> Cow<'_, Path> {
+        Cow::Borrowed(&file.path)
+    }
+
+    fn load<T, E>(
+        &self,
+        loader: &Loader<F>,
+        parse_options: &parse::ParseOptions,
+        file: Self::File,
+        state: &mut LoadState<'_>,
+        callback: &mut T,
+    ) -> Result<IncludeTree, E>
+    where
+        T: FnMut(LoadEvent<'_, '_, Deco>) -> Result<(), E>,
+        E: std::error::Error + From<LoadError>,
+    {
+        loader.load_prefetched(self.parallel, parse_options, file, state, callback)
+    }
+}
+
+/// File read by [`Loader`].
+struct ReadFile<'a> {
+    /// Canonicalized path of the file.
+    path: &'a Path,
+    /// Decoded content of the file.
+    content: &'a str,
+}
+
+/// Included file read and parsed ahead.
+struct PrefetchedFile<'i, Deco: syntax::decoration::Decoration> {
+    /// Canonicalized path of the file.
+    path: PathBuf,
+    /// Decoded content of the file, empty on the read error.
+    content: &'i str,
+    entries: Result<Vec<ParsedEntry<'i, Deco>>, LoadError>,
+}
+
+/// Canonicalizes the `path`, except [`STDIN_PATH`].
+fn canonicalize<F: FileSystem>(path: &Path) -> Cow<'_, Path> {
+    if path == Path::new(STDIN_PATH) {
+        // Parent of `-` is empty, so includes are resolved from the working directory.
+        Cow::Borrowed(path)
+    } else {
+        F::canonicalize_path(path)
+    }
+}
+
+/// Fails if `path` is already being loaded in the `stack`.
+fn check_cycle(stack: &mut Vec<PathBuf>, path: &Path) -> Result<(), LoadError> {
+    match stack.iter().position(|p| p == path) {
+        Some(pos) => {
+            let mut cycle = stack.split_off(pos);
+            cycle.push(path.to_owned());
+            Err(LoadError::IncludeCycle(cycle))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Applies `f` to all `items` on up to `threads` threads, and returns the results in the same order.
+fn parallel_map<T, R, M>(threads: NonZeroUsize, items: &[T], f: M) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    M: Fn(&T) -> R + Sync,
+{
+    let workers = threads.get().min(items.len());
+    if workers <= 1 {
+        return items.iter().map(f).collect();
     }
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<Option<R>> = std::iter::repeat_with(|| None).take(items.len()).collect();
+    std::thread::scope(|s| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(i) else {
+                            break;
+                        };
+                        done.push((i, f(item)));
+                    }
+                    done
+                })
+            })
+            .collect();
+        for handle in handles {
+            let done = handle
+                .join()
+                .unwrap_or_else(|e| std::panic::resume_unwind(e));
+            for (i, result) in done {
+                results[i] = Some(result);
+            }
+        }
+    });
+    results
+        .into_iter()
+        .map(|result| result.expect("all items must be processed"))
+        .collect()
 }
 
 /// Interface to abstract file system.
 /// Normally you want to use [ProdFileSystem].
+///
+/// It must be [`Sync`] for [`Loader::with_threads`] to read the files from multiple threads.
 pub trait FileSystem {
     /// canonicalize the given path.
     fn canonicalize_path<'a>(path: &'a Path) -> Cow<'a, Path>;
@@ -156,6 +1008,30 @@ pub trait FileSystem {
     /// Load the given path and returns it as UTF-8 String.
     fn file_content_utf8<P: AsRef<Path>>(&self, path: P) -> Result<String, std::io::Error>;
 
+    /// Load the given path and returns its raw content.
+    ///
+    /// Decompression and decoding into UTF-8 are done by the caller.
+    /// Defaults to [`FileSystem::file_content_utf8`],
+    /// which needs to be overridden to load the compressed or non UTF-8 files.
+    fn file_content<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>, std::io::Error> {
+        self.file_content_utf8(path).map(String::into_bytes)
+    }
+
+    /// Returns the last modification time of the file, if available.
+    ///
+    /// The cache of the processed ledger trusts the files with the same modification time,
+    /// otherwise it compares the content.
+    fn modified(&self, _path: &Path) -> Option<SystemTime> {
+        None
+    }
+
+    /// Reads the standard input and returns its raw content.
+    fn stdin_content(&self) -> Result<Vec<u8>, std::io::Error> {
+        let mut content = Vec::new();
+        std::io::Read::read_to_end(&mut std::io::stdin(), &mut content)?;
+        Ok(content)
+    }
+
     /// Returns all paths matching the given glob.
     /// Paths can be in arbitrary order, and caller must sort it beforehand.
     fn glob(&self, pattern: &str) -> Result<Vec<PathBuf>, LoadError>;
@@ -188,6 +1064,16 @@ impl FileSystem for ProdFileSystem {
         std::fs::read_to_string(path)
     }
 
+    fn file_content<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>, std::io::Error> {
+        std::fs::read(path)
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
     fn glob(&self, pattern: &str) -> Result<Vec<PathBuf>, LoadError> {
         let paths: Vec<PathBuf> = glob::glob_with(pattern, glob_match_options())?
             .collect::<Result<Vec<_>, glob::GlobError>>()?;
@@ -238,17 +1124,21 @@ impl FileSystem for FakeFileSystem {
     }
 
     fn file_content_utf8<P: AsRef<Path>>(&self, path: P) -> Result<String, std::io::Error> {
+        String::from_utf8(self.file_content(path)?)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+
+    fn file_content<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>, std::io::Error> {
         let path = path.as_ref();
-        self.0
-            .get(path)
-            .ok_or(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                format!("fake file {} not found", path.display()),
-            ))
-            .and_then(|x| {
-                String::from_utf8(x.clone())
-                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
-            })
+        self.0.get(path).cloned().ok_or(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("fake file {} not found", path.display()),
+        ))
+    }
+
+    /// Returns the fake file at [`STDIN_PATH`] as the standard input.
+    fn stdin_content(&self) -> Result<Vec<u8>, std::io::Error> {
+        self.file_content(STDIN_PATH)
     }
 
     fn glob(&self, pattern: &str) -> Result<Vec<PathBuf>, LoadError> {
@@ -270,9 +1160,13 @@ mod tests {
 
     use std::{borrow::Borrow, path::Path, vec::Vec};
 
+    use crate::diagnostic::{Label, Position, Severity};
+
+    use chrono::NaiveDate;
     use indoc::indoc;
     use maplit::hashmap;
     use pretty_assertions::assert_eq;
+    use rstest::rstest;
 
     /// Parses the expected entries, given as a fragment per contiguous run of
     /// entries the loader reports from one file.
@@ -316,6 +1210,49 @@ mod tests {
         Ok(ret)
     }
 
+    /// [`FileSystem`] implementing only the required methods, reading the files as UTF-8.
+    struct Utf8FileSystem(FakeFileSystem);
+
+    impl FileSystem for Utf8FileSystem {
+        fn canonicalize_path<'a>(path: &'a Path) -> Cow<'a, Path> {
+            FakeFileSystem::canonicalize_path(path)
+        }
+
+        fn file_content_utf8<P: AsRef<Path>>(&self, path: P) -> Result<String, std::io::Error> {
+            self.0.file_content_utf8(path)
+        }
+
+        fn glob(&self, pattern: &str) -> Result<Vec<PathBuf>, LoadError> {
+            self.0.glob(pattern)
+        }
+    }
+
+    #[test]
+    fn load_with_file_system_reading_utf8() {
+        let content = indoc! {"
+            2024/01/01 Lunch
+                Expenses:Food                          100 JPY
+                Assets:Bank
+        "};
+        let fake = hashmap! {
+            PathBuf::from("path/to/root.ledger") => content.as_bytes().to_vec(),
+        };
+        let loader = Loader::new(
+            PathBuf::from("path/to/root.ledger"),
+            Utf8FileSystem(FakeFileSystem::from(fake)),
+        );
+
+        let got = parse_into_vec(&loader).expect("failed to parse");
+
+        let want = parse_static_ledger_entry(&[(
+            Path::new("path/to/root.ledger"),
+            syntax::Separation::Immediate,
+            content,
+        )])
+        .unwrap();
+        assert_eq!(want, got);
+    }
+
     #[test]
     fn load_valid_input_real_file() {
         let mut testdata_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -441,6 +1378,51 @@ mod tests {
         assert_eq!(want, got);
     }
 
+    #[test]
+    fn load_carries_default_year_into_include() {
+        let fake = hashmap! {
+            PathBuf::from("path/to/root.ledger") => indoc! {"
+                year 2024
+                include child.ledger
+
+                03/15 Lunch
+                    Expenses:Food                         10 CHF
+                    Assets:Bank
+            "}.as_bytes().to_vec(),
+            PathBuf::from("path/to/child.ledger") => indoc! {"
+                01/31 Dinner
+                    Expenses:Food                         20 CHF
+                    Assets:Bank
+
+                Y 2025
+                02/01 Breakfast
+                    Expenses:Food                          5 CHF
+                    Assets:Bank
+            "}.as_bytes().to_vec(),
+        };
+
+        let got: Vec<NaiveDate> = parse_into_vec(Loader::new(
+            PathBuf::from("path/to/root.ledger"),
+            FakeFileSystem::from(fake),
+        ))
+        .expect("parse failed")
+        .into_iter()
+        .filter_map(|(_, entry)| match entry.statement {
+            syntax::LedgerStatement::Txn(txn) => Some(txn.date),
+            _ => None,
+        })
+        .collect();
+
+        assert_eq!(
+            vec![
+                NaiveDate::from_ymd_opt(2024, 1, 31).unwrap(),
+                NaiveDate::from_ymd_opt(2025, 2, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 3, 15).unwrap(),
+            ],
+            got
+        );
+    }
+
     #[test]
     fn load_non_existing_file() {
         let fake = hashmap! {
@@ -489,6 +1471,405 @@ mod tests {
         }
     }
 
+    #[test]
+    fn load_reports_all_syntax_errors() {
+        let fake = hashmap! {
+            PathBuf::from("path/to/root.ledger") => indoc! {"
+                2024/01/01 Valid
+                    Expenses:Food     10 CHF
+                    Assets:Bank
+
+                2024/13/01 Invalid date
+                    Expenses:Food     10 CHF
+                    Assets:Bank
+
+                include sub.ledger
+
+                2024/02/01 Valid after error
+                    Expenses:Food     10 CHF
+                    Assets:Bank
+            "}.as_bytes().to_vec(),
+            PathBuf::from("path/to/sub.ledger") => indoc! {"
+                2024/01/15 Invalid amount
+                    Expenses:Food     10 CHF CHF
+                    Assets:Bank
+            "}.as_bytes().to_vec(),
+        };
+        let mut loaded = Vec::new();
+
+        let got_err = Loader::new(
+            PathBuf::from("path/to/root.ledger"),
+            FakeFileSystem::from(fake),
+        )
+        .load(|_path, _ctx, entry: &syntax::plain::LedgerEntry| {
+            loaded.push(entry.to_static());
+            Ok::<(), LoadError>(())
+        })
+        .expect_err("load must fail");
+
+        assert_eq!(1, loaded.len(), "callback must stop after the first error");
+        let LoadError::Parse(errors) = got_err else {
+            panic!("unexpected error: {:?}", got_err);
+        };
+        assert_eq!(2, errors.len());
+        let paths: Vec<bool> = errors
+            .errors()
+            .iter()
+            .zip(["path/to/root.ledger:5", "path/to/sub.ledger:2"])
+            .map(|(err, want)| err.to_string().contains(want))
+            .collect();
+        assert_eq!(vec![true, true], paths, "errors: {}", errors);
+    }
+
+    #[rstest]
+    fn load_fails_on_include_cycle(#[values(false, true)] parallel: bool) {
+        let fake = hashmap! {
+            PathBuf::from("path/to/root.ledger") => b"include child.ledger\n".to_vec(),
+            PathBuf::from("path/to/child.ledger") => b"include root.ledger\n".to_vec(),
+        };
+        let loader = Loader::new(
+            PathBuf::from("path/to/root.ledger"),
+            FakeFileSystem::from(fake),
+        );
+
+        let got_err = parse_into_vec_parallel(loader, parallel).expect_err("load must fail");
+
+        let LoadError::IncludeCycle(cycle) = got_err else {
+            panic!("unexpected error: {:?}", got_err);
+        };
+        assert_eq!(
+            vec![
+                PathBuf::from("path/to/root.ledger"),
+                PathBuf::from("path/to/child.ledger"),
+                PathBuf::from("path/to/root.ledger"),
+            ],
+            cycle
+        );
+    }
+
+    #[test]
+    fn include_tree_expands_globs() {
+        let fake = hashmap! {
+            PathBuf::from("path/to/root.ledger") => indoc! {"
+                include sub/*.ledger
+                include other.ledger
+            "}.as_bytes().to_vec(),
+            PathBuf::from("path/to/sub/a.ledger") => b"include inner/c.ledger\n".to_vec(),
+            PathBuf::from("path/to/sub/b.ledger") => Vec::new(),
+            PathBuf::from("path/to/sub/inner/c.ledger") => Vec::new(),
+            PathBuf::from("path/to/other.ledger") => Vec::new(),
+        };
+        let loader = Loader::new(
+            PathBuf::from("path/to/root.ledger"),
+            FakeFileSystem::from(fake),
+        );
+
+        let got = loader.include_trees().unwrap();
+
+        let leaf = |path: &str| IncludeTree {
+            path: PathBuf::from(path),
+            includes: Vec::new(),
+        };
+        let include = |pattern: &str, files| Include {
+            pattern: pattern.to_string(),
+            files,
+        };
+        assert_eq!(
+            vec![IncludeTree {
+                path: PathBuf::from("path/to/root.ledger"),
+                includes: vec![
+                    include(
+                        "sub/*.ledger",
+                        vec![
+                            IncludeTree {
+                                path: PathBuf::from("path/to/sub/a.ledger"),
+                                includes: vec![include(
+                                    "inner/c.ledger",
+                                    vec![leaf("path/to/sub/inner/c.ledger")]
+                                )],
+                            },
+                            leaf("path/to/sub/b.ledger"),
+                        ]
+                    ),
+                    include("other.ledger", vec![leaf("path/to/other.ledger")]),
+                ],
+            }],
+            got
+        );
+    }
+
+    #[test]
+    fn load_multiple_sources_in_order() {
+        let fake = hashmap! {
+            PathBuf::from("path/to/first.ledger") => b"account Assets:First\n".to_vec(),
+            PathBuf::from("path/to/second.ledger") => indoc! {"
+                account Assets:Second
+                include sub.ledger
+            "}.as_bytes().to_vec(),
+            PathBuf::from("path/to/sub.ledger") => b"account Assets:Sub\n".to_vec(),
+        };
+        let loader = Loader::from_sources(
+            vec![
+                PathBuf::from("path/to/second.ledger"),
+                PathBuf::from("path/to/first.ledger"),
+            ],
+            FakeFileSystem::from(fake),
+        );
+
+        let got: Vec<PathBuf> = parse_into_vec(loader)
+            .unwrap()
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+
+        assert_eq!(
+            vec![
+                PathBuf::from("path/to/second.ledger"),
+                PathBuf::from("path/to/sub.ledger"),
+                PathBuf::from("path/to/first.ledger"),
+            ],
+            got
+        );
+    }
+
+    #[test]
+    fn load_decodes_compressed_and_declared_encoding() {
+        let mut gzipped = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        std::io::Write::write_all(&mut gzipped, b"account Assets:Archived\n").unwrap();
+        let (sjis, _, _) =
+            encoding_rs::SHIFT_JIS.encode("; -*- coding: shift_jis -*-\naccount 資産:銀行\n");
+        let fake = hashmap! {
+            PathBuf::from("path/to/root.ledger") => indoc! {"
+                include 2019.ledger.gz
+                include sjis.ledger
+            "}.as_bytes().to_vec(),
+            PathBuf::from("path/to/2019.ledger.gz") => gzipped.finish().unwrap(),
+            PathBuf::from("path/to/sjis.ledger") => sjis.into_owned(),
+        };
+        let loader = Loader::new(
+            PathBuf::from("path/to/root.ledger"),
+            FakeFileSystem::from(fake),
+        );
+
+        let got: Vec<String> = parse_into_vec(loader)
+            .unwrap()
+            .into_iter()
+            .filter_map(|(_, entry)| match entry.statement {
+                syntax::LedgerStatement::Account(account) => Some(account.name.to_string()),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(vec!["Assets:Archived", "資産:銀行"], got);
+    }
+
+    /// Same as [`parse_into_vec`], but with [`Loader::load_parallel`] on 4 threads if `parallel`.
+    fn parse_into_vec_parallel(
+        loader: Loader<FakeFileSystem>,
+        parallel: bool,
+    ) -> Result<Vec<(PathBuf, syntax::plain::LedgerEntry<'static>)>, LoadError> {
+        if !parallel {
+            return parse_into_vec(loader);
+        }
+        let mut ret: Vec<(PathBuf, syntax::plain::LedgerEntry<'static>)> = Vec::new();
+        loader
+            .with_threads(NonZeroUsize::new(4).unwrap())
+            .load_parallel(|path, _ctx, entry| {
+                ret.push((path.to_owned(), entry.to_static()));
+                Ok::<(), LoadError>(())
+            })?;
+        Ok(ret)
+    }
+
+    fn parallel_test_files() -> HashMap<PathBuf, Vec<u8>> {
+        hashmap! {
+            PathBuf::from("path/to/root.ledger") => indoc! {"
+                year 2024
+                include 2024/*.ledger
+                account Assets:Root
+                include other.ledger
+            "}.as_bytes().to_vec(),
+            PathBuf::from("path/to/2024/01.ledger") => indoc! {"
+                01/31 January
+                    Expenses:Food     10 CHF
+                    Assets:Bank
+                include inner/*.ledger
+            "}.as_bytes().to_vec(),
+            PathBuf::from("path/to/2024/02.ledger") => indoc! {"
+                02/29 February
+                    Expenses:Food     20 CHF
+                    Assets:Bank
+            "}.as_bytes().to_vec(),
+            PathBuf::from("path/to/2024/inner/a.ledger") => b"account Assets:A\n".to_vec(),
+            PathBuf::from("path/to/2024/inner/b.ledger") => b"account Assets:B\n".to_vec(),
+            PathBuf::from("path/to/other.ledger") => indoc! {"
+                2025/01/01 Other
+                    Expenses:Food     30 CHF
+                    Assets:Bank
+            "}.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn load_parallel_gives_same_order_as_sequential() {
+        let load = |parallel| {
+            parse_into_vec_parallel(
+                Loader::new(
+                    PathBuf::from("path/to/root.ledger"),
+                    FakeFileSystem::from(parallel_test_files()),
+                ),
+                parallel,
+            )
+            .unwrap()
+        };
+
+        let want = load(false);
+        let got = load(true);
+
+        assert_eq!(
+            vec![
+                PathBuf::from("path/to/root.ledger"),
+                PathBuf::from("path/to/2024/01.ledger"),
+                PathBuf::from("path/to/2024/inner/a.ledger"),
+                PathBuf::from("path/to/2024/inner/b.ledger"),
+                PathBuf::from("path/to/2024/02.ledger"),
+                PathBuf::from("path/to/root.ledger"),
+                PathBuf::from("path/to/other.ledger"),
+            ],
+            want.iter()
+                .map(|(path, _)| path.clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(want, got);
+    }
+
+    #[test]
+    fn load_parallel_fails_same_as_sequential() {
+        let mut files = parallel_test_files();
+        files.insert(
+            PathBuf::from("path/to/2024/02.ledger"),
+            b"2024/13/01 Invalid date\n".to_vec(),
+        );
+        files.insert(
+            PathBuf::from("path/to/2024/inner/a.ledger"),
+            b"account\n".to_vec(),
+        );
+        files.remove(Path::new("path/to/other.ledger"));
+        let load = |parallel| {
+            parse_into_vec_parallel(
+                Loader::new(
+                    PathBuf::from("path/to/root.ledger"),
+                    FakeFileSystem::from(files.clone()),
+                ),
+                parallel,
+            )
+            .unwrap_err()
+            .to_string()
+        };
+
+        let want = load(false);
+        let got = load(true);
+
+        assert!(want.contains("other.ledger"), "unexpected error: {}", want);
+        assert_eq!(want, got);
+    }
+
+    #[test]
+    fn load_parallel_reports_syntax_errors_same_as_sequential() {
+        let mut files = parallel_test_files();
+        files.insert(
+            PathBuf::from("path/to/2024/02.ledger"),
+            b"2024/13/01 Invalid date\n".to_vec(),
+        );
+        files.insert(
+            PathBuf::from("path/to/2024/inner/a.ledger"),
+            b"account\n".to_vec(),
+        );
+        let load = |parallel| {
+            let got_err = parse_into_vec_parallel(
+                Loader::new(
+                    PathBuf::from("path/to/root.ledger"),
+                    FakeFileSystem::from(files.clone()),
+                ),
+                parallel,
+            )
+            .unwrap_err();
+            let LoadError::Parse(errors) = got_err else {
+                panic!("unexpected error: {:?}", got_err);
+            };
+            errors.to_string()
+        };
+
+        let want = load(false);
+        let got = load(true);
+
+        assert_eq!(want, got);
+    }
+
+    #[test]
+    fn load_stdin_resolves_include_from_working_directory() {
+        let fake = hashmap! {
+            PathBuf::from(STDIN_PATH) => indoc! {"
+                account Assets:Stdin
+                include sub/child.ledger
+            "}.as_bytes().to_vec(),
+            PathBuf::from("sub/child.ledger") => b"account Assets:Child\n".to_vec(),
+        };
+        let loader = Loader::new(PathBuf::from(STDIN_PATH), FakeFileSystem::from(fake));
+
+        let got: Vec<PathBuf> = parse_into_vec(loader)
+            .unwrap()
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+
+        assert_eq!(
+            vec![PathBuf::from(STDIN_PATH), PathBuf::from("sub/child.ledger")],
+            got
+        );
+    }
+
+    #[test]
+    fn load_error_to_diagnostics_gives_syntax_error_positions() {
+        let fake = hashmap! {
+            PathBuf::from("path/to/root.ledger") => indoc! {"
+                2024/01/01 Valid
+                    Expenses:Food     10 CHF
+                    Assets:Bank
+
+                2024/13/01 Invalid date
+                    Expenses:Food     10 CHF
+                    Assets:Bank
+            "}.as_bytes().to_vec(),
+        };
+
+        let got_err = Loader::new(
+            PathBuf::from("path/to/root.ledger"),
+            FakeFileSystem::from(fake),
+        )
+        .load(|_path, _ctx, _entry: &syntax::plain::LedgerEntry| Ok::<(), LoadError>(()))
+        .expect_err("load must fail");
+
+        let got: Vec<_> = got_err
+            .to_diagnostics()
+            .into_iter()
+            .map(|d| (d.severity, d.path, d.labels))
+            .collect();
+        assert_eq!(
+            vec![(
+                Severity::Error,
+                Some("path/to/root.ledger".to_string()),
+                vec![Label {
+                    start: Position { line: 5, column: 1 },
+                    end: Position { line: 5, column: 2 },
+                    message: String::new(),
+                    primary: true,
+                }],
+            )],
+            got
+        );
+    }
+
     mod fake_file_system {
         use super::*;
 
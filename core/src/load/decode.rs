@@ -0,0 +1,201 @@
+//! Decodes the file content into UTF-8 string,
+//! decompressing gzip / zstd and converting from the declared encoding.
+
+use std::io::Read;
+
+use encoding_rs::Encoding;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Number of the leading lines to look for the encoding declaration.
+const CODING_LINES: usize = 2;
+
+/// Decodes the raw `content` of the file into String.
+///
+/// gzip or zstd compressed content is detected with its magic number.
+/// Encoding is decided in the following order:
+///
+/// 1. Byte order mark.
+/// 2. Declaration such as `; -*- coding: shift_jis -*-` in the first two lines.
+/// 3. Given `default_encoding`.
+pub(crate) fn decode(
+    content: Vec<u8>,
+    default_encoding: &'static Encoding,
+) -> Result<String, std::io::Error> {
+    let content = decompress(content)?;
+    let (encoding, bom_len) = match Encoding::for_bom(&content) {
+        Some(found) => found,
+        None => (declared_encoding(&content)?.unwrap_or(default_encoding), 0),
+    };
+    if encoding == encoding_rs::UTF_8 {
+        let mut content = content;
+        content.drain(..bom_len);
+        return String::from_utf8(content)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err));
+    }
+    encoding
+        .decode_without_bom_handling_and_without_replacement(&content[bom_len..])
+        .map(|decoded| decoded.into_owned())
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("content is not valid {}", encoding.name()),
+            )
+        })
+}
+
+fn decompress(content: Vec<u8>) -> Result<Vec<u8>, std::io::Error> {
+    let mut decompressed = Vec::new();
+    if content.starts_with(GZIP_MAGIC) {
+        flate2::read::MultiGzDecoder::new(content.as_slice()).read_to_end(&mut decompressed)?;
+    } else if content.starts_with(ZSTD_MAGIC) {
+        ruzstd::decoding::StreamingDecoder::new(content.as_slice())
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?
+            .read_to_end(&mut decompressed)?;
+    } else {
+        return Ok(content);
+    }
+    Ok(decompressed)
+}
+
+/// Returns the encoding declared with `coding: name` or `coding=name`
+/// in the leading lines, as Emacs or Python does.
+fn declared_encoding(content: &[u8]) -> Result<Option<&'static Encoding>, std::io::Error> {
+    for line in content.split(|b| *b == b'\n').take(CODING_LINES) {
+        let Some(label) = coding_label(line) else {
+            continue;
+        };
+        return Encoding::for_label(label).map(Some).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "unknown encoding {} is declared",
+                    String::from_utf8_lossy(label)
+                ),
+            )
+        });
+    }
+    Ok(None)
+}
+
+/// Returns the encoding label in the comment line.
+fn coding_label(line: &[u8]) -> Option<&[u8]> {
+    if !line
+        .first()
+        .is_some_and(|c| matches!(c, b';' | b'#' | b'%' | b'|' | b'*'))
+    {
+        return None;
+    }
+    let pos = line.windows(6).position(|w| w == b"coding")?;
+    let rest = line[pos + 6..]
+        .strip_prefix(b":")
+        .or_else(|| line[pos + 6..].strip_prefix(b"="))?;
+    let rest = rest.trim_ascii_start();
+    let len = rest
+        .iter()
+        .position(|c| !(c.is_ascii_alphanumeric() || matches!(c, b'-' | b'_' | b'.')))
+        .unwrap_or(rest.len());
+    (len > 0).then_some(&rest[..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    use pretty_assertions::assert_eq;
+
+    fn gzip(content: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(content).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn decode_utf8_as_is() {
+        assert_eq!(
+            "account 資産\n",
+            decode("account 資産\n".as_bytes().to_vec(), encoding_rs::UTF_8).unwrap()
+        );
+    }
+
+    #[test]
+    fn decode_strips_bom() {
+        assert_eq!(
+            "account Assets\n",
+            decode(b"\xef\xbb\xbfaccount Assets\n".to_vec(), encoding_rs::UTF_8).unwrap()
+        );
+    }
+
+    #[test]
+    fn decode_invalid_utf8_fails() {
+        let got = decode(b"account \x8e\x91\x8eY\n".to_vec(), encoding_rs::UTF_8).unwrap_err();
+
+        assert_eq!(std::io::ErrorKind::InvalidData, got.kind());
+    }
+
+    #[test]
+    fn decode_declared_encoding() {
+        let (content, _, _) =
+            encoding_rs::SHIFT_JIS.encode("; -*- coding: shift_jis -*-\naccount 資産\n");
+
+        assert_eq!(
+            "; -*- coding: shift_jis -*-\naccount 資産\n",
+            decode(content.into_owned(), encoding_rs::UTF_8).unwrap()
+        );
+    }
+
+    #[test]
+    fn decode_default_encoding() {
+        let (content, _, _) = encoding_rs::SHIFT_JIS.encode("account 資産\n");
+
+        assert_eq!(
+            "account 資産\n",
+            decode(content.into_owned(), encoding_rs::SHIFT_JIS).unwrap()
+        );
+    }
+
+    #[test]
+    fn decode_unknown_encoding_fails() {
+        let got = decode(b"; coding=no-such-encoding\n".to_vec(), encoding_rs::UTF_8).unwrap_err();
+
+        assert_eq!(
+            "unknown encoding no-such-encoding is declared",
+            got.to_string()
+        );
+    }
+
+    #[test]
+    fn decode_gzip() {
+        assert_eq!(
+            "account Assets\n",
+            decode(gzip(b"account Assets\n"), encoding_rs::UTF_8).unwrap()
+        );
+    }
+
+    #[test]
+    fn decode_zstd() {
+        let content = ruzstd::encoding::compress_to_vec(
+            b"account Assets\n".as_slice(),
+            ruzstd::encoding::CompressionLevel::Fastest,
+        );
+
+        assert_eq!(
+            "account Assets\n",
+            decode(content, encoding_rs::UTF_8).unwrap()
+        );
+    }
+
+    #[test]
+    fn coding_label_finds_declaration() {
+        assert_eq!(
+            Some(b"euc-jp".as_slice()),
+            coding_label(b"; -*- mode: ledger; coding: euc-jp -*-")
+        );
+        assert_eq!(Some(b"latin1".as_slice()), coding_label(b"# coding=latin1"));
+        assert_eq!(None, coding_label(b"account coding: utf-8"));
+        assert_eq!(None, coding_label(b"; coding"));
+    }
+}
@@ -4,23 +4,28 @@
 mod adaptor;
 mod character;
 mod combinator;
-mod directive;
+pub(crate) mod directive;
 mod error;
 mod expr;
 mod metadata;
+mod period;
 mod posting;
 pub(crate) mod price;
-mod primitive;
+pub(crate) mod primitive;
+mod timeclock;
 pub(crate) mod transaction;
 
 #[cfg(test)]
 pub(crate) mod testing;
 
 pub use adaptor::{ParseOptions, ParsedContext, ParsedSpan};
+
+use adaptor::{DefaultYearStream, StatefulInput};
 pub use error::ParseError;
 
 use winnow::{
     ModalResult, Parser,
+    ascii::Caseless,
     combinator::{alt, cut_err, dispatch, fail, peek, preceded, trace},
     error::StrContext,
     stream::{Stream, StreamIsPartial},
@@ -35,8 +40,15 @@ pub fn parse_ledger<'i, Deco: 'i + Decoration>(
     options: &ParseOptions,
     input: &'i str,
 ) -> impl Iterator<Item = Result<(ParsedContext<'i>, syntax::LedgerEntry<'i, Deco>), ParseError>> {
+    let statement = |input: &mut StatefulInput<'i>| -> ModalResult<_> {
+        let statement = parse_ledger_statement(input)?;
+        if let syntax::LedgerStatement::Year(year) = &statement {
+            input.state.default_year = Some(*year);
+        }
+        Ok(statement)
+    };
     options
-        .parse_repeated(parse_ledger_statement, character::newlines.void(), input)
+        .parse_repeated(statement, character::newlines.void(), input)
         .enumerate()
         .map(|(i, parsed)| {
             parsed.map(|(ctx, statement)| {
@@ -66,8 +78,10 @@ where
     I: Stream<Token = char, Slice = &'i str>
         + StreamIsPartial
         + winnow::stream::Compare<&'static str>
+        + winnow::stream::Compare<Caseless<&'static str>>
         + winnow::stream::FindSlice<(char, char)>
         + winnow::stream::Location
+        + DefaultYearStream
         + Clone,
     Deco: Decoration + 'static,
 {
@@ -79,14 +93,47 @@ where
                     peek(literal("account")),
                     cut_err(directive::account_declaration.map(syntax::LedgerStatement::Account)),
                 ),
+                preceded(
+                    peek(literal("alias")),
+                    cut_err(directive::account_alias.map(syntax::LedgerStatement::Alias)),
+                ),
                 preceded(
                     peek(literal("apply")),
-                    cut_err(directive::apply_tag.map(syntax::LedgerStatement::ApplyTag)),
+                    cut_err(alt((
+                        directive::apply_account.map(syntax::LedgerStatement::ApplyAccount),
+                        directive::apply_tag.map(syntax::LedgerStatement::ApplyTag),
+                    ))),
+                ),
+            )),
+            'c' => alt((
+                preceded(
+                    peek(literal("comment")),
+                    cut_err(directive::block_comment.map(syntax::LedgerStatement::BlockComment)),
                 ),
+                directive::commodity_declaration.map(syntax::LedgerStatement::Commodity),
             )),
-            'c' => directive::commodity_declaration.map(syntax::LedgerStatement::Commodity),
-            'e' => directive::end_apply_tag.map(|_| syntax::LedgerStatement::EndApplyTag),
-            'i' => directive::include.map(syntax::LedgerStatement::Include),
+            '=' => directive::automated_transaction.map(syntax::LedgerStatement::AutomatedTxn),
+            'D' => directive::default_commodity.map(syntax::LedgerStatement::DefaultCommodity),
+            'e' => alt((
+                directive::end_apply_tag.map(|_| syntax::LedgerStatement::EndApplyTag),
+                directive::end_apply_account.map(|_| syntax::LedgerStatement::EndApplyAccount),
+            )),
+            'i' => alt((
+                directive::include.map(syntax::LedgerStatement::Include),
+                timeclock::clock_in.map(syntax::LedgerStatement::ClockIn),
+            )),
+            'o' => timeclock::clock_out.map(syntax::LedgerStatement::ClockOut),
+            'p' => directive::payee_declaration.map(syntax::LedgerStatement::Payee),
+            'P' => price::price_db_entry.map(syntax::LedgerStatement::Price),
+            't' => alt((
+                preceded(
+                    peek(literal("test")),
+                    cut_err(directive::block_comment.map(syntax::LedgerStatement::BlockComment)),
+                ),
+                directive::tag_declaration.map(syntax::LedgerStatement::Tag),
+            )),
+            'Y' | 'y' => directive::default_year.map(syntax::LedgerStatement::Year),
+            '~' => directive::periodic_transaction.map(syntax::LedgerStatement::PeriodicTxn),
             c if directive::is_comment_prefix(c) => {
                 directive::top_comment.map(syntax::LedgerStatement::Comment)
             },
@@ -229,4 +276,136 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn parse_ledger_price_directive() {
+        let input = indoc! {"
+            P 2024/10/28 EUR 0.9367 CHF
+            P 2024-10-29 EUR 0.9368 CHF"};
+
+        let got: Vec<_> = parse_ledger_into(input)
+            .into_iter()
+            .map(|(_, entry)| entry.statement)
+            .collect();
+
+        assert_eq!(
+            vec![
+                LedgerStatement::Price(syntax::PriceDBEntry {
+                    datetime: NaiveDate::from_ymd_opt(2024, 10, 28).unwrap().into(),
+                    target: "EUR".into(),
+                    rate: syntax::expr::Amount {
+                        value: pretty_decimal::PrettyDecimal::unformatted(
+                            rust_decimal_macros::dec!(0.9367)
+                        ),
+                        commodity: "CHF".into(),
+                        style: syntax::expr::CommodityStyle::default(),
+                    },
+                    comment: None,
+                }),
+                LedgerStatement::Price(syntax::PriceDBEntry {
+                    datetime: NaiveDate::from_ymd_opt(2024, 10, 29).unwrap().into(),
+                    target: "EUR".into(),
+                    rate: syntax::expr::Amount {
+                        value: pretty_decimal::PrettyDecimal::unformatted(
+                            rust_decimal_macros::dec!(0.9368)
+                        ),
+                        commodity: "CHF".into(),
+                        style: syntax::expr::CommodityStyle::default(),
+                    },
+                    comment: None,
+                }),
+            ],
+            got
+        );
+    }
+
+    #[test]
+    fn parse_ledger_year_directive_sets_default_year() {
+        let input = indoc! {"
+            year 2024
+            03/15 Lunch
+            Y 2025
+            01/02=01/03 Dinner
+        "};
+
+        let got: Vec<_> = parse_ledger_into(input)
+            .into_iter()
+            .map(|(_, entry)| entry.statement)
+            .collect();
+
+        assert_eq!(
+            vec![
+                LedgerStatement::Year(2024),
+                LedgerStatement::Txn(syntax::Transaction::new(
+                    NaiveDate::from_ymd_opt(2024, 3, 15).unwrap(),
+                    "Lunch"
+                )),
+                LedgerStatement::Year(2025),
+                LedgerStatement::Txn(syntax::Transaction {
+                    effective_date: Some(NaiveDate::from_ymd_opt(2025, 1, 3).unwrap()),
+                    ..syntax::Transaction::new(
+                        NaiveDate::from_ymd_opt(2025, 1, 2).unwrap(),
+                        "Dinner"
+                    )
+                }),
+            ],
+            got
+        );
+    }
+
+    #[test]
+    fn parse_ledger_recovers_from_errors() {
+        let input = indoc! {"
+            2024/01/01 Valid
+                Expenses:Food     10 CHF
+                Assets:Bank
+            2024/13/01 Invalid date
+                Expenses:Food     10 CHF
+
+                Assets:Bank
+            account Assets:Bank
+            2024/01/02 Invalid amount
+                Expenses:Food     10 CHF CHF
+                Assets:Bank
+            2024/01/03 Valid
+        "};
+        let summarize = |options: &ParseOptions| -> Vec<&'static str> {
+            parse_ledger(options, input)
+                .map(|parsed: Result<(ParsedContext, LedgerEntry), ParseError>| {
+                    match parsed.map(|(_, entry)| entry.statement) {
+                        Ok(LedgerStatement::Txn(_)) => "txn",
+                        Ok(LedgerStatement::Account(_)) => "account",
+                        Ok(_) => "other",
+                        Err(_) => "error",
+                    }
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            vec!["txn", "error", "error", "account", "error", "txn"],
+            summarize(&ParseOptions::default().with_recovery(true))
+        );
+        assert_eq!(
+            vec!["txn", "error"],
+            summarize(&ParseOptions::default()),
+            "must stop at the first error"
+        );
+    }
+
+    #[test]
+    fn parse_ledger_fails_on_date_without_year() {
+        let input = indoc! {"
+            03/15 Lunch
+        "};
+
+        let got: Result<Vec<(ParsedContext, LedgerEntry)>, ParseError> =
+            parse_ledger(&ParseOptions::default(), input).collect();
+
+        let err = got.unwrap_err().to_string();
+        assert!(
+            err.contains("date without year requires \"year\" directive beforehand"),
+            "unexpected error: {err}"
+        );
+    }
 }
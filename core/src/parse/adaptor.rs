@@ -2,7 +2,7 @@
 
 use std::{fmt::Debug, marker::PhantomData, ops::Range};
 
-use winnow::{LocatingSlice, Parser, error::ContextError};
+use winnow::{LocatingSlice, Parser, Stateful, error::ContextError};
 
 use crate::syntax;
 
@@ -12,12 +12,16 @@ use super::error::{self, ParseError};
 #[derive(Debug, Clone)]
 pub struct ParseOptions {
     error_style: annotate_snippets::Renderer,
+    default_year: Option<i32>,
+    recovery: bool,
 }
 
 impl Default for ParseOptions {
     fn default() -> Self {
         Self {
             error_style: annotate_snippets::Renderer::plain(),
+            default_year: None,
+            recovery: false,
         }
     }
 }
@@ -29,6 +33,27 @@ impl ParseOptions {
         self
     }
 
+    /// Sets the default year used for the dates without year,
+    /// until the input overrides it with "year" directive.
+    pub fn with_default_year(mut self, default_year: Option<i32>) -> Self {
+        self.default_year = default_year;
+        self
+    }
+
+    /// Returns the default year used for the dates without year.
+    pub fn default_year(&self) -> Option<i32> {
+        self.default_year
+    }
+
+    /// Sets if the parser recovers from the syntax error.
+    /// When `true`, the parser skips to the next top-level directive after an error,
+    /// so that all the syntax errors in the input can be reported at once.
+    /// Otherwise the parser stops at the first error.
+    pub fn with_recovery(mut self, recovery: bool) -> Self {
+        self.recovery = recovery;
+        self
+    }
+
     pub(super) fn parse_single<'i, Out, P>(
         &self,
         parser: P,
@@ -71,21 +96,61 @@ impl ParseOptions {
     where
         Out: 'i,
         Sep: 'i,
-        P: Parser<LocatingSlice<&'i str>, Out, E> + 'i,
-        Q: Parser<LocatingSlice<&'i str>, Sep, E> + 'i,
+        P: Parser<StatefulInput<'i>, Out, E> + 'i,
+        Q: Parser<StatefulInput<'i>, Sep, E> + 'i,
         E: winnow::error::ParserError<&'i str, Inner = ContextError> + Debug + 'i,
     {
         ParsedIter {
             parser,
             separator,
             initial: input,
-            input: LocatingSlice::new(input),
+            input: Stateful {
+                input: LocatingSlice::new(input),
+                state: ParseState {
+                    default_year: self.default_year,
+                },
+            },
             renderer: self.error_style.clone(),
+            recovery: self.recovery,
             _phantom: PhantomData,
         }
     }
 }
 
+/// State carried across the entries while parsing the input.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(super) struct ParseState {
+    /// Default year for the dates without year, given by "year" directive.
+    pub default_year: Option<i32>,
+}
+
+/// Input stream used to parse the entries with [`ParseState`].
+pub(super) type StatefulInput<'i> = Stateful<LocatingSlice<&'i str>, ParseState>;
+
+/// Stream which may know the default year, used to parse the dates without year.
+pub(crate) trait DefaultYearStream {
+    /// Returns the current default year if any.
+    fn default_year(&self) -> Option<i32>;
+}
+
+impl DefaultYearStream for &str {
+    fn default_year(&self) -> Option<i32> {
+        None
+    }
+}
+
+impl<I> DefaultYearStream for LocatingSlice<I> {
+    fn default_year(&self) -> Option<i32> {
+        None
+    }
+}
+
+impl<I> DefaultYearStream for Stateful<I, ParseState> {
+    fn default_year(&self) -> Option<i32> {
+        self.state.default_year
+    }
+}
+
 /// Context information carrying the metadata of the entry.
 #[derive(Debug, PartialEq, Eq)]
 pub struct ParsedContext<'i> {
@@ -149,41 +214,65 @@ struct ParsedIter<'i, Out, Sep, P, Q, E> {
     parser: P,
     separator: Q,
     initial: &'i str,
-    input: LocatingSlice<&'i str>,
+    input: StatefulInput<'i>,
     renderer: annotate_snippets::Renderer,
+    recovery: bool,
     _phantom: PhantomData<(Out, Sep, E)>,
 }
 
 impl<'i, Out, Sep, P, Q, E> Iterator for ParsedIter<'i, Out, Sep, P, Q, E>
 where
-    P: Parser<LocatingSlice<&'i str>, Out, E>,
-    Q: Parser<LocatingSlice<&'i str>, Sep, E>,
+    P: Parser<StatefulInput<'i>, Out, E>,
+    Q: Parser<StatefulInput<'i>, Sep, E>,
     E: winnow::error::ParserError<&'i str, Inner = ContextError> + Debug + 'i,
 {
     type Item = Result<(ParsedContext<'i>, Out), ParseError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         use winnow::stream::Stream as _;
-        let start = self.input.checkpoint();
-        self.next_impl()
-            .map_err(|e| {
-                ParseError::new(
-                    self.renderer.clone(),
-                    self.initial,
-                    self.input,
-                    start,
-                    e.into_inner()
-                        .expect("ParseIter doesn't work with streaming parse yet"),
-                )
-            })
-            .transpose()
+        let start = self.input.input.checkpoint();
+        let err = match self.next_impl() {
+            Ok(parsed) => return parsed.map(Ok),
+            Err(e) => ParseError::new(
+                self.renderer.clone(),
+                self.initial,
+                self.input.input,
+                start,
+                e.into_inner()
+                    .expect("ParseIter doesn't work with streaming parse yet"),
+            ),
+        };
+        if self.recovery {
+            self.input.input.reset(&start);
+            // Separator has succeeded once already, so it must succeed again.
+            let _ = self.separator.by_ref().parse_next(&mut self.input);
+            let skipped = skip_entry_len(&self.input.input);
+            self.input.input.next_slice(skipped);
+        } else {
+            self.input.input.finish();
+        }
+        Some(Err(err))
+    }
+}
+
+/// Returns the length of the failed entry at the beginning of `input`,
+/// which is the first line and the following indented lines.
+/// The next top-level directive starts right after the length.
+fn skip_entry_len(input: &str) -> usize {
+    let mut len = 0;
+    for line in input.split_inclusive('\n') {
+        if len > 0 && !line.starts_with([' ', '\t']) {
+            break;
+        }
+        len += line.len();
     }
+    len
 }
 
 impl<'i, Out, Sep, P, Q, E> ParsedIter<'i, Out, Sep, P, Q, E>
 where
-    P: Parser<LocatingSlice<&'i str>, Out, E>,
-    Q: Parser<LocatingSlice<&'i str>, Sep, E>,
+    P: Parser<StatefulInput<'i>, Out, E>,
+    Q: Parser<StatefulInput<'i>, Sep, E>,
     E: winnow::error::ParserError<&'i str> + 'i,
 {
     fn next_impl(&mut self) -> Result<Option<(ParsedContext<'i>, Out)>, E> {
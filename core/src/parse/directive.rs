@@ -1,16 +1,20 @@
-use crate::syntax;
+use crate::syntax::{self, decoration::Decoration};
 
-use super::{character::line_ending_or_eof, expr, metadata};
+use super::{
+    adaptor::DefaultYearStream,
+    character::{self, line_ending_or_eof},
+    expr, metadata, period, primitive, transaction,
+};
 
 use std::borrow::Cow;
 
 use winnow::{
-    Parser,
-    ascii::{space0, space1, till_line_ending},
-    combinator::{alt, delimited, opt, preceded, repeat, terminated, trace},
-    error::{FromExternalError, ParserError},
+    ModalResult, Parser,
+    ascii::{Caseless, digit1, line_ending, space0, space1, till_line_ending},
+    combinator::{alt, cut_err, delimited, empty, not, opt, preceded, repeat, terminated, trace},
+    error::{FromExternalError, ParserError, StrContext},
     stream::{AsChar, Stream, StreamIsPartial},
-    token::{literal, take_while},
+    token::{literal, take_till, take_while},
 };
 
 /// Returns true if the given character is comment prefix.
@@ -77,7 +81,7 @@ where
     (
         delimited(
             (literal("commodity"), space1),
-            till_line_ending,
+            commodity_name,
             line_ending_or_eof,
         ),
         // TODO: Consider using dispatch
@@ -93,30 +97,34 @@ where
                     .map(syntax::CommodityDetail::Note),
                 delimited(
                     (space1, literal("alias"), space1),
-                    till_line_ending,
+                    commodity_name,
                     line_ending_or_eof,
                 )
-                .map(|a: &str| syntax::CommodityDetail::Alias(a.trim_end().into())),
+                .map(|a: &str| syntax::CommodityDetail::Alias(a.into())),
                 delimited(
                     (space1, literal("format"), space1),
                     expr::amount,
                     line_ending_or_eof,
                 )
                 .map(syntax::CommodityDetail::Format),
+                (space1, literal("default"), space0, line_ending_or_eof)
+                    .map(|_| syntax::CommodityDetail::Default),
+                (space1, literal("nomarket"), space0, line_ending_or_eof)
+                    .map(|_| syntax::CommodityDetail::NoMarket),
             )),
         ),
     )
         .map(
             |(name, details): (&'i str, _)| syntax::CommodityDeclaration {
-                name: name.trim_end().into(),
+                name: name.into(),
                 details,
             },
         )
         .parse_next(input)
 }
 
-/// Parses "apply tag" directive.
-pub fn apply_tag<'i, I, E>(input: &mut I) -> winnow::Result<syntax::ApplyTag<'i>, E>
+/// Parses "payee" directive.
+pub fn payee_declaration<'i, I, E>(input: &mut I) -> winnow::Result<syntax::PayeeDeclaration<'i>, E>
 where
     I: Stream<Slice = &'i str>
         + StreamIsPartial
@@ -124,6 +132,111 @@ where
         + winnow::stream::FindSlice<(char, char)>,
     <I as Stream>::Token: AsChar + Clone,
     E: ParserError<I>,
+{
+    (
+        delimited(
+            (literal("payee"), space1),
+            till_line_ending,
+            line_ending_or_eof,
+        ),
+        repeat(
+            0..,
+            alt((
+                multiline_text((space1, take_while(1.., is_comment_prefix)))
+                    .map(syntax::PayeeDetail::Comment),
+                delimited(
+                    (space1, literal("alias"), space1),
+                    till_line_ending,
+                    line_ending_or_eof,
+                )
+                .map(|a: &str| syntax::PayeeDetail::Alias(a.trim_end().into())),
+            )),
+        ),
+    )
+        .map(|(name, details): (&str, _)| syntax::PayeeDeclaration {
+            name: name.trim_end().into(),
+            details,
+        })
+        .parse_next(input)
+}
+
+/// Parses "tag" directive.
+pub fn tag_declaration<'i, I, E>(input: &mut I) -> winnow::Result<syntax::TagDeclaration<'i>, E>
+where
+    I: Stream<Slice = &'i str>
+        + StreamIsPartial
+        + winnow::stream::Compare<&'static str>
+        + winnow::stream::FindSlice<(char, char)>,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>,
+{
+    (
+        delimited(
+            (literal("tag"), space1),
+            till_line_ending,
+            line_ending_or_eof,
+        ),
+        repeat(
+            0..,
+            alt((
+                multiline_text((space1, take_while(1.., is_comment_prefix)))
+                    .map(syntax::TagDetail::Comment),
+                delimited(
+                    (space1, literal("check"), space1),
+                    till_line_ending,
+                    line_ending_or_eof,
+                )
+                .map(|a: &str| syntax::TagDetail::Check(a.trim_end().into())),
+                delimited(
+                    (space1, literal("assert"), space1),
+                    till_line_ending,
+                    line_ending_or_eof,
+                )
+                .map(|a: &str| syntax::TagDetail::Assert(a.trim_end().into())),
+            )),
+        ),
+    )
+        .map(|(name, details): (&str, _)| syntax::TagDeclaration {
+            name: name.trim_end().into(),
+            details,
+        })
+        .parse_next(input)
+}
+
+/// Parses the commodity name in "commodity" directive, which can be double-quoted.
+fn commodity_name<'i, I, E>(input: &mut I) -> winnow::Result<&'i str, E>
+where
+    I: Stream<Slice = &'i str>
+        + StreamIsPartial
+        + winnow::stream::Compare<&'static str>
+        + winnow::stream::FindSlice<(char, char)>,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>,
+{
+    trace(
+        "directive::commodity_name",
+        alt((
+            terminated(primitive::quoted_commodity, space0),
+            till_line_ending.map(str::trim_end),
+        )),
+    )
+    .parse_next(input)
+}
+
+/// Parses "apply tag" directive.
+pub fn apply_tag<'i, I, E>(input: &mut I) -> winnow::Result<syntax::ApplyTag<'i>, E>
+where
+    I: Stream<Token = char, Slice = &'i str>
+        + StreamIsPartial
+        + winnow::stream::Compare<&'static str>
+        + winnow::stream::FindSlice<(char, char)>
+        + DefaultYearStream
+        + Clone,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>
+        + FromExternalError<I, pretty_decimal::ParseError>
+        + FromExternalError<I, chrono::ParseError>
+        + FromExternalError<I, primitive::MissingYearError>,
 {
     // TODO: value needs to be supported.
     trace(
@@ -173,6 +286,126 @@ where
     .parse_next(input)
 }
 
+/// Parses "apply account" directive.
+pub fn apply_account<'i, I, E>(input: &mut I) -> winnow::Result<syntax::ApplyAccount<'i>, E>
+where
+    I: Stream<Slice = &'i str>
+        + StreamIsPartial
+        + winnow::stream::Compare<&'static str>
+        + winnow::stream::FindSlice<(char, char)>,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>,
+{
+    trace(
+        "directive::apply_account",
+        delimited(
+            (literal("apply"), space1, literal("account"), space1),
+            till_line_ending,
+            line_ending_or_eof,
+        )
+        .verify_map(|x: &str| {
+            let x = x.trim_end();
+            (!x.is_empty()).then(|| syntax::ApplyAccount(x.into()))
+        }),
+    )
+    .parse_next(input)
+}
+
+/// Parses "end apply account" directive.
+pub fn end_apply_account<I, E>(input: &mut I) -> winnow::Result<<I as Stream>::Slice, E>
+where
+    I: Stream + StreamIsPartial + winnow::stream::Compare<&'static str>,
+    <I as Stream>::Token: AsChar,
+    E: ParserError<I>,
+{
+    trace(
+        "directive::end_apply_account",
+        terminated(
+            (
+                literal("end"),
+                space1,
+                literal("apply"),
+                space1,
+                literal("account"),
+            )
+                .take(),
+            (space0, line_ending_or_eof),
+        ),
+    )
+    .parse_next(input)
+}
+
+/// Parses top-level "alias" directive, in the form of `alias Short=Long:Account`.
+pub fn account_alias<'i, I, E>(input: &mut I) -> winnow::Result<syntax::AccountAlias<'i>, E>
+where
+    I: Stream<Slice = &'i str>
+        + StreamIsPartial
+        + winnow::stream::Compare<&'static str>
+        + winnow::stream::FindSlice<(char, char)>,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>,
+{
+    trace(
+        "directive::account_alias",
+        (
+            delimited(
+                (literal("alias"), space1),
+                take_till(1.., ['=', '\r', '\n']),
+                (literal("="), space0),
+            ),
+            terminated(till_line_ending, line_ending_or_eof),
+        )
+            .verify_map(|(alias, account): (&str, &str)| {
+                let alias = alias.trim_end();
+                let account = account.trim_end();
+                (!alias.is_empty() && !account.is_empty()).then(|| syntax::AccountAlias {
+                    alias: alias.into(),
+                    account: account.into(),
+                })
+            }),
+    )
+    .parse_next(input)
+}
+
+/// Parses "year" or "Y" directive, and returns the year.
+pub fn default_year<'i, I, E>(input: &mut I) -> winnow::Result<i32, E>
+where
+    I: Stream<Slice = &'i str> + StreamIsPartial + winnow::stream::Compare<&'static str>,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>,
+{
+    trace(
+        "directive::default_year",
+        delimited(
+            (alt((literal("year"), literal("Y"))), space1),
+            digit1.parse_to::<i32>(),
+            (space0, line_ending_or_eof),
+        ),
+    )
+    .parse_next(input)
+}
+
+/// Parses "D" directive, such as `D 1,000.00 JPY`.
+pub fn default_commodity<'i, I, E>(input: &mut I) -> winnow::Result<syntax::expr::Amount<'i>, E>
+where
+    I: Stream<Slice = &'i str>
+        + StreamIsPartial
+        + winnow::stream::Compare<&'static str>
+        + winnow::stream::FindSlice<(char, char)>,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I> + FromExternalError<I, pretty_decimal::ParseError>,
+{
+    trace(
+        "directive::default_commodity",
+        delimited(
+            (literal("D"), space1),
+            expr::amount,
+            (space0, line_ending_or_eof),
+        ),
+    )
+    .parse_next(input)
+}
+
 /// Parses include directive.
 /// Note given we'll always have UTF-8 input,
 /// we're not using PathBuf but String for the path.
@@ -197,6 +430,78 @@ where
     .parse_next(input)
 }
 
+/// Parses automated transaction, "=" directive followed by postings.
+pub fn automated_transaction<'i, I, Deco>(
+    input: &mut I,
+) -> ModalResult<syntax::AutomatedTransaction<'i, Deco>>
+where
+    I: Stream<Token = char, Slice = &'i str>
+        + StreamIsPartial
+        + winnow::stream::Compare<&'static str>
+        + winnow::stream::FindSlice<(char, char)>
+        + winnow::stream::Location
+        + DefaultYearStream
+        + Clone,
+    <I as Stream>::Token: AsChar + Clone,
+    Deco: Decoration,
+{
+    trace("directive::automated_transaction", move |input: &mut I| {
+        let predicate = preceded(
+            ("=", space0),
+            cut_err(
+                character::till_line_ending_or_semi
+                    .map(str::trim_end)
+                    .context(StrContext::Label("predicate of automated transaction")),
+            ),
+        )
+        .parse_next(input)?;
+        let metadata = metadata::block_metadata(input)?;
+        let posts = transaction::postings::<_, Deco>(input)?;
+        Ok(syntax::AutomatedTransaction {
+            posts,
+            metadata,
+            ..syntax::AutomatedTransaction::new(predicate)
+        })
+    })
+    .parse_next(input)
+}
+
+/// Parses periodic transaction, "~" directive followed by postings.
+pub fn periodic_transaction<'i, I, Deco>(
+    input: &mut I,
+) -> ModalResult<syntax::PeriodicTransaction<'i, Deco>>
+where
+    I: Stream<Token = char, Slice = &'i str>
+        + StreamIsPartial
+        + winnow::stream::Compare<&'static str>
+        + winnow::stream::Compare<Caseless<&'static str>>
+        + winnow::stream::FindSlice<(char, char)>
+        + winnow::stream::Location
+        + DefaultYearStream
+        + Clone,
+    <I as Stream>::Token: AsChar + Clone,
+    Deco: Decoration,
+{
+    trace("directive::periodic_transaction", move |input: &mut I| {
+        let period = delimited(
+            ("~", space0),
+            cut_err(
+                period::period_expr.context(StrContext::Label("period of periodic transaction")),
+            ),
+            space0,
+        )
+        .parse_next(input)?;
+        let metadata = metadata::block_metadata(input)?;
+        let posts = transaction::postings::<_, Deco>(input)?;
+        Ok(syntax::PeriodicTransaction {
+            posts,
+            metadata,
+            ..syntax::PeriodicTransaction::new(period)
+        })
+    })
+    .parse_next(input)
+}
+
 /// Parses top level comment in the Ledger file format.
 /// Notable difference with block_metadata is, this accepts multiple prefix.
 pub fn top_comment<'i, I, E>(input: &mut I) -> winnow::Result<syntax::TopLevelComment<'i>, E>
@@ -215,6 +520,63 @@ where
     .parse_next(input)
 }
 
+/// Parses "comment" or "test" block, which lasts until the matching "end comment" or "end test".
+/// The enclosed lines are kept as-is.
+pub fn block_comment<'i, I, E>(input: &mut I) -> winnow::Result<syntax::BlockComment<'i>, E>
+where
+    I: Stream<Slice = &'i str>
+        + StreamIsPartial
+        + winnow::stream::Compare<&'static str>
+        + winnow::stream::FindSlice<(char, char)>,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>,
+{
+    trace("directive::block_comment", move |input: &mut I| {
+        let kind = alt((
+            literal("comment").value(syntax::BlockCommentKind::Comment),
+            literal("test").value(syntax::BlockCommentKind::Test),
+        ))
+        .parse_next(input)?;
+        let header = terminated(
+            alt((
+                preceded(space1, till_line_ending).map(str::trim_end),
+                empty.value(""),
+            )),
+            line_ending,
+        )
+        .parse_next(input)?;
+        let body = repeat::<_, _, (), _, _>(
+            0..,
+            (not(end_block_comment(kind)), till_line_ending, line_ending),
+        )
+        .take()
+        .parse_next(input)?;
+        end_block_comment(kind).parse_next(input)?;
+        Ok(syntax::BlockComment {
+            kind,
+            header: header.into(),
+            body: body.into(),
+        })
+    })
+    .parse_next(input)
+}
+
+fn end_block_comment<I, E>(kind: syntax::BlockCommentKind) -> impl Parser<I, (), E>
+where
+    I: Stream + StreamIsPartial + winnow::stream::Compare<&'static str>,
+    <I as Stream>::Token: AsChar,
+    E: ParserError<I>,
+{
+    (
+        literal("end"),
+        space1,
+        literal(kind.keyword()),
+        space0,
+        line_ending_or_eof,
+    )
+        .void()
+}
+
 /// Parses multi-line text with preceding prefix.
 fn multiline_text<'a, I, E, F, O1>(prefix: F) -> impl Parser<I, Cow<'a, str>, E>
 where
@@ -243,6 +605,7 @@ mod tests {
     use super::*;
     use crate::parse::testing::expect_parse_ok;
 
+    use chrono::NaiveDate;
     use indoc::indoc;
     use pretty_assertions::assert_eq;
     use winnow::error::{ErrMode, InputError};
@@ -274,6 +637,54 @@ mod tests {
         );
     }
 
+    #[test]
+    fn payee_declaration_with_details() {
+        let input = indoc! {"
+            payee KFC
+               ; fried chicken
+               alias KENTUCKY FRIED CHICKEN
+               alias Kentucky
+
+            2020"};
+        assert_eq!(
+            expect_parse_ok(payee_declaration, input),
+            (
+                "\n2020",
+                syntax::PayeeDeclaration {
+                    name: "KFC".into(),
+                    details: vec![
+                        syntax::PayeeDetail::Comment(" fried chicken\n".into()),
+                        syntax::PayeeDetail::Alias("KENTUCKY FRIED CHICKEN".into()),
+                        syntax::PayeeDetail::Alias("Kentucky".into()),
+                    ],
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn tag_declaration_with_details() {
+        let input = indoc! {"
+            tag Receipt
+               check value =~ /pattern/
+               assert value != \"foobar\"
+            tag trip
+            "};
+        assert_eq!(
+            expect_parse_ok(tag_declaration, input),
+            (
+                "tag trip\n",
+                syntax::TagDeclaration {
+                    name: "Receipt".into(),
+                    details: vec![
+                        syntax::TagDetail::Check("value =~ /pattern/".into()),
+                        syntax::TagDetail::Assert("value != \"foobar\"".into()),
+                    ],
+                }
+            )
+        );
+    }
+
     #[test]
     fn account_declaration_with_details() {
         let input = indoc! {"
@@ -364,7 +775,9 @@ mod tests {
                 "",
                 syntax::ApplyTag {
                     key: "test@1-2!#[]".into(),
-                    value: Some(syntax::MetadataValue::Expr("[2022-3-4]".into())),
+                    value: Some(syntax::MetadataValue::Date(
+                        NaiveDate::from_ymd_opt(2022, 3, 4).unwrap()
+                    )),
                 }
             )
         );
@@ -403,6 +816,109 @@ mod tests {
         );
     }
 
+    #[test]
+    fn apply_account_parses_account() {
+        assert_eq!(
+            expect_parse_ok(apply_account, "apply  account  Assets:Bank A  \nfoo"),
+            ("foo", syntax::ApplyAccount("Assets:Bank A".into()))
+        );
+    }
+
+    #[test]
+    fn end_apply_account_accepts_valid_inputs() {
+        assert_eq!(
+            expect_parse_ok(end_apply_account, "end  apply account  \nfoo"),
+            ("foo", "end  apply account")
+        );
+
+        let input: &str = "end apply tag";
+        assert_eq!(
+            end_apply_account.parse_peek(input),
+            Err(ErrMode::Backtrack(InputError::at("tag")))
+        );
+    }
+
+    #[test]
+    fn account_alias_parses_alias_and_account() {
+        assert_eq!(
+            expect_parse_ok(account_alias, "alias Cash Box = Assets:Cash Box  \n2020"),
+            (
+                "2020",
+                syntax::AccountAlias {
+                    alias: "Cash Box".into(),
+                    account: "Assets:Cash Box".into(),
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn account_alias_rejects_missing_account() {
+        let input: &str = "alias Cash\n";
+        assert!(account_alias::<_, InputError<_>>.parse_peek(input).is_err());
+
+        let input: &str = "alias Cash=\n";
+        assert!(account_alias::<_, InputError<_>>.parse_peek(input).is_err());
+    }
+
+    #[test]
+    fn default_year_parses_both_forms() {
+        assert_eq!(
+            expect_parse_ok(default_year, "year 2025  \nfoo"),
+            ("foo", 2025)
+        );
+        assert_eq!(expect_parse_ok(default_year, "Y\t2024"), ("", 2024));
+
+        let input: &str = "year twenty\n";
+        assert!(default_year::<_, InputError<_>>.parse_peek(input).is_err());
+    }
+
+    #[test]
+    fn default_commodity_parses_amount() {
+        assert_eq!(
+            expect_parse_ok(default_commodity, "D 1,000.00 JPY  \nfoo"),
+            (
+                "foo",
+                syntax::expr::Amount {
+                    value: pretty_decimal::PrettyDecimal::comma3dot(rust_decimal_macros::dec!(
+                        1000.00
+                    )),
+                    commodity: "JPY".into(),
+                    style: syntax::expr::CommodityStyle::default(),
+                }
+            )
+        );
+
+        let input: &str = "Default JPY\n";
+        assert!(
+            default_commodity::<_, InputError<_>>
+                .parse_peek(input)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn commodity_declaration_parses_default_and_nomarket() {
+        let input = indoc! {"
+            commodity JPY
+                default
+                nomarket\t
+            2024"};
+        assert_eq!(
+            expect_parse_ok(commodity_declaration, input),
+            (
+                "2024",
+                syntax::CommodityDeclaration {
+                    name: "JPY".into(),
+                    details: vec![
+                        syntax::CommodityDetail::Default,
+                        syntax::CommodityDetail::NoMarket,
+                    ],
+                }
+            )
+        );
+    }
+
     #[test]
     fn include_parses_normal_file() {
         assert_eq!(
@@ -427,6 +943,92 @@ mod tests {
         );
     }
 
+    #[test]
+    fn automated_transaction_parses_postings() {
+        let input = indoc! {"
+            = /^Expenses:Food/ ; envelope
+                (Budget:Food)                 -1
+                Expenses:VAT                  0.2 CHF
+            2024"};
+        assert_eq!(
+            expect_parse_ok(automated_transaction, input),
+            (
+                "2024",
+                syntax::plain::AutomatedTransaction {
+                    metadata: vec![syntax::Metadata::Comment("envelope".into())],
+                    posts: vec![
+                        syntax::Posting {
+                            amount: Some(
+                                syntax::expr::ValueExpr::Amount(syntax::expr::Amount {
+                                    value: pretty_decimal::PrettyDecimal::unformatted(
+                                        rust_decimal_macros::dec!(-1)
+                                    ),
+                                    commodity: "".into(),
+                                    style: syntax::expr::CommodityStyle::default(),
+                                })
+                                .into()
+                            ),
+                            kind: syntax::PostingKind::Virtual,
+                            ..syntax::Posting::new_untracked("Budget:Food")
+                        },
+                        syntax::Posting {
+                            amount: Some(
+                                syntax::expr::ValueExpr::Amount(syntax::expr::Amount {
+                                    value: pretty_decimal::PrettyDecimal::unformatted(
+                                        rust_decimal_macros::dec!(0.2)
+                                    ),
+                                    commodity: "CHF".into(),
+                                    style: syntax::expr::CommodityStyle::default(),
+                                })
+                                .into()
+                            ),
+                            ..syntax::Posting::new_untracked("Expenses:VAT")
+                        },
+                    ],
+                    ..syntax::AutomatedTransaction::new("/^Expenses:Food/")
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn periodic_transaction_parses_postings() {
+        let input = indoc! {"
+            ~ Monthly from 2025/01  ; rent
+                Expenses:Rent                 1000 CHF
+                Assets:Bank
+            2025"};
+        assert_eq!(
+            expect_parse_ok(periodic_transaction, input),
+            (
+                "2025",
+                syntax::plain::PeriodicTransaction {
+                    metadata: vec![syntax::Metadata::Comment("rent".into())],
+                    posts: vec![
+                        syntax::Posting {
+                            amount: Some(
+                                syntax::expr::ValueExpr::Amount(syntax::expr::Amount {
+                                    value: pretty_decimal::PrettyDecimal::plain(
+                                        rust_decimal_macros::dec!(1000)
+                                    ),
+                                    commodity: "CHF".into(),
+                                    style: syntax::expr::CommodityStyle::default(),
+                                })
+                                .into()
+                            ),
+                            ..syntax::Posting::new_untracked("Expenses:Rent")
+                        },
+                        syntax::Posting::new_untracked("Assets:Bank"),
+                    ],
+                    ..syntax::PeriodicTransaction::new(syntax::PeriodExpr {
+                        begin: chrono::NaiveDate::from_ymd_opt(2025, 1, 1),
+                        ..syntax::PeriodExpr::new(syntax::Interval::Months(1))
+                    })
+                }
+            )
+        );
+    }
+
     #[test]
     fn top_comment_single_line() {
         assert_eq!(
@@ -450,4 +1052,60 @@ mod tests {
             ("baz", syntax::TopLevelComment("foo\nbar\n".into()))
         );
     }
+
+    #[test]
+    fn block_comment_keeps_lines_as_is() {
+        let input = indoc! {"
+            comment
+            2024/01/01 disabled
+                Expenses:Food    10 CHF
+              ; indented
+            end comment
+            foo"};
+        assert_eq!(
+            expect_parse_ok(block_comment, input),
+            (
+                "foo",
+                syntax::BlockComment {
+                    kind: syntax::BlockCommentKind::Comment,
+                    header: "".into(),
+                    body: "2024/01/01 disabled\n    Expenses:Food    10 CHF\n  ; indented\n".into(),
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn block_comment_parses_test_block() {
+        let input = indoc! {"
+            test reg --flat  
+            24-Jan-01 Grocery     Expenses:Food      10 CHF       10 CHF
+            end  test"};
+        let (rest, got) = expect_parse_ok(block_comment, input);
+        assert_eq!(rest, "");
+        assert_eq!(got.test_command(), Some("reg --flat"));
+        assert_eq!(
+            got.expected_output(),
+            Some("24-Jan-01 Grocery     Expenses:Food      10 CHF       10 CHF\n")
+        );
+
+        let (_, got) = expect_parse_ok(block_comment, "comment\nend comment\n");
+        assert_eq!(got.test_command(), None);
+        assert_eq!(got.expected_output(), None);
+    }
+
+    #[test]
+    fn block_comment_rejects_unterminated_block() {
+        let input: &str = "comment\nfoo\nend test\n";
+        assert_eq!(
+            block_comment.parse_peek(input),
+            Err(ErrMode::Backtrack(InputError::at("")))
+        );
+
+        let input: &str = "commentary\nend comment\n";
+        assert_eq!(
+            block_comment.parse_peek(input),
+            Err(ErrMode::Backtrack(InputError::at("ary\nend comment\n")))
+        );
+    }
 }
@@ -1,9 +1,8 @@
-use std::{
-    fmt::Display,
-    ops::{Deref, Range},
-};
+use std::{fmt::Display, ops::Range, path::Path};
 
 use annotate_snippets::{AnnotationKind, Group, Level, Renderer, Snippet};
+
+use crate::diagnostic::{self, Diagnostic, Severity, SourceText};
 use winnow::{
     LocatingSlice,
     error::{ContextError, StrContext},
@@ -17,6 +16,7 @@ pub struct ParseError(Box<ParseErrorImpl>);
 #[derive(Debug)]
 struct ParseErrorImpl {
     renderer: Renderer,
+    path: Option<String>,
     error_span: Range<usize>,
     input: String,
     line_start: usize,
@@ -40,14 +40,42 @@ impl ParseError {
         let end = (offset + 1..)
             .find(|e| input.is_char_boundary(*e))
             .unwrap_or(offset);
+        // Keeps the input only until the line with the error, as the rest is never rendered.
+        let line_end = input
+            .get(end..)
+            .and_then(|rest| rest.find('\n'))
+            .map_or(input.len(), |pos| end + pos);
         Self(Box::new(ParseErrorImpl {
             renderer,
+            path: None,
             error_span: offset..end,
-            input: input.deref().to_string(),
+            input: input[..line_end].to_string(),
             line_start,
             winnow_error: error,
         }))
     }
+
+    /// Sets the path of the file having the error, shown in the message.
+    pub(crate) fn set_path(&mut self, path: &Path) {
+        self.0.path = Some(path.to_string_lossy().into_owned());
+    }
+
+    /// Converts the error into [`Diagnostic`].
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let err = &self.0;
+        let source = SourceText::new(&err.input, err.line_start);
+        Diagnostic {
+            severity: Severity::Error,
+            message: err.winnow_error.to_string(),
+            path: err.path.clone(),
+            labels: vec![source.label(err.error_span.clone(), String::new(), true)],
+            notes: err
+                .winnow_error
+                .cause()
+                .map(|cause| diagnostic::error_chain(cause))
+                .unwrap_or_default(),
+        }
+    }
 }
 
 impl Display for ParseErrorImpl {
@@ -56,6 +84,7 @@ impl Display for ParseErrorImpl {
         let message = &[
             Group::with_title(Level::ERROR.primary_title(&message)).element(
                 Snippet::source(&self.input)
+                    .path(self.path.as_deref())
                     .line_start(self.line_start)
                     .fold(true)
                     .annotation(AnnotationKind::Primary.span(self.error_span.clone())),
@@ -5,13 +5,14 @@ use winnow::{
     ascii::space0,
     combinator::{
         alt, delimited, dispatch, opt, peek, preceded, separated_foldl1, terminated, trace,
-        unordered_seq,
     },
     error::{FromExternalError, ParserError},
     stream::{AsChar, Stream, StreamIsPartial},
     token::{any, one_of},
 };
 
+use pretty_decimal::PrettyDecimal;
+
 use crate::syntax::expr;
 
 use super::{adaptor::ParseOptions, character::paren, error, primitive};
@@ -33,6 +34,24 @@ where
     .parse_next(input)
 }
 
+/// Parses expression without the surrounding parens, such as the `::` metadata value.
+/// Returns the expression wrapped with parens, unless it's a single value.
+pub fn bare_expr<'i, I, E>(input: &mut I) -> winnow::Result<expr::ValueExpr<'i>, E>
+where
+    I: Stream<Token = char, Slice = &'i str> + StreamIsPartial + Clone,
+    E: ParserError<I> + FromExternalError<I, pretty_decimal::ParseError>,
+    <I as Stream>::Token: AsChar + Clone,
+{
+    trace(
+        "expr::bare_expr",
+        add_expr.map(|e| match e {
+            expr::Expr::Value(v) => *v,
+            e => expr::ValueExpr::Paren(e),
+        }),
+    )
+    .parse_next(input)
+}
+
 impl<'i> TryFrom<&'i str> for expr::ValueExpr<'i> {
     type Error = error::ParseError;
 
@@ -130,27 +149,18 @@ where
     E: ParserError<I> + FromExternalError<I, pretty_decimal::ParseError>,
     <I as Stream>::Token: AsChar + Clone,
 {
-    // This supports prefix commodity.
     trace(
         "expr::unary_amount",
-        (
-            opt(one_of('-')),
-            unordered_seq!((
-                terminated(primitive::pretty_decimal, space0),
-                terminated(primitive::commodity, space0),
-            )),
-        )
-            .map(|(negate, (mut value, c)): (_, (_, &str))| {
+        (opt(one_of('-')), terminated(amount, space0)).map(
+            |(negate, mut amount): (_, expr::Amount)| {
                 if negate.is_some() {
-                    value
+                    amount
                         .value
-                        .set_sign_positive(!value.value.is_sign_positive());
+                        .set_sign_positive(!amount.value.is_sign_positive());
                 }
-                expr::Amount {
-                    value,
-                    commodity: c.into(),
-                }
-            }),
+                amount
+            },
+        ),
     )
     .parse_next(input)
 }
@@ -190,18 +200,44 @@ where
     E: ParserError<I> + FromExternalError<I, pretty_decimal::ParseError>,
     <I as Stream>::Token: AsChar + Clone,
 {
-    // Currently it only supports suffix commodity,
-    // and there is no plan to support prefix commodities.
     trace(
         "expr::amount",
-        (
-            terminated(primitive::pretty_decimal, space0),
-            primitive::commodity,
-        )
-            .map(|(value, c): (_, &str)| expr::Amount {
-                value,
-                commodity: c.into(),
-            }),
+        alt((
+            // Prefix commodity, optionally preceded with the sign as `-$100`.
+            (
+                opt(one_of('-')),
+                primitive::commodity.verify(|c: &str| !c.is_empty()),
+                space0,
+                primitive::pretty_decimal,
+            )
+                .map(
+                    |(negate, c, space, mut value): (_, &str, &str, PrettyDecimal)| {
+                        if negate.is_some() {
+                            value
+                                .value
+                                .set_sign_positive(!value.value.is_sign_positive());
+                        }
+                        expr::Amount {
+                            value,
+                            commodity: c.into(),
+                            style: expr::CommodityStyle {
+                                position: expr::CommodityPosition::Prefix,
+                                spaced: !space.is_empty(),
+                            },
+                        }
+                    },
+                ),
+            (primitive::pretty_decimal, space0, primitive::commodity).map(
+                |(value, space, c): (_, &str, &str)| expr::Amount {
+                    value,
+                    commodity: c.into(),
+                    style: expr::CommodityStyle {
+                        position: expr::CommodityPosition::Suffix,
+                        spaced: c.is_empty() || !space.is_empty(),
+                    },
+                },
+            ),
+        )),
     )
     .parse_next(input)
 }
@@ -253,7 +289,8 @@ mod tests {
                 "",
                 expr::ValueExpr::Amount(expr::Amount {
                     value: PrettyDecimal::plain(dec!(1000)),
-                    commodity: "JPY".into()
+                    commodity: "JPY".into(),
+                    style: expr::CommodityStyle::default(),
                 }),
             )
         );
@@ -264,17 +301,74 @@ mod tests {
                 "",
                 expr::ValueExpr::Amount(expr::Amount {
                     value: PrettyDecimal::comma3dot(dec!(1234567.89)),
-                    commodity: "USD".into()
+                    commodity: "USD".into(),
+                    style: expr::CommodityStyle::default(),
                 })
             )
         );
     }
 
+    #[test]
+    fn value_expr_prefix_commodity() {
+        let prefix = |spaced| expr::CommodityStyle {
+            position: expr::CommodityPosition::Prefix,
+            spaced,
+        };
+        assert_eq!(
+            expect_parse_ok(value_expr, "$100"),
+            (
+                "",
+                expr::ValueExpr::Amount(expr::Amount {
+                    value: PrettyDecimal::unformatted(dec!(100)),
+                    commodity: "$".into(),
+                    style: prefix(false),
+                }),
+            )
+        );
+        assert_eq!(
+            expect_parse_ok(value_expr, "€ 12.50"),
+            (
+                "",
+                expr::ValueExpr::Amount(expr::Amount {
+                    value: PrettyDecimal::unformatted(dec!(12.50)),
+                    commodity: "€".into(),
+                    style: prefix(true),
+                }),
+            )
+        );
+        assert_eq!(
+            expect_parse_ok(value_expr, "-$1,000"),
+            (
+                "",
+                expr::ValueExpr::Amount(expr::Amount {
+                    value: PrettyDecimal::comma3dot(dec!(-1000)),
+                    commodity: "$".into(),
+                    style: prefix(false),
+                }),
+            )
+        );
+        assert_eq!(
+            expect_parse_ok(value_expr, "12.5EUR"),
+            (
+                "",
+                expr::ValueExpr::Amount(expr::Amount {
+                    value: PrettyDecimal::unformatted(dec!(12.5)),
+                    commodity: "EUR".into(),
+                    style: expr::CommodityStyle {
+                        position: expr::CommodityPosition::Suffix,
+                        spaced: false,
+                    },
+                }),
+            )
+        );
+    }
+
     fn amount_expr<T: Into<Decimal>>(value: T, commodity: &'static str) -> expr::Expr<'static> {
         let v: Decimal = value.into();
         expr::Expr::Value(Box::new(expr::ValueExpr::Amount(expr::Amount {
             commodity: commodity.into(),
             value: PrettyDecimal::unformatted(v),
+            style: expr::CommodityStyle::default(),
         })))
     }
 
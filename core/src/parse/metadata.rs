@@ -8,12 +8,12 @@ use winnow::{
     combinator::{
         alt, delimited, dispatch, opt, peek, preceded, repeat, separated, terminated, trace,
     },
-    error::ParserError,
+    error::{FromExternalError, ParserError},
     stream::{AsChar, Stream, StreamIsPartial},
     token::{any, literal, one_of, take_till},
 };
 
-use crate::parse::character;
+use crate::parse::{adaptor::DefaultYearStream, character, expr, primitive};
 use crate::syntax;
 
 /// Parses a ClearState.
@@ -44,9 +44,14 @@ where
     I: Stream<Token = char, Slice = &'i str>
         + StreamIsPartial
         + winnow::stream::Compare<&'static str>
-        + winnow::stream::FindSlice<(char, char)>,
-    E: ParserError<I>,
+        + winnow::stream::FindSlice<(char, char)>
+        + DefaultYearStream
+        + Clone,
     <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>
+        + FromExternalError<I, pretty_decimal::ParseError>
+        + FromExternalError<I, chrono::ParseError>
+        + FromExternalError<I, primitive::MissingYearError>,
 {
     // For now, we can't go with regular repeat because it's hard to have a initial value in Accumulate.
     trace(
@@ -61,12 +66,17 @@ where
 
 fn line_metadata<'i, I, E>(input: &mut I) -> winnow::Result<syntax::Metadata<'i>, E>
 where
-    I: Stream<Slice = &'i str>
+    I: Stream<Token = char, Slice = &'i str>
         + StreamIsPartial
+        + winnow::stream::Compare<&'static str>
         + winnow::stream::FindSlice<(char, char)>
-        + winnow::stream::Compare<&'static str>,
-    E: ParserError<I>,
+        + DefaultYearStream
+        + Clone,
     <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>
+        + FromExternalError<I, pretty_decimal::ParseError>
+        + FromExternalError<I, chrono::ParseError>
+        + FromExternalError<I, primitive::MissingYearError>,
 {
     trace(
         "metadata::line_metadata",
@@ -74,6 +84,7 @@ where
             (one_of(';'), space0),
             alt((
                 metadata_tags,
+                metadata_dates,
                 metadata_kv,
                 till_line_ending.map(|s: &str| {
                     if s.contains(':') {
@@ -106,14 +117,54 @@ where
     .parse_next(input)
 }
 
-fn metadata_kv<'i, I, E>(input: &mut I) -> winnow::Result<syntax::Metadata<'i>, E>
+/// Parses dates in brackets, such as `[2025/01/04=2025/01/05]` or `[=2025/01/05]`.
+/// Falls back to the comment unless the dates fill the whole line.
+fn metadata_dates<'i, I, E>(input: &mut I) -> winnow::Result<syntax::Metadata<'i>, E>
 where
     I: Stream<Slice = &'i str>
         + StreamIsPartial
         + winnow::stream::Compare<&'static str>
-        + winnow::stream::FindSlice<(char, char)>,
-    E: ParserError<I>,
+        + DefaultYearStream,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>
+        + FromExternalError<I, chrono::ParseError>
+        + FromExternalError<I, primitive::MissingYearError>,
+{
+    trace(
+        "metadata::metadata_dates",
+        terminated(
+            delimited(
+                one_of('['),
+                (
+                    opt(primitive::date),
+                    opt(preceded(one_of('='), primitive::date)),
+                ),
+                one_of(']'),
+            )
+            .verify(|(date, effective_date)| date.is_some() || effective_date.is_some()),
+            (space0, peek(character::line_ending_or_eof)),
+        )
+        .map(|(date, effective_date)| syntax::Metadata::Dates {
+            date,
+            effective_date,
+        }),
+    )
+    .parse_next(input)
+}
+
+fn metadata_kv<'i, I, E>(input: &mut I) -> winnow::Result<syntax::Metadata<'i>, E>
+where
+    I: Stream<Token = char, Slice = &'i str>
+        + StreamIsPartial
+        + winnow::stream::Compare<&'static str>
+        + winnow::stream::FindSlice<(char, char)>
+        + DefaultYearStream
+        + Clone,
     <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>
+        + FromExternalError<I, pretty_decimal::ParseError>
+        + FromExternalError<I, chrono::ParseError>
+        + FromExternalError<I, primitive::MissingYearError>,
 {
     trace(
         "metadata::metadata_kv",
@@ -128,20 +179,43 @@ where
 }
 
 /// Parses metadata value with `:` or `::` prefix.
+/// Value prefixed with `::` is parsed as a date in brackets, or an expression.
 pub fn metadata_value<'i, I, E>(input: &mut I) -> winnow::Result<syntax::MetadataValue<'i>, E>
 where
-    I: Stream<Slice = &'i str>
+    I: Stream<Token = char, Slice = &'i str>
         + StreamIsPartial
         + winnow::stream::Compare<&'static str>
-        + winnow::stream::FindSlice<(char, char)>,
+        + winnow::stream::FindSlice<(char, char)>
+        + DefaultYearStream
+        + Clone,
     <I as Stream>::Token: AsChar + Clone,
-    E: ParserError<I>,
+    E: ParserError<I>
+        + FromExternalError<I, pretty_decimal::ParseError>
+        + FromExternalError<I, chrono::ParseError>
+        + FromExternalError<I, primitive::MissingYearError>,
 {
-    let expr = preceded(literal("::"), till_line_ending)
-        .map(|x: &str| syntax::MetadataValue::Expr(x.trim().into()));
-    let text = preceded(one_of(':'), till_line_ending)
-        .map(|x: &str| syntax::MetadataValue::Text(x.trim().into()));
-    trace("metadata::metadata_value", alt((expr, text))).parse_next(input)
+    let mut typed = delimited(
+        space0,
+        alt((
+            delimited(
+                (one_of('['), space0),
+                primitive::date,
+                (space0, one_of(']')),
+            )
+            .map(syntax::MetadataValue::Date),
+            expr::bare_expr.map(syntax::MetadataValue::Expr),
+        )),
+        (space0, peek(character::line_ending_or_eof)),
+    );
+    let mut text = till_line_ending.map(|x: &str| syntax::MetadataValue::Text(x.trim().into()));
+    trace(
+        "metadata::metadata_value",
+        dispatch! {alt((literal("::").value(true), literal(":").value(false)));
+            true => typed,
+            false => text,
+        },
+    )
+    .parse_next(input)
 }
 
 /// Parses metadata tag.
@@ -166,7 +240,10 @@ mod tests {
     use super::*;
     use crate::parse::testing::expect_parse_ok;
 
+    use chrono::NaiveDate;
     use pretty_assertions::assert_eq;
+    use pretty_decimal::PrettyDecimal;
+    use rust_decimal_macros::dec;
 
     #[test]
     fn block_metadata_empty() {
@@ -244,10 +321,118 @@ mod tests {
                 "",
                 syntax::Metadata::KeyValueTag {
                     key: "日付".into(),
-                    value: syntax::MetadataValue::Expr("[2022-01-19]".into()),
+                    value: syntax::MetadataValue::Date(
+                        NaiveDate::from_ymd_opt(2022, 1, 19).unwrap()
+                    ),
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn parse_line_metadata_valid_expr() {
+        let input: &str = "; Shares:: (10 AAPL * 2)  \n";
+        assert_eq!(
+            expect_parse_ok(line_metadata, input),
+            (
+                "",
+                syntax::Metadata::KeyValueTag {
+                    key: "Shares".into(),
+                    value: syntax::MetadataValue::Expr(syntax::expr::ValueExpr::Paren(
+                        syntax::expr::Expr::Binary(syntax::expr::BinaryOpExpr {
+                            lhs: Box::new(syntax::expr::Expr::Value(Box::new(
+                                syntax::expr::Amount {
+                                    value: PrettyDecimal::unformatted(dec!(10)),
+                                    commodity: "AAPL".into(),
+                                    style: syntax::expr::CommodityStyle::default(),
+                                }
+                                .into()
+                            ))),
+                            op: syntax::expr::BinaryOp::Mul,
+                            rhs: Box::new(syntax::expr::Expr::Value(Box::new(
+                                syntax::expr::Amount {
+                                    value: PrettyDecimal::unformatted(dec!(2)),
+                                    commodity: "".into(),
+                                    style: syntax::expr::CommodityStyle::default(),
+                                }
+                                .into()
+                            ))),
+                        })
+                    )),
+                }
+            )
+        );
+
+        let input: &str = "; total:: 1 + 2 \n";
+        assert_eq!(
+            expect_parse_ok(line_metadata, input),
+            (
+                "",
+                syntax::Metadata::KeyValueTag {
+                    key: "total".into(),
+                    value: syntax::MetadataValue::Expr(syntax::expr::ValueExpr::Paren(
+                        syntax::expr::Expr::Binary(syntax::expr::BinaryOpExpr {
+                            lhs: Box::new(syntax::expr::Expr::Value(Box::new(
+                                syntax::expr::Amount {
+                                    value: PrettyDecimal::unformatted(dec!(1)),
+                                    commodity: "".into(),
+                                    style: syntax::expr::CommodityStyle::default(),
+                                }
+                                .into()
+                            ))),
+                            op: syntax::expr::BinaryOp::Add,
+                            rhs: Box::new(syntax::expr::Expr::Value(Box::new(
+                                syntax::expr::Amount {
+                                    value: PrettyDecimal::unformatted(dec!(2)),
+                                    commodity: "".into(),
+                                    style: syntax::expr::CommodityStyle::default(),
+                                }
+                                .into()
+                            ))),
+                        })
+                    )),
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn parse_line_metadata_valid_dates() {
+        let date = |y, m, d| chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(
+            expect_parse_ok(line_metadata, "; [2025/01/04=2025/01/05]\n"),
+            (
+                "",
+                syntax::Metadata::Dates {
+                    date: Some(date(2025, 1, 4)),
+                    effective_date: Some(date(2025, 1, 5)),
                 }
             )
         );
+        assert_eq!(
+            expect_parse_ok(line_metadata, "; [=2025/01/05]  \n"),
+            (
+                "",
+                syntax::Metadata::Dates {
+                    date: None,
+                    effective_date: Some(date(2025, 1, 5)),
+                }
+            )
+        );
+        assert_eq!(
+            expect_parse_ok(line_metadata, "; [2025/01/04]\n"),
+            (
+                "",
+                syntax::Metadata::Dates {
+                    date: Some(date(2025, 1, 4)),
+                    effective_date: None,
+                }
+            )
+        );
+        assert_eq!(
+            expect_parse_ok(line_metadata, "; [2025/01/04] paid\n"),
+            ("", syntax::Metadata::Comment("[2025/01/04] paid".into()))
+        );
     }
 
     #[test]
@@ -0,0 +1,270 @@
+//! Defines parser for [`syntax::PeriodExpr`].
+
+use chrono::NaiveDate;
+use winnow::{
+    Parser,
+    ascii::{Caseless, digit1, space1},
+    combinator::{alt, opt, preceded, terminated, trace},
+    error::ParserError,
+    stream::{AsChar, Stream, StreamIsPartial},
+    token::{literal, one_of},
+};
+
+use crate::syntax;
+
+/// Parses period expression, such as `Monthly from 2025/01`.
+pub fn period_expr<'i, I, E>(input: &mut I) -> winnow::Result<syntax::PeriodExpr, E>
+where
+    I: Stream<Slice = &'i str> + StreamIsPartial + winnow::stream::Compare<Caseless<&'static str>>,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>,
+{
+    trace(
+        "period::period_expr",
+        (interval, opt(preceded(space1, date_range))).map(|(interval, range)| {
+            let (begin, end) = range.unwrap_or_default();
+            syntax::PeriodExpr {
+                interval,
+                begin,
+                end,
+            }
+        }),
+    )
+    .parse_next(input)
+}
+
+fn interval<'i, I, E>(input: &mut I) -> winnow::Result<syntax::Interval, E>
+where
+    I: Stream<Slice = &'i str> + StreamIsPartial + winnow::stream::Compare<Caseless<&'static str>>,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>,
+{
+    trace(
+        "period::interval",
+        alt((
+            literal(Caseless("daily")).value(syntax::Interval::Days(1)),
+            literal(Caseless("weekly")).value(syntax::Interval::Weeks(1)),
+            literal(Caseless("biweekly")).value(syntax::Interval::Weeks(2)),
+            literal(Caseless("monthly")).value(syntax::Interval::Months(1)),
+            literal(Caseless("bimonthly")).value(syntax::Interval::Months(2)),
+            literal(Caseless("quarterly")).value(syntax::Interval::Months(3)),
+            literal(Caseless("yearly")).value(syntax::Interval::Years(1)),
+            literal(Caseless("annually")).value(syntax::Interval::Years(1)),
+            preceded(
+                (literal(Caseless("every")), space1),
+                (
+                    opt(terminated(digit1.parse_to::<u32>(), space1)),
+                    interval_unit,
+                ),
+            )
+            .verify_map(
+                |(count, unit): (Option<u32>, fn(u32) -> syntax::Interval)| {
+                    let count = count.unwrap_or(1);
+                    (count > 0).then(|| unit(count))
+                },
+            ),
+        )),
+    )
+    .parse_next(input)
+}
+
+/// Parses the unit of `every` interval, and returns the constructor of the [`syntax::Interval`].
+fn interval_unit<'i, I, E>(input: &mut I) -> winnow::Result<fn(u32) -> syntax::Interval, E>
+where
+    I: Stream<Slice = &'i str> + StreamIsPartial + winnow::stream::Compare<Caseless<&'static str>>,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>,
+{
+    fn quarters(n: u32) -> syntax::Interval {
+        syntax::Interval::Months(n.saturating_mul(3))
+    }
+    terminated(
+        alt((
+            literal(Caseless("day")).value(syntax::Interval::Days as fn(u32) -> _),
+            literal(Caseless("week")).value(syntax::Interval::Weeks as fn(u32) -> _),
+            literal(Caseless("month")).value(syntax::Interval::Months as fn(u32) -> _),
+            literal(Caseless("quarter")).value(quarters as fn(u32) -> _),
+            literal(Caseless("year")).value(syntax::Interval::Years as fn(u32) -> _),
+        )),
+        opt(literal(Caseless("s"))),
+    )
+    .parse_next(input)
+}
+
+/// Parses the range part of the period expression, and returns the pair of begin and end.
+fn date_range<'i, I, E>(input: &mut I) -> winnow::Result<(Option<NaiveDate>, Option<NaiveDate>), E>
+where
+    I: Stream<Slice = &'i str> + StreamIsPartial + winnow::stream::Compare<Caseless<&'static str>>,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>,
+{
+    let until = || {
+        (
+            alt((literal(Caseless("until")), literal(Caseless("to")))),
+            space1,
+        )
+    };
+    trace(
+        "period::date_range",
+        alt((
+            preceded((literal(Caseless("in")), space1), partial_date).verify_map(
+                |(begin, precision)| Some((Some(begin), Some(precision.next_date(begin)?))),
+            ),
+            (
+                preceded((literal(Caseless("from")), space1), partial_date),
+                opt(preceded((space1, until()), partial_date)),
+            )
+                .map(|((begin, _), end)| (Some(begin), end.map(|(end, _)| end))),
+            preceded(until(), partial_date).map(|(end, _)| (None, Some(end))),
+        )),
+    )
+    .parse_next(input)
+}
+
+/// Parses date which may omit day or month, such as `2025/01` or `2025`.
+/// Returns the first day of the given date, with the interval representing its precision.
+fn partial_date<'i, I, E>(input: &mut I) -> winnow::Result<(NaiveDate, syntax::Interval), E>
+where
+    I: Stream<Slice = &'i str> + StreamIsPartial,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>,
+{
+    let sep = || one_of(['/', '-']);
+    trace(
+        "period::partial_date",
+        (
+            digit1.parse_to::<i32>(),
+            opt(preceded(
+                sep(),
+                (
+                    digit1.parse_to::<u32>(),
+                    opt(preceded(sep(), digit1.parse_to::<u32>())),
+                ),
+            )),
+        )
+            .verify_map(|(year, rest)| match rest {
+                None => Some((
+                    NaiveDate::from_ymd_opt(year, 1, 1)?,
+                    syntax::Interval::Years(1),
+                )),
+                Some((month, None)) => Some((
+                    NaiveDate::from_ymd_opt(year, month, 1)?,
+                    syntax::Interval::Months(1),
+                )),
+                Some((month, Some(day))) => Some((
+                    NaiveDate::from_ymd_opt(year, month, day)?,
+                    syntax::Interval::Days(1),
+                )),
+            }),
+    )
+    .parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use winnow::error::ContextError;
+
+    use crate::parse::testing::expect_parse_ok;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn period_expr_parses_interval_keywords() {
+        assert_eq!(
+            expect_parse_ok(period_expr, "Monthly"),
+            ("", syntax::PeriodExpr::new(syntax::Interval::Months(1)))
+        );
+        assert_eq!(
+            expect_parse_ok(period_expr, "weekly ; comment"),
+            (
+                " ; comment",
+                syntax::PeriodExpr::new(syntax::Interval::Weeks(1))
+            )
+        );
+        assert_eq!(
+            expect_parse_ok(period_expr, "QUARTERLY"),
+            ("", syntax::PeriodExpr::new(syntax::Interval::Months(3)))
+        );
+    }
+
+    #[test]
+    fn period_expr_parses_every() {
+        assert_eq!(
+            expect_parse_ok(period_expr, "every 2 weeks"),
+            ("", syntax::PeriodExpr::new(syntax::Interval::Weeks(2)))
+        );
+        assert_eq!(
+            expect_parse_ok(period_expr, "Every year"),
+            ("", syntax::PeriodExpr::new(syntax::Interval::Years(1)))
+        );
+        assert_eq!(
+            expect_parse_ok(period_expr, "every 2 quarters"),
+            ("", syntax::PeriodExpr::new(syntax::Interval::Months(6)))
+        );
+    }
+
+    #[test]
+    fn period_expr_parses_range() {
+        assert_eq!(
+            expect_parse_ok(period_expr, "Monthly from 2025/01"),
+            (
+                "",
+                syntax::PeriodExpr {
+                    begin: Some(date(2025, 1, 1)),
+                    ..syntax::PeriodExpr::new(syntax::Interval::Months(1))
+                }
+            )
+        );
+        assert_eq!(
+            expect_parse_ok(period_expr, "Monthly from 2025/01/15 to 2025-07"),
+            (
+                "",
+                syntax::PeriodExpr {
+                    begin: Some(date(2025, 1, 15)),
+                    end: Some(date(2025, 7, 1)),
+                    ..syntax::PeriodExpr::new(syntax::Interval::Months(1))
+                }
+            )
+        );
+        assert_eq!(
+            expect_parse_ok(period_expr, "Yearly until 2030"),
+            (
+                "",
+                syntax::PeriodExpr {
+                    end: Some(date(2030, 1, 1)),
+                    ..syntax::PeriodExpr::new(syntax::Interval::Years(1))
+                }
+            )
+        );
+        assert_eq!(
+            expect_parse_ok(period_expr, "Weekly in 2025/02"),
+            (
+                "",
+                syntax::PeriodExpr {
+                    begin: Some(date(2025, 2, 1)),
+                    end: Some(date(2025, 3, 1)),
+                    ..syntax::PeriodExpr::new(syntax::Interval::Weeks(1))
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn period_expr_rejects_invalid() {
+        assert!(
+            period_expr::<_, ContextError>
+                .parse_peek("Sometimes")
+                .is_err()
+        );
+        assert!(
+            period_expr::<_, ContextError>
+                .parse_peek("every 0 days")
+                .is_err()
+        );
+    }
+}
@@ -6,7 +6,7 @@ use winnow::{
     Parser,
     ascii::space0,
     combinator::{
-        alt, cond, delimited, eof, fail, opt, peek, preceded, repeat_till, terminated, trace,
+        alt, cond, delimited, eof, fail, not, opt, peek, preceded, repeat_till, terminated, trace,
     },
     error::{AddContext, FromExternalError, ParserError, StrContext},
     stream::{AsChar, Stream, StreamIsPartial},
@@ -15,6 +15,7 @@ use winnow::{
 
 use crate::{
     parse::{
+        adaptor::DefaultYearStream,
         character::{line_ending_or_semi, paren},
         combinator::{cond_else, has_peek},
         expr, metadata, primitive,
@@ -33,6 +34,7 @@ where
     Input: Stream<Token = char, Slice = &'i str>
         + StreamIsPartial
         + winnow::stream::Location
+        + DefaultYearStream
         + winnow::stream::Compare<&'static str>
         + winnow::stream::FindSlice<(char, char)>
         + Clone,
@@ -40,17 +42,19 @@ where
     E: ParserError<Input>
         + AddContext<Input, StrContext>
         + FromExternalError<Input, pretty_decimal::ParseError>
-        + FromExternalError<Input, chrono::ParseError>,
+        + FromExternalError<Input, chrono::ParseError>
+        + FromExternalError<Input, primitive::MissingYearError>,
 {
     trace("posting::posting", move |input: &mut Input| {
         let clear_state = preceded(space0, metadata::clear_state).parse_next(input)?;
-        let account = posting_account::<Deco, _, _>
+        let (kind, account) = posting_account::<Deco, _, _>
             .context(StrContext::Label("account of the posting"))
             .parse_next(input)?;
         let shortcut_amount = has_peek(line_ending_or_semi).parse_next(input)?;
         if shortcut_amount {
             let metadata = metadata::block_metadata.parse_next(input)?;
             return Ok(syntax::Posting {
+                kind,
                 clear_state,
                 metadata,
                 ..syntax::Posting::new(account)
@@ -69,6 +73,7 @@ where
             .context(StrContext::Label("metadata section of the posting"))
             .parse_next(input)?;
         Ok(syntax::Posting {
+            kind,
             clear_state,
             amount,
             balance,
@@ -80,10 +85,10 @@ where
     .parse_next(input)
 }
 
-/// Parses the posting account name.
+/// Parses the posting account name, possibly enclosed as virtual account.
 fn posting_account<'i, Deco, Input, E>(
     input: &mut Input,
-) -> winnow::Result<Deco::Decorated<Cow<'i, str>>, E>
+) -> winnow::Result<(syntax::PostingKind, Deco::Decorated<Cow<'i, str>>), E>
 where
     Deco: Decoration,
     Input: Stream<Slice = &'i str>
@@ -94,32 +99,81 @@ where
     E: ParserError<Input>,
 {
     trace(
-        "posting::posting",
+        "posting::posting_account",
         terminated(
-            Deco::decorate_parser(
-                repeat_till(
-                    1..,
-                    // at most one space, followed by account capable chars.
-                    (opt(" "), take_till(1.., b"\n\r; \t")),
-                    // stop when you can see double space, or terminate char.
-                    // you need opt(" ") for the case like ` \t`.
-                    peek(alt((
-                        "  ",
-                        (opt(" "), one_of(('\t', ';', '\r', '\n'))).take(),
-                        eof,
-                    ))),
-                )
-                // let repeat_till accumulated into () (unit)
-                .map(|((), _)| ())
-                .take()
-                .map(|x: &str| Cow::Borrowed(x.trim_start())),
-            ),
+            alt((
+                virtual_account::<Deco, _, _>('(', ')')
+                    .map(|account| (syntax::PostingKind::Virtual, account)),
+                virtual_account::<Deco, _, _>('[', ']')
+                    .map(|account| (syntax::PostingKind::BalancedVirtual, account)),
+                // Unclosed bracket must not fall back to the real account.
+                preceded(not(one_of(['(', '['])), real_account::<Deco, _, _>)
+                    .map(|account| (syntax::PostingKind::Real, account)),
+            )),
             space0,
         ),
     )
     .parse_next(input)
 }
 
+/// Parses the account name enclosed with the given brackets, such as `(Budget:Food)`.
+/// The brackets are not included in the account name.
+fn virtual_account<'i, Deco, Input, E>(
+    open: char,
+    close: char,
+) -> impl Parser<Input, Deco::Decorated<Cow<'i, str>>, E>
+where
+    Deco: Decoration,
+    Input: Stream<Slice = &'i str> + StreamIsPartial + winnow::stream::Location,
+    <Input as Stream>::Token: AsChar + Clone,
+    E: ParserError<Input>,
+{
+    delimited(
+        one_of(open),
+        Deco::decorate_parser(
+            take_till(1.., (close, '\r', '\n')).map(|x: &str| Cow::Borrowed(x.trim())),
+        ),
+        one_of(close),
+    )
+}
+
+/// Parses the regular account name.
+pub(super) fn real_account<'i, Deco, Input, E>(
+    input: &mut Input,
+) -> winnow::Result<Deco::Decorated<Cow<'i, str>>, E>
+where
+    Deco: Decoration,
+    Input: Stream<Slice = &'i str>
+        + StreamIsPartial
+        + winnow::stream::Location
+        + winnow::stream::Compare<&'static str>,
+    <Input as Stream>::Token: AsChar + Clone,
+    E: ParserError<Input>,
+{
+    trace(
+        "posting::real_account",
+        Deco::decorate_parser(
+            repeat_till(
+                1..,
+                // at most one space, followed by account capable chars.
+                (opt(" "), take_till(1.., b"\n\r; \t")),
+                // stop when you can see double space, or terminate char.
+                // you need opt(" ") for the case like ` \t`.
+                peek(alt((
+                    "  ",
+                    (opt(" "), one_of(('\t', ';', '\r', '\n'))).take(),
+                    eof,
+                ))),
+            )
+            // let repeat_till accumulated into () (unit)
+            .map(|((), _)| ())
+            .take()
+            .map(|x: &str| Cow::Borrowed(x.trim_start())),
+        ),
+    )
+    .parse_next(input)
+}
+
 fn posting_amount<'i, Deco, Input, E>(
     input: &mut Input,
 ) -> winnow::Result<syntax::PostingAmount<'i, Deco>, E>
@@ -128,13 +182,15 @@ where
     Input: Stream<Token = char, Slice = &'i str>
         + StreamIsPartial
         + winnow::stream::Location
+        + DefaultYearStream
         + winnow::stream::Compare<&'static str>
         + std::clone::Clone,
     <Input as Stream>::Token: AsChar + Clone,
     E: ParserError<Input>
         + AddContext<Input, StrContext>
         + FromExternalError<Input, pretty_decimal::ParseError>
-        + FromExternalError<Input, chrono::ParseError>,
+        + FromExternalError<Input, chrono::ParseError>
+        + FromExternalError<Input, primitive::MissingYearError>,
 {
     let amount = terminated(Deco::decorate_parser(expr::value_expr), space0).parse_next(input)?;
     let lot = lot(input)?;
@@ -157,13 +213,15 @@ where
     Input: Stream<Token = char, Slice = &'i str>
         + StreamIsPartial
         + winnow::stream::Location
+        + DefaultYearStream
         + winnow::stream::Compare<&'static str>
         + Clone,
     <Input as Stream>::Token: AsChar + Clone,
     E: ParserError<Input>
         + AddContext<Input, StrContext>
         + FromExternalError<Input, pretty_decimal::ParseError>
-        + FromExternalError<Input, chrono::ParseError>,
+        + FromExternalError<Input, chrono::ParseError>
+        + FromExternalError<Input, primitive::MissingYearError>,
 {
     space0.void().parse_next(input)?;
     let mut lot = syntax::Lot::default();
@@ -293,12 +351,14 @@ mod tests {
                 syntax::plain::PostingAmount {
                     amount: syntax::expr::ValueExpr::Amount(syntax::expr::Amount {
                         value: PrettyDecimal::unformatted(dec!(100)),
-                        commodity: "EUR".into()
+                        commodity: "EUR".into(),
+                        style: syntax::expr::CommodityStyle::default(),
                     }),
                     cost: Some(syntax::Exchange::Rate(syntax::expr::ValueExpr::Amount(
                         syntax::expr::Amount {
                             value: PrettyDecimal::unformatted(dec!(1.2)),
                             commodity: "CHF".into(),
+                            style: syntax::expr::CommodityStyle::default(),
                         }
                     ))),
                     lot: syntax::Lot::default(),
@@ -312,12 +372,14 @@ mod tests {
                 PostingAmount {
                     amount: syntax::expr::ValueExpr::Amount(syntax::expr::Amount {
                         value: PrettyDecimal::plain(dec!(1000)),
-                        commodity: "EUR".into()
+                        commodity: "EUR".into(),
+                        style: syntax::expr::CommodityStyle::default(),
                     }),
                     cost: Some(syntax::Exchange::Total(syntax::expr::ValueExpr::Amount(
                         syntax::expr::Amount {
                             value: PrettyDecimal::comma3dot(dec!(1020)),
                             commodity: "CHF".into(),
+                            style: syntax::expr::CommodityStyle::default(),
                         }
                     ))),
                     lot: syntax::Lot::default()
@@ -338,6 +400,7 @@ mod tests {
                         syntax::expr::ValueExpr::Amount(syntax::expr::Amount {
                             value: PrettyDecimal::unformatted(dec!(1)),
                             commodity: "USD".into(),
+                            style: syntax::expr::CommodityStyle::default(),
                         })
                         .into()
                     ),
@@ -348,7 +411,9 @@ mod tests {
                         },
                         syntax::Metadata::KeyValueTag {
                             key: "Date".into(),
-                            value: syntax::MetadataValue::Expr("[2022-3-4]".into()),
+                            value: syntax::MetadataValue::Date(
+                                NaiveDate::from_ymd_opt(2022, 3, 4).unwrap()
+                            ),
                         },
                         syntax::Metadata::Comment("My card took commission".into()),
                         syntax::Metadata::WordTags(vec!["financial".into(), "経済".into(),],),
@@ -386,7 +451,8 @@ mod tests {
                     amount: Some(PostingAmount {
                         amount: syntax::expr::ValueExpr::Amount(syntax::expr::Amount {
                             value: PrettyDecimal::unformatted(dec!(100)),
-                            commodity: "JPY".into()
+                            commodity: "JPY".into(),
+                            style: syntax::expr::CommodityStyle::default(),
                         }),
                         cost: None,
                         lot: Lot::default()
@@ -401,7 +467,43 @@ mod tests {
     where
         E: ParserError<LocatingSlice<&'a str>>,
     {
-        super::posting_account::<plain::Ident, _, E>(input)
+        super::posting_account::<plain::Ident, _, E>
+            .map(|(_, account)| account)
+            .parse_next(input)
+    }
+
+    #[test]
+    fn posting_account_parses_virtual() {
+        let posting_account = super::posting_account::<plain::Ident, _, _>;
+        assert_eq!(
+            expect_parse_ok(posting_account, "Real Account  1 JPY"),
+            (
+                "1 JPY",
+                (syntax::PostingKind::Real, Cow::Borrowed("Real Account"))
+            )
+        );
+        assert_eq!(
+            expect_parse_ok(posting_account, "(Budget:Food)  1 JPY"),
+            (
+                "1 JPY",
+                (syntax::PostingKind::Virtual, Cow::Borrowed("Budget:Food"))
+            )
+        );
+        assert_eq!(
+            expect_parse_ok(posting_account, "[Savings:Goal 2025]\n"),
+            (
+                "\n",
+                (
+                    syntax::PostingKind::BalancedVirtual,
+                    Cow::Borrowed("Savings:Goal 2025")
+                )
+            )
+        );
+        assert!(
+            super::posting_account::<plain::Ident, _, ContextError>
+                .parse_peek(LocatingSlice::new("(Budget:Food  1 JPY\n"))
+                .is_err()
+        );
     }
 
     #[test]
@@ -493,7 +595,8 @@ mod tests {
                                     price: Some(syntax::Exchange::Rate(
                                         syntax::expr::ValueExpr::Amount(syntax::expr::Amount {
                                             value: PrettyDecimal::unformatted(dec!(200)),
-                                            commodity: "JPY".into()
+                                            commodity: "JPY".into(),
+                                            style: syntax::expr::CommodityStyle::default(),
                                         })
                                     )),
                                     date: Some(NaiveDate::from_ymd_opt(2022, 9, 1).unwrap()),
@@ -4,17 +4,18 @@ use std::borrow::Cow;
 
 use winnow::{
     Parser as _,
-    ascii::{line_ending, space1, till_line_ending},
-    combinator::{alt, repeat, seq, trace},
+    ascii::{line_ending, space0, space1, till_line_ending},
+    combinator::{alt, opt, preceded, repeat, seq, trace},
     error::{ContextError, FromExternalError, ParserError},
     stream::{AsChar, Stream, StreamIsPartial},
-    token::take_while,
+    token::{one_of, take_while},
 };
 
 use crate::syntax::{self};
 
 use super::{
-    adaptor::{ParseOptions, ParsedContext},
+    adaptor::{DefaultYearStream, ParseOptions, ParsedContext},
+    character::line_ending_or_eof,
     error::ParseError,
     expr, primitive,
 };
@@ -38,18 +39,20 @@ pub fn parse_price_db<'i>(
     )
 }
 
-/// Parses a price DB entry line.
-fn price_db_entry<'i, I, E>(input: &mut I) -> winnow::Result<syntax::PriceDBEntry<'i>, E>
+/// Parses a price DB entry line, which is also valid as "P" directive in the Ledger file.
+pub(super) fn price_db_entry<'i, I, E>(input: &mut I) -> winnow::Result<syntax::PriceDBEntry<'i>, E>
 where
     I: Stream<Token = char, Slice = &'i str>
         + StreamIsPartial
+        + DefaultYearStream
         + winnow::stream::Compare<&'static str>
         + winnow::stream::FindSlice<(char, char)>
         + Clone,
     <I as Stream>::Token: AsChar + Clone,
     E: ParserError<I>
         + FromExternalError<I, pretty_decimal::ParseError>
-        + FromExternalError<I, chrono::ParseError>,
+        + FromExternalError<I, chrono::ParseError>
+        + FromExternalError<I, primitive::MissingYearError>,
 {
     trace(
         "price::price_db_entry",
@@ -61,7 +64,10 @@ where
             target: primitive::commodity.map(Cow::Borrowed),
             _: space1,
             rate: expr::amount,
-            _: line_ending,
+            _: space0,
+            comment: opt(preceded((one_of(';'), space0), till_line_ending.map(str::trim_end)))
+                .map(|comment| comment.map(Cow::Borrowed)),
+            _: line_ending_or_eof,
         }},
     )
     .parse_next(input)
@@ -79,7 +85,10 @@ mod tests {
 
     use crate::parse::testing::expect_parse_ok;
 
-    use syntax::{PriceDBEntry, expr::Amount};
+    use syntax::{
+        PriceDBEntry,
+        expr::{Amount, CommodityStyle},
+    };
 
     fn parse_price_db_into(input: &str) -> Vec<PriceDBEntry<'_>> {
         let got: Result<Vec<_>, _> = parse_price_db(&ParseOptions::default(), input).collect();
@@ -112,8 +121,10 @@ mod tests {
                     target: Cow::Borrowed("JRTOK"),
                     rate: Amount {
                         value: PrettyDecimal::comma3dot(dec!(3584)),
-                        commodity: Cow::Borrowed("JPY")
+                        commodity: Cow::Borrowed("JPY"),
+                        style: CommodityStyle::default(),
                     },
+                    comment: None,
                 },
                 PriceDBEntry {
                     datetime: NaiveDateTime::new(
@@ -123,8 +134,10 @@ mod tests {
                     target: Cow::Borrowed("CHF"),
                     rate: Amount {
                         value: PrettyDecimal::unformatted(dec!(200.07)),
-                        commodity: Cow::Borrowed("JPY")
+                        commodity: Cow::Borrowed("JPY"),
+                        style: CommodityStyle::default(),
                     },
+                    comment: None,
                 }
             ]
         );
@@ -146,8 +159,10 @@ mod tests {
                     target: Cow::Borrowed("JRTOK"),
                     rate: Amount {
                         value: PrettyDecimal::comma3dot(dec!(3584)),
-                        commodity: Cow::Borrowed("JPY")
+                        commodity: Cow::Borrowed("JPY"),
+                        style: CommodityStyle::default(),
                     },
+                    comment: None,
                 }
             )
         );
@@ -166,8 +181,59 @@ mod tests {
                     target: Cow::Borrowed("EUR"),
                     rate: Amount {
                         value: PrettyDecimal::unformatted(dec!(0.9367)),
-                        commodity: Cow::Borrowed("CHF")
+                        commodity: Cow::Borrowed("CHF"),
+                        style: CommodityStyle::default(),
                     },
+                    comment: None,
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn price_db_parses_trailing_comment() {
+        let input = "P 2024/10/28 EUR 0.9367 CHF  ; from ECB \nP";
+
+        assert_eq!(
+            expect_parse_ok(price_db_entry, input),
+            (
+                "P",
+                PriceDBEntry {
+                    datetime: NaiveDate::from_ymd_opt(2024, 10, 28)
+                        .expect("2024-10-28 must exist")
+                        .into(),
+                    target: Cow::Borrowed("EUR"),
+                    rate: Amount {
+                        value: PrettyDecimal::unformatted(dec!(0.9367)),
+                        commodity: Cow::Borrowed("CHF"),
+                        style: CommodityStyle::default(),
+                    },
+                    comment: Some(Cow::Borrowed("from ECB")),
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn price_db_parses_quoted_commodity() {
+        let input = "P 2024/10/28 \"VANGUARD FTSE 100\" 7.50 \"GBP\"\n";
+
+        assert_eq!(
+            expect_parse_ok(price_db_entry, input),
+            (
+                "",
+                PriceDBEntry {
+                    datetime: NaiveDateTime::new(
+                        NaiveDate::from_ymd_opt(2024, 10, 28).expect("2024-10-28 must exist"),
+                        NaiveTime::from_hms_opt(0, 0, 0).expect("00:00:00 must exist")
+                    ),
+                    target: Cow::Borrowed("VANGUARD FTSE 100"),
+                    rate: Amount {
+                        value: PrettyDecimal::unformatted(dec!(7.50)),
+                        commodity: Cow::Borrowed("GBP"),
+                        style: CommodityStyle::default(),
+                    },
+                    comment: None,
                 }
             )
         );
@@ -189,8 +255,10 @@ mod tests {
                     target: Cow::Borrowed("DCTOPIX"),
                     rate: Amount {
                         value: PrettyDecimal::comma3dot(dec!(22745)),
-                        commodity: Cow::Borrowed("JPY")
+                        commodity: Cow::Borrowed("JPY"),
+                        style: CommodityStyle::default(),
                     },
+                    comment: None,
                 }
             )
         );
@@ -1,16 +1,18 @@
 //! Defines parser functions for the primitive types used in Ledger format.
 
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveTime};
 use pretty_decimal::{self, PrettyDecimal};
 use winnow::{
     Parser,
     ascii::digit1,
-    combinator::{alt, trace},
+    combinator::{alt, delimited, dispatch, opt, peek, trace},
     error::{FromExternalError, ParserError},
     stream::{AsChar, Stream, StreamIsPartial},
-    token::{one_of, take_till, take_while},
+    token::{any, one_of, take_till, take_while},
 };
 
+use super::adaptor::DefaultYearStream;
+
 /// Parses comma separated decimal.
 pub fn pretty_decimal<'a, I, E>(input: &mut I) -> winnow::Result<PrettyDecimal, E>
 where
@@ -24,23 +26,62 @@ where
             let c = c.as_char();
             c.is_ascii_digit() || c == '-' || c == ',' || c == '.'
         })
-        .try_map(str::parse),
+        .try_map(|s: &str| {
+            // Rejects the last comma group shorter than 3 digits, such as the decimal comma `12,50`,
+            // instead of reading it as `1250`.
+            let integer = s.split('.').next().unwrap_or_default();
+            if let Some(pos) = integer.rfind(',')
+                && integer.len() - pos != 4
+            {
+                return Err(pretty_decimal::ParseError::UnexpectedChar(
+                    ",".to_string(),
+                    pos,
+                ));
+            }
+            s.parse()
+        }),
     )
     .parse_next(input)
 }
 
-const NON_COMMODITY_CHARS: &[u8] = b" \t\r\n0123456789.,;:?!-+*/^&|=<>[](){}@";
+const NON_COMMODITY_CHARS: &[u8] = b" \t\r\n0123456789.,;:?!-+*/^&|=<>[](){}@\"";
+
+/// Returns `true` if the given commodity can't be written without double quotes.
+pub(crate) fn commodity_requires_quote(commodity: &str) -> bool {
+    commodity.bytes().any(|b| NON_COMMODITY_CHARS.contains(&b))
+}
 
 /// Parses commodity in greedy manner.
+/// Commodity can be double-quoted as `"ABC 123"`, which returns the string without quotes.
 /// Returns empty string if the upcoming characters are not valid as commodity to support empty commodity.
 pub fn commodity<I, E>(input: &mut I) -> winnow::Result<<I as Stream>::Slice, E>
 where
     I: Stream + StreamIsPartial,
     E: ParserError<I>,
-    <I as Stream>::Token: AsChar,
+    <I as Stream>::Token: AsChar + Clone,
 {
-    // Quoted commodity not supported.
-    trace("primitive::commodity", take_till(0.., NON_COMMODITY_CHARS)).parse_next(input)
+    trace(
+        "primitive::commodity",
+        dispatch! {peek(opt(any).map(|c: Option<<I as Stream>::Token>| c.map(AsChar::as_char)));
+            Some('"') => quoted_commodity,
+            _ => take_till(0.., NON_COMMODITY_CHARS),
+        },
+    )
+    .parse_next(input)
+}
+
+/// Parses double-quoted commodity, and returns the string without quotes.
+pub fn quoted_commodity<I, E>(input: &mut I) -> winnow::Result<<I as Stream>::Slice, E>
+where
+    I: Stream + StreamIsPartial,
+    E: ParserError<I>,
+    <I as Stream>::Token: AsChar + Clone,
+{
+    trace(
+        "primitive::quoted_commodity",
+        delimited(one_of('"'), take_till(1.., ['"', '\r', '\n']), one_of('"')),
+    )
+    .parse_next(input)
 }
 
 #[derive(Copy, Clone)]
@@ -50,6 +91,13 @@ enum DateType {
 }
 
 impl DateType {
+    fn separator(self) -> char {
+        match self {
+            DateType::Slash => '/',
+            DateType::Hyphen => '-',
+        }
+    }
+
     fn pattern(self) -> &'static str {
         match self {
             DateType::Slash => "%Y/%m/%d",
@@ -58,32 +106,81 @@ impl DateType {
     }
 }
 
+/// Error for the date without year, when the default year isn't given.
+#[derive(Debug, thiserror::Error)]
+#[error("date without year requires \"year\" directive beforehand")]
+pub struct MissingYearError;
+
 /// Parses date in yyyy/mm/dd format.
+/// Year can be omitted as mm/dd, if the input has the default year.
 pub fn date<'a, I, E>(input: &mut I) -> winnow::Result<NaiveDate, E>
+where
+    I: Stream<Slice = &'a str> + StreamIsPartial + DefaultYearStream,
+    E: ParserError<I>
+        + FromExternalError<I, chrono::ParseError>
+        + FromExternalError<I, MissingYearError>,
+    <I as Stream>::Token: AsChar + Clone,
+{
+    trace("primitive::date", move |input: &mut I| {
+        let slash = (digit1, one_of('/'), digit1, opt((one_of('/'), digit1)));
+        let hyphen = (digit1, one_of('-'), digit1, opt((one_of('-'), digit1)));
+        let start = input.checkpoint();
+        let ((date_type, has_year), s) = alt((
+            slash.map(|(_, _, _, day)| (DateType::Slash, day.is_some())),
+            hyphen.map(|(_, _, _, day)| (DateType::Hyphen, day.is_some())),
+        ))
+        .with_taken()
+        .parse_next(input)?;
+        let parsed = if has_year {
+            NaiveDate::parse_from_str(s, date_type.pattern())
+        } else if let Some(year) = input.default_year() {
+            let s = format!("{}{}{}", year, date_type.separator(), s);
+            NaiveDate::parse_from_str(&s, date_type.pattern())
+        } else {
+            input.reset(&start);
+            return Err(E::from_external_error(input, MissingYearError));
+        };
+        parsed.map_err(|e| {
+            input.reset(&start);
+            E::from_external_error(input, e)
+        })
+    })
+    .parse_next(input)
+}
+
+/// Parses time in HH:MM:SS format. Seconds can be omitted as HH:MM.
+pub fn time<'a, I, E>(input: &mut I) -> winnow::Result<NaiveTime, E>
 where
     I: Stream<Slice = &'a str> + StreamIsPartial,
     E: ParserError<I> + FromExternalError<I, chrono::ParseError>,
     <I as Stream>::Token: AsChar + Clone,
 {
-    let slash = (digit1, one_of('/'), digit1, one_of('/'), digit1);
-    let hyphen = (digit1, one_of('-'), digit1, one_of('-'), digit1);
-    trace(
-        "primitive::date",
-        alt((slash.value(DateType::Slash), hyphen.value(DateType::Hyphen)))
+    trace("primitive::time", move |input: &mut I| {
+        let start = input.checkpoint();
+        let (has_second, s) = (digit1, one_of(':'), digit1, opt((one_of(':'), digit1)))
+            .map(|(_, _, _, second)| second.is_some())
             .with_taken()
-            .try_map(|(date_type, s)| NaiveDate::parse_from_str(s, date_type.pattern())),
-    )
+            .parse_next(input)?;
+        let pattern = if has_second { "%H:%M:%S" } else { "%H:%M" };
+        NaiveTime::parse_from_str(s, pattern).map_err(|e| {
+            input.reset(&start);
+            E::from_external_error(input, e)
+        })
+    })
     .parse_next(input)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::parse::testing::expect_parse_ok;
+    use crate::parse::{adaptor::ParseState, testing::expect_parse_ok};
 
     use pretty_assertions::assert_eq;
     use rust_decimal_macros::dec;
-    use winnow::error::{ErrMode, InputError};
+    use winnow::{
+        LocatingSlice, Stateful,
+        error::{ErrMode, InputError},
+    };
 
     #[test]
     fn comma_decimal_parses_valid_inputs() {
@@ -111,6 +208,16 @@ mod tests {
             pretty_decimal.parse_peek("!"),
             Err(ErrMode::Backtrack(InputError::at("!")))
         );
+        assert!(
+            pretty_decimal::<_, InputError<_>>
+                .parse_peek("12,50")
+                .is_err()
+        );
+        assert!(
+            pretty_decimal::<_, InputError<_>>
+                .parse_peek("1,234,56.7")
+                .is_err()
+        );
     }
 
     #[test]
@@ -119,6 +226,32 @@ mod tests {
         assert_eq!(expect_parse_ok(commodity, "JPY\n"), ("\n", "JPY"));
         assert_eq!(expect_parse_ok(commodity, "$ $"), (" $", "$"));
         assert_eq!(expect_parse_ok(commodity, "£ "), (" ", "£"));
+        assert_eq!(
+            expect_parse_ok(commodity, "\"VANGUARD FTSE 100\" "),
+            (" ", "VANGUARD FTSE 100")
+        );
+        assert_eq!(expect_parse_ok(commodity, "\"A1B2\"@"), ("@", "A1B2"));
+    }
+
+    #[test]
+    fn commodity_fails_on_unterminated_quote() {
+        assert!(commodity::<_, InputError<_>>.parse_peek("\"ABC 1").is_err());
+        assert!(
+            commodity::<_, InputError<_>>
+                .parse_peek("\"ABC\n\"")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn commodity_requires_quote_only_when_needed() {
+        assert!(!commodity_requires_quote(""));
+        assert!(!commodity_requires_quote("USD"));
+        assert!(!commodity_requires_quote("$"));
+        assert!(!commodity_requires_quote("円"));
+        assert!(commodity_requires_quote("A1B2"));
+        assert!(commodity_requires_quote("VANGUARD FTSE 100"));
+        assert!(commodity_requires_quote("AB-C"));
     }
 
     #[test]
@@ -139,6 +272,31 @@ mod tests {
         assert_eq!(res, ("", NaiveDate::from_ymd_opt(2022, 1, 15).unwrap()));
     }
 
+    #[test]
+    fn date_parses_without_year_using_default_year() {
+        let input = |s| Stateful {
+            input: LocatingSlice::new(s),
+            state: ParseState {
+                default_year: Some(2025),
+            },
+        };
+
+        let (_, got) = date::<_, InputError<_>>
+            .parse_peek(input("03/15 foo"))
+            .unwrap();
+        assert_eq!(NaiveDate::from_ymd_opt(2025, 3, 15).unwrap(), got);
+
+        let (_, got) = date::<_, InputError<_>>.parse_peek(input("3-1")).unwrap();
+        assert_eq!(NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(), got);
+
+        let (_, got) = date::<_, InputError<_>>
+            .parse_peek(input("2024/02/29"))
+            .unwrap();
+        assert_eq!(NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(), got);
+
+        assert!(date::<_, InputError<_>>.parse_peek(input("02/30")).is_err());
+    }
+
     #[test]
     fn date_fails_on_invalid_inputs() {
         assert_eq!(
@@ -147,11 +305,35 @@ mod tests {
         );
         assert_eq!(
             date.parse_peek("2022/01"),
-            Err(ErrMode::Backtrack(InputError::at("/01")))
+            Err(ErrMode::Backtrack(InputError::at("2022/01")))
         );
         assert_eq!(
             date.parse_peek("2022/13/21"),
             Err(ErrMode::Backtrack(InputError::at("2022/13/21")))
         );
     }
+
+    #[test]
+    fn time_parses_valid_inputs() {
+        assert_eq!(
+            expect_parse_ok(time, "10:05:30 foo"),
+            (" foo", NaiveTime::from_hms_opt(10, 5, 30).unwrap())
+        );
+        assert_eq!(
+            expect_parse_ok(time, "9:05"),
+            ("", NaiveTime::from_hms_opt(9, 5, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn time_fails_on_invalid_inputs() {
+        assert_eq!(
+            time.parse_peek("10"),
+            Err(ErrMode::Backtrack(InputError::at("")))
+        );
+        assert_eq!(
+            time.parse_peek("25:00:00"),
+            Err(ErrMode::Backtrack(InputError::at("25:00:00")))
+        );
+    }
 }
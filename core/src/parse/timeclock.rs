@@ -0,0 +1,191 @@
+//! Defines parser for the timeclock entries, "i" and "o" directives.
+
+use std::borrow::Cow;
+
+use chrono::NaiveDateTime;
+use winnow::{
+    Parser,
+    ascii::{space0, space1, till_line_ending},
+    combinator::{alt, not, opt, preceded, seq, trace},
+    error::{FromExternalError, ParserError},
+    stream::{AsChar, Stream, StreamIsPartial},
+    token::one_of,
+};
+
+use crate::syntax::{self, plain};
+
+use super::{adaptor::DefaultYearStream, character::line_ending_or_eof, posting, primitive};
+
+/// Parses "i" directive, such as `i 2025/03/01 10:00:00 Client:Acme  Design review`.
+pub fn clock_in<'i, I, E>(input: &mut I) -> winnow::Result<syntax::ClockIn<'i>, E>
+where
+    I: Stream<Slice = &'i str>
+        + StreamIsPartial
+        + DefaultYearStream
+        + winnow::stream::Location
+        + winnow::stream::Compare<&'static str>
+        + winnow::stream::FindSlice<(char, char)>,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>
+        + FromExternalError<I, chrono::ParseError>
+        + FromExternalError<I, primitive::MissingYearError>,
+{
+    trace(
+        "timeclock::clock_in",
+        seq! {syntax::ClockIn {
+            _: ("i", space1),
+            datetime: datetime,
+            _: space1,
+            account: posting::real_account::<plain::Ident, _, _>,
+            payee: note,
+            _: (space0, line_ending_or_eof),
+        }},
+    )
+    .parse_next(input)
+}
+
+/// Parses "o" directive, such as `o 2025/03/01 12:30:00`.
+/// The account must follow the time with a single space,
+/// while the note follows after two spaces or a tab.
+pub fn clock_out<'i, I, E>(input: &mut I) -> winnow::Result<syntax::ClockOut<'i>, E>
+where
+    I: Stream<Slice = &'i str>
+        + StreamIsPartial
+        + DefaultYearStream
+        + winnow::stream::Location
+        + winnow::stream::Compare<&'static str>
+        + winnow::stream::FindSlice<(char, char)>,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>
+        + FromExternalError<I, chrono::ParseError>
+        + FromExternalError<I, primitive::MissingYearError>,
+{
+    trace(
+        "timeclock::clock_out",
+        seq! {syntax::ClockOut {
+            _: ("o", space1),
+            datetime: datetime,
+            account: opt(preceded(
+                (" ", not(one_of([' ', '\t']))),
+                posting::real_account::<plain::Ident, _, _>,
+            )),
+            note: note,
+            _: (space0, line_ending_or_eof),
+        }},
+    )
+    .parse_next(input)
+}
+
+fn datetime<'i, I, E>(input: &mut I) -> winnow::Result<NaiveDateTime, E>
+where
+    I: Stream<Slice = &'i str> + StreamIsPartial + DefaultYearStream,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>
+        + FromExternalError<I, chrono::ParseError>
+        + FromExternalError<I, primitive::MissingYearError>,
+{
+    (primitive::date, space1, primitive::time)
+        .map(|(date, _, time)| date.and_time(time))
+        .parse_next(input)
+}
+
+fn note<'i, I, E>(input: &mut I) -> winnow::Result<Option<Cow<'i, str>>, E>
+where
+    I: Stream<Slice = &'i str>
+        + StreamIsPartial
+        + winnow::stream::Compare<&'static str>
+        + winnow::stream::FindSlice<(char, char)>,
+    <I as Stream>::Token: AsChar + Clone,
+    E: ParserError<I>,
+{
+    opt(preceded(
+        (alt(("  ", "\t")), space0),
+        till_line_ending
+            .map(str::trim_end)
+            .verify(|note: &str| !note.is_empty()),
+    ))
+    .map(|note| note.map(Cow::Borrowed))
+    .parse_next(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::testing::expect_parse_ok;
+
+    use chrono::NaiveDate;
+    use pretty_assertions::assert_eq;
+
+    fn datetime(hour: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 3, 1)
+            .unwrap()
+            .and_hms_opt(hour, min, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn clock_in_parses_account_and_payee() {
+        assert_eq!(
+            expect_parse_ok(
+                clock_in,
+                "i 2025/03/01 10:00:00 Client:Acme Corp  Design review  \nfoo"
+            ),
+            (
+                "foo",
+                syntax::ClockIn {
+                    datetime: datetime(10, 0),
+                    account: "Client:Acme Corp".into(),
+                    payee: Some("Design review".into()),
+                }
+            )
+        );
+        assert_eq!(
+            expect_parse_ok(clock_in, "i 2025/03/01 10:00 Client:Acme"),
+            (
+                "",
+                syntax::ClockIn {
+                    datetime: datetime(10, 0),
+                    account: "Client:Acme".into(),
+                    payee: None,
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn clock_out_parses_optional_account_and_note() {
+        assert_eq!(
+            expect_parse_ok(clock_out, "o 2025/03/01 12:30:00\n"),
+            (
+                "",
+                syntax::ClockOut {
+                    datetime: datetime(12, 30),
+                    account: None,
+                    note: None,
+                }
+            )
+        );
+        assert_eq!(
+            expect_parse_ok(clock_out, "o 2025/03/01 12:30:00 Client:Acme  done"),
+            (
+                "",
+                syntax::ClockOut {
+                    datetime: datetime(12, 30),
+                    account: Some("Client:Acme".into()),
+                    note: Some("done".into()),
+                }
+            )
+        );
+        assert_eq!(
+            expect_parse_ok(clock_out, "o 2025/03/01 12:30:00  done"),
+            (
+                "",
+                syntax::ClockOut {
+                    datetime: datetime(12, 30),
+                    account: None,
+                    note: Some("done".into()),
+                }
+            )
+        );
+    }
+}
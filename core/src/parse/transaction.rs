@@ -12,7 +12,9 @@ use winnow::{
 };
 
 use crate::{
-    parse::{character, combinator::has_peek, metadata, posting, primitive},
+    parse::{
+        adaptor::DefaultYearStream, character, combinator::has_peek, metadata, posting, primitive,
+    },
     syntax::{self, decoration::Decoration},
 };
 
@@ -24,6 +26,7 @@ where
         + winnow::stream::Compare<&'static str>
         + winnow::stream::FindSlice<(char, char)>
         + winnow::stream::Location
+        + DefaultYearStream
         + Clone,
     <I as Stream>::Token: AsChar + Clone,
     Deco: Decoration,
@@ -47,14 +50,7 @@ where
         let payee =
             opt(character::till_line_ending_or_semi.map(str::trim_end)).parse_next(input)?;
         let metadata = metadata::block_metadata(input)?;
-        let posts = repeat(
-            0..,
-            preceded(
-                take_while(1.., b" \t"),
-                cut_err(Deco::decorate_parser(posting::posting)),
-            ),
-        )
-        .parse_next(input)?;
+        let posts = postings::<_, Deco>(input)?;
         Ok(syntax::Transaction {
             effective_date,
             clear_state,
@@ -67,6 +63,34 @@ where
     .parse_next(input)
 }
 
+/// Parses indented postings following the transaction header.
+pub(super) fn postings<'i, I, Deco>(
+    input: &mut I,
+) -> ModalResult<Vec<Deco::Decorated<syntax::Posting<'i, Deco>>>>
+where
+    I: Stream<Token = char, Slice = &'i str>
+        + StreamIsPartial
+        + winnow::stream::Compare<&'static str>
+        + winnow::stream::FindSlice<(char, char)>
+        + winnow::stream::Location
+        + DefaultYearStream
+        + Clone,
+    <I as Stream>::Token: AsChar + Clone,
+    Deco: Decoration,
+{
+    trace(
+        "transaction::postings",
+        repeat(
+            0..,
+            preceded(
+                take_while(1.., b" \t"),
+                cut_err(Deco::decorate_parser(posting::posting)),
+            ),
+        ),
+    )
+    .parse_next(input)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -116,6 +140,7 @@ mod tests {
                                 amount: syntax::expr::ValueExpr::Amount(syntax::expr::Amount {
                                     value: PrettyDecimal::comma3dot(dec!(123456.78)),
                                     commodity: Cow::Borrowed("USD"),
+                                    style: syntax::expr::CommodityStyle::default(),
                                 }),
                                 cost: None,
                                 lot: syntax::Lot::default(),
@@ -161,6 +186,7 @@ mod tests {
                                 amount: syntax::expr::ValueExpr::Amount(syntax::expr::Amount {
                                     value: PrettyDecimal::comma3dot(dec!(-123456.78)),
                                     commodity: Cow::Borrowed("USD"),
+                                    style: syntax::expr::CommodityStyle::default(),
                                 }),
                                 cost: None,
                                 lot: syntax::Lot::default(),
@@ -179,6 +205,7 @@ mod tests {
                                 amount: syntax::expr::ValueExpr::Amount(syntax::expr::Amount {
                                     value: PrettyDecimal::unformatted(dec!(12)),
                                     commodity: Cow::Borrowed("JPY"),
+                                    style: syntax::expr::CommodityStyle::default(),
                                 }),
                                 cost: None,
                                 lot: syntax::Lot::default(),
@@ -186,6 +213,7 @@ mod tests {
                             balance: Some(syntax::expr::ValueExpr::Amount(syntax::expr::Amount {
                                 value: PrettyDecimal::plain(dec!(-1000)),
                                 commodity: Cow::Borrowed("CHF"),
+                                style: syntax::expr::CommodityStyle::default(),
                             })),
                             metadata: vec![syntax::Metadata::WordTags(vec![
                                 Cow::Borrowed("tag1"),
@@ -197,6 +225,7 @@ mod tests {
                             balance: Some(syntax::expr::ValueExpr::Amount(syntax::expr::Amount {
                                 value: PrettyDecimal::unformatted(dec!(0)),
                                 commodity: Cow::Borrowed(""),
+                                style: syntax::expr::CommodityStyle::default(),
                             })),
                             metadata: vec![
                                 syntax::Metadata::Comment(Cow::Borrowed("Cのノート")),
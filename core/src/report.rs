@@ -2,6 +2,7 @@
 //! report various metrics out of the given Ledger file.
 
 mod account;
+mod automated;
 mod balance;
 mod balance_tree;
 mod book_keeping;
@@ -9,10 +10,16 @@ mod commodity;
 mod context;
 mod error;
 mod eval;
+mod incremental;
+mod payee;
+mod periodic;
 mod price_db;
 mod process;
 pub mod query;
+mod snapshot;
+mod strict;
 mod tags;
+mod timeclock;
 mod transaction;
 
 use std::borrow::Borrow;
@@ -24,10 +31,12 @@ pub use commodity::{Commodity, CommodityStore, CommodityTag, OwnedCommodity};
 pub use context::ReportContext;
 pub use error::ReportError;
 pub use eval::{Amount, SingleAmount};
+pub use incremental::Incremental;
 pub use price_db::LoadError;
-pub use process::{ProcessOptions, process};
+pub use process::{ProcessOptions, process, process_incremental};
+pub use strict::Strictness;
 pub use tags::{Tag, TagQuery, TagValue, tags};
-pub use transaction::{Posting, Transaction};
+pub use transaction::{MetadataValue, Posting, Transaction};
 
 use crate::{load, syntax::plain::LedgerStatement};
 
@@ -42,28 +51,38 @@ where
     L: Borrow<load::Loader<F>>,
     F: load::FileSystem,
 {
-    loader.borrow().load(|path, pctx, entry| {
+    ctx.accounts.reset_apply_account();
+    loader.borrow().load_parallel(|path, pctx, entry| {
         match &entry.statement {
-            LedgerStatement::Account(account) => {
-                process::process_account(ctx, account).map_err(|berr| {
-                    ReportError::BookKeep(
-                        berr,
-                        error::ErrorContext::new(
-                            loader.borrow().error_style().clone(),
-                            path.to_owned(),
-                            pctx,
-                        ),
-                    )
-                })?
+            LedgerStatement::Account(account) => process::process_account(ctx, account),
+            LedgerStatement::Alias(alias) => process::process_alias(ctx, alias),
+            LedgerStatement::ApplyAccount(apply) => {
+                ctx.accounts.enter_apply_account(&apply.0);
+                Ok(())
             }
+            LedgerStatement::EndApplyAccount => process::process_end_apply_account(ctx),
             LedgerStatement::Txn(txn) => {
                 for posting in &txn.posts {
-                    ctx.accounts.ensure(&posting.account);
+                    ctx.accounts.ensure_applied(&posting.account);
                 }
+                Ok(())
             }
-            _ => (),
+            LedgerStatement::ClockIn(clock) => {
+                ctx.accounts.ensure_applied(&clock.account);
+                Ok(())
+            }
+            _ => Ok(()),
         }
-        Ok::<(), ReportError>(())
+        .map_err(|berr| {
+            ReportError::BookKeep(
+                berr,
+                error::ErrorContext::new(
+                    loader.borrow().error_style().clone(),
+                    path.to_owned(),
+                    pctx,
+                ),
+            )
+        })
     })?;
     Ok(ctx.all_accounts())
 }
@@ -34,6 +34,9 @@ impl<'arena> Account<'arena> {
 pub struct AccountStore<'arena> {
     /// Interned Account store.
     intern: DirectInternStore<'arena, Account<'arena>>,
+    /// Account prefixes given by the enclosing "apply account" directives,
+    /// the innermost last. Each element is already joined with the outer ones.
+    apply_prefixes: Vec<String>,
 }
 
 impl<'arena> AccountStore<'arena> {
@@ -41,6 +44,7 @@ impl<'arena> AccountStore<'arena> {
     pub fn new(arena: &'arena Bump) -> Self {
         Self {
             intern: DirectInternStore::new(arena),
+            apply_prefixes: Vec::new(),
         }
     }
 
@@ -66,6 +70,55 @@ impl<'arena> AccountStore<'arena> {
         self.intern.register_alias(value, canonical)
     }
 
+    /// Returns the [`Account`] referred in the posting or the "alias" directive,
+    /// with the prefix of the enclosing "apply account" directives.
+    /// Aliases are resolved as-is without the prefix.
+    /// If not available, registers the resulting name as the canonical.
+    pub fn ensure_applied(&mut self, value: &str) -> Account<'arena> {
+        // Canonical accounts are always stored with its own name,
+        // so the resolved account with the different name must be an alias.
+        let alias = self.intern.resolve(value).filter(|a| a.as_str() != value);
+        match (alias, self.apply_prefixes.last()) {
+            (Some(canonical), _) => canonical,
+            (None, None) => self.intern.ensure(value),
+            (None, Some(prefix)) => {
+                let applied = format!("{}:{}", prefix, value);
+                self.intern.ensure(&applied)
+            }
+        }
+    }
+
+    /// Enters the "apply account" block with the given `prefix`,
+    /// which is nested in the current block if any.
+    pub fn enter_apply_account(&mut self, prefix: &str) {
+        let applied = match self.apply_prefixes.last() {
+            None => prefix.to_string(),
+            Some(outer) => format!("{}:{}", outer, prefix),
+        };
+        self.apply_prefixes.push(applied);
+    }
+
+    /// Exits the innermost "apply account" block.
+    /// Returns `false` if there's no such block.
+    pub fn exit_apply_account(&mut self) -> bool {
+        self.apply_prefixes.pop().is_some()
+    }
+
+    /// Exits all "apply account" blocks, to start over the new input.
+    pub fn reset_apply_account(&mut self) {
+        self.apply_prefixes.clear();
+    }
+
+    /// Returns the prefixes of the enclosing "apply account" blocks, the innermost last.
+    pub(super) fn apply_prefixes(&self) -> &[String] {
+        &self.apply_prefixes
+    }
+
+    /// Restores the "apply account" blocks given by [`AccountStore::apply_prefixes`].
+    pub(super) fn restore_apply_prefixes(&mut self, prefixes: Vec<String>) {
+        self.apply_prefixes = prefixes;
+    }
+
     /// Returns the [`Iterator`] for just `Account`.
     /// Order is unspecified.
     pub fn iter(&self) -> impl Iterator<Item = Account<'arena>> {
@@ -74,6 +127,15 @@ impl<'arena> AccountStore<'arena> {
             StoredValue::Canonical(a) => Some(a),
         })
     }
+
+    /// Returns the [`Iterator`] for the aliases and its canonical `Account`.
+    /// Order is unspecified.
+    pub(super) fn aliases(&self) -> impl Iterator<Item = (&'arena str, Account<'arena>)> {
+        self.intern.iter().filter_map(|v| match v {
+            StoredValue::Alias { alias, canonical } => Some((alias, canonical)),
+            StoredValue::Canonical(_) => None,
+        })
+    }
 }
 
 /// Represents not the [`Account`] itself, but rather a ancestor group which
@@ -327,6 +389,31 @@ mod tests {
         assert_eq!("Expenses", AccountAggregate::from(expenses).last_segment());
     }
 
+    #[test]
+    fn account_store_ensure_applied_prefixes_except_alias() {
+        let arena = Bump::new();
+        let mut accounts = AccountStore::new(&arena);
+        let cash = accounts.ensure("Assets:Cash");
+        accounts.register_alias("Cash", cash).unwrap();
+
+        accounts.enter_apply_account("Assets");
+        accounts.enter_apply_account("Bank");
+        assert_eq!(
+            "Assets:Bank:Checking",
+            accounts.ensure_applied("Checking").as_str()
+        );
+        assert_eq!(cash, accounts.ensure_applied("Cash"));
+
+        assert!(accounts.exit_apply_account());
+        assert_eq!(
+            "Assets:Savings",
+            accounts.ensure_applied("Savings").as_str()
+        );
+        assert!(accounts.exit_apply_account());
+        assert!(!accounts.exit_apply_account());
+        assert_eq!("Income", accounts.ensure_applied("Income").as_str());
+    }
+
     mod account_tree {
         use super::assert_eq;
         use super::*;
@@ -0,0 +1,161 @@
+//! Provides [AutomatedTransaction], which adds postings to the matching transactions.
+
+use rust_decimal::Decimal;
+
+use crate::syntax::{self, decoration::AsUndecorated};
+
+use super::{
+    account::Account,
+    book_keeping::BookKeepError,
+    context::ReportContext,
+    eval::{Evaluable, Evaluated, PostingAmount},
+    query::AccountFilter,
+};
+
+/// Processed automated transaction.
+#[derive(Debug)]
+pub(super) struct AutomatedTransaction<'ctx> {
+    filter: AccountFilter<'ctx>,
+    postings: Vec<AutomatedPosting<'ctx>>,
+}
+
+/// Posting generated for each matching posting.
+#[derive(Debug)]
+struct AutomatedPosting<'ctx> {
+    account: Account<'ctx>,
+    kind: syntax::PostingKind,
+    amount: AutomatedAmount<'ctx>,
+}
+
+#[derive(Debug)]
+enum AutomatedAmount<'ctx> {
+    /// Multiplier of the matching posting amount, given as the amount without commodity.
+    Multiplier(Decimal),
+    /// Amount added as-is regardless of the matching posting amount.
+    Fixed(PostingAmount<'ctx>),
+}
+
+impl<'ctx> AutomatedTransaction<'ctx> {
+    /// Creates an instance out of the syntax.
+    pub fn from_syntax(
+        ctx: &mut ReportContext<'ctx>,
+        txn: &syntax::tracked::AutomatedTransaction<'_>,
+    ) -> Result<Self, BookKeepError> {
+        let patterns = predicate_patterns(&txn.predicate)
+            .ok_or_else(|| BookKeepError::InvalidAutomatedPredicate(txn.predicate.to_string()))?;
+        let filter = AccountFilter::from_lazy_regex_patterns(&patterns)
+            .map_err(|_| BookKeepError::InvalidAutomatedPredicate(txn.predicate.to_string()))?;
+        let mut postings = Vec::with_capacity(txn.posts.len());
+        for posting in &txn.posts {
+            let posting = posting.as_undecorated();
+            let Some(syntax_amount) = &posting.amount else {
+                return Err(BookKeepError::AutomatedPostingWithoutAmount(
+                    posting.account.span(),
+                ));
+            };
+            let amount_span = syntax_amount.amount.span();
+            let amount = match syntax_amount.amount.as_undecorated().eval_mut(ctx) {
+                Ok(Evaluated::Number(multiplier)) => Ok(AutomatedAmount::Multiplier(multiplier)),
+                Ok(Evaluated::Commodities(amount)) => amount.try_into().map(AutomatedAmount::Fixed),
+                Err(e) => Err(e),
+            }
+            .map_err(|e| BookKeepError::EvalFailure(e.into_owned(ctx), amount_span))?;
+            postings.push(AutomatedPosting {
+                account: ctx
+                    .accounts
+                    .ensure_applied(posting.account.as_undecorated()),
+                kind: posting.kind,
+                amount,
+            });
+        }
+        Ok(Self { filter, postings })
+    }
+
+    /// Returns postings to be added for the posting with `account` and `amount`.
+    /// Returns nothing if the posting doesn't match.
+    pub fn generate<'a>(
+        &'a self,
+        account: Account<'ctx>,
+        amount: PostingAmount<'ctx>,
+    ) -> impl Iterator<Item = (Account<'ctx>, syntax::PostingKind, PostingAmount<'ctx>)> + 'a {
+        let matched = self.filter.is_match(&account);
+        self.postings
+            .iter()
+            .filter(move |_| matched)
+            .map(move |p| match p.amount {
+                AutomatedAmount::Multiplier(multiplier) => (p.account, p.kind, amount * multiplier),
+                AutomatedAmount::Fixed(fixed) => (p.account, p.kind, fixed),
+            })
+    }
+}
+
+/// Splits the predicate into account regex patterns, which match if any of them matches.
+/// Each term is either `/regex/` or the bare regex, optionally joined with `or`.
+/// Returns `None` if the predicate is empty, malformed,
+/// or uses other operators such as `and`, `not`, `expr`, `@payee` or `%tag`.
+fn predicate_patterns(predicate: &str) -> Option<Vec<&str>> {
+    let mut patterns = Vec::new();
+    let mut rest = predicate.trim_start();
+    while !rest.is_empty() {
+        let (term, next) = match rest.strip_prefix('/') {
+            Some(quoted) => {
+                let end = quoted.find('/')?;
+                (&quoted[..end], &quoted[end + 1..])
+            }
+            None => {
+                let (term, next) =
+                    rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len()));
+                if matches!(term, "and" | "not" | "expr")
+                    || term.starts_with(['@', '%', '(', ')', '!', '&', '|'])
+                {
+                    return None;
+                }
+                (term, next)
+            }
+        };
+        if term != "or" {
+            patterns.push(term);
+        }
+        rest = next.trim_start();
+    }
+    (!patterns.is_empty()).then_some(patterns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    #[test]
+    fn predicate_patterns_splits_terms() {
+        assert_eq!(
+            Some(vec!["^Expenses:Food"]),
+            predicate_patterns("/^Expenses:Food/")
+        );
+        assert_eq!(
+            Some(vec!["Food", "^Expenses:Eating Out$"]),
+            predicate_patterns("Food or /^Expenses:Eating Out$/")
+        );
+    }
+
+    #[test]
+    fn predicate_patterns_rejects_malformed() {
+        assert_eq!(None, predicate_patterns(""));
+        assert_eq!(None, predicate_patterns("/^Expenses"));
+    }
+
+    #[rstest]
+    #[case("Food and Grocery")]
+    #[case("Food and /Grocery/")]
+    #[case("not Food")]
+    #[case("expr 'account =~ /Food/'")]
+    #[case("@Migros")]
+    #[case("Food or @Migros")]
+    #[case("%trip")]
+    #[case("(Food or Rent)")]
+    fn predicate_patterns_rejects_unsupported_operators(#[case] predicate: &str) {
+        assert_eq!(None, predicate_patterns(predicate));
+    }
+}
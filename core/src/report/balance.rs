@@ -34,11 +34,23 @@ impl std::fmt::Display for BalanceErrorNote<'_> {
 }
 
 /// Accumulated balance of accounts.
-#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[derive(Debug, Default, Clone)]
 pub struct Balance<'ctx> {
     accounts: HashMap<Account<'ctx>, Amount<'ctx>>,
+    /// Original amounts of the accounts changed since [`Balance::begin`],
+    /// `None` unless the changes are recorded.
+    undo: Option<Vec<(Account<'ctx>, Option<Amount<'ctx>>)>>,
 }
 
+/// Compares the accounts only, regardless of the changes being recorded.
+impl PartialEq for Balance<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.accounts == other.accounts
+    }
+}
+
+impl Eq for Balance<'_> {}
+
 impl<'ctx> FromIterator<(Account<'ctx>, Amount<'ctx>)> for Balance<'ctx> {
     fn from_iter<T>(iter: T) -> Self
     where
@@ -47,6 +59,7 @@ impl<'ctx> FromIterator<(Account<'ctx>, Amount<'ctx>)> for Balance<'ctx> {
         let iter = iter.into_iter();
         let mut value = Self {
             accounts: HashMap::with_capacity(iter.size_hint().0),
+            undo: None,
         };
         for (account, amount) in iter {
             value.add_amount(account, amount);
@@ -68,8 +81,38 @@ impl<'ctx> Balance<'ctx> {
         ret
     }
 
+    /// Starts recording the changes, to revert them with [`Balance::rollback`].
+    pub(super) fn begin(&mut self) {
+        self.undo = Some(Vec::new());
+    }
+
+    /// Keeps the changes since [`Balance::begin`], and stops recording.
+    pub(super) fn commit(&mut self) {
+        self.undo = None;
+    }
+
+    /// Reverts the changes since [`Balance::begin`], and stops recording.
+    pub(super) fn rollback(&mut self) {
+        for (account, original) in self.undo.take().into_iter().flatten().rev() {
+            match original {
+                Some(amount) => self.accounts.insert(account, amount),
+                None => self.accounts.remove(&account),
+            };
+        }
+    }
+
+    /// Records the original amount of the `account` if it's the first change since [`Balance::begin`].
+    fn record(&mut self, account: Account<'ctx>) {
+        if let Some(undo) = &mut self.undo
+            && !undo.iter().any(|(recorded, _)| *recorded == account)
+        {
+            undo.push((account, self.accounts.get(&account).cloned()));
+        }
+    }
+
     /// Adds a particular account value, and returns the updated balance.
     pub fn add_amount(&mut self, account: Account<'ctx>, amount: Amount<'ctx>) -> &Amount<'ctx> {
+        self.record(account);
         let curr: &mut Amount = self.accounts.entry(account).or_default();
         *curr += amount;
         curr.remove_zero_entries();
@@ -82,6 +125,7 @@ impl<'ctx> Balance<'ctx> {
         account: Account<'ctx>,
         amount: PostingAmount<'ctx>,
     ) -> &Amount<'ctx> {
+        self.record(account);
         let curr: &mut Amount = self.accounts.entry(account).or_default();
         *curr += amount;
         curr.remove_zero_entries();
@@ -96,6 +140,7 @@ impl<'ctx> Balance<'ctx> {
         account: Account<'ctx>,
         amount: PostingAmount<'ctx>,
     ) -> Result<PostingAmount<'ctx>, BalanceError> {
+        self.record(account);
         match amount {
             PostingAmount::Zero => {
                 let prev: Amount<'ctx> = self
@@ -182,6 +227,40 @@ mod tests {
         assert_eq!(m, m2);
     }
 
+    #[test]
+    fn rollback_reverts_changes_since_begin() {
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let expenses = ctx.accounts.ensure("Expenses");
+        let bank = ctx.accounts.ensure("Assets:Bank");
+        let chf = ctx.commodities.ensure("CHF");
+        let mut b = Balance::from_iter([(bank, Amount::from_value(chf, dec!(100)))]);
+        let want = b.clone();
+
+        b.begin();
+        assert_eq!(
+            want, b,
+            "recording the changes must not affect the equality"
+        );
+        b.add_amount(expenses, Amount::from_value(chf, dec!(30)));
+        b.add_amount(bank, Amount::from_value(chf, dec!(-30)));
+        b.add_amount(bank, Amount::from_value(chf, dec!(-20)));
+        b.rollback();
+
+        assert_eq!(want, b);
+
+        b.begin();
+        b.add_amount(bank, Amount::from_value(chf, dec!(-30)));
+        b.commit();
+        b.rollback();
+
+        assert_eq!(
+            Some(&Amount::from_value(chf, dec!(70))),
+            b.get(bank),
+            "rollback after commit must keep the changes"
+        );
+    }
+
     #[test]
     fn from_iter_removes_zero_valued_commodity() {
         let arena = Bump::new();
@@ -1,7 +1,6 @@
 //! Contains book keeping logics to process the input stream,
 //! and convert them into a processed Transactions.
 
-use annotate_snippets::{Annotation, AnnotationKind};
 use bumpalo::Bump;
 use bumpalo::collections as bcc;
 use chrono::NaiveDate;
@@ -18,11 +17,15 @@ use crate::{
 };
 
 use super::{
+    automated::AutomatedTransaction,
     balance::{Balance, BalanceError},
     context::ReportContext,
-    eval::{Amount, Evaluable, OwnedEvalError, PostingAmount, SingleAmount},
-    price_db::{PriceEvent, PriceRepositoryBuilder, PriceSource},
-    transaction::{Posting, Transaction},
+    error::ErrorLabel,
+    eval::{
+        Amount, Evaluable, Evaluated, OwnedAmount, OwnedEvalError, PostingAmount, SingleAmount,
+    },
+    price_db::PriceEvent,
+    transaction::{MetadataValue, Posting, Transaction},
 };
 
 /// Error related to transaction understanding.
@@ -39,8 +42,11 @@ pub enum BookKeepError {
     },
     #[error("transaction cannot have multiple postings without constraints")]
     UndeduciblePostingAmount(Tracked<usize>, Tracked<usize>),
-    #[error("transaction cannot have unbalanced postings: {0}")]
-    UnbalancedPostings(String),
+    #[error("transaction cannot have unbalanced postings: {residual}")]
+    UnbalancedPostings {
+        residual: OwnedAmount,
+        postings: Vec<UnbalancedPosting>,
+    },
     #[error("balance assertion off by {diff}, computed balance is {computed}")]
     BalanceAssertionFailure {
         account_span: TrackedSpan,
@@ -48,10 +54,43 @@ pub enum BookKeepError {
         computed: String,
         diff: String,
     },
+    #[error("failed to evaluate the metadata {0}: {1}")]
+    MetadataEvalFailure(String, #[source] OwnedEvalError),
     #[error("already registered account alias: {0}")]
     InvalidAccountAlias(String),
+    #[error("\"end apply account\" without matching \"apply account\"")]
+    UnmatchedEndApplyAccount,
+    #[error("check in of {0} while it's already checked in")]
+    DuplicateCheckIn(String),
+    #[error("check in of {0} without matching check out")]
+    UnmatchedCheckIn(String),
+    #[error("check out of {0} without matching check in")]
+    UnmatchedCheckOut(String),
+    #[error("check out without account requires exactly one checked in account, but got {0}")]
+    AmbiguousCheckOut(usize),
+    #[error("check out at {check_out} is earlier than check in at {check_in}")]
+    CheckOutBeforeCheckIn {
+        check_in: chrono::NaiveDateTime,
+        check_out: chrono::NaiveDateTime,
+    },
+    #[error("already registered payee alias: {0}")]
+    InvalidPayeeAlias(String),
     #[error("already registered commodity alias: {0}")]
     InvalidCommodityAlias(String),
+    #[error("invalid automated transaction predicate: {0}")]
+    InvalidAutomatedPredicate(String),
+    #[error("account {0} is not declared")]
+    UndeclaredAccount(String, TrackedSpan),
+    #[error("commodity {0} is not declared")]
+    UndeclaredCommodity(String, TrackedSpan),
+    /// Undeclared payee, with the span of the posting overriding the payee if any.
+    #[error("payee {0} is not declared")]
+    UndeclaredPayee(String, Option<TrackedSpan>),
+    /// Undeclared tag, with the span of the posting having the tag if any.
+    #[error("tag {0} is not declared")]
+    UndeclaredTag(String, Option<TrackedSpan>),
+    #[error("automated transaction posting must have amount")]
+    AutomatedPostingWithoutAmount(TrackedSpan),
     #[error("posting without commodity should not have exchange")]
     ZeroAmountWithExchange(TrackedSpan),
     #[error("cost or lot exchange must not be zero")]
@@ -63,39 +102,53 @@ pub enum BookKeepError {
     },
 }
 
+/// Posting contributing to [`BookKeepError::UnbalancedPostings`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnbalancedPosting {
+    /// Span of the posting.
+    pub span: TrackedSpan,
+    /// Amount the posting adds to the transaction balance, after the cost or lot conversion.
+    pub contribution: OwnedAmount,
+    /// Amount the posting must add instead, to balance the transaction.
+    pub needed: OwnedAmount,
+}
+
 impl BookKeepError {
-    /// Returns annotations corresponding to the given span.
-    pub(super) fn annotations<'arena>(
+    /// Returns labels pointing the error within the given `text`.
+    pub(super) fn labels<'arena>(
         &self,
         bump: &'arena Bump,
         parsed_span: &ParsedSpan,
         text: &str,
-    ) -> Vec<Annotation<'arena>> {
+    ) -> Vec<ErrorLabel<'arena>> {
         match self {
-            BookKeepError::EvalFailure(err, pos) => vec![
-                AnnotationKind::Primary
-                    .span(parsed_span.resolve(pos))
-                    .label(bumpalo::format!(in &bump, "{}", err).into_bump_str()),
-            ],
+            BookKeepError::EvalFailure(err, pos) => vec![ErrorLabel::primary(
+                parsed_span.resolve(pos),
+                bumpalo::format!(in &bump, "{}", err).into_bump_str(),
+            )],
             BookKeepError::BalanceFailure {
                 source: err,
                 account,
                 balance,
             } => vec![
-                AnnotationKind::Primary
-                    .span(parsed_span.resolve(balance))
-                    .label(bumpalo::format!(in &bump, "{}", err).into_bump_str()),
-                AnnotationKind::Context
-                    .span(parsed_span.resolve(account))
-                    .label(bumpalo::format!(in &bump, "{}", err.note()).into_bump_str()),
+                ErrorLabel::primary(
+                    parsed_span.resolve(balance),
+                    bumpalo::format!(in &bump, "{}", err).into_bump_str(),
+                ),
+                ErrorLabel::context(
+                    parsed_span.resolve(account),
+                    bumpalo::format!(in &bump, "{}", err.note()).into_bump_str(),
+                ),
             ],
             BookKeepError::UndeduciblePostingAmount(first, second) => vec![
-                AnnotationKind::Context
-                    .span(parsed_span.resolve(&first.span()))
-                    .label("first posting without constraints"),
-                AnnotationKind::Primary
-                    .span(parsed_span.resolve(&second.span()))
-                    .label("cannot deduce this posting"),
+                ErrorLabel::context(
+                    parsed_span.resolve(&first.span()),
+                    "first posting without constraints",
+                ),
+                ErrorLabel::primary(
+                    parsed_span.resolve(&second.span()),
+                    "cannot deduce this posting",
+                ),
             ],
             BookKeepError::BalanceAssertionFailure {
                 balance_span,
@@ -108,115 +161,380 @@ impl BookKeepError {
                     "computed balance: {}", computed,
                 );
                 vec![
-                    AnnotationKind::Primary
-                        .span(parsed_span.resolve(balance_span))
-                        .label("not match the computed balance"),
-                    AnnotationKind::Context
-                        .span(parsed_span.resolve(account_span))
-                        .label(msg.into_bump_str()),
+                    ErrorLabel::primary(
+                        parsed_span.resolve(balance_span),
+                        "not match the computed balance",
+                    ),
+                    ErrorLabel::context(parsed_span.resolve(account_span), msg.into_bump_str()),
                 ]
             }
-            BookKeepError::ZeroAmountWithExchange(exchange) => vec![
-                AnnotationKind::Primary
-                    .span(parsed_span.resolve(exchange))
-                    .label("absolute zero posting should not have exchange"),
-            ],
-            BookKeepError::ZeroExchangeRate(exchange) => vec![
-                AnnotationKind::Primary
-                    .span(parsed_span.resolve(exchange))
-                    .label("exchange with zero amount"),
-            ],
+            BookKeepError::UndeclaredAccount(_, account) => vec![ErrorLabel::primary(
+                parsed_span.resolve(account),
+                "not declared with \"account\" directive",
+            )],
+            BookKeepError::UndeclaredCommodity(_, amount) => vec![ErrorLabel::primary(
+                parsed_span.resolve(amount),
+                "not declared with \"commodity\" directive",
+            )],
+            BookKeepError::UndeclaredPayee(payee, posting) => {
+                let span = match posting {
+                    Some(posting) => comment_span(text, parsed_span.resolve(posting), payee),
+                    None => name_span(text, first_line(text, 0..text.len()), payee),
+                };
+                vec![ErrorLabel::primary(
+                    span,
+                    "not declared with \"payee\" directive",
+                )]
+            }
+            BookKeepError::UndeclaredTag(tag, posting) => {
+                let span = posting
+                    .as_ref()
+                    .map_or(0..text.len(), |posting| parsed_span.resolve(posting));
+                vec![ErrorLabel::primary(
+                    comment_span(text, span, tag),
+                    "not declared with \"tag\" directive",
+                )]
+            }
+            BookKeepError::AutomatedPostingWithoutAmount(account) => vec![ErrorLabel::primary(
+                parsed_span.resolve(account),
+                "posting without amount",
+            )],
+            BookKeepError::ZeroAmountWithExchange(exchange) => vec![ErrorLabel::primary(
+                parsed_span.resolve(exchange),
+                "absolute zero posting should not have exchange",
+            )],
+            BookKeepError::ZeroExchangeRate(exchange) => vec![ErrorLabel::primary(
+                parsed_span.resolve(exchange),
+                "exchange with zero amount",
+            )],
             BookKeepError::ExchangeWithAmountCommodity {
                 posting_amount,
                 exchange,
             } => vec![
-                AnnotationKind::Context
-                    .span(parsed_span.resolve(posting_amount))
-                    .label("posting amount"),
-                AnnotationKind::Primary
-                    .span(parsed_span.resolve(exchange))
-                    .label("exchange cannot have the same commodity with posting"),
+                ErrorLabel::context(parsed_span.resolve(posting_amount), "posting amount"),
+                ErrorLabel::primary(
+                    parsed_span.resolve(exchange),
+                    "exchange cannot have the same commodity with posting",
+                ),
             ],
-            _ => {
-                // TODO: Add more detailed error into this.
-                // Also, put these logic into BookKeepError.
-                // https://github.com/xkikeg/okane/issues/189
-                vec![
-                    AnnotationKind::Primary
-                        .span(0..text.len())
-                        .label("error occured"),
-                ]
+            BookKeepError::UnbalancedPostings { residual, postings } => {
+                if postings.is_empty() {
+                    let msg = bumpalo::format!(in &bump, "unbalanced by {}", residual);
+                    return vec![ErrorLabel::primary(0..text.len(), msg.into_bump_str())];
+                }
+                postings
+                    .iter()
+                    .map(|p| {
+                        let msg = bumpalo::format!(
+                            in &bump,
+                            "adds {}, while {} balances the transaction",
+                            p.contribution,
+                            p.needed,
+                        );
+                        ErrorLabel::primary(
+                            first_line(text, parsed_span.resolve(&p.span)),
+                            msg.into_bump_str(),
+                        )
+                    })
+                    .collect()
+            }
+            BookKeepError::InvalidAccountAlias(alias) => vec![ErrorLabel::primary(
+                alias_span(text, alias),
+                "alias already registered for an account",
+            )],
+            BookKeepError::InvalidCommodityAlias(alias) => vec![ErrorLabel::primary(
+                alias_span(text, alias),
+                "alias already registered for a commodity",
+            )],
+            BookKeepError::InvalidPayeeAlias(alias) => vec![ErrorLabel::primary(
+                alias_span(text, alias),
+                "alias already registered for a payee",
+            )],
+            BookKeepError::MetadataEvalFailure(..) => {
+                whole_text(text, "metadata cannot be evaluated")
+            }
+            BookKeepError::UnmatchedEndApplyAccount => {
+                whole_text(text, "no \"apply account\" to end")
+            }
+            BookKeepError::DuplicateCheckIn(_) => whole_text(text, "checked in twice"),
+            BookKeepError::UnmatchedCheckIn(_) => whole_text(text, "never checked out"),
+            BookKeepError::UnmatchedCheckOut(_) => whole_text(text, "not checked in"),
+            BookKeepError::AmbiguousCheckOut(_) => {
+                whole_text(text, "account must be specified to check out")
+            }
+            BookKeepError::CheckOutBeforeCheckIn { .. } => {
+                whole_text(text, "check out must be after the check in")
+            }
+            BookKeepError::InvalidAutomatedPredicate(_) => {
+                whole_text(text, "predicate cannot be parsed")
             }
         }
     }
 }
 
+/// Returns annotation over the whole `text`, for the error without any specific span.
+fn whole_text<'arena>(text: &str, label: &'arena str) -> Vec<ErrorLabel<'arena>> {
+    vec![ErrorLabel::primary(0..text.len(), label)]
+}
+
+/// Returns the first line of the `span` in `text`, without the trailing spaces.
+/// Useful to point out the posting without its metadata lines.
+fn first_line(text: &str, span: std::ops::Range<usize>) -> std::ops::Range<usize> {
+    let line = text[span.clone()].lines().next().unwrap_or_default();
+    span.start..span.start + line.trim_end().len()
+}
+
+/// Returns the span of the first `name` within `span` of `text`,
+/// or `span` itself if not found.
+fn name_span(text: &str, span: std::ops::Range<usize>, name: &str) -> std::ops::Range<usize> {
+    text[span.clone()]
+        .find(name)
+        .map(|start| span.start + start..span.start + start + name.len())
+        .unwrap_or(span)
+}
+
+/// Returns the span of the first `name` written in the comment within `span` of `text`,
+/// or `span` itself if not found.
+fn comment_span(text: &str, span: std::ops::Range<usize>, name: &str) -> std::ops::Range<usize> {
+    text[span.clone()]
+        .match_indices(name)
+        .map(|(start, _)| span.start + start)
+        .find(|start| {
+            text[..*start]
+                .rsplit('\n')
+                .next()
+                .is_some_and(|line| line.contains(';'))
+        })
+        .map(|start| start..start + name.len())
+        .unwrap_or(span)
+}
+
+/// Returns the span of `alias` following the "alias" keyword in `text`,
+/// or the whole `text` if not found.
+fn alias_span(text: &str, alias: &str) -> std::ops::Range<usize> {
+    text.match_indices(alias)
+        .map(|(start, _)| start)
+        .find(|start| text[..*start].trim_end().ends_with("alias"))
+        .map(|start| start..start + alias.len())
+        .unwrap_or(0..text.len())
+}
+
 /// Adds a syntax transaction, and converts it into a processed Transaction.
+/// Postings generated by the matching `automated` transactions are added
+/// after the elided amounts are deduced, before balancing.
+/// Also returns the price events in the transaction,
+/// which the caller records only once it accepts the transaction.
 pub fn add_transaction<'ctx>(
     ctx: &mut ReportContext<'ctx>,
-    price_repos: &mut PriceRepositoryBuilder<'ctx>,
     bal: &mut Balance<'ctx>,
+    automated: &[AutomatedTransaction<'ctx>],
     txn: &syntax::tracked::Transaction,
-) -> Result<Transaction<'ctx>, BookKeepError> {
+) -> Result<(Transaction<'ctx>, Vec<PriceEvent<'ctx>>), BookKeepError> {
     // First, process all postings, except the one without balance and amount,
-    // which must be deduced later. And that should appear at most once.
+    // which must be deduced later. And that should appear at most once per balancing group.
+    // Real postings and balanced virtual postings are balanced separately,
+    // while unbalanced virtual postings are not balanced at all.
     let mut postings = bcc::Vec::with_capacity_in(txn.posts.len(), ctx.arena);
-    let mut unfilled: Option<Tracked<usize>> = None;
-    let mut balance = Amount::default();
-    let txn_payee: &'ctx str = ctx.arena.alloc_str(&txn.payee);
+    // Price events are returned only when the whole transaction is booked.
+    let mut price_events = Vec::new();
+    let mut real = BalanceGroup::default();
+    let mut balanced_virtual = BalanceGroup::default();
+    let txn_payee: &'ctx str = ctx.payees.ensure(&txn.payee).as_str();
+    let (txn_date, txn_effective_date) = item_dates(&txn.metadata, txn.date, txn.effective_date);
     for (i, posting) in txn.posts.iter().enumerate() {
         let posting = posting.as_undecorated();
         let account_span = posting.account.span();
-        let account = ctx.accounts.ensure(posting.account.as_undecorated());
+        let account = ctx
+            .accounts
+            .ensure_applied(posting.account.as_undecorated());
+        let mut group = match posting.kind {
+            syntax::PostingKind::Real => Some(&mut real),
+            syntax::PostingKind::BalancedVirtual => Some(&mut balanced_virtual),
+            syntax::PostingKind::Virtual => None,
+        };
         // Per-posting `; Payee: ...` metadata overrides the transaction payee.
         let payee: &'ctx str = posting_payee_override(ctx, posting).unwrap_or(txn_payee);
-        let (evaluated, price_event) = match process_posting(ctx, bal, txn.date, account, posting)?
+        let (date, effective_date) = item_dates(&posting.metadata, txn_date, txn_effective_date);
+        let (evaluated, price_event) = match process_posting(ctx, bal, txn_date, account, posting)?
         {
             (Some(x), y) => (x, y),
             (None, y) => {
-                if let Some(first) = unfilled.replace(Tracked::new(i, account_span.clone())) {
+                if let Some(group) = group.as_deref_mut()
+                    && let Some(first) = group
+                        .unfilled
+                        .replace(Tracked::new(i, account_span.clone()))
+                {
                     return Err(BookKeepError::UndeduciblePostingAmount(
                         first,
                         Tracked::new(i, account_span.clone()),
                     ));
-                } else {
-                    // placeholder which will be replaced later.
-                    // balance_delta is also zero as we don't know the value yet.
-                    (
-                        EvaluatedPosting {
-                            amount: PostingAmount::zero(),
-                            converted_amount: None,
-                            balance_delta: PostingAmount::zero(),
-                        },
-                        y,
-                    )
                 }
+                // placeholder which will be replaced later.
+                // balance_delta is also zero as we don't know the value yet.
+                // Unbalanced virtual posting without amount is simply kept as zero.
+                (
+                    EvaluatedPosting {
+                        amount: PostingAmount::zero(),
+                        converted_amount: None,
+                        balance_delta: PostingAmount::zero(),
+                    },
+                    y,
+                )
             }
         };
-        if let Some(event) = price_event {
-            price_repos.insert_price(PriceSource::Ledger, event);
+        price_events.extend(price_event);
+        if let Some(group) = group {
+            group.balance += evaluated.balance_delta;
+            group
+                .contributions
+                .push((txn.posts[i].span(), evaluated.balance_delta));
         }
-        balance += evaluated.balance_delta;
         postings.push(Posting {
             account,
+            kind: posting.kind,
             payee,
+            date,
+            effective_date,
             amount: evaluated.amount.into(),
             converted_amount: evaluated.converted_amount,
+            metadata: eval_metadata(ctx, &posting.metadata)?,
         });
     }
-    if let Some(u) = unfilled {
-        let u = *u.as_undecorated();
-        // Note that deduced amount can be multi-commodity, neither SingleAmount nor PostingAmount.
-        let deduced: Amount = balance.negate();
-        postings[u].amount = deduced.clone();
-        bal.add_amount(postings[u].account, deduced);
-    } else {
-        check_balance(ctx, price_repos, &mut postings, txn.date, balance)?;
+    for group in [&mut real, &mut balanced_virtual] {
+        if let Some(u) = &group.unfilled {
+            let u = *u.as_undecorated();
+            // Note that deduced amount can be multi-commodity, neither SingleAmount nor PostingAmount.
+            let deduced: Amount = std::mem::take(&mut group.balance).negate();
+            postings[u].amount = deduced.clone();
+            bal.add_amount(postings[u].account, deduced);
+        }
     }
-    Ok(Transaction {
-        date: txn.date,
+    // Automated transactions match the postings with the deduced amounts,
+    // so the generated postings must balance among themselves.
+    let mut generated = Vec::new();
+    for posting in postings.iter() {
+        let amounts: Vec<PostingAmount> = match (&posting.amount).try_into() {
+            Ok(amount) => vec![amount],
+            // Deduced multi-commodity amount is matched per commodity.
+            Err(_) => posting.amount.iter().map(Into::into).collect(),
+        };
+        for amount in amounts {
+            for rule in automated {
+                generated.extend(rule.generate(posting.account, amount).map(
+                    |(account, kind, amount)| {
+                        (
+                            account,
+                            kind,
+                            posting.payee,
+                            posting.date,
+                            posting.effective_date,
+                            amount,
+                        )
+                    },
+                ));
+            }
+        }
+    }
+    for (account, kind, payee, date, effective_date, amount) in generated {
+        bal.add_posting_amount(account, amount);
+        match kind {
+            syntax::PostingKind::Real => real.balance += amount,
+            syntax::PostingKind::BalancedVirtual => balanced_virtual.balance += amount,
+            syntax::PostingKind::Virtual => (),
+        }
+        postings.push(Posting {
+            account,
+            kind,
+            payee,
+            date,
+            effective_date,
+            amount: amount.into(),
+            converted_amount: None,
+            metadata: Vec::new(),
+        });
+    }
+    for (kind, group) in [
+        (syntax::PostingKind::Real, real),
+        (syntax::PostingKind::BalancedVirtual, balanced_virtual),
+    ] {
+        check_balance(ctx, &mut price_events, &mut postings, kind, txn_date, group)?;
+    }
+    let metadata = eval_metadata(ctx, &txn.metadata)?;
+    let added = Transaction {
+        date: txn_date,
+        effective_date: txn_effective_date,
         postings: postings.into_boxed_slice(),
-    })
+        metadata,
+    };
+    Ok((added, price_events))
+}
+
+/// Returns the date and the effective date of the item,
+/// overridden by the `[date=effective_date]` metadata if any.
+fn item_dates(
+    metadata: &[syntax::Metadata],
+    date: NaiveDate,
+    effective_date: Option<NaiveDate>,
+) -> (NaiveDate, Option<NaiveDate>) {
+    metadata.iter().fold(
+        (date, effective_date),
+        |(date, effective_date), m| match m {
+            syntax::Metadata::Dates {
+                date: d,
+                effective_date: e,
+            } => (d.unwrap_or(date), e.or(effective_date)),
+            _ => (date, effective_date),
+        },
+    )
+}
+
+/// Evaluates all `::` metadata values in the given `metadata`.
+fn eval_metadata<'ctx>(
+    ctx: &mut ReportContext<'ctx>,
+    metadata: &[syntax::Metadata<'_>],
+) -> Result<Vec<(&'ctx str, MetadataValue<'ctx>)>, BookKeepError> {
+    let mut ret = Vec::new();
+    for m in metadata {
+        if let syntax::Metadata::KeyValueTag { key, value } = m
+            && let Some(value) = eval_metadata_value(ctx, key, value)?
+        {
+            ret.push((&*ctx.arena.alloc_str(key), value));
+        }
+    }
+    Ok(ret)
+}
+
+/// Evaluates the given metadata value.
+/// Returns `None` if the value is a plain text, not prefixed with `::`.
+pub(super) fn eval_metadata_value<'ctx>(
+    ctx: &mut ReportContext<'ctx>,
+    key: &str,
+    value: &syntax::MetadataValue<'_>,
+) -> Result<Option<MetadataValue<'ctx>>, BookKeepError> {
+    match value {
+        syntax::MetadataValue::Text(_) => Ok(None),
+        syntax::MetadataValue::Date(date) => Ok(Some(MetadataValue::Date(*date))),
+        syntax::MetadataValue::Expr(expr) => match expr.eval_mut(ctx) {
+            Ok(Evaluated::Number(x)) => Ok(Some(MetadataValue::Number(x))),
+            Ok(Evaluated::Commodities(x)) => Ok(Some(MetadataValue::Amount(x))),
+            Err(e) => Err(BookKeepError::MetadataEvalFailure(
+                key.to_string(),
+                e.into_owned(ctx),
+            )),
+        },
+    }
+}
+
+/// Postings which must balance together within the transaction.
+#[derive(Default)]
+struct BalanceGroup<'ctx> {
+    /// Sum of the postings in the group.
+    balance: Amount<'ctx>,
+    /// Posting whose amount must be deduced from the other postings in the group.
+    unfilled: Option<Tracked<usize>>,
+    /// Span and balance delta of each posting written in the group,
+    /// to point out the postings for unbalanced error.
+    contributions: Vec<(TrackedSpan, PostingAmount<'ctx>)>,
 }
 
 /// Computed amount of [`Posting`],
@@ -255,6 +573,7 @@ fn process_posting<'ctx>(
             let current: PostingAmount = balance_constraints
                 .as_undecorated()
                 .eval_mut(ctx)
+                .map(|x| x.with_default_commodity(ctx.commodities.default_commodity()))
                 .and_then(|x| x.try_into())
                 .map_err(|e| map_eval_err(ctx, e, balance_span.clone()))?;
             let prev: PostingAmount = bal.set_partial(ctx, account, current).map_err(|e| {
@@ -285,6 +604,7 @@ fn process_posting<'ctx>(
                 let expected: PostingAmount = balance_constraints
                     .as_undecorated()
                     .eval_mut(ctx)
+                    .map(|x| x.with_default_commodity(ctx.commodities.default_commodity()))
                     .and_then(|x| x.try_into())
                     .map_err(|e| map_eval_err(ctx, e, balance_span.clone()))?;
                 let diff = current.assert_balance(&expected);
@@ -304,7 +624,7 @@ fn process_posting<'ctx>(
                     converted_amount: computed.calculate_converted_amount(ctx)?,
                     balance_delta,
                 }),
-                posting_price_event(date, &computed)?,
+                posting_price_event(ctx, date, &computed)?,
             ))
         }
     }
@@ -329,6 +649,7 @@ impl<'ctx> ComputedPosting<'ctx> {
             .amount
             .as_undecorated()
             .eval_mut(ctx)
+            .map(|x| x.with_default_commodity(ctx.commodities.default_commodity()))
             .and_then(|x| x.try_into())
             .map_err(|e| map_eval_err(ctx, e, amount_span.clone()))?;
         let cost = posting_cost_exchange(syntax_amount)
@@ -388,7 +709,9 @@ impl<'ctx> ComputedPosting<'ctx> {
 }
 
 /// Adds the price of the commodity in the posting into PriceRepositoryBuilder.
+/// Commodities declared as "nomarket" are skipped.
 fn posting_price_event<'ctx>(
+    ctx: &ReportContext<'ctx>,
     date: NaiveDate,
     computed: &ComputedPosting<'ctx>,
 ) -> Result<Option<PriceEvent<'ctx>>, BookKeepError> {
@@ -401,6 +724,9 @@ fn posting_price_event<'ctx>(
         PostingAmount::Zero => {
             unreachable!("Given Exchange is set None, this must be SingleAmount.")
         }
+        PostingAmount::Single(amount) if ctx.commodities.is_nomarket(amount.commodity) => {
+            return Ok(None);
+        }
         PostingAmount::Single(amount) => match exchange {
             Exchange::Rate(rate) => PriceEvent {
                 price_x: SingleAmount::from_value(amount.commodity, Decimal::ONE),
@@ -440,6 +766,7 @@ impl<'ctx> Exchange<'ctx> {
             syntax::Exchange::Rate(rate) => {
                 let rate: SingleAmount<'ctx> = rate
                     .eval_mut(ctx)
+                    .map(|x| x.with_default_commodity(ctx.commodities.default_commodity()))
                     .and_then(|x| x.try_into())
                     .map_err(|e| map_eval_err(ctx, e, exchange.span()))?;
                 (rate.commodity, Exchange::Rate(rate))
@@ -447,6 +774,7 @@ impl<'ctx> Exchange<'ctx> {
             syntax::Exchange::Total(rate) => {
                 let rate: SingleAmount<'ctx> = rate
                     .eval_mut(ctx)
+                    .map(|x| x.with_default_commodity(ctx.commodities.default_commodity()))
                     .and_then(|x| x.try_into())
                     .map_err(|e| map_eval_err(ctx, e, exchange.span()))?;
                 (rate.commodity, Exchange::Total(rate))
@@ -483,11 +811,13 @@ impl<'ctx> Exchange<'ctx> {
 /// Checks if the posting amounts sum to zero.
 fn check_balance<'ctx>(
     ctx: &ReportContext<'ctx>,
-    price_repos: &mut PriceRepositoryBuilder<'ctx>,
+    price_events: &mut Vec<PriceEvent<'ctx>>,
     postings: &mut bcc::Vec<'ctx, Posting<'ctx>>,
+    kind: syntax::PostingKind,
     date: NaiveDate,
-    balance: Amount<'ctx>,
+    group: BalanceGroup<'ctx>,
 ) -> Result<(), BookKeepError> {
+    let balance = group.balance;
     log::trace!(
         "balance before rounding in txn: {}",
         balance.as_inline_display(ctx)
@@ -498,7 +828,7 @@ fn check_balance<'ctx>(
     }
     if let Some((a1, a2)) = balance.maybe_pair() {
         // fill in converted amount.
-        for p in postings.iter_mut() {
+        for p in postings.iter_mut().filter(|p| p.kind == kind) {
             let amount: Result<SingleAmount<'_>, _> = (&p.amount).try_into();
             if let Ok(amount) = amount {
                 // amount can be PostingAmount::Zero, or even multi commodities (in rare cases).
@@ -516,24 +846,37 @@ fn check_balance<'ctx>(
                 }
             }
         }
-        // deduced price is logged to price repository.
-        price_repos.insert_price(
-            PriceSource::Ledger,
-            PriceEvent {
+        // deduced price is logged to price repository, unless either is "nomarket".
+        if !ctx.commodities.is_nomarket(a1.commodity) && !ctx.commodities.is_nomarket(a2.commodity)
+        {
+            price_events.push(PriceEvent {
                 date,
                 price_x: a1.abs(),
                 price_y: a2.abs(),
-            },
-        );
+            });
+        }
         return Ok(());
     }
-    if !balance.is_zero() {
-        return Err(BookKeepError::UnbalancedPostings(format!(
-            "{}",
-            balance.as_inline_display(ctx)
-        )));
-    }
-    Ok(())
+    let postings = group
+        .contributions
+        .into_iter()
+        .filter_map(|(span, delta)| {
+            let PostingAmount::Single(delta) = delta else {
+                return None;
+            };
+            let residual = balance.get(delta.commodity)?;
+            let needed = SingleAmount::from_value(delta.commodity, delta.value - residual);
+            Some(UnbalancedPosting {
+                span,
+                contribution: Amount::from(delta).to_owned_lossy(ctx),
+                needed: Amount::from(needed).to_owned_lossy(ctx),
+            })
+        })
+        .collect();
+    Err(BookKeepError::UnbalancedPostings {
+        residual: balance.to_owned_lossy(ctx),
+        postings,
+    })
 }
 
 /// Returns cost Exchange of the posting.
@@ -555,14 +898,14 @@ fn posting_lot_exchange<'a, 'ctx>(
 /// Returns the per-posting payee override (`; Payee: <name>` metadata), if any,
 /// interned into the report arena.
 fn posting_payee_override<'ctx>(
-    ctx: &ReportContext<'ctx>,
+    ctx: &mut ReportContext<'ctx>,
     posting: &syntax::tracked::Posting,
 ) -> Option<&'ctx str> {
     posting.metadata.iter().find_map(|m| match m {
         syntax::Metadata::KeyValueTag {
             key,
             value: syntax::MetadataValue::Text(t),
-        } if key.as_ref() == "Payee" => Some(ctx.arena.alloc_str(t.as_ref()) as &'ctx str),
+        } if key.as_ref() == "Payee" => Some(ctx.payees.ensure(t.as_ref()).as_str()),
         _ => None,
     })
 }
@@ -580,6 +923,7 @@ mod tests {
 
     use crate::{
         parse::{self, testing::expect_parse_ok},
+        report::price_db::{PriceRepositoryBuilder, PriceSource},
         syntax::tracked::TrackedSpan,
     };
 
@@ -588,6 +932,26 @@ mod tests {
         ret
     }
 
+    /// Calls [`add_transaction`], and records the returned prices into `price_repos`.
+    fn add_recording_prices<'ctx>(
+        ctx: &mut ReportContext<'ctx>,
+        price_repos: &mut PriceRepositoryBuilder<'ctx>,
+        bal: &mut Balance<'ctx>,
+        automated: &[AutomatedTransaction<'ctx>],
+        txn: &syntax::tracked::Transaction,
+    ) -> Result<Transaction<'ctx>, BookKeepError> {
+        let (added, price_events) = add_transaction(ctx, bal, automated, txn)?;
+        for event in price_events {
+            price_repos.insert_price(PriceSource::Ledger, event);
+        }
+        Ok(added)
+    }
+
+    fn parse_automated_transaction(input: &'_ str) -> syntax::tracked::AutomatedTransaction<'_> {
+        let (_, ret) = expect_parse_ok(parse::directive::automated_transaction, input);
+        ret
+    }
+
     #[test]
     fn add_transaction_fails_with_inconsistent_balance() {
         let arena = Bump::new();
@@ -605,7 +969,8 @@ mod tests {
         let txn = parse_transaction(input);
         let mut price_repos = PriceRepositoryBuilder::default();
 
-        let got_err = add_transaction(&mut ctx, &mut price_repos, &mut bal, &txn).unwrap_err();
+        let got_err =
+            add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn).unwrap_err();
 
         assert!(
             matches!(got_err, BookKeepError::BalanceAssertionFailure { .. }),
@@ -631,7 +996,8 @@ mod tests {
         let txn = parse_transaction(input);
         let mut price_repos = PriceRepositoryBuilder::default();
 
-        let got_err = add_transaction(&mut ctx, &mut price_repos, &mut bal, &txn).unwrap_err();
+        let got_err =
+            add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn).unwrap_err();
 
         assert!(
             matches!(got_err, BookKeepError::BalanceAssertionFailure { .. }),
@@ -673,7 +1039,8 @@ mod tests {
         let txn = parse_transaction(input);
         let mut price_repos = PriceRepositoryBuilder::default();
 
-        let _ = add_transaction(&mut ctx, &mut price_repos, &mut bal, &txn).expect("must succeed");
+        let _ = add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn)
+            .expect("must succeed");
 
         let want_balance: Balance = hashmap! {
             ctx.accounts.ensure("Account 1") =>
@@ -713,39 +1080,151 @@ mod tests {
         let txn = parse_transaction(input);
         let mut price_repos = PriceRepositoryBuilder::default();
 
-        let got =
-            add_transaction(&mut ctx, &mut price_repos, &mut bal, &txn).expect("must succeed");
+        let got = add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn)
+            .expect("must succeed");
 
         let want = Transaction {
             date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+            effective_date: None,
             postings: bcc::Vec::from_iter_in(
                 [
                     Posting {
                         account: ctx.accounts.ensure("Account 1"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 1",
+                        date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+                        effective_date: None,
                         amount: Amount::from_value(ctx.commodities.ensure("JPY"), dec!(200)),
                         converted_amount: None,
+                        metadata: Vec::new(),
                     },
                     Posting {
                         account: ctx.accounts.ensure("Account 2"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 2",
+                        date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+                        effective_date: None,
                         amount: Amount::from_value(ctx.commodities.ensure("JPY"), dec!(-100)),
                         converted_amount: None,
+                        metadata: Vec::new(),
                     },
                     Posting {
                         account: ctx.accounts.ensure("Account 2"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 3",
+                        date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+                        effective_date: None,
                         amount: Amount::from_value(ctx.commodities.ensure("JPY"), dec!(-100)),
                         converted_amount: None,
+                        metadata: Vec::new(),
                     },
                 ],
                 &arena,
             )
             .into_boxed_slice(),
+            metadata: Vec::new(),
         };
         assert_eq!(want, got);
     }
 
+    #[test]
+    fn add_transaction_evaluates_typed_metadata() {
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let mut bal = Balance::default();
+        let input = indoc! {"
+            2024/08/01 Sample
+              ; Due:: [2024/09/01]
+              ; Note: free text is not kept
+              Assets:Broker      10 AAPL
+                ; Shares:: (5 AAPL * 2)
+              Equity
+        "};
+        let txn = parse_transaction(input);
+        let mut price_repos = PriceRepositoryBuilder::default();
+
+        let got = add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn)
+            .expect("must succeed");
+
+        let aapl = ctx.commodities.resolve("AAPL").unwrap();
+        assert_eq!(
+            vec![(
+                "Due",
+                MetadataValue::Date(NaiveDate::from_ymd_opt(2024, 9, 1).unwrap())
+            )],
+            got.metadata
+        );
+        assert_eq!(
+            vec![(
+                "Shares",
+                MetadataValue::Amount(Amount::from_value(aapl, dec!(10)))
+            )],
+            got.postings[0].metadata
+        );
+        assert_eq!(
+            Vec::<(&str, MetadataValue)>::new(),
+            got.postings[1].metadata
+        );
+    }
+
+    #[test]
+    fn add_transaction_keeps_posting_dates() {
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let mut bal = Balance::default();
+        let input = indoc! {"
+            2024/08/01=2024/08/03 Sample
+              Liabilities:Card      -10 CHF
+                ; [=2024/08/25]
+              Expenses:Food          6 CHF
+                ; [2024/07/31]
+              Expenses:Drink
+        "};
+        let txn = parse_transaction(input);
+        let mut price_repos = PriceRepositoryBuilder::default();
+
+        let got = add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn)
+            .expect("must succeed");
+
+        let date = |m, d| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+        assert_eq!(date(8, 1), got.date);
+        assert_eq!(Some(date(8, 3)), got.effective_date);
+        assert_eq!(
+            vec![
+                (date(8, 1), Some(date(8, 25))),
+                (date(7, 31), Some(date(8, 3))),
+                (date(8, 1), Some(date(8, 3))),
+            ],
+            got.postings
+                .iter()
+                .map(|p| (p.date, p.effective_date))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn add_transaction_fails_on_invalid_metadata_expr() {
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let mut bal = Balance::default();
+        let input = indoc! {"
+            2024/08/01 Sample
+              ; Ratio:: 1 JPY / 0
+              Assets:Broker      10 AAPL
+              Equity
+        "};
+        let txn = parse_transaction(input);
+        let mut price_repos = PriceRepositoryBuilder::default();
+
+        let got =
+            add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn).unwrap_err();
+
+        assert!(
+            matches!(&got, BookKeepError::MetadataEvalFailure(key, _) if key == "Ratio"),
+            "unexpected error: {got:?}"
+        );
+    }
+
     #[test]
     fn add_transaction_emits_transaction_with_deduce_and_balance_concern() {
         let arena = Bump::new();
@@ -783,41 +1262,59 @@ mod tests {
         let txn = parse_transaction(input);
         let mut price_repos = PriceRepositoryBuilder::default();
 
-        let got =
-            add_transaction(&mut ctx, &mut price_repos, &mut bal, &txn).expect("must succeed");
+        let got = add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn)
+            .expect("must succeed");
 
         let want = Transaction {
             date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+            effective_date: None,
             postings: bcc::Vec::from_iter_in(
                 [
                     Posting {
                         account: ctx.accounts.ensure("Account 1"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 1",
+                        date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+                        effective_date: None,
                         amount: Amount::from_value(jpy, dec!(200)),
                         converted_amount: None,
+                        metadata: Vec::new(),
                     },
                     Posting {
                         account: ctx.accounts.ensure("Account 2"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 2",
+                        date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+                        effective_date: None,
                         amount: Amount::from_value(jpy, dec!(100)),
                         converted_amount: None,
+                        metadata: Vec::new(),
                     },
                     Posting {
                         account: ctx.accounts.ensure("Account 3"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 3",
+                        date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+                        effective_date: None,
                         amount: Amount::from_value(jpy, dec!(150)),
                         converted_amount: None,
+                        metadata: Vec::new(),
                     },
                     Posting {
                         account: ctx.accounts.ensure("Account 4"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 4",
+                        date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+                        effective_date: None,
                         amount: Amount::from_value(jpy, dec!(-450)),
                         converted_amount: None,
+                        metadata: Vec::new(),
                     },
                 ],
                 &arena,
             )
             .into_boxed_slice(),
+            metadata: Vec::new(),
         };
         assert_eq!(want, got);
     }
@@ -839,44 +1336,62 @@ mod tests {
         "};
         let txn = parse_transaction(input);
         let mut price_repos = PriceRepositoryBuilder::default();
-        let got =
-            add_transaction(&mut ctx, &mut price_repos, &mut bal, &txn).expect("must succeed");
+        let got = add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn)
+            .expect("must succeed");
         let want = Transaction {
             date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+            effective_date: None,
             postings: bcc::Vec::from_iter_in(
                 [
                     Posting {
                         account: ctx.accounts.ensure("Account 1"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 1",
+                        date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+                        effective_date: None,
                         amount: Amount::from_value(jpy, dec!(1200)),
                         converted_amount: None,
+                        metadata: Vec::new(),
                     },
                     Posting {
                         account: ctx.accounts.ensure("Account 2"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 2",
+                        date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+                        effective_date: None,
                         amount: Amount::from_value(eur, dec!(234)),
                         converted_amount: None,
+                        metadata: Vec::new(),
                     },
                     Posting {
                         account: ctx.accounts.ensure("Account 3"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 3",
+                        date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+                        effective_date: None,
                         amount: Amount::from_value(chf, dec!(34.56)),
                         converted_amount: None,
+                        metadata: Vec::new(),
                     },
                     Posting {
                         account: ctx.accounts.ensure("Account 4"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 4",
+                        date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+                        effective_date: None,
                         amount: Amount::from_iter([
                             (jpy, dec!(-1200)),
                             (eur, dec!(-234)),
                             (chf, dec!(-34.56)),
                         ]),
                         converted_amount: None,
+                        metadata: Vec::new(),
                     },
                 ],
                 &arena,
             )
             .into_boxed_slice(),
+            metadata: Vec::new(),
         };
         assert_eq!(want, got);
     }
@@ -894,8 +1409,8 @@ mod tests {
         let mut bal = Balance::default();
         let mut price_repos = PriceRepositoryBuilder::default();
 
-        let got =
-            add_transaction(&mut ctx, &mut price_repos, &mut bal, &txn).expect_err("must fail");
+        let got = add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn)
+            .expect_err("must fail");
 
         assert_eq!(
             got,
@@ -918,8 +1433,8 @@ mod tests {
         let mut bal = Balance::default();
         let mut price_repos = PriceRepositoryBuilder::default();
 
-        let got =
-            add_transaction(&mut ctx, &mut price_repos, &mut bal, &txn).expect_err("must fail");
+        let got = add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn)
+            .expect_err("must fail");
 
         assert_eq!(
             got,
@@ -941,31 +1456,41 @@ mod tests {
         let txn = parse_transaction(input);
         let mut price_repos = PriceRepositoryBuilder::default();
 
-        let got =
-            add_transaction(&mut ctx, &mut price_repos, &mut bal, &txn).expect("must succeed");
+        let got = add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn)
+            .expect("must succeed");
 
         let okane = ctx.commodities.resolve("OKANE").unwrap();
         let jpy = ctx.commodities.resolve("JPY").unwrap();
         let want = Transaction {
             date,
+            effective_date: None,
             postings: bcc::Vec::from_iter_in(
                 [
                     Posting {
                         account: ctx.accounts.ensure("Account 1"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 1",
+                        date,
+                        effective_date: None,
                         amount: Amount::from_value(okane, dec!(12)),
                         converted_amount: Some(SingleAmount::from_value(jpy, dec!(1200))),
+                        metadata: Vec::new(),
                     },
                     Posting {
                         account: ctx.accounts.ensure("Account 2"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 2",
+                        date,
+                        effective_date: None,
                         amount: Amount::from_value(jpy, dec!(-1200)),
                         converted_amount: None,
+                        metadata: Vec::new(),
                     },
                 ],
                 &arena,
             )
             .into_boxed_slice(),
+            metadata: Vec::new(),
         };
         assert_eq!(want, got);
 
@@ -999,29 +1524,39 @@ mod tests {
         let okane = ctx.commodities.ensure("OKANE");
         let jpy = ctx.commodities.ensure("JPY");
 
-        let got =
-            add_transaction(&mut ctx, &mut price_repos, &mut bal, &txn).expect("must succeed");
+        let got = add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn)
+            .expect("must succeed");
 
         let want = Transaction {
             date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+            effective_date: None,
             postings: bcc::Vec::from_iter_in(
                 [
                     Posting {
                         account: ctx.accounts.ensure("Account 1"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 1",
+                        date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+                        effective_date: None,
                         amount: Amount::from_value(okane, dec!(12)),
                         converted_amount: Some(SingleAmount::from_value(jpy, dec!(1200))),
+                        metadata: Vec::new(),
                     },
                     Posting {
                         account: ctx.accounts.ensure("Account 2"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 2",
+                        date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+                        effective_date: None,
                         amount: Amount::from_value(jpy, dec!(-1200)),
                         converted_amount: None,
+                        metadata: Vec::new(),
                     },
                 ],
                 &arena,
             )
             .into_boxed_slice(),
+            metadata: Vec::new(),
         };
         assert_eq!(want, got);
     }
@@ -1041,37 +1576,51 @@ mod tests {
         let txn = parse_transaction(input);
         let mut price_repos = PriceRepositoryBuilder::default();
 
-        let got =
-            add_transaction(&mut ctx, &mut price_repos, &mut bal, &txn).expect("must succeed");
+        let got = add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn)
+            .expect("must succeed");
 
         let okane = ctx.commodities.resolve("OKANE").unwrap();
         let jpy = ctx.commodities.resolve("JPY").unwrap();
         let want = Transaction {
             date,
+            effective_date: None,
             postings: bcc::Vec::from_iter_in(
                 [
                     Posting {
                         account: ctx.accounts.ensure("Account 1"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 1",
+                        date,
+                        effective_date: None,
                         amount: Amount::from_value(okane, dec!(-12)),
                         converted_amount: Some(SingleAmount::from_value(jpy, dec!(-1440))),
+                        metadata: Vec::new(),
                     },
                     Posting {
                         account: ctx.accounts.ensure("Account 2"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 2",
+                        date,
+                        effective_date: None,
                         amount: Amount::from_value(jpy, dec!(1440)),
                         converted_amount: None,
+                        metadata: Vec::new(),
                     },
                     Posting {
                         account: ctx.accounts.ensure("Income"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 3",
+                        date,
+                        effective_date: None,
                         amount: Amount::from_value(jpy, dec!(-240)),
                         converted_amount: None,
+                        metadata: Vec::new(),
                     },
                 ],
                 &arena,
             )
             .into_boxed_slice(),
+            metadata: Vec::new(),
         };
         assert_eq!(want, got);
 
@@ -1105,43 +1654,59 @@ mod tests {
         let txn = parse_transaction(input);
         let mut price_repos = PriceRepositoryBuilder::default();
 
-        let got =
-            add_transaction(&mut ctx, &mut price_repos, &mut bal, &txn).expect("must succeed");
+        let got = add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn)
+            .expect("must succeed");
 
         let okane = ctx.commodities.resolve("OKANE").unwrap();
         let jpy = ctx.commodities.resolve("JPY").unwrap();
         let want = Transaction {
             date,
+            effective_date: None,
             postings: bcc::Vec::from_iter_in(
                 [
                     Posting {
                         account: ctx.accounts.ensure("Account 1"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 1",
+                        date,
+                        effective_date: None,
                         amount: Amount::from_value(okane, dec!(-12)),
                         converted_amount: Some(SingleAmount::from_value(jpy, dec!(-1440))),
+                        metadata: Vec::new(),
                     },
                     Posting {
                         account: ctx.accounts.ensure("Account 2"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 2",
+                        date,
+                        effective_date: None,
                         amount: Amount::from_value(jpy, dec!(1000)),
                         converted_amount: Some(SingleAmount::from_value(
                             okane,
                             dec!(8.333333333333333333333333300),
                         )),
+
+                        metadata: Vec::new(),
                     },
                     Posting {
                         account: ctx.accounts.ensure("Account 3"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Posting 3",
+                        date,
+                        effective_date: None,
                         amount: Amount::from_value(jpy, dec!(440)),
                         converted_amount: Some(SingleAmount::from_value(
                             okane,
                             dec!(3.6666666666666666666666666520),
                         )),
+
+                        metadata: Vec::new(),
                     },
                 ],
                 &arena,
             )
             .into_boxed_slice(),
+            metadata: Vec::new(),
         };
         assert_eq!(want, got);
 
@@ -1160,6 +1725,32 @@ mod tests {
         assert_eq!(want_prices, price_repos.to_events());
     }
 
+    #[test]
+    fn add_transaction_skips_deduced_price_of_nomarket() {
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let okane = ctx.commodities.insert("OKANE").unwrap();
+        ctx.commodities.set_nomarket(okane);
+        let mut bal = Balance::default();
+        let input = indoc! {"
+            2024/08/01 Sample
+              Account 1            -12 OKANE
+              Account 2          1,440 JPY
+        "};
+        let txn = parse_transaction(input);
+        let mut price_repos = PriceRepositoryBuilder::default();
+
+        let got = add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn)
+            .expect("must succeed");
+
+        let jpy = ctx.commodities.resolve("JPY").unwrap();
+        assert_eq!(
+            Some(SingleAmount::from_value(jpy, dec!(-1440))),
+            got.postings[0].converted_amount
+        );
+        assert_eq!(Vec::<PriceEvent>::new(), price_repos.to_events());
+    }
+
     #[test]
     fn add_transaction_balances_minor_diff() {
         let arena = Bump::new();
@@ -1180,47 +1771,69 @@ mod tests {
         let txn = parse_transaction(input);
         let mut price_repos = PriceRepositoryBuilder::default();
 
-        let got =
-            add_transaction(&mut ctx, &mut price_repos, &mut bal, &txn).expect("must succeed");
+        let got = add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn)
+            .expect("must succeed");
         let eur = ctx.commodities.resolve("EUR").unwrap();
         let want = Transaction {
             date,
+            effective_date: None,
             postings: bcc::Vec::from_iter_in(
                 [
                     Posting {
                         account: ctx.accounts.ensure("Expenses:Travel:Petrol"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Petrol Station",
+                        date,
+                        effective_date: None,
                         amount: Amount::from_value(eur, dec!(30.33)),
                         converted_amount: Some(SingleAmount::from_value(chf, dec!(33.065766))),
+                        metadata: Vec::new(),
                     },
                     Posting {
                         account: ctx.accounts.ensure("Expenses:Commissions"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Bank",
+                        date,
+                        effective_date: None,
                         amount: Amount::from_value(chf, dec!(1.50)),
                         converted_amount: None,
+                        metadata: Vec::new(),
                     },
                     Posting {
                         account: ctx.accounts.ensure("Expenses:Commissions"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Bank",
+                        date,
+                        effective_date: None,
                         amount: Amount::from_value(eur, dec!(0.06)),
                         converted_amount: Some(SingleAmount::from_value(chf, dec!(0.065412))),
+                        metadata: Vec::new(),
                     },
                     Posting {
                         account: ctx.accounts.ensure("Expenses:Commissions"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Bank",
+                        date,
+                        effective_date: None,
                         amount: Amount::from_value(chf, dec!(0.07)),
                         converted_amount: None,
+                        metadata: Vec::new(),
                     },
                     Posting {
                         account: ctx.accounts.ensure("Assets:Banks"),
+                        kind: syntax::PostingKind::Real,
                         payee: "Petrol Station",
+                        date,
+                        effective_date: None,
                         amount: Amount::from_value(chf, dec!(-34.70)),
                         converted_amount: None,
+                        metadata: Vec::new(),
                     },
                 ],
                 &arena,
             )
             .into_boxed_slice(),
+            metadata: Vec::new(),
         };
         assert_eq!(want, got);
 
@@ -1248,4 +1861,239 @@ mod tests {
         ];
         assert_eq!(want_prices, price_repos.to_events());
     }
+
+    #[test]
+    fn add_transaction_adds_automated_postings() {
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let mut bal = Balance::default();
+        let automated = parse_automated_transaction(indoc! {"
+            = /^Expenses:Food/
+                Expenses:VAT                 0.2
+                Assets:VAT Reserve          -2 CHF
+        "});
+        let automated =
+            [AutomatedTransaction::from_syntax(&mut ctx, &automated).expect("must succeed")];
+        let input = indoc! {"
+            2024/08/01 Grocery
+              Expenses:Food                 10 CHF
+              Expenses:Household             5 CHF
+              Assets:Bank
+        "};
+        let txn = parse_transaction(input);
+        let mut price_repos = PriceRepositoryBuilder::default();
+
+        let got = add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &automated, &txn)
+            .expect("must succeed");
+
+        let chf = ctx.commodities.resolve("CHF").unwrap();
+        let posting = |account, value| Posting {
+            account,
+            kind: syntax::PostingKind::Real,
+            payee: "Grocery",
+            date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+            effective_date: None,
+            amount: Amount::from_value(chf, value),
+            converted_amount: None,
+            metadata: Vec::new(),
+        };
+        let want = Transaction {
+            date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+            effective_date: None,
+            postings: bcc::Vec::from_iter_in(
+                [
+                    posting(ctx.accounts.ensure("Expenses:Food"), dec!(10)),
+                    posting(ctx.accounts.ensure("Expenses:Household"), dec!(5)),
+                    posting(ctx.accounts.ensure("Assets:Bank"), dec!(-15)),
+                    posting(ctx.accounts.ensure("Expenses:VAT"), dec!(2.0)),
+                    posting(ctx.accounts.ensure("Assets:VAT Reserve"), dec!(-2)),
+                ],
+                &arena,
+            )
+            .into_boxed_slice(),
+            metadata: Vec::new(),
+        };
+        assert_eq!(want, got);
+    }
+
+    #[test]
+    fn add_transaction_matches_automated_postings_with_deduced_amount() {
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let mut bal = Balance::default();
+        let automated = parse_automated_transaction(indoc! {"
+            = /^Assets:Bank/
+                Expenses:Fees                0.01
+                Assets:Fee Reserve          -0.01
+        "});
+        let automated =
+            [AutomatedTransaction::from_syntax(&mut ctx, &automated).expect("must succeed")];
+        let input = indoc! {"
+            2024/08/01 Grocery
+              Expenses:Food                 10 CHF
+              Expenses:Household             5 CHF
+              Assets:Bank
+        "};
+        let txn = parse_transaction(input);
+        let mut price_repos = PriceRepositoryBuilder::default();
+
+        let got = add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &automated, &txn)
+            .expect("must succeed");
+
+        let chf = ctx.commodities.resolve("CHF").unwrap();
+        let posting = |account, value| Posting {
+            account,
+            kind: syntax::PostingKind::Real,
+            payee: "Grocery",
+            date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+            effective_date: None,
+            amount: Amount::from_value(chf, value),
+            converted_amount: None,
+            metadata: Vec::new(),
+        };
+        let want = Transaction {
+            date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+            effective_date: None,
+            postings: bcc::Vec::from_iter_in(
+                [
+                    posting(ctx.accounts.ensure("Expenses:Food"), dec!(10)),
+                    posting(ctx.accounts.ensure("Expenses:Household"), dec!(5)),
+                    posting(ctx.accounts.ensure("Assets:Bank"), dec!(-15)),
+                    posting(ctx.accounts.ensure("Expenses:Fees"), dec!(-0.15)),
+                    posting(ctx.accounts.ensure("Assets:Fee Reserve"), dec!(0.15)),
+                ],
+                &arena,
+            )
+            .into_boxed_slice(),
+            metadata: Vec::new(),
+        };
+        assert_eq!(want, got);
+        assert_eq!(
+            Some(&Amount::from_value(chf, dec!(0.15))),
+            bal.get(ctx.accounts.ensure("Assets:Fee Reserve"))
+        );
+    }
+
+    #[test]
+    fn add_transaction_balances_virtual_postings_separately() {
+        let input = indoc! {"
+            2024/08/01 Salary
+              Assets:Bank                 1000 CHF
+              Income:Salary
+              (Budget:Food)                200 CHF
+              [Savings:Goal]               300 CHF
+              [Savings:Available]
+        "};
+        let txn = parse_transaction(input);
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let mut bal = Balance::default();
+        let mut price_repos = PriceRepositoryBuilder::default();
+
+        let got = add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn)
+            .expect("must succeed");
+
+        let chf = ctx.commodities.resolve("CHF").unwrap();
+        let posting = |account, kind, value| Posting {
+            account,
+            kind,
+            payee: "Salary",
+            date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+            effective_date: None,
+            amount: Amount::from_value(chf, value),
+            converted_amount: None,
+            metadata: Vec::new(),
+        };
+        let want = Transaction {
+            date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+            effective_date: None,
+            postings: bcc::Vec::from_iter_in(
+                [
+                    posting(
+                        ctx.accounts.ensure("Assets:Bank"),
+                        syntax::PostingKind::Real,
+                        dec!(1000),
+                    ),
+                    posting(
+                        ctx.accounts.ensure("Income:Salary"),
+                        syntax::PostingKind::Real,
+                        dec!(-1000),
+                    ),
+                    posting(
+                        ctx.accounts.ensure("Budget:Food"),
+                        syntax::PostingKind::Virtual,
+                        dec!(200),
+                    ),
+                    posting(
+                        ctx.accounts.ensure("Savings:Goal"),
+                        syntax::PostingKind::BalancedVirtual,
+                        dec!(300),
+                    ),
+                    posting(
+                        ctx.accounts.ensure("Savings:Available"),
+                        syntax::PostingKind::BalancedVirtual,
+                        dec!(-300),
+                    ),
+                ],
+                &arena,
+            )
+            .into_boxed_slice(),
+            metadata: Vec::new(),
+        };
+        assert_eq!(want, got);
+    }
+
+    #[test]
+    fn add_transaction_fails_with_unbalanced_balanced_virtual_postings() {
+        let input = indoc! {"
+            2024/08/01 Salary
+              Assets:Bank                 1000 CHF
+              Income:Salary              -1000 CHF
+              [Savings:Goal]               300 CHF
+              [Savings:Available]         -200 CHF
+        "};
+        let txn = parse_transaction(input);
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let mut bal = Balance::default();
+        let mut price_repos = PriceRepositoryBuilder::default();
+
+        let got = add_recording_prices(&mut ctx, &mut price_repos, &mut bal, &[], &txn)
+            .expect_err("must fail");
+
+        let BookKeepError::UnbalancedPostings { residual, postings } = got else {
+            panic!("unexpected error: {got:?}");
+        };
+        assert_eq!("100 CHF", residual.to_string());
+        let chf = ctx.commodities.resolve("CHF").unwrap();
+        assert_eq!(
+            vec![(&chf.to_owned_lossy(&ctx.commodities), dec!(100))],
+            residual.values().collect::<Vec<_>>()
+        );
+        let got: Vec<(&str, String, String)> = postings
+            .iter()
+            .map(|p| {
+                (
+                    input[p.span.as_range()].trim_end(),
+                    p.contribution.to_string(),
+                    p.needed.to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (
+                    "[Savings:Goal]               300 CHF",
+                    "300 CHF".to_string(),
+                    "200 CHF".to_string()
+                ),
+                (
+                    "[Savings:Available]         -200 CHF",
+                    "-200 CHF".to_string(),
+                    "-300 CHF".to_string()
+                ),
+            ],
+            got
+        );
+    }
 }
@@ -114,7 +114,12 @@ impl<'ctx> CommodityTag<'ctx> {
 /// Manages [`Commodity`] instances.
 pub struct CommodityStore<'arena> {
     intern: DenseInternStore<'arena, Commodity<'arena>>,
+    /// Registered aliases in the registration order,
+    /// as [`DenseInternStore`] can't enumerate them.
+    aliases: Vec<(String, CommodityTag<'arena>)>,
     formatting: CommodityMap<PrettyDecimal>,
+    default: Option<CommodityTag<'arena>>,
+    nomarket: CommodityMap<()>,
 }
 
 impl<'arena> std::fmt::Debug for CommodityStore<'arena> {
@@ -130,7 +135,10 @@ impl<'arena> CommodityStore<'arena> {
     pub(super) fn new(arena: &'arena Bump) -> Self {
         Self {
             intern: DenseInternStore::new(arena),
+            aliases: Vec::new(),
             formatting: CommodityMap::new(),
+            default: None,
+            nomarket: CommodityMap::new(),
         }
     }
 
@@ -165,7 +173,25 @@ impl<'arena> CommodityStore<'arena> {
         value: &str,
         canonical: CommodityTag<'arena>,
     ) -> Result<(), OccupiedError<Commodity<'arena>>> {
-        self.intern.register_alias(Commodity(value), canonical.0)
+        let registered = self.intern.resolve(value).is_some();
+        self.intern.register_alias(Commodity(value), canonical.0)?;
+        if !registered {
+            self.aliases.push((value.to_string(), canonical));
+        }
+        Ok(())
+    }
+
+    /// Returns the [`Iterator`] for all commodities, in the order of the tag index.
+    pub(super) fn iter(&self) -> impl Iterator<Item = CommodityTag<'arena>> + '_ {
+        self.intern.iter().map(CommodityTag)
+    }
+
+    /// Returns the [`Iterator`] for the aliases and its canonical commodity,
+    /// in the registration order.
+    pub(super) fn aliases(&self) -> impl Iterator<Item = (&str, CommodityTag<'arena>)> + '_ {
+        self.aliases
+            .iter()
+            .map(|(alias, canonical)| (alias.as_str(), *canonical))
     }
 
     /// Returns the scale (the number of digits under decimal point) of the `commodity` if specified.
@@ -174,12 +200,43 @@ impl<'arena> CommodityStore<'arena> {
         self.formatting.get(commodity).map(|x| x.scale())
     }
 
+    /// Returns the format of the `commodity` as [`PrettyDecimal`] if specified.
+    #[inline]
+    pub(super) fn format(&self, commodity: CommodityTag<'arena>) -> Option<&PrettyDecimal> {
+        self.formatting.get(commodity)
+    }
+
     /// Sets the format of the `commodity` as [`PrettyDecimal`].
     #[inline]
     pub(super) fn set_format(&mut self, commodity: CommodityTag<'arena>, format: PrettyDecimal) {
         self.formatting.set(commodity, format);
     }
 
+    /// Returns the default commodity, used for the amounts without commodity.
+    #[inline]
+    pub fn default_commodity(&self) -> Option<CommodityTag<'arena>> {
+        self.default
+    }
+
+    /// Sets the default commodity, given by "D" directive or "default" sub-directive.
+    #[inline]
+    pub(super) fn set_default_commodity(&mut self, commodity: CommodityTag<'arena>) {
+        self.default = Some(commodity);
+    }
+
+    /// Returns if the `commodity` is declared as "nomarket",
+    /// where its price shouldn't be recorded from the postings.
+    #[inline]
+    pub fn is_nomarket(&self, commodity: CommodityTag<'arena>) -> bool {
+        self.nomarket.get(commodity).is_some()
+    }
+
+    /// Marks the `commodity` as "nomarket".
+    #[inline]
+    pub(super) fn set_nomarket(&mut self, commodity: CommodityTag<'arena>) {
+        self.nomarket.set(commodity, ());
+    }
+
     /// Returns if the commodity store is empty.
     #[inline]
     pub fn is_empty(&self) -> bool {
@@ -299,6 +356,22 @@ mod tests {
         assert_eq!(None, commodities.scale(jpy));
     }
 
+    #[test]
+    fn default_and_nomarket_commodity() {
+        let arena = Bump::new();
+        let mut commodities = CommodityStore::new(&arena);
+        let jpy = commodities.insert("JPY").unwrap();
+        let usd = commodities.insert("USD").unwrap();
+        assert_eq!(None, commodities.default_commodity());
+
+        commodities.set_default_commodity(jpy);
+        commodities.set_nomarket(usd);
+
+        assert_eq!(Some(jpy), commodities.default_commodity());
+        assert!(commodities.is_nomarket(usd));
+        assert!(!commodities.is_nomarket(jpy));
+    }
+
     #[test]
     fn scale_returns_some_if_set() {
         let arena = Bump::new();
@@ -4,6 +4,8 @@ use crate::report::AccountAggregate;
 
 use super::account::{Account, AccountStore, AccountTree};
 use super::commodity::{CommodityStore, CommodityTag};
+use super::payee::PayeeStore;
+use crate::syntax::display::DisplayContext;
 
 /// Context object extensively used across Ledger file evaluation.
 pub struct ReportContext<'ctx> {
@@ -11,6 +13,8 @@ pub struct ReportContext<'ctx> {
     pub(super) accounts: AccountStore<'ctx>,
     pub(super) account_tree: AccountTree<'ctx>,
     pub(super) commodities: CommodityStore<'ctx>,
+    pub(super) payees: PayeeStore<'ctx>,
+    pub(super) display_context: DisplayContext,
 }
 
 impl<'ctx> ReportContext<'ctx> {
@@ -19,11 +23,14 @@ impl<'ctx> ReportContext<'ctx> {
         let accounts = AccountStore::new(arena);
         let account_tree = AccountTree::new(arena);
         let commodities = CommodityStore::new(arena);
+        let payees = PayeeStore::new(arena);
         Self {
             arena,
             accounts,
             account_tree,
             commodities,
+            payees,
+            display_context: DisplayContext::default().keeping_scale(),
         }
     }
 
@@ -72,6 +79,13 @@ impl<'ctx> ReportContext<'ctx> {
     pub fn commodity_store_mut(&mut self) -> &mut CommodityStore<'ctx> {
         &mut self.commodities
     }
+
+    /// Returns [`DisplayContext`] built from the "commodity" and "D" directives,
+    /// used to print the amounts in the reports.
+    #[inline]
+    pub fn display_context(&self) -> &DisplayContext {
+        &self.display_context
+    }
 }
 
 #[cfg(test)]
@@ -1,11 +1,12 @@
 //! Defines error in report functions.
 
-use std::{fmt::Display, path::PathBuf};
+use std::{fmt::Display, ops::Range, path::PathBuf};
 
-use annotate_snippets::{Annotation, Level, Snippet};
+use annotate_snippets::{Annotation, AnnotationKind, Level, Snippet};
 use bumpalo::Bump;
 
 use crate::{
+    diagnostic::{Diagnostic, Severity, SourceText},
     load,
     parse::{self, ParsedSpan},
 };
@@ -23,6 +24,18 @@ pub enum ReportError {
     BookKeep(book_keeping::BookKeepError, Box<ErrorContext>),
 }
 
+impl ReportError {
+    /// Converts the error into the list of [`Diagnostic`].
+    /// It can be more than one, as [`load::LoadError`] may contain multiple syntax errors.
+    pub fn to_diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            ReportError::Load(err) => err.to_diagnostics(),
+            ReportError::PriceDB(err) => vec![err.to_diagnostic()],
+            ReportError::BookKeep(err, ctx) => vec![ctx.to_diagnostic(err)],
+        }
+    }
+}
+
 impl Display for ReportError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -33,10 +46,42 @@ impl Display for ReportError {
     }
 }
 
+/// Label pointing a part of the erroneous text.
+pub(super) struct ErrorLabel<'a> {
+    kind: AnnotationKind,
+    span: Range<usize>,
+    message: &'a str,
+}
+
+impl<'a> ErrorLabel<'a> {
+    /// Creates a label pointing the cause of the error.
+    pub(super) fn primary(span: Range<usize>, message: &'a str) -> Self {
+        Self {
+            kind: AnnotationKind::Primary,
+            span,
+            message,
+        }
+    }
+
+    /// Creates a label giving the context of the error.
+    pub(super) fn context(span: Range<usize>, message: &'a str) -> Self {
+        Self {
+            kind: AnnotationKind::Context,
+            span,
+            message,
+        }
+    }
+
+    fn into_annotation(self) -> Annotation<'a> {
+        self.kind.span(self.span).label(self.message)
+    }
+}
+
 /// Context of [ReportError], to carry the failure information.
 #[derive(Debug)]
 pub struct ErrorContext {
     renderer: annotate_snippets::Renderer,
+    severity: Severity,
     path: PathBuf,
     line_start: usize,
     text: String,
@@ -48,8 +93,16 @@ impl ErrorContext {
         let message = err.to_string();
         let path = self.path.to_string_lossy();
         let bump = Bump::new();
-        let annotations: Vec<Annotation> = err.annotations(&bump, &self.parsed_span, &self.text);
-        let message = Level::ERROR.primary_title(&message).element(
+        let annotations: Vec<Annotation> = err
+            .labels(&bump, &self.parsed_span, &self.text)
+            .into_iter()
+            .map(ErrorLabel::into_annotation)
+            .collect();
+        let level = match self.severity {
+            Severity::Error => Level::ERROR,
+            Severity::Warning => Level::WARNING,
+        };
+        let message = level.primary_title(&message).element(
             Snippet::source(&self.text)
                 .path(&path)
                 .line_start(self.line_start)
@@ -60,6 +113,33 @@ impl ErrorContext {
         rendered.fmt(f)
     }
 
+    fn to_diagnostic(&self, err: &BookKeepError) -> Diagnostic {
+        let bump = Bump::new();
+        let source = SourceText::new(&self.text, self.line_start);
+        let labels = err
+            .labels(&bump, &self.parsed_span, &self.text)
+            .into_iter()
+            .map(|label| {
+                source.label(
+                    label.span,
+                    label.message.to_string(),
+                    label.kind == AnnotationKind::Primary,
+                )
+            })
+            .collect();
+        let mut diagnostic = Diagnostic::from_error(err);
+        diagnostic.severity = self.severity;
+        diagnostic.path = Some(self.path.to_string_lossy().into_owned());
+        diagnostic.labels = labels;
+        diagnostic
+    }
+
+    /// Turns the context into a warning, rendered with the warning level.
+    pub(super) fn into_warning(mut self: Box<Self>) -> Box<Self> {
+        self.severity = Severity::Warning;
+        self
+    }
+
     pub(super) fn new(
         renderer: annotate_snippets::Renderer,
         path: PathBuf,
@@ -67,6 +147,7 @@ impl ErrorContext {
     ) -> Box<Self> {
         Box::new(Self {
             renderer,
+            severity: Severity::Error,
             path,
             line_start: pctx.compute_line_start(),
             text: pctx.as_str().to_owned(),
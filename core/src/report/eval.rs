@@ -11,7 +11,7 @@ mod evaluated;
 mod posting_amount;
 mod single_amount;
 
-pub use amount::Amount;
+pub use amount::{Amount, OwnedAmount};
 pub use error::{EvalError, OwnedEvalError};
 pub use evaluated::Evaluated;
 pub(super) use posting_amount::PostingAmount;
@@ -122,6 +122,7 @@ mod tests {
         let input = expr::ValueExpr::Amount(expr::Amount {
             value: PrettyDecimal::plain(dec!(100.12345)),
             commodity: "USD".into(),
+            style: expr::CommodityStyle::default(),
         });
         let arena = Bump::new();
         let mut ctx = ReportContext::new(&arena);
@@ -169,4 +170,16 @@ mod tests {
             got.into_values()
         );
     }
+
+    #[test]
+    fn eval_mut_prints_commodity_in_first_written_style() {
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let first: expr::ValueExpr = "($12.50 + 1 EUR)".try_into().unwrap();
+        let second: expr::ValueExpr = "(3 $ + 2 EUR)".try_into().unwrap();
+        let got: Amount<'_> = first.eval_mut(&mut ctx).unwrap().try_into().unwrap();
+        second.eval_mut(&mut ctx).unwrap();
+
+        assert_eq!("($12.50 + 1 EUR)", got.as_inline_display(&ctx).to_string());
+    }
 }
@@ -8,11 +8,11 @@ use std::{
 use rust_decimal::Decimal;
 
 use crate::report::{
-    commodity::{CommodityStore, CommodityTag},
+    commodity::{CommodityTag, OwnedCommodity},
     context::ReportContext,
 };
 
-use super::{PostingAmount, SingleAmount, error::EvalError};
+use super::{PostingAmount, SingleAmount, error::EvalError, single_amount::write_amount};
 
 /// Amount with multiple commodities, or simple zero.
 #[derive(Debug, Default, PartialEq, Eq, Clone)]
@@ -23,6 +23,29 @@ pub struct Amount<'ctx> {
     values: BTreeMap<CommodityTag<'ctx>, Decimal>,
 }
 
+/// Owned version of [`Amount`], to be embedded in the errors.
+///
+/// It keeps the display at the time of the conversion,
+/// so that it's still printed in the declared commodity format.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OwnedAmount {
+    values: Vec<(OwnedCommodity, Decimal)>,
+    display: String,
+}
+
+impl OwnedAmount {
+    /// Returns an iterator over the commodity and its value.
+    pub fn values(&self) -> impl Iterator<Item = (&OwnedCommodity, Decimal)> + '_ {
+        self.values.iter().map(|(c, v)| (c, *v))
+    }
+}
+
+impl Display for OwnedAmount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.display)
+    }
+}
+
 impl<'ctx> TryFrom<Amount<'ctx>> for SingleAmount<'ctx> {
     type Error = EvalError<'ctx>;
 
@@ -139,12 +162,27 @@ impl<'ctx> Amount<'ctx> {
     where
         'a: 'ctx,
     {
-        InlinePrintAmount {
-            commodity_store: &ctx.commodities,
-            amount: self,
+        InlinePrintAmount { ctx, amount: self }
+    }
+
+    /// Converts the self into [`OwnedAmount`].
+    /// Unregistered commodities are printed as "unknown#xx" like [`CommodityTag::to_owned_lossy`].
+    pub fn to_owned_lossy(&self, ctx: &ReportContext<'ctx>) -> OwnedAmount {
+        OwnedAmount {
+            values: self
+                .values
+                .iter()
+                .map(|(c, v)| (c.to_owned_lossy(&ctx.commodities), *v))
+                .collect(),
+            display: self.as_inline_display(ctx).to_string(),
         }
     }
 
+    /// Returns the value of the given `commodity`, if any.
+    pub fn get(&self, commodity: CommodityTag<'ctx>) -> Option<Decimal> {
+        self.values.get(&commodity).copied()
+    }
+
     /// Returns `true` if this is 'non-commoditized zero', which is used to assert
     /// the account balance is completely zero.
     pub fn is_absolute_zero(&self) -> bool {
@@ -281,9 +319,8 @@ impl<'ctx> Iterator for AmountIter<'_, 'ctx> {
 
 impl FusedIterator for AmountIter<'_, '_> {}
 
-#[derive(Debug)]
 struct InlinePrintAmount<'a, 'ctx> {
-    commodity_store: &'a CommodityStore<'ctx>,
+    ctx: &'a ReportContext<'ctx>,
     amount: &'a Amount<'ctx>,
 }
 
@@ -292,9 +329,7 @@ impl Display for InlinePrintAmount<'_, '_> {
         let vs = &self.amount.values;
         if vs.len() <= 1 {
             return match vs.iter().next() {
-                Some((c, v)) => {
-                    write!(f, "{} {}", v, c.to_str_lossy(self.commodity_store))
-                }
+                Some((c, v)) => write_amount(f, self.ctx, *c, *v),
                 None => write!(f, "0"),
             };
         }
@@ -310,7 +345,7 @@ impl Display for InlinePrintAmount<'_, '_> {
                     write!(f, " + ")?;
                 }
             }
-            write!(f, "{} {}", v, c.to_str_lossy(self.commodity_store))?;
+            write_amount(f, self.ctx, *c, v)?;
         }
         write!(f, ")")
     }
@@ -460,6 +495,57 @@ mod tests {
         assert_eq!(format!("{}", amount.as_inline_display(&ctx)), "123.45 JPY")
     }
 
+    #[test]
+    fn inline_display_follows_display_context() {
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let usd = ctx.commodities.ensure("$");
+        let jpy = ctx.commodities.ensure("JPY");
+        ctx.display_context
+            .apply_default_commodity(&crate::syntax::expr::Amount {
+                value: PrettyDecimal::comma3dot(dec!(1000.00)),
+                commodity: "$".into(),
+                style: crate::syntax::expr::CommodityStyle {
+                    position: crate::syntax::expr::CommodityPosition::Prefix,
+                    spaced: false,
+                },
+            });
+
+        let amount = Amount::from_iter([(usd, dec!(-1234.5)), (jpy, dec!(1234))]);
+        assert_eq!(
+            format!("{}", amount.as_inline_display(&ctx)),
+            "(-$1,234.50 + 1234 JPY)"
+        );
+    }
+
+    #[test]
+    fn inline_display_keeps_precision_higher_than_declared() {
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let usd = ctx.commodities.ensure("USD");
+        ctx.display_context
+            .apply_default_commodity(&crate::syntax::expr::Amount {
+                value: PrettyDecimal::comma3dot(dec!(1000.00)),
+                commodity: "USD".into(),
+                style: crate::syntax::expr::CommodityStyle::default(),
+            });
+
+        assert_eq!(
+            "-26,230.0000 USD",
+            format!(
+                "{}",
+                Amount::from_value(usd, dec!(-26230.0000)).as_inline_display(&ctx)
+            )
+        );
+        assert_eq!(
+            "26,230.50 USD",
+            format!(
+                "{}",
+                Amount::from_value(usd, dec!(26230.5)).as_inline_display(&ctx)
+            )
+        );
+    }
+
     #[test]
     fn test_from_values() {
         let arena = Bump::new();
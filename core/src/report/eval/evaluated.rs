@@ -3,7 +3,10 @@ use std::fmt::Display;
 use rust_decimal::Decimal;
 
 use crate::{
-    report::{ReportContext, commodity::OwnedCommodity},
+    report::{
+        ReportContext,
+        commodity::{CommodityTag, OwnedCommodity},
+    },
     syntax::expr,
 };
 
@@ -61,7 +64,7 @@ impl<'ctx> From<Amount<'ctx>> for Evaluated<'ctx> {
 
 impl<'ctx> Evaluated<'ctx> {
     /// Creates [`Evaluated`] from [`expr::Amount`],
-    /// with registering the commodity.
+    /// with registering the commodity and the style it's written in.
     pub(super) fn from_expr_amount_mut(
         ctx: &mut ReportContext<'ctx>,
         amount: &expr::Amount,
@@ -70,6 +73,8 @@ impl<'ctx> Evaluated<'ctx> {
             return amount.value.value.into();
         }
         let commodity = ctx.commodities.ensure(&amount.commodity);
+        ctx.display_context
+            .observe_commodity_style(&commodity.to_str_lossy(&ctx.commodities), amount.style);
         Amount::from_value(commodity, amount.value.into()).into()
     }
 
@@ -90,6 +95,17 @@ impl<'ctx> Evaluated<'ctx> {
         Ok(Amount::from_value(commodity, amount.value.into()).into())
     }
 
+    /// Returns the amount of `default` commodity if self is a non-zero number,
+    /// otherwise returns self as-is.
+    pub fn with_default_commodity(self, default: Option<CommodityTag<'ctx>>) -> Self {
+        match (self, default) {
+            (Evaluated::Number(x), Some(commodity)) if !x.is_zero() => {
+                Amount::from_value(commodity, x).into()
+            }
+            (x, _) => x,
+        }
+    }
+
     /// Returns if the amount is zero.
     pub fn is_zero(&self) -> bool {
         match self {
@@ -1,4 +1,6 @@
-use std::ops::Neg;
+use std::ops::{Mul, Neg};
+
+use rust_decimal::Decimal;
 
 #[cfg(test)]
 use crate::report::ReportContext;
@@ -41,6 +43,17 @@ impl Neg for PostingAmount<'_> {
     }
 }
 
+impl Mul<Decimal> for PostingAmount<'_> {
+    type Output = Self;
+
+    fn mul(self, rhs: Decimal) -> Self::Output {
+        match self {
+            PostingAmount::Zero => PostingAmount::Zero,
+            PostingAmount::Single(amount) => PostingAmount::Single(amount * rhs),
+        }
+    }
+}
+
 impl<'ctx> PostingAmount<'ctx> {
     /// Returns absolute zero.
     pub fn zero() -> Self {
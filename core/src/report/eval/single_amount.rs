@@ -3,9 +3,11 @@ use std::{
     ops::{Mul, Neg},
 };
 
+use pretty_decimal::PrettyDecimal;
 use rust_decimal::Decimal;
 
 use crate::report::{ReportContext, commodity::CommodityTag};
+use crate::syntax::expr;
 
 use super::error::EvalError;
 
@@ -123,15 +125,26 @@ struct SingleAmountDisplay<'a, 'ctx>(&'a SingleAmount<'ctx>, &'a ReportContext<'
 
 impl Display for SingleAmountDisplay<'_, '_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{} {}",
-            self.0.value,
-            self.0.commodity.to_str_lossy(&self.1.commodities)
-        )
+        write_amount(f, self.1, self.0.commodity, self.0.value)
     }
 }
 
+/// Writes the `value` of the `commodity`,
+/// in the format declared in the [`ReportContext::display_context()`].
+pub(super) fn write_amount<'ctx>(
+    f: &mut std::fmt::Formatter<'_>,
+    ctx: &ReportContext<'ctx>,
+    commodity: CommodityTag<'ctx>,
+    value: Decimal,
+) -> std::fmt::Result {
+    let amount = expr::Amount {
+        value: PrettyDecimal::unformatted(value),
+        commodity: commodity.to_str_lossy(&ctx.commodities),
+        style: expr::CommodityStyle::default(),
+    };
+    ctx.display_context.as_display(&amount).fmt(f)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
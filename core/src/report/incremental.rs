@@ -0,0 +1,552 @@
+//! Provides [`Incremental`], which keeps the processed state of each file
+//! so that [`super::process_incremental`] can book-keep again only from the first changed file.
+
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use xxhash_rust::xxh3::xxh3_128;
+
+use crate::load::{self, LoadEvent};
+
+use super::{
+    context::ReportContext,
+    error::ReportError,
+    process::{ProcessAccumulator, ProcessOptions},
+    snapshot::{self, Snapshot},
+    strict::Declarations,
+};
+
+/// Maximum number of the checkpoints kept in [`Incremental`].
+///
+/// Each checkpoint encodes the whole state so far,
+/// so they're thinned out to keep the cost proportional to the transactions.
+const MAX_CHECKPOINTS: usize = 16;
+
+/// State kept across [`super::process_incremental`] calls.
+///
+/// It owns all the data without referring the arena,
+/// so that it can be kept while the arena is reset.
+#[derive(Debug, Default)]
+pub struct Incremental {
+    /// Options and sources of the previous call, or `None` if it can't be reused.
+    key: Option<Vec<u8>>,
+    /// Files visited in the previous call, in the visiting order.
+    files: Vec<FileRecord>,
+    /// Minimum number of the transactions between the checkpoints,
+    /// doubled whenever the checkpoints are thinned out.
+    spacing: usize,
+    /// Encoded state after all the files.
+    last: Option<Vec<u8>>,
+}
+
+/// File visited in the previous call.
+#[derive(Debug)]
+struct FileRecord {
+    /// Canonicalized path of the file.
+    path: PathBuf,
+    /// Hash of the decoded content.
+    hash: u128,
+    includes: Vec<IncludeRecord>,
+    /// Number of the files visited from this file, including itself.
+    visits: usize,
+    /// State before the file, if kept.
+    checkpoint: Option<SavedState>,
+}
+
+/// Encoded state before the file.
+#[derive(Debug)]
+struct SavedState {
+    state: Vec<u8>,
+    /// Number of the transactions in the `state`.
+    transactions: usize,
+}
+
+/// `include` directive in the visited file.
+#[derive(Debug)]
+struct IncludeRecord {
+    pattern: String,
+    /// Canonicalized paths of the matched files.
+    files: Vec<PathBuf>,
+    /// Index of the first matched file in the visiting order.
+    first: usize,
+}
+
+impl Incremental {
+    /// Creates an empty instance, which processes all the files on the first call.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the index of the file to resume from, with the state available before it.
+    /// All the files before the index are unchanged since the previous call.
+    /// [`Incremental::files`] length means nothing is changed.
+    fn resume_point<F: load::FileSystem>(&self, loader: &load::Loader<F>) -> Option<usize> {
+        let changed = self
+            .files
+            .iter()
+            .position(|file| !file.is_unchanged(loader))
+            .unwrap_or(self.files.len());
+        if changed == self.files.len() && self.last.is_some() {
+            return Some(changed);
+        }
+        (0..self.files.len().min(changed + 1))
+            .rev()
+            .find(|&index| self.files[index].checkpoint.is_some())
+    }
+
+    /// Restores the state kept for the `resume` index given by [`Incremental::resume_point`].
+    /// Returns `None` if the state is broken, after forgetting all the kept states.
+    fn restore<'ctx>(
+        &mut self,
+        ctx: &mut ReportContext<'ctx>,
+        resume: usize,
+    ) -> Option<(Snapshot<'ctx>, Declarations<'ctx>)> {
+        let state = match self.files.get(resume) {
+            Some(file) => file.checkpoint.as_ref().map(|saved| &saved.state),
+            None => self.last.as_ref(),
+        }?;
+        let restored = snapshot::decode_checkpoint(ctx, state);
+        if restored.is_none() {
+            self.files.clear();
+            self.last = None;
+        }
+        restored
+    }
+
+    /// Processes the files, resuming from the `resume` index given by [`Incremental::resume_point`].
+    fn process<'ctx, F: load::FileSystem>(
+        &mut self,
+        ctx: &mut ReportContext<'ctx>,
+        loader: &load::Loader<F>,
+        options: &ProcessOptions,
+        resume: Option<usize>,
+    ) -> Result<(Snapshot<'ctx>, Vec<ReportError>, Vec<ReportError>), ReportError> {
+        let started = SystemTime::now();
+        let mut accum = ProcessAccumulator::new(options.strictness, options.keep_going);
+        ctx.accounts.reset_apply_account();
+        // Names restored from the broken state are left in the context,
+        // which is harmless as they're not referred from the processed ledger.
+        let restored = resume.and_then(|index| Some((index, self.restore(ctx, index)?)));
+        let resume = match restored {
+            Some((index, (snapshot, _))) if index == self.files.len() => {
+                log::info!("reused the processed state of all the files");
+                return Ok((snapshot, Vec::new(), Vec::new()));
+            }
+            Some((index, (snapshot, declarations))) => {
+                accum.restore(snapshot, declarations);
+                log::info!(
+                    "reused the processed state until {}",
+                    self.files[index].path.display()
+                );
+                self.files.truncate(index);
+                // Includes given after resuming are recorded again.
+                for file in &mut self.files {
+                    file.includes.retain(|include| include.first <= index);
+                }
+                Some(load::Resume {
+                    index,
+                    visits: self.files.iter().map(|file| file.visits).collect(),
+                })
+            }
+            None => {
+                self.files.clear();
+                self.spacing = 0;
+                None
+            }
+        };
+        self.last = None;
+        let files = &mut self.files;
+        let spacing = &mut self.spacing;
+        let trees = loader.load_events(resume.as_ref(), &mut |event| match event {
+            LoadEvent::Enter {
+                index,
+                path,
+                content,
+            } => {
+                debug_assert_eq!(index, files.len());
+                let transactions = accum.transaction_count();
+                let due = files
+                    .iter()
+                    .rev()
+                    .find_map(|file| file.checkpoint.as_ref())
+                    .is_none_or(|last| transactions >= last.transactions + *spacing);
+                let checkpoint =
+                    due.then(|| accum.checkpoint(ctx))
+                        .flatten()
+                        .map(|state| SavedState {
+                            state,
+                            transactions,
+                        });
+                files.push(FileRecord {
+                    path: path.to_owned(),
+                    hash: xxh3_128(content.as_bytes()),
+                    includes: Vec::new(),
+                    visits: 1,
+                    checkpoint,
+                });
+                thin_out(files, spacing);
+                Ok(())
+            }
+            LoadEvent::Entry(path, pctx, entry) => {
+                accum.process_loaded(ctx, loader, path, pctx, entry)
+            }
+            LoadEvent::Include {
+                index,
+                pattern,
+                files: matched,
+            } => {
+                let first = files.len();
+                files[index].includes.push(IncludeRecord {
+                    pattern: pattern.to_owned(),
+                    files: matched.to_vec(),
+                    first,
+                });
+                Ok(())
+            }
+            LoadEvent::Exit { index, visits } => {
+                files[index].visits = visits;
+                Ok(())
+            }
+        })?;
+        self.last = accum.checkpoint(ctx);
+        let (snapshot, warnings, errors) = accum.finish()?;
+        // Trees are complete only when all the files are loaded.
+        if resume.is_none()
+            && let Some(cache_path) = options.cache_path.as_deref()
+            && warnings.is_empty()
+            && errors.is_empty()
+        {
+            snapshot::store(ctx, loader, options, &trees, started, &snapshot, cache_path);
+        }
+        Ok((snapshot, warnings, errors))
+    }
+}
+
+/// Drops every other checkpoint in `files` if there're more than [`MAX_CHECKPOINTS`],
+/// and widens the `spacing` so that the next checkpoints are taken less often.
+fn thin_out(files: &mut [FileRecord], spacing: &mut usize) {
+    let kept: Vec<usize> = (0..files.len())
+        .filter(|&index| files[index].checkpoint.is_some())
+        .collect();
+    if kept.len() <= MAX_CHECKPOINTS {
+        return;
+    }
+    // The first one is kept, which is the cheap state before any transaction in most cases.
+    for &index in kept.iter().skip(1).step_by(2) {
+        files[index].checkpoint = None;
+    }
+    let transactions: Vec<usize> = files
+        .iter()
+        .filter_map(|file| Some(file.checkpoint.as_ref()?.transactions))
+        .collect();
+    let narrowest = transactions
+        .windows(2)
+        .map(|pair| pair[1] - pair[0])
+        .min()
+        .unwrap_or(0);
+    *spacing = (*spacing * 2).max(narrowest).max(1);
+}
+
+impl FileRecord {
+    /// Returns `true` if the file has the same content and the same include matches.
+    fn is_unchanged<F: load::FileSystem>(&self, loader: &load::Loader<F>) -> bool {
+        match loader.read(&self.path) {
+            Ok(content) if xxh3_128(content.as_bytes()) == self.hash => (),
+            _ => return false,
+        }
+        self.includes.iter().all(|include| {
+            loader
+                .resolve_include(&self.path, &include.pattern)
+                .is_ok_and(|files| {
+                    files.len() == include.files.len()
+                        && files.iter().zip(&include.files).all(|(file, recorded)| {
+                            F::canonicalize_path(file) == recorded.as_path()
+                        })
+                })
+        })
+    }
+}
+
+/// Loads and book-keeps the entries like [`super::process::process_entries`],
+/// reusing the state kept in `incremental` for the unchanged files.
+pub(super) fn process_entries<'ctx, F: load::FileSystem>(
+    ctx: &mut ReportContext<'ctx>,
+    loader: &load::Loader<F>,
+    options: &ProcessOptions,
+    incremental: &mut Incremental,
+) -> Result<(Snapshot<'ctx>, Vec<ReportError>, Vec<ReportError>), ReportError> {
+    // Nothing is kept to resume from the snapshot, so it's used only when there's nothing else.
+    if incremental.files.is_empty()
+        && let Some(cache_path) = options.cache_path.as_deref()
+        && let Some(snapshot) = snapshot::restore(ctx, loader, options, cache_path)
+    {
+        return Ok((snapshot, Vec::new(), Vec::new()));
+    }
+    let key = snapshot::options_key(loader, options);
+    // Tags in the checkpoints can't be restored into the context already in use.
+    let fresh = ctx.commodities.is_empty() && ctx.accounts.iter().next().is_none();
+    let resume = match &key {
+        Some(key) if fresh && incremental.key.as_ref() == Some(key) => {
+            incremental.resume_point(loader)
+        }
+        _ => None,
+    };
+    match incremental.process(ctx, loader, options, resume) {
+        Ok(processed) => {
+            incremental.key = key;
+            Ok(processed)
+        }
+        Err(err) => {
+            // Partially visited files can't be resumed.
+            *incremental = Incremental::default();
+            Err(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+    use std::num::NonZeroUsize;
+    use std::path::Path;
+
+    use bumpalo::Bump;
+    use indoc::indoc;
+    use maplit::hashmap;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    fn fake_files() -> HashMap<PathBuf, Vec<u8>> {
+        hashmap! {
+            PathBuf::from("path/to/root.ledger") => indoc! {"
+                commodity CHF
+                    alias SFr
+
+                account Assets:Bank
+                    alias Bank
+
+                apply account Expenses
+                include 2024/*.ledger
+                end apply account
+
+                2024/12/31 Closing
+                    Expenses:Food                        1 CHF
+                    Bank
+            "}.as_bytes().to_vec(),
+            PathBuf::from("path/to/2024/01.ledger") => indoc! {"
+                2024/01/02 Migros
+                    Food                                10 SFr
+                    Bank
+            "}.as_bytes().to_vec(),
+            PathBuf::from("path/to/2024/02.ledger") => indoc! {"
+                2024/02/03 Coop
+                    Food                                20 CHF
+                    Bank
+            "}.as_bytes().to_vec(),
+        }
+    }
+
+    fn fake_loader(files: HashMap<PathBuf, Vec<u8>>) -> load::Loader<load::FakeFileSystem> {
+        load::Loader::new(
+            PathBuf::from("path/to/root.ledger"),
+            load::FakeFileSystem::from(files),
+        )
+    }
+
+    /// Processes the `files` with `incremental` into the fresh context,
+    /// and returns the processed state comparable across the arenas.
+    fn process_files(incremental: &mut Incremental, files: HashMap<PathBuf, Vec<u8>>) -> String {
+        process_with(incremental, &fake_loader(files))
+    }
+
+    /// Same as [`process_files`], with the given `loader`.
+    fn process_with(
+        incremental: &mut Incremental,
+        loader: &load::Loader<load::FakeFileSystem>,
+    ) -> String {
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let (snapshot, warnings, errors) =
+            process_entries(&mut ctx, loader, &ProcessOptions::default(), incremental).unwrap();
+        assert!(warnings.is_empty() && errors.is_empty());
+        let mut accounts: Vec<_> = ctx.accounts.iter().map(|a| a.as_str()).collect();
+        accounts.sort_unstable();
+        let mut balance: Vec<_> = snapshot
+            .balance
+            .iter()
+            .map(|(a, v)| (a.as_str(), v))
+            .collect();
+        balance.sort_unstable_by_key(|(a, _)| *a);
+        let debug = format!("{:#?}", (accounts, &snapshot.transactions, balance));
+        // Interned strings are printed with its address, which differs across the arenas.
+        regex::Regex::new(r"addr: 0x[0-9a-f]+")
+            .unwrap()
+            .replace_all(&debug, "addr: _")
+            .into_owned()
+    }
+
+    fn resume_path(incremental: &Incremental, files: HashMap<PathBuf, Vec<u8>>) -> Option<PathBuf> {
+        let index = incremental.resume_point(&fake_loader(files))?;
+        Some(
+            incremental
+                .files
+                .get(index)
+                .map_or_else(|| PathBuf::from("(end)"), |file| file.path.clone()),
+        )
+    }
+
+    #[test]
+    fn unchanged_files_are_not_processed() {
+        let mut incremental = Incremental::new();
+        let want = process_files(&mut incremental, fake_files());
+
+        assert_eq!(
+            Some(PathBuf::from("(end)")),
+            resume_path(&incremental, fake_files())
+        );
+        assert_eq!(want, process_files(&mut incremental, fake_files()));
+    }
+
+    #[test]
+    fn changed_file_is_processed_with_rest() {
+        let mut incremental = Incremental::new();
+        process_files(&mut incremental, fake_files());
+        let mut files = fake_files();
+        files
+            .get_mut(Path::new("path/to/2024/02.ledger"))
+            .unwrap()
+            .extend(b"\n2024/02/04 Shop\n    Food  3 SFr\n    Bank\n");
+        let want = process_files(&mut Incremental::new(), files.clone());
+
+        assert_eq!(
+            Some(PathBuf::from("path/to/2024/02.ledger")),
+            resume_path(&incremental, files.clone())
+        );
+        assert_eq!(want, process_files(&mut incremental, files.clone()));
+        assert_eq!(
+            Some(PathBuf::from("(end)")),
+            resume_path(&incremental, files)
+        );
+    }
+
+    #[rstest]
+    fn changed_file_is_processed_with_threads(#[values(1, 2)] changed: usize) {
+        let loader = |files| fake_loader(files).with_threads(NonZeroUsize::new(4).unwrap());
+        let mut incremental = Incremental::new();
+        process_with(&mut incremental, &loader(fake_files()));
+        let mut files = fake_files();
+        files
+            .get_mut(Path::new(&format!("path/to/2024/0{changed}.ledger")))
+            .unwrap()
+            .extend(b"\n2024/02/04 Shop\n    Food  3 SFr\n    Bank\n");
+        let loader = loader(files);
+        let want = process_with(&mut Incremental::new(), &loader);
+
+        assert_eq!(Some(changed), incremental.resume_point(&loader));
+        assert_eq!(want, process_with(&mut incremental, &loader));
+    }
+
+    #[test]
+    fn new_include_match_is_processed_from_including_file() {
+        let mut incremental = Incremental::new();
+        process_files(&mut incremental, fake_files());
+        let mut files = fake_files();
+        files.insert(
+            PathBuf::from("path/to/2024/03.ledger"),
+            b"2024/03/01 Shop\n    Food  5 CHF\n    Bank\n".to_vec(),
+        );
+        let want = process_files(&mut Incremental::new(), files.clone());
+
+        assert_eq!(
+            Some(PathBuf::from("path/to/root.ledger")),
+            resume_path(&incremental, files.clone())
+        );
+        assert_eq!(want, process_files(&mut incremental, files));
+    }
+
+    #[test]
+    fn automated_transaction_is_not_kept() {
+        let mut files = fake_files();
+        files.insert(
+            PathBuf::from("path/to/root.ledger"),
+            indoc! {"
+                = Expenses:Food
+                    (Budget:Food)                      -1
+
+                include 2024/*.ledger
+            "}
+            .as_bytes()
+            .to_vec(),
+        );
+        let mut incremental = Incremental::new();
+        process_files(&mut incremental, files.clone());
+        files
+            .get_mut(Path::new("path/to/2024/02.ledger"))
+            .unwrap()
+            .extend(b"\n2024/02/04 Shop\n    Expenses:Food  3 CHF\n    Assets:Bank\n");
+
+        assert_eq!(
+            Some(PathBuf::from("path/to/root.ledger")),
+            resume_path(&incremental, files.clone())
+        );
+        assert_eq!(
+            process_files(&mut Incremental::new(), files.clone()),
+            process_files(&mut incremental, files)
+        );
+    }
+
+    /// Returns the files including 100 files with a transaction each.
+    fn many_files() -> HashMap<PathBuf, Vec<u8>> {
+        let mut files: HashMap<_, _> = (0..100)
+            .map(|i| {
+                let content =
+                    format!("2024/01/01 Shop {i}\n    Expenses:Food  {i} CHF\n    Assets:Bank\n");
+                (
+                    PathBuf::from(format!("path/to/2024/{i:03}.ledger")),
+                    content.into_bytes(),
+                )
+            })
+            .collect();
+        files.insert(
+            PathBuf::from("path/to/root.ledger"),
+            b"include 2024/*.ledger\n".to_vec(),
+        );
+        files
+    }
+
+    #[test]
+    fn checkpoints_are_bounded() {
+        let mut incremental = Incremental::new();
+        process_files(&mut incremental, many_files());
+        let kept = incremental
+            .files
+            .iter()
+            .filter(|file| file.checkpoint.is_some())
+            .count();
+        assert!(kept <= MAX_CHECKPOINTS, "{kept} checkpoints are kept");
+
+        let mut files = many_files();
+        files
+            .get_mut(Path::new("path/to/2024/090.ledger"))
+            .unwrap()
+            .extend(b"\n2024/02/04 Shop\n    Expenses:Food  3 CHF\n    Assets:Bank\n");
+        let want = process_files(&mut Incremental::new(), files.clone());
+        let resume = incremental
+            .resume_point(&fake_loader(files.clone()))
+            .unwrap();
+        assert!(resume > 1 && resume <= 91, "resumes from {resume}");
+        assert_eq!(want, process_files(&mut incremental, files));
+    }
+
+    #[test]
+    fn broken_state_falls_back_to_full_processing() {
+        let mut incremental = Incremental::new();
+        let want = process_files(&mut incremental, fake_files());
+        incremental.last.as_mut().unwrap().truncate(3);
+
+        assert_eq!(want, process_files(&mut incremental, fake_files()));
+        assert!(incremental.last.is_some());
+    }
+}
@@ -0,0 +1,86 @@
+//! Defines payee store, to resolve the payee aliases.
+
+use bumpalo::Bump;
+use bumpalo_intern::direct::{
+    DirectInternStore, FromInterned, InternedStr, OccupiedError, StoredValue,
+};
+
+/// Payee of the transaction, interned in the arena.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub(super) struct Payee<'arena>(InternedStr<'arena>);
+
+impl<'arena> FromInterned<'arena> for Payee<'arena> {
+    fn from_interned(v: InternedStr<'arena>) -> Self {
+        Self(v)
+    }
+
+    fn as_interned(&self) -> InternedStr<'arena> {
+        self.0
+    }
+}
+
+impl<'arena> Payee<'arena> {
+    /// Returns the `&str`.
+    #[inline]
+    pub fn as_str(&self) -> &'arena str {
+        self.0.as_str()
+    }
+}
+
+/// Manages [`Payee`] instances, declared with "payee" directive or used in transactions.
+pub(super) struct PayeeStore<'arena> {
+    intern: DirectInternStore<'arena, Payee<'arena>>,
+}
+
+impl<'arena> PayeeStore<'arena> {
+    /// Creates a new instance.
+    pub fn new(arena: &'arena Bump) -> Self {
+        Self {
+            intern: DirectInternStore::new(arena),
+        }
+    }
+
+    /// Returns the [`Payee`] with the given `value`,
+    /// potentially resolving the alias.
+    /// If not available, registers the given `value` as the canonical.
+    pub fn ensure(&mut self, value: &str) -> Payee<'arena> {
+        self.intern.ensure(value)
+    }
+
+    /// Registers given `value` as always alias of `canonical`.
+    /// Returns error if given `value` is already registered as canonical.
+    pub fn register_alias(
+        &mut self,
+        value: &str,
+        canonical: Payee<'arena>,
+    ) -> Result<(), OccupiedError<'arena, Payee<'arena>>> {
+        self.intern.register_alias(value, canonical)
+    }
+
+    /// Returns the [`Iterator`] for all payees and aliases.
+    /// Order is unspecified.
+    pub fn iter(&self) -> impl Iterator<Item = StoredValue<'arena, Payee<'arena>>> {
+        self.intern.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn ensure_resolves_alias() {
+        let arena = Bump::new();
+        let mut payees = PayeeStore::new(&arena);
+        let kfc = payees.ensure("KFC");
+        payees
+            .register_alias("KENTUCKY FRIED CHICKEN", kfc)
+            .unwrap();
+
+        assert_eq!(kfc, payees.ensure("KENTUCKY FRIED CHICKEN"));
+        assert_eq!("KFC", payees.ensure("KENTUCKY FRIED CHICKEN").as_str());
+        assert_eq!("Other", payees.ensure("Other").as_str());
+    }
+}
@@ -0,0 +1,71 @@
+//! Provides [PeriodicTransaction], which describes the postings recurring on every period.
+
+use crate::syntax::{self, decoration::AsUndecorated, tracked::Tracked};
+
+use super::{
+    account::Account,
+    book_keeping::BookKeepError,
+    context::ReportContext,
+    eval::{Amount, Evaluable},
+};
+
+/// Processed periodic transaction.
+/// Unlike [`super::transaction::Transaction`], this never affects the balance.
+#[derive(Debug)]
+pub(super) struct PeriodicTransaction<'ctx> {
+    pub period: syntax::PeriodExpr,
+    pub postings: Vec<(Account<'ctx>, Amount<'ctx>)>,
+}
+
+impl<'ctx> PeriodicTransaction<'ctx> {
+    /// Creates an instance out of the syntax.
+    /// At most one posting can omit the amount, which is deduced to balance the other postings.
+    pub fn from_syntax(
+        ctx: &mut ReportContext<'ctx>,
+        txn: &syntax::tracked::PeriodicTransaction<'_>,
+    ) -> Result<Self, BookKeepError> {
+        let mut postings = Vec::with_capacity(txn.posts.len());
+        let mut unfilled: Option<Tracked<usize>> = None;
+        let mut total = Amount::zero();
+        for (i, posting) in txn.posts.iter().enumerate() {
+            let posting = posting.as_undecorated();
+            let account = ctx
+                .accounts
+                .ensure_applied(posting.account.as_undecorated());
+            let amount = match &posting.amount {
+                None => {
+                    if let Some(first) = unfilled.replace(Tracked::new(i, posting.account.span())) {
+                        return Err(BookKeepError::UndeduciblePostingAmount(
+                            first,
+                            Tracked::new(i, posting.account.span()),
+                        ));
+                    }
+                    Amount::zero()
+                }
+                Some(syntax_amount) => {
+                    let amount: Amount<'ctx> = syntax_amount
+                        .amount
+                        .as_undecorated()
+                        .eval_mut(ctx)
+                        .and_then(Amount::try_from)
+                        .map_err(|e| {
+                            BookKeepError::EvalFailure(
+                                e.into_owned(ctx),
+                                syntax_amount.amount.span(),
+                            )
+                        })?;
+                    total += &amount;
+                    amount
+                }
+            };
+            postings.push((account, amount));
+        }
+        if let Some(unfilled) = unfilled {
+            postings[*unfilled.as_undecorated()].1 = total.negate();
+        }
+        Ok(Self {
+            period: txn.period,
+            postings,
+        })
+    }
+}
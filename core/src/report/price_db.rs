@@ -6,9 +6,11 @@ use std::path::{Path, PathBuf};
 use chrono::{NaiveDate, TimeDelta};
 use rust_decimal::Decimal;
 
+use crate::diagnostic::Diagnostic;
 use crate::load;
 use crate::parse;
 use crate::report::commodity::{CommodityMap, CommodityTag, OwnedCommodity};
+use crate::syntax;
 
 use super::context::ReportContext;
 use super::eval::{Amount, SingleAmount};
@@ -21,13 +23,37 @@ pub enum LoadError {
     Parse(PathBuf, #[source] parse::ParseError),
 }
 
+impl LoadError {
+    /// Converts the error into [`Diagnostic`].
+    pub(super) fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            LoadError::IO(path, _) => {
+                let mut diagnostic = Diagnostic::from_error(self);
+                diagnostic.path = Some(path.to_string_lossy().into_owned());
+                diagnostic
+            }
+            LoadError::Parse(path, err) => {
+                let mut diagnostic = err.to_diagnostic();
+                diagnostic
+                    .path
+                    .get_or_insert_with(|| path.to_string_lossy().into_owned());
+                diagnostic
+            }
+        }
+    }
+}
+
 /// Source of the price information.
 /// In the DB, latter one (larger one as Ord) has priority,
 /// and if you have events with higher priority,
 /// lower priority events are discarded.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub(super) enum PriceSource {
+    /// Price deduced from the transaction postings.
     Ledger,
+    /// Price declared with "P" directive in the Ledger file.
+    Directive,
+    /// Price loaded from the price DB file.
     PriceDB,
 }
 
@@ -91,6 +117,9 @@ impl<'ctx> PriceRepositoryBuilder<'ctx> {
             .or_default()
             .entry(price_of.commodity)
             .or_insert(Entry(PriceSource::Ledger, Vec::new()));
+        if *stored_source > source {
+            return;
+        }
         if *stored_source < source {
             *stored_source = source;
             entries.clear();
@@ -113,7 +142,8 @@ impl<'ctx> PriceRepositoryBuilder<'ctx> {
         // Even though price db can be up to a few megabytes,
         // still it's much easier to load everything into memory.
         let content = filesystem
-            .file_content_utf8(path)
+            .file_content(path)
+            .and_then(|content| load::decode::decode(content, encoding_rs::UTF_8))
             .map_err(|e| LoadError::IO(path.to_owned(), e))?;
         for entry in parse::price::parse_price_db(&parse::ParseOptions::default(), &content) {
             let (_, entry) = entry.map_err(|e| LoadError::Parse(path.to_owned(), e))?;
@@ -121,23 +151,67 @@ impl<'ctx> PriceRepositoryBuilder<'ctx> {
             // as the price might be computed via indirect relationship.
             // For example, if we have only AUD and JPY in Ledger,
             // price DB might expose AUD/EUR EUR/CHF CHF/JPY conversion.
-            let target = ctx.commodities.ensure(entry.target.as_ref());
-            let rate: SingleAmount<'ctx> = SingleAmount::from_value(
-                ctx.commodities.ensure(&entry.rate.commodity),
-                entry.rate.value.value,
-            );
-            self.insert_price(
-                PriceSource::PriceDB,
-                PriceEvent {
-                    price_x: SingleAmount::from_value(target, Decimal::ONE),
-                    price_y: rate,
-                    date: entry.datetime.date(),
-                },
-            );
+            self.insert_entry(ctx, PriceSource::PriceDB, &entry);
         }
         Ok(())
     }
 
+    /// Inserts the given [`syntax::PriceDBEntry`], either from price DB or "P" directive.
+    pub fn insert_entry(
+        &mut self,
+        ctx: &mut ReportContext<'ctx>,
+        source: PriceSource,
+        entry: &syntax::PriceDBEntry<'_>,
+    ) {
+        let target = ctx.commodities.ensure(entry.target.as_ref());
+        let rate: SingleAmount<'ctx> = SingleAmount::from_value(
+            ctx.commodities.ensure(&entry.rate.commodity),
+            entry.rate.value.value,
+        );
+        self.insert_price(
+            source,
+            PriceEvent {
+                price_x: SingleAmount::from_value(target, Decimal::ONE),
+                price_y: rate,
+                date: entry.datetime.date(),
+            },
+        );
+    }
+
+    /// Returns the recorded rates as `(price_with, price_of, source, rates)`,
+    /// where each rate is the value of `price_of` in `price_with`.
+    /// Order is unspecified.
+    pub fn iter_rates(
+        &self,
+    ) -> impl Iterator<
+        Item = (
+            CommodityTag<'ctx>,
+            CommodityTag<'ctx>,
+            PriceSource,
+            &[(NaiveDate, Decimal)],
+        ),
+    > {
+        self.records.iter().flat_map(|(price_with, v)| {
+            v.iter().map(|(price_of, Entry(source, rates))| {
+                (*price_with, *price_of, *source, rates.as_slice())
+            })
+        })
+    }
+
+    /// Sets the rates given by [`Self::iter_rates`], overwriting the existing ones.
+    pub fn set_rates(
+        &mut self,
+        price_with: CommodityTag<'ctx>,
+        price_of: CommodityTag<'ctx>,
+        source: PriceSource,
+        rates: Vec<(NaiveDate, Decimal)>,
+    ) {
+        self.records
+            .entry(price_with)
+            .or_default()
+            .insert(price_of, Entry(source, rates));
+    }
+
     /// Returns iterator of [`PriceEvent`] in unspecified order.
     #[cfg(test)]
     pub fn iter_events(&self) -> impl Iterator<Item = (PriceSource, PriceEvent<'ctx>)> {
@@ -361,8 +435,8 @@ impl<'ctx> NaivePriceRepository<'ctx> {
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
 struct Distance {
     /// Number of conversions with [`PriceSource::Ledger`] used to compute the rate.
-    /// Minimize this because we assume [`PriceSource::PriceDB`] is more reliable
-    /// than the one in Ledger.
+    /// Minimize this because we assume [`PriceSource::PriceDB`] and [`PriceSource::Directive`]
+    /// are more reliable than the one deduced from transactions.
     num_ledger_conversions: usize,
     /// Number of conversions used to compute the rate.
     num_all_conversions: usize,
@@ -375,7 +449,7 @@ impl Distance {
         let num_ledger_conversions = self.num_ledger_conversions
             + match source {
                 PriceSource::Ledger => 1,
-                PriceSource::PriceDB => 0,
+                PriceSource::Directive | PriceSource::PriceDB => 0,
             };
         Self {
             num_ledger_conversions,
@@ -531,6 +605,52 @@ mod tests {
         assert_eq!(got, Ok(SingleAmount::from_value(jpy, dec!(156.25))));
     }
 
+    #[test]
+    fn price_db_directive_overrides_ledger_price() {
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let chf = ctx.commodities.ensure("CHF");
+        let eur = ctx.commodities.ensure("EUR");
+        let mut builder = PriceRepositoryBuilder::default();
+        let event = |date, rate| PriceEvent {
+            date,
+            price_x: SingleAmount::from_value(eur, dec!(1)),
+            price_y: SingleAmount::from_value(chf, rate),
+        };
+
+        builder.insert_price(
+            PriceSource::Ledger,
+            event(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(), dec!(0.8)),
+        );
+        builder.insert_price(
+            PriceSource::Directive,
+            event(NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(), dec!(0.9)),
+        );
+        // Lower priority price after the directive must be discarded too.
+        builder.insert_price(
+            PriceSource::Ledger,
+            event(NaiveDate::from_ymd_opt(2024, 1, 3).unwrap(), dec!(0.7)),
+        );
+
+        let got: Vec<_> = builder.iter_events().collect();
+        assert!(
+            got.iter()
+                .all(|(source, _)| *source == PriceSource::Directive)
+        );
+        let got: Vec<_> = builder
+            .to_events()
+            .into_iter()
+            .filter(|event| event.price_x.commodity == eur)
+            .collect();
+        assert_eq!(
+            vec![event(
+                NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+                dec!(0.9)
+            )],
+            got
+        );
+    }
+
     #[test]
     fn price_db_load_overrides_ledger_price() {
         let price_db =
@@ -1,14 +1,21 @@
 use std::borrow::Borrow;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
-use crate::{load, syntax};
+use crate::{load, parse, syntax};
 
+use super::automated::AutomatedTransaction;
 use super::balance::Balance;
 use super::book_keeping::{self, BookKeepError};
 use super::context::ReportContext;
 use super::error::{self, ReportError};
-use super::price_db::PriceRepositoryBuilder;
+use super::incremental::{self, Incremental};
+use super::periodic::PeriodicTransaction;
+use super::price_db::{PriceRepositoryBuilder, PriceSource};
 use super::query::Ledger;
+use super::snapshot::{self, Checkpoint, Snapshot};
+use super::strict::{Declarations, Strictness};
+use super::timeclock::Timeclock;
 use super::transaction::Transaction;
 
 /// Options to control process behavior.
@@ -17,6 +24,20 @@ use super::transaction::Transaction;
 pub struct ProcessOptions {
     /// Path to the price DB file.
     pub price_db_path: Option<PathBuf>,
+    /// How to report the undeclared accounts, commodities, payees and tags.
+    /// Warnings are available with [`Ledger::warnings()`].
+    pub strictness: Strictness,
+    /// Continues past the entries failed to book-keep, instead of returning the first error.
+    /// Failed transactions are excluded from the ledger,
+    /// and the errors are available with [`Ledger::errors()`].
+    pub keep_going: bool,
+    /// Path to the snapshot of the processed ledger, used as the cache.
+    ///
+    /// If none of the loaded files has changed since the snapshot is stored,
+    /// the ledger is restored from it without loading and book keeping.
+    /// Otherwise, the snapshot is updated unless there're warnings or errors.
+    /// The price DB is always loaded, as it's not a part of the snapshot.
+    pub cache_path: Option<PathBuf>,
 }
 
 /// Takes the loader, and gives back the all read transactions.
@@ -31,69 +52,332 @@ where
     L: Borrow<load::Loader<F>>,
     F: load::FileSystem,
 {
-    let mut accum = ProcessAccumulator::new();
-    loader.borrow().load(|path, pctx, entry| {
-        accum.process(ctx, entry).map_err(|berr| {
-            ReportError::BookKeep(
-                berr,
-                error::ErrorContext::new(
-                    loader.borrow().error_style().clone(),
-                    path.to_owned(),
-                    pctx,
-                ),
-            )
-        })
-    })?;
+    let loader = loader.borrow();
+    let cached = options
+        .cache_path
+        .as_deref()
+        .and_then(|cache_path| snapshot::restore(ctx, loader, options, cache_path));
+    let (snapshot, warnings, errors) = match cached {
+        Some(snapshot) => (snapshot, Vec::new(), Vec::new()),
+        None => process_entries(ctx, loader, options)?,
+    };
+    build_ledger(ctx, loader, options, snapshot, warnings, errors)
+}
+
+/// Same as [`process`], but reuses the results kept in `incremental` by the previous call
+/// for the files not changed since then.
+///
+/// Balances are cumulative, so the entries are book-kept again
+/// from the first changed file in the loading order, including the rest of the files.
+/// As the state is kept only before some of the files, it may start from an earlier file.
+/// The results are reused only if the options and the sources are the same as the previous call,
+/// and `ctx` is fresh, such as a new one after resetting the arena.
+/// The snapshot at [`ProcessOptions::cache_path`] is restored only when `incremental` has nothing to reuse,
+/// and stored only when all the files are processed.
+pub fn process_incremental<'ctx, L, F>(
+    ctx: &mut ReportContext<'ctx>,
+    loader: L,
+    options: &ProcessOptions,
+    incremental: &mut Incremental,
+) -> Result<Ledger<'ctx>, ReportError>
+where
+    L: Borrow<load::Loader<F>>,
+    F: load::FileSystem,
+{
+    let loader = loader.borrow();
+    let (snapshot, warnings, errors) =
+        incremental::process_entries(ctx, loader, options, incremental)?;
+    build_ledger(ctx, loader, options, snapshot, warnings, errors)
+}
+
+/// Builds the [`Ledger`] out of the processed state, loading the price DB.
+fn build_ledger<'ctx, F: load::FileSystem>(
+    ctx: &mut ReportContext<'ctx>,
+    loader: &load::Loader<F>,
+    options: &ProcessOptions,
+    snapshot: Snapshot<'ctx>,
+    warnings: Vec<ReportError>,
+    errors: Vec<ReportError>,
+) -> Result<Ledger<'ctx>, ReportError> {
     ctx.account_tree.construct(&ctx.accounts);
+    let mut price_repos = snapshot.price_repos;
     if let Some(price_db_path) = options.price_db_path.as_deref() {
-        accum
-            .price_repos
-            .load_price_db(ctx, loader.borrow().filesystem(), price_db_path)?;
+        price_repos.load_price_db(ctx, loader.filesystem(), price_db_path)?;
     }
     Ok(Ledger {
         arena: ctx.arena,
-        transactions: accum.txns,
-        date_sorted_txns: None,
-        raw_balance: accum.balance,
-        price_repos: accum.price_repos.build(),
+        transactions: snapshot.transactions,
+        periodic: snapshot.periodic,
+        date_sorted_txns: [None, None],
+        raw_balance: snapshot.balance,
+        warnings,
+        errors,
+        price_repos: price_repos.build(),
     })
 }
 
-struct ProcessAccumulator<'ctx> {
+/// Loads and book-keeps all the entries, and returns the processed state with the warnings and errors.
+/// Also stores the snapshot if [`ProcessOptions::cache_path`] is given.
+pub(super) fn process_entries<'ctx, F: load::FileSystem>(
+    ctx: &mut ReportContext<'ctx>,
+    loader: &load::Loader<F>,
+    options: &ProcessOptions,
+) -> Result<(Snapshot<'ctx>, Vec<ReportError>, Vec<ReportError>), ReportError> {
+    let started = SystemTime::now();
+    let mut accum = ProcessAccumulator::new(options.strictness, options.keep_going);
+    ctx.accounts.reset_apply_account();
+    let trees = loader
+        .load_tree(&mut |path, pctx, entry| accum.process_loaded(ctx, loader, path, pctx, entry))?;
+    let (snapshot, warnings, errors) = accum.finish()?;
+    if let Some(cache_path) = options.cache_path.as_deref()
+        && warnings.is_empty()
+        && errors.is_empty()
+    {
+        snapshot::store(ctx, loader, options, &trees, started, &snapshot, cache_path);
+    }
+    Ok((snapshot, warnings, errors))
+}
+
+pub(super) struct ProcessAccumulator<'ctx> {
     balance: Balance<'ctx>,
     txns: Vec<Transaction<'ctx>>,
+    automated: Vec<AutomatedTransaction<'ctx>>,
+    periodic: Vec<PeriodicTransaction<'ctx>>,
     price_repos: PriceRepositoryBuilder<'ctx>,
+    timeclock: Timeclock<'ctx>,
+    strictness: Strictness,
+    declarations: Declarations<'ctx>,
+    warnings: Vec<ReportError>,
+    keep_going: bool,
+    errors: Vec<ReportError>,
 }
 
 impl<'ctx> ProcessAccumulator<'ctx> {
-    fn new() -> Self {
+    pub fn new(strictness: Strictness, keep_going: bool) -> Self {
         Self {
             balance: Balance::default(),
             txns: Vec::new(),
+            automated: Vec::new(),
+            periodic: Vec::new(),
             price_repos: PriceRepositoryBuilder::default(),
+            timeclock: Timeclock::default(),
+            strictness,
+            declarations: Declarations::default(),
+            warnings: Vec::new(),
+            keep_going,
+            errors: Vec::new(),
         }
     }
 
-    fn process(
+    /// Restores the state given by [`snapshot::decode_checkpoint`].
+    pub fn restore(&mut self, snapshot: Snapshot<'ctx>, declarations: Declarations<'ctx>) {
+        self.txns = snapshot.transactions;
+        self.periodic = snapshot.periodic;
+        self.balance = snapshot.balance;
+        self.price_repos = snapshot.price_repos;
+        self.declarations = declarations;
+    }
+
+    /// Returns the number of the transactions processed so far.
+    pub fn transaction_count(&self) -> usize {
+        self.txns.len()
+    }
+
+    /// Encodes the current state to resume the processing later,
+    /// or returns `None` if the state has what [`Checkpoint`] can't keep.
+    pub fn checkpoint(&self, ctx: &ReportContext<'ctx>) -> Option<Vec<u8>> {
+        if !self.automated.is_empty()
+            || !self.timeclock.is_empty()
+            || !self.warnings.is_empty()
+            || !self.errors.is_empty()
+        {
+            return None;
+        }
+        snapshot::encode_checkpoint(
+            ctx,
+            &Checkpoint {
+                transactions: &self.txns,
+                periodic: &self.periodic,
+                balance: &self.balance,
+                price_repos: &self.price_repos,
+                declarations: &self.declarations,
+            },
+        )
+    }
+
+    /// Processes the `entry` given by the `loader`, found in the file at `path`.
+    pub fn process_loaded<F: load::FileSystem>(
         &mut self,
         ctx: &mut ReportContext<'ctx>,
+        loader: &load::Loader<F>,
+        path: &Path,
+        pctx: &parse::ParsedContext<'_>,
         entry: &syntax::tracked::LedgerEntry,
-    ) -> Result<(), BookKeepError> {
+    ) -> Result<(), ReportError> {
+        let error_context =
+            || error::ErrorContext::new(loader.error_style().clone(), path.to_owned(), pctx);
+        let result = self
+            .process(ctx, entry, error_context)
+            .map_err(|berr| ReportError::BookKeep(berr, error_context()));
+        self.recover(result)
+    }
+
+    /// Ensures the all accounts are checked out,
+    /// and returns the processed state with the warnings and errors.
+    pub fn finish(
+        mut self,
+    ) -> Result<(Snapshot<'ctx>, Vec<ReportError>, Vec<ReportError>), ReportError> {
+        let timeclock = std::mem::take(&mut self.timeclock);
+        self.recover(timeclock.finish())?;
+        let snapshot = Snapshot {
+            transactions: self.txns,
+            periodic: self.periodic,
+            balance: self.balance,
+            price_repos: self.price_repos,
+        };
+        Ok((snapshot, self.warnings, self.errors))
+    }
+
+    /// Records the error and continues if `keep_going` is set,
+    /// otherwise gives back the `result` as-is.
+    fn recover(&mut self, result: Result<(), ReportError>) -> Result<(), ReportError> {
+        match result {
+            Err(err) if self.keep_going => {
+                self.errors.push(err);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// Processes the given `entry`.
+    /// `source` gives the location of the entry, for the error detected later.
+    fn process<S>(
+        &mut self,
+        ctx: &mut ReportContext<'ctx>,
+        entry: &syntax::tracked::LedgerEntry,
+        source: S,
+    ) -> Result<(), BookKeepError>
+    where
+        S: Fn() -> Box<error::ErrorContext>,
+    {
         match &entry.statement {
             syntax::LedgerStatement::Txn(txn) => {
-                self.txns.push(book_keeping::add_transaction(
-                    ctx,
-                    &mut self.price_repos,
-                    &mut self.balance,
-                    txn,
-                )?);
+                self.book(|this| this.add_transaction(ctx, txn, source))
+            }
+            syntax::LedgerStatement::AutomatedTxn(txn) => {
+                self.automated
+                    .push(AutomatedTransaction::from_syntax(ctx, txn)?);
+                Ok(())
+            }
+            syntax::LedgerStatement::PeriodicTxn(txn) => {
+                self.periodic
+                    .push(PeriodicTransaction::from_syntax(ctx, txn)?);
                 Ok(())
             }
-            syntax::LedgerStatement::Account(account) => process_account(ctx, account),
-            syntax::LedgerStatement::Commodity(commodity) => process_commodity(ctx, commodity),
+            syntax::LedgerStatement::Account(account) => {
+                process_account(ctx, account)?;
+                self.declarations
+                    .declare_account(ctx.accounts.ensure(&account.name));
+                Ok(())
+            }
+            syntax::LedgerStatement::Alias(alias) => process_alias(ctx, alias),
+            syntax::LedgerStatement::ApplyAccount(apply) => {
+                ctx.accounts.enter_apply_account(&apply.0);
+                Ok(())
+            }
+            syntax::LedgerStatement::EndApplyAccount => process_end_apply_account(ctx),
+            syntax::LedgerStatement::Commodity(commodity) => {
+                process_commodity(ctx, commodity)?;
+                ctx.display_context.apply_commodity_declaration(commodity);
+                self.declarations
+                    .declare_commodity(ctx.commodities.ensure(&commodity.name));
+                Ok(())
+            }
+            syntax::LedgerStatement::DefaultCommodity(amount) => {
+                // "D 1,000.00" without commodity has nothing to default to.
+                if !amount.commodity.is_empty() {
+                    let commodity = ctx.commodities.ensure(&amount.commodity);
+                    ctx.commodities.set_default_commodity(commodity);
+                    ctx.commodities.set_format(commodity, amount.value);
+                }
+                ctx.display_context.apply_default_commodity(amount);
+                Ok(())
+            }
+            syntax::LedgerStatement::Payee(payee) => {
+                let canonical = process_payee(ctx, payee)?;
+                self.declarations.declare_payee(canonical);
+                Ok(())
+            }
+            syntax::LedgerStatement::Tag(tag) => {
+                self.declarations.declare_tag(&tag.name);
+                Ok(())
+            }
+            syntax::LedgerStatement::Price(price) => {
+                self.price_repos
+                    .insert_entry(ctx, PriceSource::Directive, price);
+                Ok(())
+            }
+            syntax::LedgerStatement::ClockIn(clock) => {
+                self.timeclock.check_in(ctx, clock, source())
+            }
+            syntax::LedgerStatement::ClockOut(clock) => {
+                self.book(|this| this.timeclock.check_out(ctx, &mut this.balance, clock))
+            }
             _ => Ok(()),
         }
     }
+
+    /// Records the transaction given by `add`.
+    /// The balance is updated posting by posting,
+    /// so the changed accounts are recorded to roll back the failed transaction.
+    fn book<F>(&mut self, add: F) -> Result<(), BookKeepError>
+    where
+        F: FnOnce(&mut Self) -> Result<Transaction<'ctx>, BookKeepError>,
+    {
+        if self.keep_going {
+            self.balance.begin();
+        }
+        match add(self) {
+            Ok(added) => {
+                self.balance.commit();
+                self.txns.push(added);
+                Ok(())
+            }
+            Err(err) => {
+                self.balance.rollback();
+                Err(err)
+            }
+        }
+    }
+
+    fn add_transaction<S>(
+        &mut self,
+        ctx: &mut ReportContext<'ctx>,
+        txn: &syntax::tracked::Transaction,
+        source: S,
+    ) -> Result<Transaction<'ctx>, BookKeepError>
+    where
+        S: Fn() -> Box<error::ErrorContext>,
+    {
+        let (added, price_events) =
+            book_keeping::add_transaction(ctx, &mut self.balance, &self.automated, txn)?;
+        if self.strictness != Strictness::Permissive {
+            let undeclared = self.declarations.check_transaction(ctx, txn, &added);
+            for err in undeclared {
+                if self.strictness == Strictness::Error {
+                    return Err(err);
+                }
+                self.warnings
+                    .push(ReportError::BookKeep(err, source().into_warning()));
+            }
+        }
+        // Prices are recorded only after the transaction is accepted.
+        for event in price_events {
+            self.price_repos.insert_price(PriceSource::Ledger, event);
+        }
+        Ok(added)
+    }
 }
 
 pub fn process_account<'ctx>(
@@ -111,6 +395,27 @@ pub fn process_account<'ctx>(
     Ok(())
 }
 
+/// Processes top-level "alias" directive.
+/// The aliased account is subject to the enclosing "apply account" directives.
+pub fn process_alias<'ctx>(
+    ctx: &mut ReportContext<'ctx>,
+    alias: &syntax::AccountAlias<'_>,
+) -> Result<(), BookKeepError> {
+    let canonical = ctx.accounts.ensure_applied(&alias.account);
+    ctx.accounts
+        .register_alias(&alias.alias, canonical)
+        .map_err(|_| BookKeepError::InvalidAccountAlias(alias.alias.to_string()))
+}
+
+/// Processes "end apply account" directive.
+pub fn process_end_apply_account(ctx: &mut ReportContext<'_>) -> Result<(), BookKeepError> {
+    if ctx.accounts.exit_apply_account() {
+        Ok(())
+    } else {
+        Err(BookKeepError::UnmatchedEndApplyAccount)
+    }
+}
+
 fn process_commodity<'ctx>(
     ctx: &mut ReportContext<'ctx>,
     commodity: &syntax::CommodityDeclaration<'_>,
@@ -126,12 +431,30 @@ fn process_commodity<'ctx>(
             syntax::CommodityDetail::Format(format_amount) => {
                 ctx.commodities.set_format(canonical, format_amount.value);
             }
+            syntax::CommodityDetail::Default => ctx.commodities.set_default_commodity(canonical),
+            syntax::CommodityDetail::NoMarket => ctx.commodities.set_nomarket(canonical),
             _ => {}
         }
     }
     Ok(())
 }
 
+/// Processes "payee" directive, and returns the declared payee.
+fn process_payee<'ctx>(
+    ctx: &mut ReportContext<'ctx>,
+    payee: &syntax::PayeeDeclaration<'_>,
+) -> Result<&'ctx str, BookKeepError> {
+    let canonical = ctx.payees.ensure(&payee.name);
+    for pd in &payee.details {
+        if let syntax::PayeeDetail::Alias(alias) = pd {
+            ctx.payees
+                .register_alias(alias, canonical)
+                .map_err(|_| BookKeepError::InvalidPayeeAlias(alias.to_string()))?;
+        }
+    }
+    Ok(canonical.as_str())
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -141,8 +464,15 @@ mod tests {
 
     use assert_matches::assert_matches;
     use bumpalo::Bump;
+    use chrono::NaiveDate;
+    use indoc::indoc;
+    use maplit::hashmap;
+    use pretty_assertions::assert_eq;
+    use rust_decimal_macros::dec;
 
     use super::super::account::{AccountAggregate, AccountTreeKey};
+    use super::super::eval::Amount;
+    use super::super::query::EvalContext;
 
     #[test]
     fn process_constructs_account_tree() {
@@ -172,4 +502,549 @@ mod tests {
             ctx.account_tree.parent(child1)
         );
     }
+
+    #[test]
+    fn process_uses_price_directive() {
+        let fake = hashmap! {
+            PathBuf::from("path/to/root.ledger") => indoc! {"
+                P 2024/01/01 EUR 0.95 CHF
+
+                2024/01/02 Lunch
+                    Expenses:Food                         10 EUR
+                    Assets:Bank
+
+                P 2024/02/01 EUR 0.90 CHF
+            "}.as_bytes().to_vec(),
+        };
+        let loader = load::Loader::new(
+            PathBuf::from("path/to/root.ledger"),
+            load::FakeFileSystem::from(fake),
+        );
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+
+        let mut ledger = process(&mut ctx, &loader, &ProcessOptions::default()).unwrap();
+
+        let chf = ctx.commodities.resolve("CHF").unwrap();
+        let eval_ctx = |date| EvalContext {
+            date,
+            exchange: Some("CHF".to_string()),
+        };
+        assert_eq!(
+            Amount::from_value(chf, dec!(9.50)),
+            ledger
+                .eval(
+                    &ctx,
+                    "10 EUR",
+                    &eval_ctx(NaiveDate::from_ymd_opt(2024, 1, 15).unwrap())
+                )
+                .unwrap()
+        );
+        assert_eq!(
+            Amount::from_value(chf, dec!(9.00)),
+            ledger
+                .eval(
+                    &ctx,
+                    "10 EUR",
+                    &eval_ctx(NaiveDate::from_ymd_opt(2024, 2, 1).unwrap())
+                )
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn process_applies_account_directives_across_include() {
+        let fake = hashmap! {
+            PathBuf::from("path/to/root.ledger") => indoc! {"
+                alias Cash=Assets:Wallet
+
+                apply account Assets
+                include bank.ledger
+                end apply account
+
+                2024/01/03 Lunch
+                    Expenses:Food                         10 CHF
+                    Cash
+            "}.as_bytes().to_vec(),
+            PathBuf::from("path/to/bank.ledger") => indoc! {"
+                apply account Bank
+                alias Checking=Checking
+
+                2024/01/01 Withdrawal
+                    Checking                             -50 CHF
+                    Cash
+                end apply account
+
+                2024/01/02 Transfer
+                    Savings                               20 CHF
+                    Bank:Checking
+            "}.as_bytes().to_vec(),
+        };
+        let loader = load::Loader::new(
+            PathBuf::from("path/to/root.ledger"),
+            load::FakeFileSystem::from(fake),
+        );
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+
+        process(&mut ctx, &loader, &ProcessOptions::default()).unwrap();
+
+        let accounts: Vec<&str> = ctx.all_accounts().iter().map(|a| a.as_str()).collect();
+        assert_eq!(
+            vec![
+                "Assets:Bank:Checking",
+                "Assets:Savings",
+                "Assets:Wallet",
+                "Expenses:Food",
+            ],
+            accounts
+        );
+    }
+
+    #[test]
+    fn process_fails_on_unmatched_end_apply_account() {
+        let fake = hashmap! {
+            PathBuf::from("path/to/root.ledger") => indoc! {"
+                apply account Assets
+                end apply account
+                end apply account
+            "}.as_bytes().to_vec(),
+        };
+        let loader = load::Loader::new(
+            PathBuf::from("path/to/root.ledger"),
+            load::FakeFileSystem::from(fake),
+        );
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+
+        let got = process(&mut ctx, &loader, &ProcessOptions::default());
+
+        assert_matches!(
+            got,
+            Err(ReportError::BookKeep(
+                BookKeepError::UnmatchedEndApplyAccount,
+                _
+            ))
+        );
+    }
+
+    fn single_file_loader(content: &str) -> load::Loader<load::FakeFileSystem> {
+        let fake = hashmap! {
+            PathBuf::from("path/to/root.ledger") => content.as_bytes().to_vec(),
+        };
+        load::Loader::new(
+            PathBuf::from("path/to/root.ledger"),
+            load::FakeFileSystem::from(fake),
+        )
+    }
+
+    #[test]
+    fn process_applies_default_commodity_and_nomarket() {
+        let loader = single_file_loader(indoc! {"
+            D 1,000 JPY
+            commodity USD
+                nomarket
+
+            2024/01/01 Exchange
+                Assets:Broker                         10 USD @ 150
+                Assets:Broker                         10 EUR @ 160
+                Assets:Bank                        -3100
+
+            2024/01/02 Check
+                Assets:Bank                             = -3,100
+        "});
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+
+        let mut ledger = process(&mut ctx, &loader, &ProcessOptions::default()).unwrap();
+
+        let jpy = ctx.commodities.resolve("JPY").unwrap();
+        assert_eq!(Some(jpy), ctx.commodities.default_commodity());
+        let bank = ctx.account("Assets:Bank").unwrap();
+        assert_eq!(
+            Some(&Amount::from_value(jpy, dec!(-3100))),
+            ledger.raw_balance.get(bank)
+        );
+        let eval_ctx = EvalContext {
+            date: NaiveDate::from_ymd_opt(2024, 1, 2).unwrap(),
+            exchange: Some("JPY".to_string()),
+        };
+        assert_eq!(
+            Amount::from_value(jpy, dec!(1600)),
+            ledger.eval(&ctx, "10 EUR", &eval_ctx).unwrap()
+        );
+        assert!(ledger.eval(&ctx, "10 USD", &eval_ctx).is_err());
+    }
+
+    #[test]
+    fn process_keep_going_skips_failed_transactions() {
+        let loader = single_file_loader(indoc! {"
+            2024/01/01 Lunch
+                Expenses:Food                          100 JPY
+                Assets:Bank
+
+            2024/01/02 Unbalanced
+                Assets:Broker                         10 USD @ 150 JPY
+                Assets:Bank                         -1,000 JPY
+
+            2024/01/03 Wrong assertion
+                Expenses:Food                           10 JPY
+                Assets:Bank                            -10 JPY = -999 JPY
+
+            2024/01/04 Check
+                Assets:Bank                              0 JPY = -100 JPY
+        "});
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+
+        let mut ledger = process(
+            &mut ctx,
+            &loader,
+            &ProcessOptions {
+                keep_going: true,
+                ..ProcessOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert_matches!(
+            ledger.errors(),
+            [
+                ReportError::BookKeep(BookKeepError::UnbalancedPostings { .. }, _),
+                ReportError::BookKeep(BookKeepError::BalanceAssertionFailure { .. }, _),
+            ]
+        );
+        assert_eq!(2, ledger.transactions().count());
+        let jpy = ctx.commodities.resolve("JPY").unwrap();
+        let bank = ctx.account("Assets:Bank").unwrap();
+        assert_eq!(
+            Some(&Amount::from_value(jpy, dec!(-100))),
+            ledger.raw_balance.get(bank)
+        );
+        let eval_ctx = EvalContext {
+            date: NaiveDate::from_ymd_opt(2024, 1, 4).unwrap(),
+            exchange: Some("JPY".to_string()),
+        };
+        assert!(ledger.eval(&ctx, "10 USD", &eval_ctx).is_err());
+    }
+
+    #[test]
+    fn process_keep_going_skips_prices_of_rejected_transactions() {
+        let loader = single_file_loader(indoc! {"
+            account Assets:Bank
+            account Assets:Broker
+            commodity JPY
+            payee Broker
+
+            2024/01/02 Broker
+                Assets:Broker                         10 USD @ 150 JPY
+                Assets:Bank                         -1,500 JPY
+        "});
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+
+        let mut ledger = process(
+            &mut ctx,
+            &loader,
+            &ProcessOptions {
+                strictness: Strictness::Error,
+                keep_going: true,
+                ..ProcessOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert_matches!(
+            ledger.errors(),
+            [ReportError::BookKeep(BookKeepError::UndeclaredCommodity(commodity, _), _)]
+                if commodity == "USD"
+        );
+        assert_eq!(0, ledger.transactions().count());
+        let eval_ctx = EvalContext {
+            date: NaiveDate::from_ymd_opt(2024, 1, 4).unwrap(),
+            exchange: Some("JPY".to_string()),
+        };
+        assert!(ledger.eval(&ctx, "10 USD", &eval_ctx).is_err());
+    }
+
+    #[test]
+    fn process_keep_going_keeps_check_in_of_failed_check_out() {
+        let loader = single_file_loader(indoc! {"
+            i 2025/03/01 09:00:00 Client:Acme
+            o 2025/03/01 08:00:00
+            o 2025/03/01 10:30:00
+        "});
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+
+        let ledger = process(
+            &mut ctx,
+            &loader,
+            &ProcessOptions {
+                keep_going: true,
+                ..ProcessOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert_matches!(
+            ledger.errors(),
+            [ReportError::BookKeep(
+                BookKeepError::CheckOutBeforeCheckIn { .. },
+                _
+            )]
+        );
+        assert_eq!(1, ledger.transactions().count());
+        let hour = ctx.commodities.resolve("h").unwrap();
+        let acme = ctx.account("Client:Acme").unwrap();
+        assert_eq!(
+            Some(&Amount::from_value(hour, dec!(1.5))),
+            ledger.raw_balance.get(acme)
+        );
+    }
+
+    #[test]
+    fn process_converts_timeclock_into_hours() {
+        let loader = single_file_loader(indoc! {"
+            apply account Client
+            i 2025/03/01 09:00:00 Acme  Design review
+            i 2025/03/01 09:30:00 Globex
+            o 2025/03/01 10:15:00 Globex
+            end apply account
+            o 2025/03/01 10:30:00
+        "});
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+
+        let ledger = process(&mut ctx, &loader, &ProcessOptions::default()).unwrap();
+
+        let hour = ctx.commodities.resolve("h").unwrap();
+        let acme = ctx.account("Client:Acme").unwrap();
+        let globex = ctx.account("Client:Globex").unwrap();
+        let got: Vec<_> = ledger
+            .transactions
+            .iter()
+            .map(|txn| {
+                let [posting] = &*txn.postings else {
+                    panic!("timeclock must have exactly one posting: {:?}", txn);
+                };
+                (txn.date, posting.account, posting.kind, posting.payee)
+            })
+            .collect();
+        let date = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        assert_eq!(
+            vec![
+                (date, globex, syntax::PostingKind::Virtual, ""),
+                (date, acme, syntax::PostingKind::Virtual, "Design review"),
+            ],
+            got
+        );
+        assert_eq!(
+            vec![
+                (acme, Amount::from_value(hour, dec!(1.5))),
+                (globex, Amount::from_value(hour, dec!(0.75))),
+            ],
+            ledger.raw_balance.into_vec()
+        );
+    }
+
+    #[test]
+    fn process_fails_on_unmatched_check_in() {
+        let loader = single_file_loader(indoc! {"
+            i 2025/03/01 09:00:00 Client:Acme
+            i 2025/03/01 09:30:00 Client:Globex
+            o 2025/03/01 10:15:00 Client:Globex
+        "});
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+
+        let got = process(&mut ctx, &loader, &ProcessOptions::default());
+
+        assert_matches!(
+            got,
+            Err(ReportError::BookKeep(BookKeepError::UnmatchedCheckIn(account), _))
+                if account == "Client:Acme"
+        );
+    }
+
+    #[test]
+    fn process_fails_on_invalid_check_out() {
+        let arena = Bump::new();
+
+        let mut ctx = ReportContext::new(&arena);
+        let got = process(
+            &mut ctx,
+            single_file_loader("o 2025/03/01 10:15:00\n"),
+            &ProcessOptions::default(),
+        );
+        assert_matches!(
+            got,
+            Err(ReportError::BookKeep(
+                BookKeepError::AmbiguousCheckOut(0),
+                _
+            ))
+        );
+
+        let mut ctx = ReportContext::new(&arena);
+        let got = process(
+            &mut ctx,
+            single_file_loader(indoc! {"
+                i 2025/03/01 09:00:00 Client:Acme
+                i 2025/03/01 09:30:00 Client:Globex
+                o 2025/03/01 10:15:00
+            "}),
+            &ProcessOptions::default(),
+        );
+        assert_matches!(
+            got,
+            Err(ReportError::BookKeep(
+                BookKeepError::AmbiguousCheckOut(2),
+                _
+            ))
+        );
+
+        let mut ctx = ReportContext::new(&arena);
+        let got = process(
+            &mut ctx,
+            single_file_loader(indoc! {"
+                i 2025/03/01 09:00:00 Client:Acme
+                o 2025/03/01 10:15:00 Client:Globex
+            "}),
+            &ProcessOptions::default(),
+        );
+        assert_matches!(
+            got,
+            Err(ReportError::BookKeep(BookKeepError::UnmatchedCheckOut(account), _))
+                if account == "Client:Globex"
+        );
+
+        let mut ctx = ReportContext::new(&arena);
+        let got = process(
+            &mut ctx,
+            single_file_loader(indoc! {"
+                i 2025/03/01 09:00:00 Client:Acme
+                i 2025/03/01 09:10:00 Client:Acme
+            "}),
+            &ProcessOptions::default(),
+        );
+        assert_matches!(
+            got,
+            Err(ReportError::BookKeep(BookKeepError::DuplicateCheckIn(account), _))
+                if account == "Client:Acme"
+        );
+
+        let mut ctx = ReportContext::new(&arena);
+        let got = process(
+            &mut ctx,
+            single_file_loader(indoc! {"
+                i 2025/03/01 09:00:00 Client:Acme
+                o 2025/03/01 08:00:00
+            "}),
+            &ProcessOptions::default(),
+        );
+        assert_matches!(
+            got,
+            Err(ReportError::BookKeep(
+                BookKeepError::CheckOutBeforeCheckIn { .. },
+                _
+            ))
+        );
+    }
+
+    fn strict_fixture() -> load::Loader<load::FakeFileSystem> {
+        single_file_loader(indoc! {"
+            account Assets:Bank
+            account Expenses:Food
+                alias Food
+            commodity CHF
+            payee Migros
+                alias MIGROS ZURICH
+            tag trip
+
+            2024/01/01 MIGROS ZURICH
+                ; :trip:
+                Food                                  10 CHF
+                Assets:Bank
+
+            2024/01/02 Coop
+                ; :receipt:
+                Expenses:Drink                         5 EUR
+                Assets:Bank                           -5 EUR
+
+            2024/01/03 Coop
+                ; :receipt:
+                Expenses:Drink                         5 EUR
+                Assets:Bank                           -5 EUR
+        "})
+    }
+
+    #[test]
+    fn process_permissive_accepts_undeclared() {
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+
+        let ledger = process(&mut ctx, strict_fixture(), &ProcessOptions::default()).unwrap();
+
+        assert!(ledger.warnings().is_empty());
+    }
+
+    #[test]
+    fn process_strict_warns_undeclared_once() {
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+
+        let ledger = process(
+            &mut ctx,
+            strict_fixture(),
+            &ProcessOptions {
+                strictness: Strictness::Warn,
+                ..ProcessOptions::default()
+            },
+        )
+        .unwrap();
+
+        let got: Vec<String> = ledger
+            .warnings()
+            .iter()
+            .map(|w| match w {
+                ReportError::BookKeep(err, _) => err.to_string(),
+                other => panic!("unexpected warning: {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                "payee Coop is not declared",
+                "account Expenses:Drink is not declared",
+                "commodity EUR is not declared",
+                "tag receipt is not declared",
+            ],
+            got
+        );
+        // Payee alias is resolved to the canonical payee.
+        assert_eq!(
+            "Migros",
+            ledger.transactions().next().unwrap().postings[0].payee
+        );
+    }
+
+    #[test]
+    fn process_pedantic_fails_on_undeclared() {
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+
+        let got = process(
+            &mut ctx,
+            strict_fixture(),
+            &ProcessOptions {
+                strictness: Strictness::Error,
+                ..ProcessOptions::default()
+            },
+        );
+
+        assert_matches!(
+            got,
+            Err(ReportError::BookKeep(BookKeepError::UndeclaredPayee(payee, None), _))
+                if payee == "Coop"
+        );
+    }
 }
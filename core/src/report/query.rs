@@ -1,10 +1,14 @@
 //! Provides query of transactions / balances on the processed [Ledger] instance.
 
-use std::{borrow::Cow, collections::HashSet};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+};
 
 use bumpalo::collections as bcc;
 use chrono::NaiveDate;
 use lender::{FallibleLender, FallibleLending, check_covariance_fallible};
+use rust_decimal::Decimal;
 
 use crate::{
     parse,
@@ -17,7 +21,9 @@ use super::{
     balance::Balance,
     commodity::OwnedCommodity,
     context::ReportContext,
-    eval::{Amount, EvalError, Evaluable},
+    error::ReportError,
+    eval::{Amount, EvalError, Evaluable, PostingAmount},
+    periodic::PeriodicTransaction,
     price_db::{self, ConversionError, PriceRepository},
     transaction::{Posting, Transaction},
 };
@@ -29,16 +35,27 @@ pub struct Ledger<'ctx> {
     /// slice boxes when building the date-sorted clone cache.
     pub(super) arena: &'ctx bumpalo::Bump,
     pub(super) transactions: Vec<Transaction<'ctx>>,
+    /// Periodic transactions, which are kept apart from `transactions`
+    /// as those are only used for the budget.
+    pub(super) periodic: Vec<PeriodicTransaction<'ctx>>,
     /// Lazily-computed clone of `transactions` sorted by date. `None` until
     /// the first query that needs date ordering.
+    /// Indexed by [`DateKind`], as the order differs between the primary date
+    /// and the effective date.
     ///
     /// We store actual `Transaction` clones (not indices into `transactions`)
     /// so the date-ordered iteration is sequential — sequential reads are
     /// L1-cache-friendly, whereas the previous index-based design did a
     /// random-access lookup into `transactions[i]` per yielded row.
-    pub(super) date_sorted_txns: Option<Vec<Transaction<'ctx>>>,
+    /// Transactions whose postings have different dates are split into
+    /// multiple clones, so that every posting in a clone has the clone's date.
+    pub(super) date_sorted_txns: [Option<Vec<Transaction<'ctx>>>; 2],
     pub(super) raw_balance: Balance<'ctx>,
     pub(super) price_repos: PriceRepository<'ctx>,
+    /// Warnings reported while processing, such as undeclared accounts.
+    pub(super) warnings: Vec<ReportError>,
+    /// Errors skipped over with [`ProcessOptions::keep_going`](super::ProcessOptions::keep_going).
+    pub(super) errors: Vec<ReportError>,
 }
 
 /// Error type for [`Ledger`] methods.
@@ -66,10 +83,27 @@ pub enum Sort {
     /// Preserve the order of appearance in the source file(s).
     #[default]
     Original,
-    /// Stable sort by transaction date, ascending.
+    /// Stable sort by posting date, ascending.
     Date,
 }
 
+/// Index of [`Ledger::date_sorted_txns`].
+#[derive(Debug, Clone, Copy)]
+enum DateKind {
+    Primary = 0,
+    Effective = 1,
+}
+
+impl DateKind {
+    fn new(effective: bool) -> Self {
+        if effective {
+            DateKind::Effective
+        } else {
+            DateKind::Primary
+        }
+    }
+}
+
 /// Query to drive [`Ledger::register_entries`].
 #[derive(Debug, Default)]
 // TODO: non_exhaustive
@@ -85,6 +119,11 @@ pub struct RegisterQuery<'ctx> {
     pub conversion: Option<Conversion<'ctx>>,
     /// Order in which the matching postings are yielded.
     pub sort: Sort,
+    /// Only yields real postings, excluding virtual ones.
+    pub real: bool,
+    /// Uses the effective date of the posting if available,
+    /// for both the date range and the historical conversion.
+    pub effective: bool,
 }
 
 /// A row of the register report.
@@ -95,7 +134,7 @@ pub struct RegisterQuery<'ctx> {
 #[derive(Debug)]
 #[non_exhaustive]
 pub struct RegisterEntry<'lend, 'ctx> {
-    /// Date of the enclosing transaction.
+    /// Date of the posting, or its effective date if requested.
     pub date: NaiveDate,
     /// Payee of the posting (per-posting `; Payee:` override falls back to the
     /// enclosing transaction's payee).
@@ -167,11 +206,16 @@ pub struct BalanceQuery<'ctx> {
     pub account: AccountFilter<'ctx>,
     pub conversion: Option<Conversion<'ctx>>,
     pub date_range: DateRange,
+    /// Only accumulates real postings, excluding virtual ones.
+    pub real: bool,
+    /// Uses the effective date of the posting if available,
+    /// for both the date range and the historical conversion.
+    pub effective: bool,
 }
 
 impl BalanceQuery<'_> {
     fn require_recompute(&self) -> bool {
-        if !self.date_range.is_bypass() {
+        if !self.date_range.is_bypass() || self.real {
             return true;
         }
         if matches!(&self.conversion, Some(conv) if conv.strategy == ConversionStrategy::Historical)
@@ -183,6 +227,59 @@ impl BalanceQuery<'_> {
     }
 }
 
+/// Query for [`Ledger::budget()`].
+#[derive(Debug, Default)]
+// TODO: non_exhaustive
+pub struct BudgetQuery<'ctx> {
+    /// Select which budgeted accounts to include.
+    /// Defaults to [`AccountFilter::All`] (every budgeted account).
+    pub account: AccountFilter<'ctx>,
+    pub conversion: Option<Conversion<'ctx>>,
+    /// Half-open date range to restrict the periods.
+    /// If unbounded, the range of the transactions is used instead.
+    pub date_range: DateRange,
+}
+
+/// A row of the budget report, comparing the actual amount against the budget
+/// for an account in a period.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BudgetEntry<'ctx> {
+    /// Start of the period (inclusive).
+    pub start: NaiveDate,
+    /// End of the period (exclusive).
+    pub end: NaiveDate,
+    /// Budgeted account.
+    pub account: Account<'ctx>,
+    /// Actual amount posted to the account or its descendants within the period.
+    pub actual: Amount<'ctx>,
+    /// Budgeted amount for the period.
+    pub budget: Amount<'ctx>,
+}
+
+impl<'ctx> BudgetEntry<'ctx> {
+    /// Returns the difference of the actual amount from the budget.
+    pub fn difference(&self) -> Amount<'ctx> {
+        self.actual.clone() - &self.budget
+    }
+
+    /// Returns the percentage of the actual amount against the budget.
+    /// Returns `None` if the budget is zero, or the amounts are not in the same single commodity.
+    pub fn percent(&self) -> Option<Decimal> {
+        let PostingAmount::Single(budget) = PostingAmount::try_from(&self.budget).ok()? else {
+            return None;
+        };
+        let actual = match PostingAmount::try_from(&self.actual).ok()? {
+            PostingAmount::Zero => Decimal::ZERO,
+            PostingAmount::Single(actual) if actual.commodity == budget.commodity => actual.value,
+            PostingAmount::Single(_) => return None,
+        };
+        actual
+            .checked_mul(Decimal::ONE_HUNDRED)?
+            .checked_div(budget.value)
+    }
+}
+
 /// Context passed to [`Ledger::eval()`].
 #[derive(Debug)]
 // TODO: non_exhaustive
@@ -199,12 +296,27 @@ impl<'ctx> Ledger<'ctx> {
         Self {
             arena: ctx.arena,
             transactions: Vec::new(),
-            date_sorted_txns: None,
+            periodic: Vec::new(),
+            date_sorted_txns: [None, None],
             raw_balance: Balance::default(),
             price_repos: price_db::PriceRepositoryBuilder::default().build(),
+            warnings: Vec::new(),
+            errors: Vec::new(),
         }
     }
 
+    /// Returns warnings reported while processing,
+    /// such as undeclared names with [`Strictness::Warn`](super::Strictness::Warn).
+    pub fn warnings(&self) -> &[ReportError] {
+        &self.warnings
+    }
+
+    /// Returns errors of the entries skipped while processing,
+    /// which is only populated with [`ProcessOptions::keep_going`](super::ProcessOptions::keep_going).
+    pub fn errors(&self) -> &[ReportError] {
+        &self.errors
+    }
+
     /// Returns iterator for all transactions.
     pub fn transactions(&self) -> impl Iterator<Item = &Transaction<'ctx>> {
         self.transactions.iter()
@@ -215,27 +327,43 @@ impl<'ctx> Ledger<'ctx> {
     /// re-allocated in the same arena) into a new `Vec`, then stable-sorts
     /// the copy by date. The result is a sequentially-iterable view that
     /// preserves the original `transactions` Vec in file order.
-    fn ensure_date_sorted_txns(&mut self) {
-        if self.date_sorted_txns.is_some() {
+    ///
+    /// A transaction is split per distinct posting date of the given `kind`,
+    /// so that each clone is dated as all of its postings.
+    fn ensure_date_sorted_txns(&mut self, kind: DateKind) {
+        let slot = &mut self.date_sorted_txns[kind as usize];
+        if slot.is_some() {
             return;
         }
+        let effective = matches!(kind, DateKind::Effective);
         let arena = self.arena;
-        let mut sorted: Vec<Transaction<'ctx>> = self
-            .transactions
-            .iter()
-            .map(|txn| {
+        let mut sorted: Vec<Transaction<'ctx>> = Vec::with_capacity(self.transactions.len());
+        let mut dates: Vec<NaiveDate> = Vec::new();
+        for txn in &self.transactions {
+            dates.clear();
+            for p in txn.postings.iter() {
+                let date = p.query_date(effective);
+                if !dates.contains(&date) {
+                    dates.push(date);
+                }
+            }
+            for &date in &dates {
                 let mut postings = bcc::Vec::with_capacity_in(txn.postings.len(), arena);
                 for p in txn.postings.iter() {
-                    postings.push(p.clone());
+                    if p.query_date(effective) == date {
+                        postings.push(p.clone());
+                    }
                 }
-                Transaction {
-                    date: txn.date,
+                sorted.push(Transaction {
+                    date,
+                    effective_date: txn.effective_date,
                     postings: postings.into_boxed_slice(),
-                }
-            })
-            .collect();
+                    metadata: txn.metadata.clone(),
+                });
+            }
+        }
         sorted.sort_by_key(|t| t.date);
-        self.date_sorted_txns = Some(sorted);
+        *slot = Some(sorted);
     }
 
     /// Returns a [`FallibleLender`] over [`RegisterEntry`] rows matching the
@@ -281,9 +409,10 @@ impl<'ctx> Ledger<'ctx> {
                 // is a no-op on every txn yielded here — it's kept for the
                 // `Linear` arms above.
                 Sort::Date => {
-                    self.ensure_date_sorted_txns();
+                    let kind = DateKind::new(query.effective);
+                    self.ensure_date_sorted_txns(kind);
                     date_range_iter(
-                        self.date_sorted_txns
+                        self.date_sorted_txns[kind as usize]
                             .as_deref()
                             .expect("just built by ensure_date_sorted_txns"),
                         query.date_range,
@@ -297,6 +426,8 @@ impl<'ctx> Ledger<'ctx> {
             current: [].iter(),
             account_filter,
             date_range: query.date_range,
+            real: query.real,
+            effective: query.effective,
             conversion,
             price_repos: &mut self.price_repos,
             current_date: NaiveDate::MIN,
@@ -324,9 +455,10 @@ impl<'ctx> Ledger<'ctx> {
             let txns = if query.date_range.is_bypass() {
                 TxnIter::linear(&self.transactions)
             } else {
-                self.ensure_date_sorted_txns();
+                let kind = DateKind::new(query.effective);
+                self.ensure_date_sorted_txns(kind);
                 date_range_iter(
-                    self.date_sorted_txns
+                    self.date_sorted_txns[kind as usize]
                         .as_deref()
                         .expect("just built by ensure_date_sorted_txns"),
                     query.date_range,
@@ -337,6 +469,8 @@ impl<'ctx> Ledger<'ctx> {
                 &mut self.price_repos,
                 txns,
                 query.conversion,
+                query.real,
+                query.effective,
             )?)
         };
         let balance = match query.conversion {
@@ -384,6 +518,160 @@ impl<'ctx> Ledger<'ctx> {
         }
     }
 
+    /// Returns the budget report matching the given query.
+    ///
+    /// Each periodic transaction is expanded into the periods starting within the query date range.
+    /// The boundaries of all those periods split the range into non-overlapping reporting periods,
+    /// and each budgeted amount is added to the reporting period where its period starts,
+    /// so that overlapping periodic transactions don't count the same actual postings twice.
+    /// Rows are ordered by the reporting period, then by the order of appearance of the account.
+    pub fn budget(
+        &mut self,
+        ctx: &ReportContext<'ctx>,
+        query: &BudgetQuery<'ctx>,
+    ) -> Result<Vec<BudgetEntry<'ctx>>, QueryError> {
+        self.ensure_date_sorted_txns(DateKind::Primary);
+        let sorted = self.date_sorted_txns[DateKind::Primary as usize]
+            .as_deref()
+            .expect("just built by ensure_date_sorted_txns");
+        // Periods without the explicit begin start from the beginning of the query,
+        // or the first transaction if the query is unbounded.
+        let default_begin = query
+            .date_range
+            .start
+            .or(sorted.first().map(|txn| txn.date));
+        let end = query
+            .date_range
+            .end
+            .or_else(|| sorted.last().and_then(|txn| txn.date.succ_opt()));
+        let (Some(default_begin), Some(end)) = (default_begin, end) else {
+            return Ok(Vec::new());
+        };
+        let mut accounts: Vec<Account<'ctx>> = Vec::new();
+        let mut account_index: HashMap<Account<'ctx>, usize> = HashMap::new();
+        let mut boundaries: BTreeSet<NaiveDate> = BTreeSet::new();
+        // Budgeted amount keyed by the start of the reporting period and the account index.
+        let mut budgets: BTreeMap<(NaiveDate, usize), Amount<'ctx>> = BTreeMap::new();
+        for periodic in &self.periodic {
+            let period = periodic.period;
+            let last = period.end.map_or(end, |period_end| period_end.min(end));
+            let mut current = period.begin.unwrap_or(default_begin);
+            while current < last {
+                let Some(next) = period.interval.next_date(current) else {
+                    break;
+                };
+                if query.date_range.contains(current) {
+                    boundaries.insert(current);
+                    boundaries.insert(next);
+                    for (account, amount) in &periodic.postings {
+                        if !query.account.is_match(account) {
+                            continue;
+                        }
+                        let index = *account_index.entry(*account).or_insert_with(|| {
+                            accounts.push(*account);
+                            accounts.len() - 1
+                        });
+                        *budgets.entry((current, index)).or_default() += amount;
+                    }
+                }
+                current = next;
+            }
+        }
+        let filters: Vec<AccountFilter<'ctx>> = accounts
+            .iter()
+            .map(|account| match ctx.account_aggregate(account.as_str()) {
+                Some(aggregate) => AccountFilter::descendants_of(ctx, aggregate),
+                None => AccountFilter::single(*account),
+            })
+            .collect();
+        let boundaries: Vec<NaiveDate> = boundaries.into_iter().collect();
+        // Actual amounts of each reporting period and account, in a single pass over the postings.
+        let mut actuals: Vec<Amount<'ctx>> =
+            vec![Amount::zero(); boundaries.len().saturating_sub(1) * accounts.len()];
+        // Indices of the accounts matching the posting account, as the aggregates can overlap.
+        let mut matching: HashMap<Account<'ctx>, Vec<usize>> = HashMap::new();
+        if let (Some(&first), Some(&last)) = (boundaries.first(), boundaries.last()) {
+            let date_range = DateRange {
+                start: Some(first),
+                end: Some(last),
+            };
+            for txn in date_range_iter(sorted, date_range) {
+                let period = boundaries.partition_point(|boundary| *boundary <= txn.date) - 1;
+                for posting in txn.postings.iter() {
+                    let indices = matching.entry(posting.account).or_insert_with(|| {
+                        (0..filters.len())
+                            .filter(|&index| filters[index].is_match(&posting.account))
+                            .collect()
+                    });
+                    if indices.is_empty() {
+                        continue;
+                    }
+                    let converted;
+                    let amount = match query.conversion {
+                        Some(Conversion {
+                            strategy: ConversionStrategy::Historical,
+                            target,
+                        }) => {
+                            converted = price_db::convert_amount(
+                                ctx,
+                                &mut self.price_repos,
+                                &posting.amount,
+                                target,
+                                txn.date,
+                            )
+                            .map_err(QueryError::CommodityConversionFailure)?;
+                            &converted
+                        }
+                        _ => &posting.amount,
+                    };
+                    for index in indices.iter() {
+                        actuals[period * accounts.len() + index] += amount;
+                    }
+                }
+            }
+        }
+        let mut entries: Vec<BudgetEntry<'ctx>> = Vec::new();
+        for (period, window) in boundaries.windows(2).enumerate() {
+            let (start, end) = (window[0], window[1]);
+            for (index, account) in accounts.iter().enumerate() {
+                let mut actual = std::mem::take(&mut actuals[period * accounts.len() + index]);
+                let mut budget = budgets.remove(&(start, index)).unwrap_or_default();
+                if actual.is_zero() && budget.is_zero() {
+                    continue;
+                }
+                if let Some(Conversion { strategy, target }) = query.conversion {
+                    let date = match strategy {
+                        ConversionStrategy::Historical => start,
+                        ConversionStrategy::UpToDate { today } => {
+                            actual = price_db::convert_amount(
+                                ctx,
+                                &mut self.price_repos,
+                                &actual,
+                                target,
+                                today,
+                            )
+                            .map_err(QueryError::CommodityConversionFailure)?;
+                            today
+                        }
+                    };
+                    budget =
+                        price_db::convert_amount(ctx, &mut self.price_repos, &budget, target, date)
+                            .map_err(QueryError::CommodityConversionFailure)?;
+                }
+                actual.round_mut(ctx);
+                budget.round_mut(ctx);
+                entries.push(BudgetEntry {
+                    start,
+                    end,
+                    account: *account,
+                    actual,
+                    budget,
+                });
+            }
+        }
+        Ok(entries)
+    }
+
     /// Evals given `expression` with the given condition.
     pub fn eval(
         &mut self,
@@ -484,6 +772,8 @@ pub struct RegisterEntries<'a, 'ctx> {
     current: std::slice::Iter<'a, Posting<'ctx>>,
     account_filter: AccountFilter<'ctx>,
     date_range: DateRange,
+    real: bool,
+    effective: bool,
     conversion: Option<Conversion<'ctx>>,
     price_repos: &'a mut PriceRepository<'ctx>,
     current_date: NaiveDate,
@@ -498,16 +788,17 @@ impl<'a, 'ctx> RegisterEntries<'a, 'ctx> {
     fn advance_to_next_posting(&mut self) -> Option<&'a Posting<'ctx>> {
         loop {
             if let Some(posting) = self.current.next() {
-                if self.account_filter.is_match(&posting.account) {
+                let date = posting.query_date(self.effective);
+                if (!self.real || posting.kind == syntax::PostingKind::Real)
+                    && self.date_range.contains(date)
+                    && self.account_filter.is_match(&posting.account)
+                {
+                    self.current_date = date;
                     return Some(posting);
                 }
                 continue;
             }
             let txn = self.txns.next()?;
-            if !self.date_range.contains(txn.date) {
-                continue;
-            }
-            self.current_date = txn.date;
             self.current = txn.postings.iter();
         }
     }
@@ -574,7 +865,8 @@ fn date_range_iter<'a, 'ctx>(
 }
 
 /// Accumulates the balance for every posting yielded by `txns`, applying
-/// `conversion` if requested.
+/// `conversion` if requested. Virtual postings are skipped if `real` is set.
+/// Historical conversion uses the effective date of the posting if `effective` is set.
 ///
 /// Free function so the caller can split borrows: `price_repos` and the
 /// iterator borrow disjoint fields of `Ledger`, but the borrow checker
@@ -584,17 +876,28 @@ fn compute_balance<'a, 'ctx>(
     price_repos: &mut PriceRepository<'ctx>,
     txns: TxnIter<'a, 'ctx>,
     conversion: Option<Conversion<'ctx>>,
+    real: bool,
+    effective: bool,
 ) -> Result<Balance<'ctx>, QueryError> {
     let mut bal = Balance::default();
     for txn in txns {
         for posting in txn.postings.iter() {
+            if real && posting.kind != syntax::PostingKind::Real {
+                continue;
+            }
             let delta = match conversion {
                 Some(Conversion {
                     strategy: ConversionStrategy::Historical,
                     target,
                 }) => Cow::Owned(
-                    price_db::convert_amount(ctx, price_repos, &posting.amount, target, txn.date)
-                        .map_err(QueryError::CommodityConversionFailure)?,
+                    price_db::convert_amount(
+                        ctx,
+                        price_repos,
+                        &posting.amount,
+                        target,
+                        posting.query_date(effective),
+                    )
+                    .map_err(QueryError::CommodityConversionFailure)?,
                 ),
                 None
                 | Some(Conversion {
@@ -619,6 +922,10 @@ pub enum AccountFilter<'ctx> {
     Exact(Account<'ctx>),
     /// Matches any account in the set.
     Set(HashSet<Account<'ctx>>),
+    /// Matches any account whose name matches any of the regex patterns.
+    /// Unlike [`AccountFilter::Set`], this also matches accounts
+    /// which are not known yet when the filter is built.
+    Pattern(regex::RegexSet),
 }
 
 impl<'ctx> AccountFilter<'ctx> {
@@ -675,6 +982,16 @@ impl<'ctx> AccountFilter<'ctx> {
         Ok(Self::from_set(matched))
     }
 
+    /// Builds a filter matching the account with any of the given regex `patterns`,
+    /// evaluated on every match instead of resolving the known accounts upfront.
+    ///
+    /// Returns [`regex::Error`] if any pattern fails to compile.
+    pub fn from_lazy_regex_patterns(patterns: &[impl AsRef<str>]) -> Result<Self, regex::Error> {
+        Ok(Self::Pattern(regex::RegexSet::new(
+            patterns.iter().map(AsRef::as_ref),
+        )?))
+    }
+
     /// Builds a filter matching `prefix` itself and every account nested
     /// under it — accounts which are equal to `prefix`, or their name starting with
     /// `"{prefix}:"`.
@@ -701,11 +1018,12 @@ impl<'ctx> AccountFilter<'ctx> {
 
     /// Returns `true` if the given account matches this filter.
     #[inline]
-    fn is_match(&self, account: &Account<'ctx>) -> bool {
+    pub(super) fn is_match(&self, account: &Account<'ctx>) -> bool {
         match self {
             AccountFilter::All => true,
             AccountFilter::Exact(target) => account == target,
             AccountFilter::Set(targets) => targets.contains(account),
+            AccountFilter::Pattern(patterns) => patterns.is_match(account.as_str()),
         }
     }
 }
@@ -873,6 +1191,8 @@ mod tests {
                         target: jpy,
                     }),
                     date_range: DateRange::default(),
+                    real: false,
+                    effective: false,
                 },
             )
             .unwrap();
@@ -961,6 +1281,8 @@ mod tests {
                         target: jpy,
                     }),
                     date_range: DateRange::default(),
+                    real: false,
+                    effective: false,
                 },
             )
             .unwrap();
@@ -1041,6 +1363,8 @@ mod tests {
                         start: Some(NaiveDate::from_ymd_opt(2024, 1, 5).unwrap()),
                         end: Some(NaiveDate::from_ymd_opt(2024, 1, 9).unwrap()),
                     },
+                    real: false,
+                    effective: false,
                 },
             )
             .unwrap();
@@ -1076,6 +1400,8 @@ mod tests {
                     account: AccountFilter::from_regex_patterns(&ctx, &["^Assets"]).unwrap(),
                     conversion: None,
                     date_range: DateRange::default(),
+                    real: false,
+                    effective: false,
                 },
             )
             .unwrap()
@@ -1160,6 +1486,8 @@ mod tests {
                         date_range: DateRange::default(),
                         conversion: None,
                         sort: Sort::Original,
+                        real: false,
+                        effective: false,
                     },
                 )
                 .unwrap(),
@@ -1220,6 +1548,8 @@ mod tests {
                         },
                         conversion: None,
                         sort: Sort::Original,
+                        real: false,
+                        effective: false,
                     },
                 )
                 .unwrap(),
@@ -1262,6 +1592,8 @@ mod tests {
                         date_range: DateRange::default(),
                         conversion: None,
                         sort: Sort::Original,
+                        real: false,
+                        effective: false,
                     },
                 )
                 .unwrap(),
@@ -1296,6 +1628,8 @@ mod tests {
                             target: jpy,
                         }),
                         sort: Sort::Original,
+                        real: false,
+                        effective: false,
                     },
                 )
                 .unwrap(),
@@ -1341,6 +1675,8 @@ mod tests {
                         target: jpy,
                     }),
                     sort: Sort::Original,
+                    real: false,
+                    effective: false,
                 },
             )
             .err()
@@ -1512,6 +1848,289 @@ mod tests {
         );
     }
 
+    fn effective_loader() -> load::Loader<load::FakeFileSystem> {
+        let content = indoc! {"
+            2024/01/30 Card
+                Expenses:Food      100 JPY
+                Liabilities:Card
+                  ; [=2024/02/25]
+
+            2024/02/10=2024/02/12 Cash
+                Expenses:Food      200 JPY
+                Assets:Cash
+        "};
+        let fake = hashmap! {
+            PathBuf::from("path/to/file.ledger") => content.as_bytes().to_vec(),
+        };
+        load::Loader::new(PathBuf::from("path/to/file.ledger"), fake.into())
+    }
+
+    #[test]
+    fn register_entries_effective_uses_posting_effective_date() {
+        let arena = Bump::new();
+        let mut ctx = report::ReportContext::new(&arena);
+        let mut ledger = report::process(
+            &mut ctx,
+            effective_loader(),
+            &report::ProcessOptions::default(),
+        )
+        .unwrap();
+        let date = |m, d| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+        for sort in [Sort::Original, Sort::Date] {
+            let rows = collect_register(
+                ledger
+                    .register_entries(
+                        &ctx,
+                        &RegisterQuery {
+                            date_range: DateRange {
+                                start: Some(date(2, 1)),
+                                end: None,
+                            },
+                            sort,
+                            effective: true,
+                            ..Default::default()
+                        },
+                    )
+                    .unwrap(),
+            )
+            .unwrap();
+            let got: Vec<(NaiveDate, &str)> = rows.iter().map(|r| (r.0, r.2.as_str())).collect();
+            let want = match sort {
+                Sort::Original => vec![
+                    (date(2, 25), "Liabilities:Card"),
+                    (date(2, 12), "Expenses:Food"),
+                    (date(2, 12), "Assets:Cash"),
+                ],
+                Sort::Date => vec![
+                    (date(2, 12), "Expenses:Food"),
+                    (date(2, 12), "Assets:Cash"),
+                    (date(2, 25), "Liabilities:Card"),
+                ],
+            };
+            assert_eq!(want, got, "sort={sort:?}");
+        }
+    }
+
+    #[test]
+    fn balance_effective_date_range() {
+        let arena = Bump::new();
+        let mut ctx = report::ReportContext::new(&arena);
+        let mut ledger = report::process(
+            &mut ctx,
+            effective_loader(),
+            &report::ProcessOptions::default(),
+        )
+        .unwrap();
+        let jpy = ctx.commodities.resolve("JPY").unwrap();
+        let date_range = DateRange {
+            start: None,
+            end: Some(NaiveDate::from_ymd_opt(2024, 2, 1).unwrap()),
+        };
+
+        let got = ledger
+            .balance(
+                &ctx,
+                &BalanceQuery {
+                    date_range,
+                    effective: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .into_owned();
+
+        let want: Balance = [(
+            ctx.account("Expenses:Food").unwrap(),
+            Amount::from_value(jpy, dec!(100)),
+        )]
+        .into_iter()
+        .collect();
+        assert_eq!(want, got);
+    }
+
+    fn budget_loader() -> load::Loader<load::FakeFileSystem> {
+        let content = indoc! {"
+            ~ Monthly from 2024/01
+                Expenses:Food                   500 CHF
+                Expenses:Rent                 1,000 CHF
+                Assets:Bank
+
+            2024/01/05 Grocery
+                Expenses:Food:Grocery           200 CHF
+                Assets:Bank
+
+            2024/01/25 Rent
+                Expenses:Rent                 1,000 CHF
+                Assets:Bank
+
+            2024/02/10 Restaurant
+                Expenses:Food                   600 CHF
+                Assets:Bank
+        "};
+        let fake = hashmap! {
+            PathBuf::from("path/to/budget.ledger") => content.as_bytes().to_vec(),
+        };
+        load::Loader::new(PathBuf::from("path/to/budget.ledger"), fake.into())
+    }
+
+    #[test]
+    fn budget_excludes_periodic_transactions_from_balance() {
+        let arena = Bump::new();
+        let mut ctx = report::ReportContext::new(&arena);
+        let mut ledger = report::process(
+            &mut ctx,
+            budget_loader(),
+            &report::ProcessOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(3, ledger.transactions().count());
+        let chf = ctx.commodities.resolve("CHF").unwrap();
+        let balance = ledger.balance(&ctx, &BalanceQuery::default()).unwrap();
+        assert_eq!(
+            Some(&Amount::from_value(chf, dec!(-1800))),
+            balance.get(ctx.account("Assets:Bank").unwrap())
+        );
+    }
+
+    #[test]
+    fn budget_compares_actual_per_period() {
+        let arena = Bump::new();
+        let mut ctx = report::ReportContext::new(&arena);
+        let mut ledger = report::process(
+            &mut ctx,
+            budget_loader(),
+            &report::ProcessOptions::default(),
+        )
+        .unwrap();
+        let chf = ctx.commodities.resolve("CHF").unwrap();
+        let food = ctx.account("Expenses:Food").unwrap();
+        let rent = ctx.account("Expenses:Rent").unwrap();
+        let bank = ctx.account("Assets:Bank").unwrap();
+        let date = |m| NaiveDate::from_ymd_opt(2024, m, 1).unwrap();
+
+        let got = ledger
+            .budget(
+                &ctx,
+                &BudgetQuery {
+                    date_range: DateRange {
+                        start: None,
+                        end: Some(date(3)),
+                    },
+                    ..BudgetQuery::default()
+                },
+            )
+            .unwrap();
+
+        let entry = |m, account, actual, budget| BudgetEntry {
+            start: date(m),
+            end: date(m + 1),
+            account,
+            actual: Amount::from_value(chf, actual),
+            budget: Amount::from_value(chf, budget),
+        };
+        let want = vec![
+            entry(1, food, dec!(200), dec!(500)),
+            entry(1, rent, dec!(1000), dec!(1000)),
+            entry(1, bank, dec!(-1200), dec!(-1500)),
+            entry(2, food, dec!(600), dec!(500)),
+            BudgetEntry {
+                actual: Amount::zero(),
+                ..entry(2, rent, dec!(0), dec!(1000))
+            },
+            entry(2, bank, dec!(-600), dec!(-1500)),
+        ];
+        assert_eq!(want, got);
+        assert_eq!(Amount::from_value(chf, dec!(-300)), got[0].difference());
+        assert_eq!(Some(dec!(40)), got[0].percent());
+        assert_eq!(Some(dec!(0)), got[4].percent());
+    }
+
+    #[test]
+    fn budget_aggregates_overlapping_periodic_transactions() {
+        let content = indoc! {"
+            ~ Monthly from 2024/01
+                Expenses:Food                   500 CHF
+                Assets:Bank
+
+            ~ Yearly in 2024
+                Expenses:Insurance            1,200 CHF
+                Assets:Bank
+
+            2024/01/05 Grocery
+                Expenses:Food                   200 CHF
+                Assets:Bank
+
+            2024/02/10 Insurance
+                Expenses:Insurance            1,150 CHF
+                Assets:Bank
+        "};
+        let fake = hashmap! {
+            PathBuf::from("path/to/budget.ledger") => content.as_bytes().to_vec(),
+        };
+        let loader: load::Loader<load::FakeFileSystem> =
+            load::Loader::new(PathBuf::from("path/to/budget.ledger"), fake.into());
+        let arena = Bump::new();
+        let mut ctx = report::ReportContext::new(&arena);
+        let mut ledger =
+            report::process(&mut ctx, loader, &report::ProcessOptions::default()).unwrap();
+        let chf = ctx.commodities.resolve("CHF").unwrap();
+        let food = ctx.account("Expenses:Food").unwrap();
+        let bank = ctx.account("Assets:Bank").unwrap();
+        let insurance = ctx.account("Expenses:Insurance").unwrap();
+        let date = |m| NaiveDate::from_ymd_opt(2024, m, 1).unwrap();
+
+        let got = ledger.budget(&ctx, &BudgetQuery::default()).unwrap();
+
+        let entry = |m, account, actual, budget| BudgetEntry {
+            start: date(m),
+            end: date(m + 1),
+            account,
+            actual: Amount::from_value(chf, actual),
+            budget: Amount::from_value(chf, budget),
+        };
+        let want = vec![
+            entry(1, food, dec!(200), dec!(500)),
+            entry(1, bank, dec!(-200), dec!(-1700)),
+            BudgetEntry {
+                actual: Amount::zero(),
+                ..entry(1, insurance, dec!(0), dec!(1200))
+            },
+            BudgetEntry {
+                actual: Amount::zero(),
+                ..entry(2, food, dec!(0), dec!(500))
+            },
+            entry(2, bank, dec!(-1150), dec!(-500)),
+            BudgetEntry {
+                budget: Amount::zero(),
+                ..entry(2, insurance, dec!(1150), dec!(0))
+            },
+        ];
+        assert_eq!(want, got);
+    }
+
+    #[test]
+    fn budget_percent_is_none_without_comparable_budget() {
+        let arena = Bump::new();
+        let mut ctx = report::ReportContext::new(&arena);
+        let chf = ctx.commodities.ensure("CHF");
+        let eur = ctx.commodities.ensure("EUR");
+        let account = ctx.accounts.ensure("Expenses:Food");
+        let entry = BudgetEntry {
+            start: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            end: NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+            account,
+            actual: Amount::from_value(eur, dec!(10)),
+            budget: Amount::from_value(chf, dec!(10)),
+        };
+        assert_eq!(None, entry.percent());
+        let entry = BudgetEntry {
+            budget: Amount::zero(),
+            ..entry
+        };
+        assert_eq!(None, entry.percent());
+    }
+
     mod account_filter {
         use super::assert_eq;
         use super::*;
@@ -1557,6 +2176,19 @@ mod tests {
             });
         }
 
+        #[test]
+        fn from_lazy_regex_patterns_matches_account_added_later() {
+            let arena = Bump::new();
+            let (mut ctx, _ledger) = create_ledger(&arena);
+
+            let filter = AccountFilter::from_lazy_regex_patterns(&["^Expenses:", "Card$"]).unwrap();
+            let added = ctx.accounts.ensure("Expenses:Added Later");
+
+            assert!(filter.is_match(&added));
+            assert!(filter.is_match(&ctx.account("Liabilities:EUR Card").unwrap()));
+            assert!(!filter.is_match(&ctx.account("Assets:CH Bank").unwrap()));
+        }
+
         #[test]
         fn from_regex_patterns_single_match_collapses_to_exact() {
             let arena = Bump::new();
@@ -0,0 +1,1357 @@
+//! Provides the snapshot of the processed ledger cached on the disk,
+//! so that [`super::process`] can skip loading and book keeping when no file has changed.
+//!
+//! The snapshot file consists of the following parts.
+//!
+//! 1. Header to identify the format and the okane version.
+//! 2. Key to validate the snapshot, namely the options and the fingerprints of the loaded files.
+//! 3. Payload, the processed state of [`ReportContext`] and the ledger,
+//!    following its checksum.
+//!
+//! The payload encoding is also used for [`Checkpoint`],
+//! the state kept in memory to resume the processing in the middle.
+
+mod codec;
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bumpalo::collections as bcc;
+use bumpalo_intern::direct::StoredValue;
+use pretty_decimal::PrettyDecimal;
+use xxhash_rust::xxh3::xxh3_128;
+
+use crate::load;
+use crate::syntax::{
+    self,
+    display::{CommodityDisplayOption, DisplayContext},
+    expr::{CommodityPosition, CommodityStyle},
+};
+
+use super::{
+    account::Account,
+    balance::Balance,
+    commodity::CommodityTag,
+    context::ReportContext,
+    eval::{Amount, SingleAmount},
+    payee::Payee,
+    periodic::PeriodicTransaction,
+    price_db::{PriceRepositoryBuilder, PriceSource},
+    process::ProcessOptions,
+    strict::{Declarations, Strictness},
+    transaction::{MetadataValue, Posting, Transaction},
+};
+
+use codec::{DecodeError, Decoder, Encoder};
+
+const MAGIC: &[u8] = b"OKANE-SNAPSHOT\n";
+
+/// Version of the snapshot format, which must be bumped on any format change.
+const FORMAT_VERSION: u64 = 1;
+
+/// Files modified within this duration before the loading may have been modified
+/// after they're read, as some filesystems record the modification time coarsely.
+const MODIFIED_RESOLUTION: Duration = Duration::from_secs(2);
+
+/// Processed state of the ledger, restored from or stored into the snapshot.
+#[derive(Debug)]
+pub(super) struct Snapshot<'ctx> {
+    pub transactions: Vec<Transaction<'ctx>>,
+    pub periodic: Vec<PeriodicTransaction<'ctx>>,
+    pub balance: Balance<'ctx>,
+    /// Prices given in the ledger files, not including the price DB.
+    pub price_repos: PriceRepositoryBuilder<'ctx>,
+}
+
+/// Processed state before visiting a file, to resume the processing from the file.
+pub(super) struct Checkpoint<'a, 'ctx> {
+    pub transactions: &'a [Transaction<'ctx>],
+    pub periodic: &'a [PeriodicTransaction<'ctx>],
+    pub balance: &'a Balance<'ctx>,
+    pub price_repos: &'a PriceRepositoryBuilder<'ctx>,
+    pub declarations: &'a Declarations<'ctx>,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum SnapshotError {
+    #[error("failed to access the file: {0}")]
+    IO(#[from] std::io::Error),
+    #[error("failed to decode: {0}")]
+    Decode(#[from] DecodeError),
+    #[error("created by another version")]
+    Version,
+    #[error("outdated as {0}")]
+    Outdated(String),
+    #[error("{0} is not supported")]
+    Unsupported(String),
+}
+
+/// Restores the snapshot at `path` into the fresh `ctx`.
+/// Returns `None` if the snapshot is missing, outdated or broken,
+/// so that the caller falls back to process the files.
+pub(super) fn restore<'ctx, F: load::FileSystem>(
+    ctx: &mut ReportContext<'ctx>,
+    loader: &load::Loader<F>,
+    options: &ProcessOptions,
+    path: &Path,
+) -> Option<Snapshot<'ctx>> {
+    // Tags in the snapshot can't be restored as-is into the context already in use.
+    if !ctx.commodities.is_empty() || ctx.accounts.iter().next().is_some() {
+        log::debug!(
+            "snapshot {} is not used for the used context",
+            path.display()
+        );
+        return None;
+    }
+    match try_restore(ctx, loader, options, path) {
+        Ok(snapshot) => {
+            log::info!("restored the ledger from snapshot {}", path.display());
+            Some(snapshot)
+        }
+        Err(SnapshotError::IO(err)) if err.kind() == std::io::ErrorKind::NotFound => {
+            log::debug!("snapshot {} not found", path.display());
+            None
+        }
+        Err(err) => {
+            log::info!("ignored snapshot {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+/// Stores the `snapshot` of the files loaded as `trees` into `path`,
+/// unless any of the files was modified after the loading `started`.
+/// Failures are only logged, as the snapshot is just a cache.
+pub(super) fn store<F: load::FileSystem>(
+    ctx: &ReportContext<'_>,
+    loader: &load::Loader<F>,
+    options: &ProcessOptions,
+    trees: &[load::IncludeTree],
+    started: SystemTime,
+    snapshot: &Snapshot<'_>,
+    path: &Path,
+) {
+    match try_store(ctx, loader, options, trees, started, snapshot, path) {
+        Ok(()) => log::debug!("stored snapshot {}", path.display()),
+        Err(err @ (SnapshotError::Outdated(_) | SnapshotError::Unsupported(_))) => {
+            log::info!("skipped storing snapshot {}: {}", path.display(), err)
+        }
+        Err(err) => log::warn!("failed to store snapshot {}: {}", path.display(), err),
+    }
+}
+
+fn try_restore<'ctx, F: load::FileSystem>(
+    ctx: &mut ReportContext<'ctx>,
+    loader: &load::Loader<F>,
+    options: &ProcessOptions,
+    path: &Path,
+) -> Result<Snapshot<'ctx>, SnapshotError> {
+    let content = std::fs::read(path)?;
+    let mut decoder = Decoder::new(content.strip_prefix(MAGIC).ok_or(SnapshotError::Version)?);
+    if decoder.uint()? != FORMAT_VERSION || decoder.str()? != env!("CARGO_PKG_VERSION") {
+        return Err(SnapshotError::Version);
+    }
+    if decoder.bytes()? != encode_options(loader, options)? {
+        return Err(SnapshotError::Outdated("options are changed".to_string()));
+    }
+    for file in decoder.seq(FileFingerprint::decode)? {
+        file.check(loader)?;
+    }
+    for include in decoder.seq(IncludeRecord::decode)? {
+        include.check(loader)?;
+    }
+    let checksum = decoder.u128()?;
+    let payload = decoder.remaining();
+    if xxh3_128(payload) != checksum {
+        return Err(DecodeError::Invalid("checksum").into());
+    }
+    // Decoding shouldn't fail after the checksum is verified,
+    // otherwise the context is left partially restored.
+    let mut decoder = Decoder::new(payload);
+    let snapshot = decode_payload(ctx, &mut decoder)?;
+    if !decoder.remaining().is_empty() {
+        return Err(DecodeError::Invalid("trailing data").into());
+    }
+    Ok(snapshot)
+}
+
+fn try_store<F: load::FileSystem>(
+    ctx: &ReportContext<'_>,
+    loader: &load::Loader<F>,
+    options: &ProcessOptions,
+    trees: &[load::IncludeTree],
+    started: SystemTime,
+    snapshot: &Snapshot<'_>,
+    path: &Path,
+) -> Result<(), SnapshotError> {
+    let mut encoder = Encoder::default();
+    encoder.uint(FORMAT_VERSION);
+    encoder.str(env!("CARGO_PKG_VERSION"));
+    encoder.bytes(&encode_options(loader, options)?);
+    let mut files = Vec::new();
+    let mut includes = Vec::new();
+    let mut visited = HashSet::new();
+    for tree in trees {
+        collect_files(tree, &mut visited, &mut files, &mut includes);
+    }
+    let stable_until = started
+        .checked_sub(MODIFIED_RESOLUTION)
+        .unwrap_or(UNIX_EPOCH);
+    encoder.len(files.len());
+    for file in files {
+        FileFingerprint::new(loader, file, stable_until)?.encode(&mut encoder);
+    }
+    encoder.len(includes.len());
+    for (file, include) in includes {
+        IncludeRecord {
+            path: path_str(file)?,
+            pattern: &include.pattern,
+            files: include
+                .files
+                .iter()
+                .map(|tree| path_str(&tree.path))
+                .collect::<Result<_, _>>()?,
+        }
+        .encode(&mut encoder);
+    }
+    let payload = encode_payload(ctx, snapshot)?;
+    encoder.u128(xxh3_128(&payload));
+    let mut content = MAGIC.to_vec();
+    content.extend(encoder.into_bytes());
+    content.extend(payload);
+    write_atomically(path, &content)?;
+    Ok(())
+}
+
+/// Collects the files in the `tree` into `files`, and their `include` directives into `includes`.
+fn collect_files<'a>(
+    tree: &'a load::IncludeTree,
+    visited: &mut HashSet<&'a Path>,
+    files: &mut Vec<&'a Path>,
+    includes: &mut Vec<(&'a Path, &'a load::Include)>,
+) {
+    if !visited.insert(&tree.path) {
+        return;
+    }
+    files.push(&tree.path);
+    for include in &tree.includes {
+        includes.push((&tree.path, include));
+        for file in &include.files {
+            collect_files(file, visited, files, includes);
+        }
+    }
+}
+
+/// Writes the `content` into `path` through the temporary file,
+/// so that the concurrent process never reads the partially written snapshot.
+fn write_atomically(path: &Path, content: &[u8]) -> Result<(), std::io::Error> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(".{}.tmp", std::process::id()));
+    std::fs::write(&temp, content)?;
+    std::fs::rename(&temp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    })
+}
+
+fn path_str(path: &Path) -> Result<&str, SnapshotError> {
+    path.to_str()
+        .ok_or_else(|| SnapshotError::Unsupported(format!("non-Unicode path {}", path.display())))
+}
+
+/// Encodes the options and the sources, which must be the same to use the snapshot.
+/// Returns `None` if the sources can't be loaded again, namely the standard input.
+pub(super) fn options_key<F: load::FileSystem>(
+    loader: &load::Loader<F>,
+    options: &ProcessOptions,
+) -> Option<Vec<u8>> {
+    encode_options(loader, options).ok()
+}
+
+fn encode_options<F: load::FileSystem>(
+    loader: &load::Loader<F>,
+    options: &ProcessOptions,
+) -> Result<Vec<u8>, SnapshotError> {
+    let mut encoder = Encoder::default();
+    encoder.u8(match options.strictness {
+        Strictness::Permissive => 0,
+        Strictness::Warn => 1,
+        Strictness::Error => 2,
+    });
+    encoder.bool(options.keep_going);
+    encoder.str(loader.encoding().name());
+    encoder.len(loader.sources().len());
+    for source in loader.sources() {
+        if source == Path::new(load::STDIN_PATH) {
+            return Err(SnapshotError::Unsupported("standard input".to_string()));
+        }
+        encoder.str(path_str(&F::canonicalize_path(source))?);
+    }
+    Ok(encoder.into_bytes())
+}
+
+/// Fingerprint of the loaded file, to detect its change.
+struct FileFingerprint<'a> {
+    path: &'a str,
+    /// Modification time since the UNIX epoch, if available.
+    modified: Option<Duration>,
+    /// Hash of the raw content.
+    hash: u128,
+}
+
+impl<'a> FileFingerprint<'a> {
+    fn new<F: load::FileSystem>(
+        loader: &load::Loader<F>,
+        path: &'a Path,
+        stable_until: SystemTime,
+    ) -> Result<Self, SnapshotError> {
+        let modified = loader.filesystem().modified(path);
+        if modified.is_some_and(|modified| modified >= stable_until) {
+            return Err(SnapshotError::Outdated(format!(
+                "{} is modified recently",
+                path.display()
+            )));
+        }
+        Ok(Self {
+            path: path_str(path)?,
+            modified: modified.and_then(|m| m.duration_since(UNIX_EPOCH).ok()),
+            hash: xxh3_128(&loader.filesystem().file_content(path)?),
+        })
+    }
+
+    /// Checks if the file is not changed,
+    /// trusting the modification time if it's the same.
+    fn check<F: load::FileSystem>(&self, loader: &load::Loader<F>) -> Result<(), SnapshotError> {
+        let path = Path::new(self.path);
+        let modified = loader
+            .filesystem()
+            .modified(path)
+            .and_then(|m| m.duration_since(UNIX_EPOCH).ok());
+        if self.modified.is_some() && self.modified == modified {
+            return Ok(());
+        }
+        match loader.filesystem().file_content(path) {
+            Ok(content) if xxh3_128(&content) == self.hash => Ok(()),
+            Ok(_) => Err(SnapshotError::Outdated(format!("{} is changed", self.path))),
+            Err(err) => Err(SnapshotError::Outdated(format!(
+                "{} is not readable: {}",
+                self.path, err
+            ))),
+        }
+    }
+
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.str(self.path);
+        encoder.option(self.modified, |encoder, modified| {
+            encoder.uint(modified.as_secs());
+            encoder.uint(modified.subsec_nanos().into());
+        });
+        encoder.u128(self.hash);
+    }
+
+    fn decode(decoder: &mut Decoder<'a>) -> Result<Self, DecodeError> {
+        Ok(Self {
+            path: decoder.str()?,
+            modified: decoder.option(|decoder| {
+                let secs = decoder.uint()?;
+                let nanos = u32::try_from(decoder.uint()?)
+                    .map_err(|_| DecodeError::Invalid("modification time"))?;
+                Ok(Duration::new(secs, nanos))
+            })?,
+            hash: decoder.u128()?,
+        })
+    }
+}
+
+/// `include` directive in the loaded file, to detect the change of the glob matches.
+struct IncludeRecord<'a> {
+    path: &'a str,
+    pattern: &'a str,
+    /// Canonicalized paths of the matched files.
+    files: Vec<&'a str>,
+}
+
+impl<'a> IncludeRecord<'a> {
+    fn check<F: load::FileSystem>(&self, loader: &load::Loader<F>) -> Result<(), SnapshotError> {
+        let outdated = || {
+            SnapshotError::Outdated(format!(
+                "include {} in {} matches other files",
+                self.pattern, self.path
+            ))
+        };
+        let files = loader
+            .resolve_include(Path::new(self.path), self.pattern)
+            .map_err(|_| outdated())?;
+        let unchanged = files.len() == self.files.len()
+            && files
+                .iter()
+                .zip(&self.files)
+                .all(|(file, recorded)| F::canonicalize_path(file) == Path::new(recorded));
+        if unchanged { Ok(()) } else { Err(outdated()) }
+    }
+
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.str(self.path);
+        encoder.str(self.pattern);
+        encoder.len(self.files.len());
+        for file in &self.files {
+            encoder.str(file);
+        }
+    }
+
+    fn decode(decoder: &mut Decoder<'a>) -> Result<Self, DecodeError> {
+        Ok(Self {
+            path: decoder.str()?,
+            pattern: decoder.str()?,
+            files: decoder.seq(Decoder::str)?,
+        })
+    }
+}
+
+fn encode_payload(
+    ctx: &ReportContext<'_>,
+    snapshot: &Snapshot<'_>,
+) -> Result<Vec<u8>, SnapshotError> {
+    let mut encoder = PayloadEncoder::new(ctx, &snapshot.transactions);
+    encoder.account_table();
+    encoder.context(ctx)?;
+    encoder.string_table();
+    encoder.transactions(&snapshot.transactions);
+    encoder.periodic(&snapshot.periodic);
+    encoder.balance(&snapshot.balance);
+    encoder.price_rates(&snapshot.price_repos);
+    Ok(encoder.take_bytes())
+}
+
+fn decode_payload<'ctx>(
+    ctx: &mut ReportContext<'ctx>,
+    decoder: &mut Decoder<'_>,
+) -> Result<Snapshot<'ctx>, DecodeError> {
+    let mut table = PayloadTable {
+        accounts: decoder.seq(|decoder| Ok(ctx.accounts.ensure(decoder.str()?)))?,
+        commodities: Vec::new(),
+        strings: Vec::new(),
+    };
+    table.context(ctx, decoder)?;
+    table.strings = decoder.seq(|decoder| Ok(&*ctx.arena.alloc_str(decoder.str()?)))?;
+    Ok(Snapshot {
+        transactions: table.transactions(ctx, decoder)?,
+        periodic: table.periodic(decoder)?,
+        balance: table.balance(decoder)?,
+        price_repos: table.price_rates(decoder)?,
+    })
+}
+
+/// Encodes the `checkpoint` with the `ctx`, or returns `None` if it can't be encoded.
+pub(super) fn encode_checkpoint(
+    ctx: &ReportContext<'_>,
+    checkpoint: &Checkpoint<'_, '_>,
+) -> Option<Vec<u8>> {
+    let mut encoder = PayloadEncoder::new(ctx, checkpoint.transactions);
+    encoder.account_table();
+    if let Err(err) = encoder.context(ctx) {
+        log::debug!("no checkpoint is kept: {}", err);
+        return None;
+    }
+    encoder.string_table();
+    encoder.apply_prefixes(ctx.accounts.apply_prefixes());
+    encoder.declarations(checkpoint.declarations);
+    encoder.transactions(checkpoint.transactions);
+    encoder.periodic(checkpoint.periodic);
+    encoder.balance(checkpoint.balance);
+    encoder.price_rates(checkpoint.price_repos);
+    Some(encoder.take_bytes())
+}
+
+/// Restores the checkpoint given by [`encode_checkpoint`] into the fresh `ctx`.
+/// Returns `None` if it's broken, leaving the `ctx` partially restored.
+pub(super) fn decode_checkpoint<'ctx>(
+    ctx: &mut ReportContext<'ctx>,
+    payload: &[u8],
+) -> Option<(Snapshot<'ctx>, Declarations<'ctx>)> {
+    let mut decoder = Decoder::new(payload);
+    let decoded = try_decode_checkpoint(ctx, &mut decoder).and_then(|decoded| {
+        if decoder.remaining().is_empty() {
+            Ok(decoded)
+        } else {
+            Err(DecodeError::Invalid("trailing data"))
+        }
+    });
+    decoded
+        .inspect_err(|err| log::warn!("failed to decode the checkpoint: {}", err))
+        .ok()
+}
+
+fn try_decode_checkpoint<'ctx>(
+    ctx: &mut ReportContext<'ctx>,
+    decoder: &mut Decoder<'_>,
+) -> Result<(Snapshot<'ctx>, Declarations<'ctx>), DecodeError> {
+    let mut table = PayloadTable {
+        accounts: decoder.seq(|decoder| Ok(ctx.accounts.ensure(decoder.str()?)))?,
+        commodities: Vec::new(),
+        strings: Vec::new(),
+    };
+    table.context(ctx, decoder)?;
+    table.strings = decoder.seq(|decoder| Ok(&*ctx.arena.alloc_str(decoder.str()?)))?;
+    let prefixes = decoder.seq(|decoder| Ok(decoder.str()?.to_string()))?;
+    ctx.accounts.restore_apply_prefixes(prefixes);
+    let declarations = table.declarations(ctx, decoder)?;
+    let snapshot = Snapshot {
+        transactions: table.transactions(ctx, decoder)?,
+        periodic: table.periodic(decoder)?,
+        balance: table.balance(decoder)?,
+        price_repos: table.price_rates(decoder)?,
+    };
+    Ok((snapshot, declarations))
+}
+
+/// Encodes the payload, referring the accounts and the strings with the index.
+struct PayloadEncoder<'ctx> {
+    encoder: Encoder,
+    accounts: HashMap<Account<'ctx>, usize>,
+    /// Payees and metadata keys used in the transactions.
+    strings: HashMap<&'ctx str, usize>,
+}
+
+impl<'ctx> PayloadEncoder<'ctx> {
+    fn new(ctx: &ReportContext<'ctx>, transactions: &[Transaction<'ctx>]) -> Self {
+        let accounts = ctx
+            .accounts
+            .iter()
+            .enumerate()
+            .map(|(i, a)| (a, i))
+            .collect();
+        let mut strings = HashMap::new();
+        let mut intern = |s: &'ctx str| {
+            let next = strings.len();
+            strings.entry(s).or_insert(next);
+        };
+        for txn in transactions {
+            txn.metadata.iter().for_each(|(key, _)| intern(key));
+            for posting in txn.postings.iter() {
+                intern(posting.payee);
+                posting.metadata.iter().for_each(|(key, _)| intern(key));
+            }
+        }
+        Self {
+            encoder: Encoder::default(),
+            accounts,
+            strings,
+        }
+    }
+
+    /// Returns the bytes encoded so far, and clears them.
+    fn take_bytes(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.encoder).into_bytes()
+    }
+
+    /// Encodes the accounts in the order of the index.
+    fn account_table(&mut self) {
+        let mut accounts: Vec<(Account<'ctx>, usize)> =
+            self.accounts.iter().map(|(a, i)| (*a, *i)).collect();
+        accounts.sort_unstable_by_key(|(_, i)| *i);
+        self.encoder.len(accounts.len());
+        for (account, _) in accounts {
+            self.encoder.str(account.as_str());
+        }
+    }
+
+    /// Encodes the strings in the order of the index.
+    fn string_table(&mut self) {
+        let mut strings: Vec<(&str, usize)> = self.strings.iter().map(|(s, i)| (*s, *i)).collect();
+        strings.sort_unstable_by_key(|(_, i)| *i);
+        self.encoder.len(strings.len());
+        for (s, _) in strings {
+            self.encoder.str(s);
+        }
+    }
+
+    /// Encodes the interned values other than the accounts, and the declarations in the `ctx`.
+    fn context(&mut self, ctx: &ReportContext<'ctx>) -> Result<(), SnapshotError> {
+        let aliases: Vec<_> = ctx.accounts.aliases().collect();
+        self.encoder.len(aliases.len());
+        for (alias, account) in aliases {
+            self.encoder.str(alias);
+            self.account(account);
+        }
+
+        let commodities = &ctx.commodities;
+        self.encoder.len(commodities.len());
+        for commodity in commodities.iter() {
+            let name = commodities
+                .get(commodity)
+                .ok_or_else(|| SnapshotError::Unsupported("unknown commodity".to_string()))?;
+            self.encoder.str(name.as_str());
+        }
+        let aliases: Vec<_> = commodities.aliases().collect();
+        self.encoder.len(aliases.len());
+        for (alias, commodity) in aliases {
+            self.encoder.str(alias);
+            self.commodity(commodity);
+        }
+        let formats: Vec<_> = commodities
+            .iter()
+            .filter_map(|c| Some((c, commodities.format(c)?)))
+            .collect();
+        self.encoder.len(formats.len());
+        for (commodity, format) in formats {
+            self.commodity(commodity);
+            self.encoder.u8(format_id(format.format)?);
+            self.encoder.decimal(format.value);
+        }
+        self.encoder
+            .option(commodities.default_commodity(), |encoder, commodity| {
+                encoder.len(commodity.as_index())
+            });
+        let nomarket: Vec<_> = commodities
+            .iter()
+            .filter(|c| commodities.is_nomarket(*c))
+            .collect();
+        self.encoder.len(nomarket.len());
+        for commodity in nomarket {
+            self.commodity(commodity);
+        }
+
+        let mut payees = Vec::new();
+        let mut payee_aliases = Vec::new();
+        for payee in ctx.payees.iter() {
+            match payee {
+                StoredValue::Canonical(payee) => payees.push(payee),
+                StoredValue::Alias { alias, canonical } => payee_aliases.push((alias, canonical)),
+            }
+        }
+        let payee_index: HashMap<Payee<'ctx>, usize> =
+            payees.iter().enumerate().map(|(i, p)| (*p, i)).collect();
+        self.encoder.len(payees.len());
+        for payee in &payees {
+            self.encoder.str(payee.as_str());
+        }
+        self.encoder.len(payee_aliases.len());
+        for (alias, canonical) in payee_aliases {
+            self.encoder.str(alias);
+            self.encoder.len(payee_index[&canonical]);
+        }
+
+        self.display_option(ctx.display_context.default_commodity())?;
+        let overrides = ctx.display_context.commodity_overrides();
+        self.encoder.len(overrides.len());
+        for (commodity, option) in overrides {
+            self.encoder.str(commodity);
+            self.display_option(option)?;
+        }
+        Ok(())
+    }
+
+    fn apply_prefixes(&mut self, prefixes: &[String]) {
+        self.encoder.len(prefixes.len());
+        for prefix in prefixes {
+            self.encoder.str(prefix);
+        }
+    }
+
+    fn declarations(&mut self, declarations: &Declarations<'ctx>) {
+        let accounts: Vec<_> = declarations.accounts().collect();
+        self.encoder.len(accounts.len());
+        for account in accounts {
+            self.account(account);
+        }
+        let commodities: Vec<_> = declarations.commodities().collect();
+        self.encoder.len(commodities.len());
+        for commodity in commodities {
+            self.commodity(commodity);
+        }
+        let payees: Vec<_> = declarations.payees().collect();
+        self.encoder.len(payees.len());
+        for payee in payees {
+            self.encoder.str(payee);
+        }
+        let tags: Vec<_> = declarations.tags().collect();
+        self.encoder.len(tags.len());
+        for tag in tags {
+            self.encoder.str(tag);
+        }
+    }
+
+    fn transactions(&mut self, transactions: &[Transaction<'ctx>]) {
+        self.encoder.len(transactions.len());
+        for txn in transactions {
+            self.encoder.date(txn.date);
+            self.encoder.option(txn.effective_date, Encoder::date);
+            self.encoder.len(txn.postings.len());
+            for posting in txn.postings.iter() {
+                self.posting(posting);
+            }
+            self.metadata(&txn.metadata);
+        }
+    }
+
+    fn periodic(&mut self, periodic: &[PeriodicTransaction<'ctx>]) {
+        self.encoder.len(periodic.len());
+        for txn in periodic {
+            let (interval, count) = match txn.period.interval {
+                syntax::Interval::Days(n) => (0, n),
+                syntax::Interval::Weeks(n) => (1, n),
+                syntax::Interval::Months(n) => (2, n),
+                syntax::Interval::Years(n) => (3, n),
+            };
+            self.encoder.u8(interval);
+            self.encoder.uint(count.into());
+            self.encoder.option(txn.period.begin, Encoder::date);
+            self.encoder.option(txn.period.end, Encoder::date);
+            self.encoder.len(txn.postings.len());
+            for (account, amount) in &txn.postings {
+                self.account(*account);
+                self.amount(amount);
+            }
+        }
+    }
+
+    fn balance(&mut self, balance: &Balance<'ctx>) {
+        let balance: Vec<_> = balance.iter().collect();
+        self.encoder.len(balance.len());
+        for (account, amount) in balance {
+            self.account(*account);
+            self.amount(amount);
+        }
+    }
+
+    fn price_rates(&mut self, price_repos: &PriceRepositoryBuilder<'ctx>) {
+        let rates: Vec<_> = price_repos.iter_rates().collect();
+        self.encoder.len(rates.len());
+        for (price_with, price_of, source, rates) in rates {
+            self.commodity(price_with);
+            self.commodity(price_of);
+            self.encoder.u8(price_source_id(source));
+            self.encoder.len(rates.len());
+            for (date, rate) in rates {
+                self.encoder.date(*date);
+                self.encoder.decimal(*rate);
+            }
+        }
+    }
+
+    fn posting(&mut self, posting: &Posting<'ctx>) {
+        self.account(posting.account);
+        self.encoder.u8(match posting.kind {
+            syntax::PostingKind::Real => 0,
+            syntax::PostingKind::Virtual => 1,
+            syntax::PostingKind::BalancedVirtual => 2,
+        });
+        self.string(posting.payee);
+        self.encoder.date(posting.date);
+        self.encoder.option(posting.effective_date, Encoder::date);
+        self.amount(&posting.amount);
+        self.encoder.bool(posting.converted_amount.is_some());
+        if let Some(converted) = posting.converted_amount {
+            self.commodity(converted.commodity);
+            self.encoder.decimal(converted.value);
+        }
+        self.metadata(&posting.metadata);
+    }
+
+    fn metadata(&mut self, metadata: &[(&'ctx str, MetadataValue<'ctx>)]) {
+        self.encoder.len(metadata.len());
+        for (key, value) in metadata {
+            self.string(key);
+            match value {
+                MetadataValue::Number(value) => {
+                    self.encoder.u8(0);
+                    self.encoder.decimal(*value);
+                }
+                MetadataValue::Amount(amount) => {
+                    self.encoder.u8(1);
+                    self.amount(amount);
+                }
+                MetadataValue::Date(date) => {
+                    self.encoder.u8(2);
+                    self.encoder.date(*date);
+                }
+            }
+        }
+    }
+
+    fn amount(&mut self, amount: &Amount<'ctx>) {
+        let values: Vec<_> = amount.iter().collect();
+        self.encoder.len(values.len());
+        for value in values {
+            self.commodity(value.commodity);
+            self.encoder.decimal(value.value);
+        }
+    }
+
+    fn display_option(&mut self, option: &CommodityDisplayOption) -> Result<(), SnapshotError> {
+        self.encoder.u8(format_id(option.format)?);
+        self.encoder.option(option.min_scale, Encoder::u8);
+        self.encoder.option(option.style, |encoder, style| {
+            encoder.u8(match style.position {
+                CommodityPosition::Prefix => 0,
+                CommodityPosition::Suffix => 1,
+            });
+            encoder.bool(style.spaced);
+        });
+        Ok(())
+    }
+
+    fn account(&mut self, account: Account<'ctx>) {
+        self.encoder.len(self.accounts[&account]);
+    }
+
+    fn commodity(&mut self, commodity: CommodityTag<'ctx>) {
+        self.encoder.len(commodity.as_index());
+    }
+
+    fn string(&mut self, s: &'ctx str) {
+        self.encoder.len(self.strings[s]);
+    }
+}
+
+fn format_id(format: Option<pretty_decimal::Format>) -> Result<u8, SnapshotError> {
+    match format {
+        None => Ok(0),
+        Some(pretty_decimal::Format::Plain) => Ok(1),
+        Some(pretty_decimal::Format::Comma3Dot) => Ok(2),
+        Some(format) => Err(SnapshotError::Unsupported(format!("format {:?}", format))),
+    }
+}
+
+fn format_from_id(id: u8) -> Result<Option<pretty_decimal::Format>, DecodeError> {
+    match id {
+        0 => Ok(None),
+        1 => Ok(Some(pretty_decimal::Format::Plain)),
+        2 => Ok(Some(pretty_decimal::Format::Comma3Dot)),
+        _ => Err(DecodeError::Invalid("format")),
+    }
+}
+
+fn price_source_id(source: PriceSource) -> u8 {
+    match source {
+        PriceSource::Ledger => 0,
+        PriceSource::Directive => 1,
+        PriceSource::PriceDB => 2,
+    }
+}
+
+fn price_source_from_id(id: u8) -> Result<PriceSource, DecodeError> {
+    match id {
+        0 => Ok(PriceSource::Ledger),
+        1 => Ok(PriceSource::Directive),
+        2 => Ok(PriceSource::PriceDB),
+        _ => Err(DecodeError::Invalid("price source")),
+    }
+}
+
+/// Interned values restored into the context, referred with the index in the payload.
+struct PayloadTable<'ctx> {
+    accounts: Vec<Account<'ctx>>,
+    commodities: Vec<CommodityTag<'ctx>>,
+    strings: Vec<&'ctx str>,
+}
+
+impl<'ctx> PayloadTable<'ctx> {
+    /// Decodes the interned values other than the accounts, and the declarations into the `ctx`.
+    fn context(
+        &mut self,
+        ctx: &mut ReportContext<'ctx>,
+        decoder: &mut Decoder<'_>,
+    ) -> Result<(), DecodeError> {
+        for _ in 0..decoder.len()? {
+            let alias = decoder.str()?;
+            let account = self.account(decoder)?;
+            ctx.accounts
+                .register_alias(alias, account)
+                .map_err(|_| DecodeError::Invalid("account alias"))?;
+        }
+
+        self.commodities = decoder.seq(|decoder| Ok(ctx.commodities.ensure(decoder.str()?)))?;
+        for _ in 0..decoder.len()? {
+            let alias = decoder.str()?;
+            let commodity = self.commodity(decoder)?;
+            ctx.commodities
+                .register_alias(alias, commodity)
+                .map_err(|_| DecodeError::Invalid("commodity alias"))?;
+        }
+        for _ in 0..decoder.len()? {
+            let commodity = self.commodity(decoder)?;
+            let format = format_from_id(decoder.u8()?)?;
+            let value = decoder.decimal()?;
+            ctx.commodities
+                .set_format(commodity, PrettyDecimal::with_format(value, format));
+        }
+        if let Some(commodity) = decoder.option(|decoder| self.commodity(decoder))? {
+            ctx.commodities.set_default_commodity(commodity);
+        }
+        for _ in 0..decoder.len()? {
+            let commodity = self.commodity(decoder)?;
+            ctx.commodities.set_nomarket(commodity);
+        }
+
+        let payees = decoder.seq(|decoder| Ok(ctx.payees.ensure(decoder.str()?)))?;
+        for _ in 0..decoder.len()? {
+            let alias = decoder.str()?;
+            let payee = *payees
+                .get(decoder.len()?)
+                .ok_or(DecodeError::Invalid("payee"))?;
+            ctx.payees
+                .register_alias(alias, payee)
+                .map_err(|_| DecodeError::Invalid("payee alias"))?;
+        }
+
+        let default_commodity = decode_display_option(decoder)?;
+        let overrides = decoder
+            .seq(|decoder| Ok((decoder.str()?.to_string(), decode_display_option(decoder)?)))?;
+        ctx.display_context =
+            DisplayContext::new(default_commodity, overrides.into_iter().collect()).keeping_scale();
+        Ok(())
+    }
+
+    fn declarations(
+        &self,
+        ctx: &ReportContext<'ctx>,
+        decoder: &mut Decoder<'_>,
+    ) -> Result<Declarations<'ctx>, DecodeError> {
+        let mut declarations = Declarations::default();
+        for _ in 0..decoder.len()? {
+            declarations.declare_account(self.account(decoder)?);
+        }
+        for _ in 0..decoder.len()? {
+            declarations.declare_commodity(self.commodity(decoder)?);
+        }
+        for _ in 0..decoder.len()? {
+            declarations.declare_payee(ctx.arena.alloc_str(decoder.str()?));
+        }
+        for _ in 0..decoder.len()? {
+            declarations.declare_tag(decoder.str()?);
+        }
+        Ok(declarations)
+    }
+
+    fn transactions(
+        &self,
+        ctx: &ReportContext<'ctx>,
+        decoder: &mut Decoder<'_>,
+    ) -> Result<Vec<Transaction<'ctx>>, DecodeError> {
+        decoder.seq(|decoder| {
+            let date = decoder.date()?;
+            let effective_date = decoder.option(Decoder::date)?;
+            let len = decoder.len()?;
+            let mut postings = bcc::Vec::with_capacity_in(len, ctx.arena);
+            for _ in 0..len {
+                postings.push(self.posting(decoder)?);
+            }
+            Ok(Transaction {
+                date,
+                effective_date,
+                postings: postings.into_boxed_slice(),
+                metadata: self.metadata(decoder)?,
+            })
+        })
+    }
+
+    fn periodic(
+        &self,
+        decoder: &mut Decoder<'_>,
+    ) -> Result<Vec<PeriodicTransaction<'ctx>>, DecodeError> {
+        decoder.seq(|decoder| {
+            let interval = match (decoder.u8()?, u32::try_from(decoder.uint()?)) {
+                (0, Ok(n)) => syntax::Interval::Days(n),
+                (1, Ok(n)) => syntax::Interval::Weeks(n),
+                (2, Ok(n)) => syntax::Interval::Months(n),
+                (3, Ok(n)) => syntax::Interval::Years(n),
+                _ => return Err(DecodeError::Invalid("interval")),
+            };
+            Ok(PeriodicTransaction {
+                period: syntax::PeriodExpr {
+                    interval,
+                    begin: decoder.option(Decoder::date)?,
+                    end: decoder.option(Decoder::date)?,
+                },
+                postings: decoder
+                    .seq(|decoder| Ok((self.account(decoder)?, self.amount(decoder)?)))?,
+            })
+        })
+    }
+
+    fn balance(&self, decoder: &mut Decoder<'_>) -> Result<Balance<'ctx>, DecodeError> {
+        Ok(decoder
+            .seq(|decoder| Ok((self.account(decoder)?, self.amount(decoder)?)))?
+            .into_iter()
+            .collect())
+    }
+
+    fn price_rates(
+        &self,
+        decoder: &mut Decoder<'_>,
+    ) -> Result<PriceRepositoryBuilder<'ctx>, DecodeError> {
+        let mut price_repos = PriceRepositoryBuilder::default();
+        for _ in 0..decoder.len()? {
+            let price_with = self.commodity(decoder)?;
+            let price_of = self.commodity(decoder)?;
+            let source = price_source_from_id(decoder.u8()?)?;
+            let rates = decoder.seq(|decoder| Ok((decoder.date()?, decoder.decimal()?)))?;
+            price_repos.set_rates(price_with, price_of, source, rates);
+        }
+        Ok(price_repos)
+    }
+
+    fn posting(&self, decoder: &mut Decoder<'_>) -> Result<Posting<'ctx>, DecodeError> {
+        Ok(Posting {
+            account: self.account(decoder)?,
+            kind: match decoder.u8()? {
+                0 => syntax::PostingKind::Real,
+                1 => syntax::PostingKind::Virtual,
+                2 => syntax::PostingKind::BalancedVirtual,
+                _ => return Err(DecodeError::Invalid("posting kind")),
+            },
+            payee: self.string(decoder)?,
+            date: decoder.date()?,
+            effective_date: decoder.option(Decoder::date)?,
+            amount: self.amount(decoder)?,
+            converted_amount: decoder.option(|decoder| {
+                Ok(SingleAmount::from_value(
+                    self.commodity(decoder)?,
+                    decoder.decimal()?,
+                ))
+            })?,
+            metadata: self.metadata(decoder)?,
+        })
+    }
+
+    fn metadata(
+        &self,
+        decoder: &mut Decoder<'_>,
+    ) -> Result<Vec<(&'ctx str, MetadataValue<'ctx>)>, DecodeError> {
+        decoder.seq(|decoder| {
+            let key = self.string(decoder)?;
+            let value = match decoder.u8()? {
+                0 => MetadataValue::Number(decoder.decimal()?),
+                1 => MetadataValue::Amount(self.amount(decoder)?),
+                2 => MetadataValue::Date(decoder.date()?),
+                _ => return Err(DecodeError::Invalid("metadata")),
+            };
+            Ok((key, value))
+        })
+    }
+
+    fn amount(&self, decoder: &mut Decoder<'_>) -> Result<Amount<'ctx>, DecodeError> {
+        let values = decoder.seq(|decoder| Ok((self.commodity(decoder)?, decoder.decimal()?)))?;
+        Ok(Amount::from_values(values.into_iter().collect()))
+    }
+
+    fn account(&self, decoder: &mut Decoder<'_>) -> Result<Account<'ctx>, DecodeError> {
+        self.accounts
+            .get(decoder.len()?)
+            .copied()
+            .ok_or(DecodeError::Invalid("account"))
+    }
+
+    fn commodity(&self, decoder: &mut Decoder<'_>) -> Result<CommodityTag<'ctx>, DecodeError> {
+        self.commodities
+            .get(decoder.len()?)
+            .copied()
+            .ok_or(DecodeError::Invalid("commodity"))
+    }
+
+    fn string(&self, decoder: &mut Decoder<'_>) -> Result<&'ctx str, DecodeError> {
+        self.strings
+            .get(decoder.len()?)
+            .copied()
+            .ok_or(DecodeError::Invalid("string"))
+    }
+}
+
+fn decode_display_option(decoder: &mut Decoder<'_>) -> Result<CommodityDisplayOption, DecodeError> {
+    Ok(CommodityDisplayOption {
+        format: format_from_id(decoder.u8()?)?,
+        min_scale: decoder.option(Decoder::u8)?,
+        style: decoder.option(|decoder| {
+            let position = match decoder.u8()? {
+                0 => CommodityPosition::Prefix,
+                1 => CommodityPosition::Suffix,
+                _ => return Err(DecodeError::Invalid("commodity position")),
+            };
+            Ok(CommodityStyle {
+                position,
+                spaced: decoder.bool()?,
+            })
+        })?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::PathBuf;
+
+    use bumpalo::Bump;
+    use indoc::indoc;
+    use maplit::hashmap;
+    use pretty_assertions::assert_eq;
+
+    use super::super::process::process_entries;
+
+    fn fake_files() -> HashMap<PathBuf, Vec<u8>> {
+        hashmap! {
+            PathBuf::from("path/to/root.ledger") => indoc! {"
+                commodity CHF
+                    alias SFr
+                    format 1,000.00 CHF
+
+                commodity JPY
+                    nomarket
+
+                account Assets:Bank
+                    alias Bank
+
+                payee Migros
+                    alias MIGROS ZH
+
+                P 2024/01/01 EUR 0.95 CHF
+
+                ~ Monthly
+                    Expenses:Food                      300 CHF
+                    Assets:Bank
+
+                include sub/*.ledger
+            "}.as_bytes().to_vec(),
+            PathBuf::from("path/to/sub/2024.ledger") => indoc! {"
+                2024/01/02=2024/01/03 MIGROS ZH
+                    ; total:: 20.00 SFr + 5.00 SFr
+                    Expenses:Food                       10 EUR @ 0.96 CHF
+                    Bank
+                    (Memo:Count)                         1 JPY
+                      ; [2024/01/04]
+            "}.as_bytes().to_vec(),
+        }
+    }
+
+    fn fake_loader(files: HashMap<PathBuf, Vec<u8>>) -> load::Loader<load::FakeFileSystem> {
+        load::Loader::new(
+            PathBuf::from("path/to/root.ledger"),
+            load::FakeFileSystem::from(files),
+        )
+    }
+
+    /// Returns the state in the deterministic order, to compare across the contexts.
+    fn describe(ctx: &ReportContext<'_>, snapshot: &Snapshot<'_>) -> String {
+        let mut accounts: Vec<_> = ctx.accounts.iter().map(|a| a.as_str()).collect();
+        accounts.sort_unstable();
+        let mut account_aliases: Vec<_> = ctx
+            .accounts
+            .aliases()
+            .map(|(alias, a)| (alias, a.as_str()))
+            .collect();
+        account_aliases.sort_unstable();
+        let commodities: Vec<_> = ctx
+            .commodities
+            .iter()
+            .map(|c| {
+                (
+                    ctx.commodities.get(c).unwrap().as_str(),
+                    ctx.commodities.format(c),
+                    ctx.commodities.is_nomarket(c),
+                )
+            })
+            .collect();
+        let commodity_aliases: Vec<_> = ctx.commodities.aliases().collect();
+        let mut balance: Vec<_> = snapshot
+            .balance
+            .iter()
+            .map(|(a, v)| (a.as_str(), v))
+            .collect();
+        balance.sort_unstable_by_key(|(a, _)| *a);
+        let mut rates: Vec<_> = snapshot.price_repos.iter_rates().collect();
+        rates.sort_unstable_by_key(|(with, of, _, _)| (*with, *of));
+        let debug = format!(
+            "{:#?}",
+            (
+                accounts,
+                account_aliases,
+                commodities,
+                commodity_aliases,
+                ctx.commodities.default_commodity(),
+                &snapshot.transactions,
+                &snapshot.periodic,
+                balance,
+                rates,
+            )
+        );
+        // Interned strings are printed with its address, which differs across the arenas.
+        regex::Regex::new(r"addr: 0x[0-9a-f]+")
+            .unwrap()
+            .replace_all(&debug, "addr: _")
+            .into_owned()
+    }
+
+    fn cache_options(dir: &tempfile::TempDir) -> ProcessOptions {
+        ProcessOptions {
+            cache_path: Some(dir.path().join("cache/ledger.snapshot")),
+            ..ProcessOptions::default()
+        }
+    }
+
+    #[test]
+    fn restore_gives_processed_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = cache_options(&dir);
+        let loader = fake_loader(fake_files());
+        let arena = Bump::new();
+        let mut want_ctx = ReportContext::new(&arena);
+        let (want, _, _) = process_entries(&mut want_ctx, &loader, &options).unwrap();
+
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let got = restore(
+            &mut ctx,
+            &loader,
+            &options,
+            options.cache_path.as_ref().unwrap(),
+        )
+        .expect("snapshot must be restored");
+
+        assert_eq!(describe(&want_ctx, &want), describe(&ctx, &got));
+        assert_eq!(
+            Some(pretty_decimal::Format::Comma3Dot),
+            ctx.display_context.decimal_format("SFr")
+        );
+        assert_eq!("Migros", ctx.payees.ensure("MIGROS ZH").as_str());
+        assert_eq!(ctx.account("Assets:Bank"), ctx.account("Bank"));
+        assert_eq!(ctx.commodity("CHF"), ctx.commodity("SFr"));
+    }
+
+    #[test]
+    fn restore_fails_on_changed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = cache_options(&dir);
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        process_entries(&mut ctx, &fake_loader(fake_files()), &options).unwrap();
+        let mut files = fake_files();
+        files
+            .get_mut(Path::new("path/to/sub/2024.ledger"))
+            .unwrap()
+            .extend(b"\n2024/01/05 Shop\n    Expenses:Food  1 CHF\n    Bank\n");
+        let loader = fake_loader(files);
+
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let got = restore(
+            &mut ctx,
+            &loader,
+            &options,
+            options.cache_path.as_ref().unwrap(),
+        );
+
+        assert!(got.is_none());
+        let ledger = super::super::process(&mut ctx, &loader, &options).unwrap();
+        assert_eq!(2, ledger.transactions.len());
+    }
+
+    #[test]
+    fn restore_fails_on_new_include_match() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = cache_options(&dir);
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        process_entries(&mut ctx, &fake_loader(fake_files()), &options).unwrap();
+        let mut files = fake_files();
+        files.insert(
+            PathBuf::from("path/to/sub/2025.ledger"),
+            b"account Expenses:New\n".to_vec(),
+        );
+
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let got = restore(
+            &mut ctx,
+            &fake_loader(files),
+            &options,
+            options.cache_path.as_ref().unwrap(),
+        );
+
+        assert!(got.is_none());
+    }
+
+    #[test]
+    fn restore_fails_on_changed_options() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = cache_options(&dir);
+        let loader = fake_loader(fake_files());
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        process_entries(&mut ctx, &loader, &options).unwrap();
+        let options = ProcessOptions {
+            keep_going: true,
+            ..cache_options(&dir)
+        };
+
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let got = restore(
+            &mut ctx,
+            &loader,
+            &options,
+            options.cache_path.as_ref().unwrap(),
+        );
+
+        assert!(got.is_none());
+    }
+
+    #[test]
+    fn store_skips_ledger_with_warnings() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = ProcessOptions {
+            strictness: Strictness::Warn,
+            ..cache_options(&dir)
+        };
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+
+        let (_, warnings, _) =
+            process_entries(&mut ctx, &fake_loader(fake_files()), &options).unwrap();
+
+        assert!(!warnings.is_empty());
+        assert!(!options.cache_path.unwrap().exists());
+    }
+
+    #[test]
+    fn restore_checks_real_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = cache_options(&dir);
+        let source = dir.path().join("root.ledger");
+        let write = |content: &str| {
+            std::fs::write(&source, content).unwrap();
+            // Files modified just before the loading are not trusted to be stable.
+            std::fs::File::options()
+                .write(true)
+                .open(&source)
+                .unwrap()
+                .set_modified(SystemTime::now() - Duration::from_secs(60))
+                .unwrap();
+        };
+        write("2024/01/01 Shop\n    Expenses:Food  1 CHF\n    Assets:Bank\n");
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        process_entries(&mut ctx, &load::new_loader(source.clone()), &options).unwrap();
+
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let cache_path = options.cache_path.as_ref().unwrap();
+        let got = restore(
+            &mut ctx,
+            &load::new_loader(source.clone()),
+            &options,
+            cache_path,
+        );
+        assert_eq!(1, got.unwrap().transactions.len());
+
+        write("2024/01/01 Shop\n    Expenses:Food  2 CHF\n    Assets:Bank\n");
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let got = restore(&mut ctx, &load::new_loader(source), &options, cache_path);
+        assert!(got.is_none());
+    }
+}
@@ -0,0 +1,274 @@
+//! Compact binary encoding of the snapshot.
+//!
+//! Integers are written as LEB128 variable-length integers,
+//! with zigzag encoding for the signed ones,
+//! so that the small numbers which dominate the ledger take only a few bytes.
+
+use chrono::{Datelike, NaiveDate};
+use rust_decimal::Decimal;
+
+/// Error on decoding the snapshot.
+#[derive(Debug, thiserror::Error)]
+pub(super) enum DecodeError {
+    #[error("unexpected end of the data")]
+    Truncated,
+    #[error("invalid {0}")]
+    Invalid(&'static str),
+}
+
+/// Writes the values into the byte buffer.
+#[derive(Debug, Default)]
+pub(super) struct Encoder {
+    buf: Vec<u8>,
+}
+
+impl Encoder {
+    /// Returns the encoded bytes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+
+    pub fn u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    pub fn bool(&mut self, v: bool) {
+        self.u8(v.into());
+    }
+
+    pub fn uint(&mut self, v: u64) {
+        self.varint(v.into());
+    }
+
+    pub fn int(&mut self, v: i64) {
+        self.uint(((v << 1) ^ (v >> 63)) as u64);
+    }
+
+    /// Writes the length of the following sequence.
+    pub fn len(&mut self, v: usize) {
+        self.uint(v as u64);
+    }
+
+    /// Writes the fixed length 128-bit value, such as the hash.
+    pub fn u128(&mut self, v: u128) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub fn bytes(&mut self, v: &[u8]) {
+        self.len(v.len());
+        self.buf.extend_from_slice(v);
+    }
+
+    pub fn str(&mut self, v: &str) {
+        self.bytes(v.as_bytes());
+    }
+
+    pub fn decimal(&mut self, v: Decimal) {
+        let mantissa = v.mantissa();
+        self.varint(((mantissa << 1) ^ (mantissa >> 127)) as u128);
+        self.u8(v.scale() as u8);
+    }
+
+    pub fn date(&mut self, v: NaiveDate) {
+        self.int(v.num_days_from_ce().into());
+    }
+
+    /// Writes the presence of the value, followed by the value written with `f`.
+    pub fn option<T>(&mut self, v: Option<T>, f: impl FnOnce(&mut Self, T)) {
+        self.bool(v.is_some());
+        if let Some(v) = v {
+            f(self, v);
+        }
+    }
+
+    fn varint(&mut self, mut v: u128) {
+        while v >= 0x80 {
+            self.buf.push((v as u8) | 0x80);
+            v >>= 7;
+        }
+        self.buf.push(v as u8);
+    }
+}
+
+/// Reads the values written by [`Encoder`].
+pub(super) struct Decoder<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    /// Returns the bytes not read yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.buf
+    }
+
+    pub fn u8(&mut self) -> Result<u8, DecodeError> {
+        let (v, rest) = self.buf.split_first().ok_or(DecodeError::Truncated)?;
+        self.buf = rest;
+        Ok(*v)
+    }
+
+    pub fn bool(&mut self) -> Result<bool, DecodeError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DecodeError::Invalid("bool")),
+        }
+    }
+
+    pub fn uint(&mut self) -> Result<u64, DecodeError> {
+        self.varint()?
+            .try_into()
+            .map_err(|_| DecodeError::Invalid("integer"))
+    }
+
+    pub fn int(&mut self) -> Result<i64, DecodeError> {
+        let v = self.uint()?;
+        Ok(((v >> 1) as i64) ^ -((v & 1) as i64))
+    }
+
+    /// Reads the length of the following sequence,
+    /// which must not exceed the remaining bytes as each element takes at least one byte.
+    pub fn len(&mut self) -> Result<usize, DecodeError> {
+        let v = self.uint()?;
+        usize::try_from(v)
+            .ok()
+            .filter(|v| *v <= self.buf.len())
+            .ok_or(DecodeError::Truncated)
+    }
+
+    pub fn u128(&mut self) -> Result<u128, DecodeError> {
+        let (v, rest) = self
+            .buf
+            .split_first_chunk::<16>()
+            .ok_or(DecodeError::Truncated)?;
+        self.buf = rest;
+        Ok(u128::from_le_bytes(*v))
+    }
+
+    pub fn bytes(&mut self) -> Result<&'a [u8], DecodeError> {
+        let len = self.len()?;
+        let (v, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(v)
+    }
+
+    pub fn str(&mut self) -> Result<&'a str, DecodeError> {
+        std::str::from_utf8(self.bytes()?).map_err(|_| DecodeError::Invalid("string"))
+    }
+
+    pub fn decimal(&mut self) -> Result<Decimal, DecodeError> {
+        let v = self.varint()?;
+        let mantissa = ((v >> 1) as i128) ^ -((v & 1) as i128);
+        let scale = self.u8()?;
+        Decimal::try_from_i128_with_scale(mantissa, scale.into())
+            .map_err(|_| DecodeError::Invalid("decimal"))
+    }
+
+    pub fn date(&mut self) -> Result<NaiveDate, DecodeError> {
+        i32::try_from(self.int()?)
+            .ok()
+            .and_then(NaiveDate::from_num_days_from_ce_opt)
+            .ok_or(DecodeError::Invalid("date"))
+    }
+
+    /// Reads the value written by [`Encoder::option`], with `f` reading the value.
+    pub fn option<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<Option<T>, DecodeError> {
+        if self.bool()? {
+            f(self).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Reads the sequence written with [`Encoder::len`] followed by the elements.
+    pub fn seq<T>(
+        &mut self,
+        mut f: impl FnMut(&mut Self) -> Result<T, DecodeError>,
+    ) -> Result<Vec<T>, DecodeError> {
+        let len = self.len()?;
+        let mut ret = Vec::with_capacity(len);
+        for _ in 0..len {
+            ret.push(f(self)?);
+        }
+        Ok(ret)
+    }
+
+    fn varint(&mut self) -> Result<u128, DecodeError> {
+        let mut ret: u128 = 0;
+        for shift in (0..128).step_by(7) {
+            let b = self.u8()?;
+            ret |= u128::from(b & 0x7f) << shift;
+            if b & 0x80 == 0 {
+                return Ok(ret);
+            }
+        }
+        Err(DecodeError::Invalid("integer"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pretty_assertions::assert_eq;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn round_trip() {
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let mut encoder = Encoder::default();
+        encoder.bool(true);
+        encoder.uint(300);
+        encoder.int(-65);
+        encoder.int(i64::MIN);
+        encoder.u128(u128::MAX - 1);
+        encoder.str("資産");
+        encoder.decimal(dec!(-1234.5678));
+        encoder.decimal(Decimal::MAX);
+        encoder.date(date);
+        encoder.option(Some(3u64), Encoder::uint);
+        encoder.option(None, Encoder::uint);
+        let bytes = encoder.into_bytes();
+        let mut decoder = Decoder::new(&bytes);
+
+        assert!(decoder.bool().unwrap());
+        assert_eq!(300, decoder.uint().unwrap());
+        assert_eq!(-65, decoder.int().unwrap());
+        assert_eq!(i64::MIN, decoder.int().unwrap());
+        assert_eq!(u128::MAX - 1, decoder.u128().unwrap());
+        assert_eq!("資産", decoder.str().unwrap());
+        assert_eq!(dec!(-1234.5678), decoder.decimal().unwrap());
+        assert_eq!(Decimal::MAX, decoder.decimal().unwrap());
+        assert_eq!(date, decoder.date().unwrap());
+        assert_eq!(Some(3), decoder.option(Decoder::uint).unwrap());
+        assert_eq!(None, decoder.option(Decoder::uint).unwrap());
+        assert!(decoder.remaining().is_empty());
+    }
+
+    #[test]
+    fn small_values_are_compact() {
+        let mut encoder = Encoder::default();
+        encoder.uint(127);
+        encoder.decimal(dec!(-12.34));
+
+        assert_eq!(4, encoder.into_bytes().len());
+    }
+
+    #[test]
+    fn truncated_input_fails() {
+        let mut encoder = Encoder::default();
+        encoder.str("Assets:Bank");
+        let bytes = encoder.into_bytes();
+
+        let mut decoder = Decoder::new(&bytes[..5]);
+
+        assert!(matches!(decoder.str(), Err(DecodeError::Truncated)));
+    }
+}
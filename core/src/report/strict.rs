@@ -0,0 +1,161 @@
+//! Checks the accounts, commodities, payees and tags are declared before use.
+
+use std::collections::HashSet;
+
+use crate::syntax::{self, decoration::AsUndecorated};
+
+use super::account::Account;
+use super::book_keeping::BookKeepError;
+use super::commodity::CommodityTag;
+use super::context::ReportContext;
+use super::transaction::Transaction;
+
+/// Controls how the undeclared accounts, commodities, payees and tags are reported.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Strictness {
+    /// Accepts undeclared names silently.
+    #[default]
+    Permissive,
+    /// Reports undeclared names as warnings, similar to ledger `--strict`.
+    Warn,
+    /// Reports undeclared names as errors, similar to ledger `--pedantic`.
+    Error,
+}
+
+/// Names declared with "account", "commodity", "payee" and "tag" directives.
+///
+/// Undeclared names are also recorded once reported,
+/// so that the same name is reported only on its first use.
+#[derive(Debug, Default)]
+pub(super) struct Declarations<'ctx> {
+    accounts: HashSet<Account<'ctx>>,
+    commodities: HashSet<CommodityTag<'ctx>>,
+    payees: HashSet<&'ctx str>,
+    tags: HashSet<String>,
+}
+
+impl<'ctx> Declarations<'ctx> {
+    pub fn declare_account(&mut self, account: Account<'ctx>) {
+        self.accounts.insert(account);
+    }
+
+    pub fn declare_commodity(&mut self, commodity: CommodityTag<'ctx>) {
+        self.commodities.insert(commodity);
+    }
+
+    pub fn declare_payee(&mut self, payee: &'ctx str) {
+        self.payees.insert(payee);
+    }
+
+    pub fn declare_tag(&mut self, tag: &str) {
+        self.tags.insert(tag.to_owned());
+    }
+
+    /// Returns the declared accounts, in unspecified order.
+    pub fn accounts(&self) -> impl Iterator<Item = Account<'ctx>> + '_ {
+        self.accounts.iter().copied()
+    }
+
+    /// Returns the declared commodities, in unspecified order.
+    pub fn commodities(&self) -> impl Iterator<Item = CommodityTag<'ctx>> + '_ {
+        self.commodities.iter().copied()
+    }
+
+    /// Returns the declared payees, in unspecified order.
+    pub fn payees(&self) -> impl Iterator<Item = &'ctx str> + '_ {
+        self.payees.iter().copied()
+    }
+
+    /// Returns the declared tags, in unspecified order.
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.tags.iter().map(String::as_str)
+    }
+
+    /// Returns errors for the undeclared names used in the transaction,
+    /// where `txn` is the processed `syntax_txn`.
+    pub fn check_transaction(
+        &mut self,
+        ctx: &ReportContext<'ctx>,
+        syntax_txn: &syntax::tracked::Transaction,
+        txn: &Transaction<'ctx>,
+    ) -> Vec<BookKeepError> {
+        let mut errors = Vec::new();
+        for (i, posting) in txn.postings.iter().enumerate() {
+            if self.payees.insert(posting.payee) {
+                // Generated postings never introduce a payee, as they copy the written one.
+                let overriding = syntax_txn
+                    .posts
+                    .get(i)
+                    .filter(|written| has_payee_override(written.as_undecorated()))
+                    .map(|written| written.span());
+                errors.push(BookKeepError::UndeclaredPayee(
+                    posting.payee.to_owned(),
+                    overriding,
+                ));
+            }
+        }
+        // Postings generated by automated transactions follow the written ones,
+        // so zip stops at the written ones.
+        for (written, posting) in syntax_txn.posts.iter().zip(txn.postings.iter()) {
+            let written = written.as_undecorated();
+            if self.accounts.insert(posting.account) {
+                errors.push(BookKeepError::UndeclaredAccount(
+                    posting.account.as_str().to_owned(),
+                    written.account.span(),
+                ));
+            }
+            let amount_span = written
+                .amount
+                .as_ref()
+                .map_or_else(|| written.account.span(), |amount| amount.amount.span());
+            for single in posting.amount.iter() {
+                if self.commodities.insert(single.commodity) {
+                    errors.push(BookKeepError::UndeclaredCommodity(
+                        single.commodity.to_str_lossy(&ctx.commodities).into_owned(),
+                        amount_span.clone(),
+                    ));
+                }
+            }
+        }
+        let metadata =
+            syntax_txn
+                .metadata
+                .iter()
+                .map(|m| (m, None))
+                .chain(syntax_txn.posts.iter().flat_map(|posting| {
+                    let span = posting.span();
+                    posting
+                        .as_undecorated()
+                        .metadata
+                        .iter()
+                        .map(move |m| (m, Some(span.clone())))
+                }));
+        for (m, span) in metadata {
+            let keys: Vec<&str> = match m {
+                syntax::Metadata::WordTags(tags) => tags.iter().map(|t| t.as_ref()).collect(),
+                syntax::Metadata::KeyValueTag { key, .. } => vec![key.as_ref()],
+                syntax::Metadata::Dates { .. } | syntax::Metadata::Comment(_) => Vec::new(),
+            };
+            for key in keys {
+                if !self.tags.contains(key) {
+                    self.tags.insert(key.to_owned());
+                    errors.push(BookKeepError::UndeclaredTag(key.to_owned(), span.clone()));
+                }
+            }
+        }
+        errors
+    }
+}
+
+/// Returns `true` if the posting overrides the payee with `; Payee: <name>` metadata.
+fn has_payee_override(posting: &syntax::tracked::Posting) -> bool {
+    posting.metadata.iter().any(|m| {
+        matches!(
+            m,
+            syntax::Metadata::KeyValueTag {
+                key,
+                value: syntax::MetadataValue::Text(_),
+            } if key.as_ref() == "Payee"
+        )
+    })
+}
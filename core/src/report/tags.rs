@@ -6,7 +6,9 @@ use std::collections::BTreeSet;
 use crate::load;
 use crate::syntax::{self, plain::LedgerStatement};
 
-use super::error::ReportError;
+use super::book_keeping::{BookKeepError, eval_metadata_value};
+use super::context::ReportContext;
+use super::error::{self, ReportError};
 
 /// Controls whether [`tags`] reports tag keys only, or key-value pairs.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -30,85 +32,97 @@ pub struct Tag {
 /// Value of the [`Tag`].
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum TagValue {
-    /// Expression tag value. Currently we treat it as just text.
+    /// Expression tag value, evaluated and then rendered as text.
     Expr(String),
     /// Plain text value.
     Text(String),
 }
 
-impl From<&syntax::MetadataValue<'_>> for TagValue {
-    fn from(value: &syntax::MetadataValue<'_>) -> Self {
-        match value {
-            syntax::MetadataValue::Expr(expr) => Self::Expr(expr.clone().into_owned()),
-            syntax::MetadataValue::Text(text) => Self::Text(text.clone().into_owned()),
-        }
-    }
-}
-
 /// Returns all tags in the given Ledger file, sorted and deduped.
 ///
 /// Tags are collected from `apply tag` directives, transaction level metadata
 /// and posting level metadata. Note `apply tag` is reported as-is, and not
 /// propagated into the transactions it encloses.
+/// Expression values prefixed with `::` are evaluated with the given `ctx`.
 /// WARNING: interface are subject to change.
-pub fn tags<L, F>(loader: L, query: TagQuery) -> Result<BTreeSet<Tag>, ReportError>
+pub fn tags<'ctx, L, F>(
+    ctx: &mut ReportContext<'ctx>,
+    loader: L,
+    query: TagQuery,
+) -> Result<BTreeSet<Tag>, ReportError>
 where
     L: Borrow<load::Loader<F>>,
     F: load::FileSystem,
 {
     let mut collected: BTreeSet<Tag> = BTreeSet::new();
-    loader.borrow().load(|_path, _pctx, entry| {
+    loader.borrow().load_parallel(|path, pctx, entry| {
+        let mut collector = Collector {
+            ctx: &mut *ctx,
+            collected: &mut collected,
+            query,
+        };
         match &entry.statement {
-            LedgerStatement::ApplyTag(apply) => {
-                insert(&mut collected, query, &apply.key, apply.value.as_ref())
-            }
-            LedgerStatement::Txn(txn) => {
-                for metadata in &txn.metadata {
-                    collect_metadata(&mut collected, query, metadata);
-                }
-                for posting in &txn.posts {
-                    for metadata in &posting.metadata {
-                        collect_metadata(&mut collected, query, metadata);
-                    }
-                }
-            }
-            _ => (),
+            LedgerStatement::ApplyTag(apply) => collector.insert(&apply.key, apply.value.as_ref()),
+            LedgerStatement::Txn(txn) => txn
+                .metadata
+                .iter()
+                .chain(txn.posts.iter().flat_map(|posting| &posting.metadata))
+                .try_for_each(|metadata| collector.collect_metadata(metadata)),
+            _ => Ok(()),
         }
-        Ok::<(), ReportError>(())
+        .map_err(|berr| {
+            ReportError::BookKeep(
+                berr,
+                error::ErrorContext::new(
+                    loader.borrow().error_style().clone(),
+                    path.to_owned(),
+                    pctx,
+                ),
+            )
+        })
     })?;
     Ok(collected)
 }
 
-fn collect_metadata(collected: &mut BTreeSet<Tag>, query: TagQuery, metadata: &syntax::Metadata) {
-    match metadata {
-        syntax::Metadata::WordTags(word_tags) => {
-            for tag in word_tags {
-                insert(collected, query, tag, None);
+struct Collector<'a, 'ctx> {
+    ctx: &'a mut ReportContext<'ctx>,
+    collected: &'a mut BTreeSet<Tag>,
+    query: TagQuery,
+}
+
+impl Collector<'_, '_> {
+    fn collect_metadata(&mut self, metadata: &syntax::Metadata) -> Result<(), BookKeepError> {
+        match metadata {
+            syntax::Metadata::WordTags(word_tags) => {
+                for tag in word_tags {
+                    self.insert(tag, None)?;
+                }
+                Ok(())
             }
+            syntax::Metadata::KeyValueTag { key, value } => self.insert(key, Some(value)),
+            syntax::Metadata::Dates { .. } | syntax::Metadata::Comment(_) => Ok(()),
         }
-        syntax::Metadata::KeyValueTag { key, value } => {
-            insert(collected, query, key, Some(value));
-        }
-        syntax::Metadata::Comment(_) => (),
     }
-}
 
-fn insert(
-    collected: &mut BTreeSet<Tag>,
-    query: TagQuery,
-    key: &str,
-    value: Option<&syntax::MetadataValue>,
-) {
-    match (query, value) {
-        (TagQuery::WithValues, Some(value)) => collected.insert(Tag {
-            key: key.to_owned(),
-            value: Some(value.into()),
-        }),
-        _ => collected.insert(Tag {
+    fn insert(
+        &mut self,
+        key: &str,
+        value: Option<&syntax::MetadataValue>,
+    ) -> Result<(), BookKeepError> {
+        let value = match (self.query, value) {
+            (TagQuery::WithValues, Some(syntax::MetadataValue::Text(text))) => {
+                Some(TagValue::Text(text.to_string()))
+            }
+            (TagQuery::WithValues, Some(value)) => eval_metadata_value(self.ctx, key, value)?
+                .map(|v| TagValue::Expr(v.as_display(self.ctx).to_string())),
+            _ => None,
+        };
+        self.collected.insert(Tag {
             key: key.to_owned(),
-            value: None,
-        }),
-    };
+            value,
+        });
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -117,6 +131,7 @@ mod tests {
 
     use std::path::PathBuf;
 
+    use bumpalo::Bump;
     use indoc::indoc;
     use maplit::{btreeset, hashmap};
     use pretty_assertions::assert_eq;
@@ -171,7 +186,9 @@ mod tests {
 
     #[test]
     fn tags_keys_only() {
-        let got = tags(all_tags_fixture(), TagQuery::KeysOnly).unwrap();
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let got = tags(&mut ctx, all_tags_fixture(), TagQuery::KeysOnly).unwrap();
 
         assert_eq!(
             btreeset![ko("Workflow"), ko("food"), ko("trip"), ko("type")],
@@ -181,7 +198,9 @@ mod tests {
 
     #[test]
     fn tags_with_values() {
-        let got = tags(all_tags_fixture(), TagQuery::WithValues).unwrap();
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
+        let got = tags(&mut ctx, all_tags_fixture(), TagQuery::WithValues).unwrap();
 
         assert_eq!(
             btreeset![
@@ -203,17 +222,25 @@ mod tests {
                 2026/01/01 lunch
                    Expenses:Food     10 CHF
                      ; total:: 1 + 2
+                     ; paid:: 10 CHF * 2
+                     ; due:: [2026/02/01]
                    Assets:Bank:X
             "}.as_bytes().to_vec(),
         });
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
 
         assert_eq!(
-            btreeset![ko("total")],
-            tags(&loader, TagQuery::KeysOnly).unwrap()
+            btreeset![ko("due"), ko("paid"), ko("total")],
+            tags(&mut ctx, &loader, TagQuery::KeysOnly).unwrap()
         );
         assert_eq!(
-            btreeset![kve("total", "1 + 2")],
-            tags(&loader, TagQuery::WithValues).unwrap(),
+            btreeset![
+                kve("due", "[2026/02/01]"),
+                kve("paid", "20 CHF"),
+                kve("total", "3"),
+            ],
+            tags(&mut ctx, &loader, TagQuery::WithValues).unwrap(),
         );
     }
 
@@ -237,14 +264,16 @@ mod tests {
                    Assets:Bank:X
             "}.as_bytes().to_vec(),
         });
+        let arena = Bump::new();
+        let mut ctx = ReportContext::new(&arena);
 
         assert_eq!(
             btreeset![ko("food"), ko("trip")],
-            tags(&loader, TagQuery::KeysOnly).unwrap()
+            tags(&mut ctx, &loader, TagQuery::KeysOnly).unwrap()
         );
         assert_eq!(
             btreeset![ko("food"), kv("trip", "kyoto")],
-            tags(&loader, TagQuery::WithValues).unwrap(),
+            tags(&mut ctx, &loader, TagQuery::WithValues).unwrap(),
         );
     }
 }
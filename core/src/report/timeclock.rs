@@ -0,0 +1,139 @@
+//! Provides [Timeclock], which converts the timeclock check-in and check-out pairs
+//! into the transactions of the time spent.
+
+use bumpalo::collections as bcc;
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+
+use crate::syntax::{self, PostingKind};
+
+use super::{
+    account::Account,
+    balance::Balance,
+    book_keeping::BookKeepError,
+    context::ReportContext,
+    error::{ErrorContext, ReportError},
+    eval::Amount,
+    transaction::{Posting, Transaction},
+};
+
+/// Commodity of the time spent, in hours.
+const HOUR_COMMODITY: &str = "h";
+
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+
+/// Accounts currently checked in, waiting for the check out.
+#[derive(Debug, Default)]
+pub(super) struct Timeclock<'ctx> {
+    checked_in: Vec<CheckIn<'ctx>>,
+}
+
+#[derive(Debug)]
+struct CheckIn<'ctx> {
+    account: Account<'ctx>,
+    datetime: NaiveDateTime,
+    payee: Option<&'ctx str>,
+    /// Location of the check in, to report it's never checked out.
+    source: Box<ErrorContext>,
+}
+
+impl<'ctx> Timeclock<'ctx> {
+    /// Checks in the account, which must not be checked in already.
+    pub fn check_in(
+        &mut self,
+        ctx: &mut ReportContext<'ctx>,
+        entry: &syntax::ClockIn<'_>,
+        source: Box<ErrorContext>,
+    ) -> Result<(), BookKeepError> {
+        let account = ctx.accounts.ensure_applied(&entry.account);
+        if self.checked_in.iter().any(|c| c.account == account) {
+            return Err(BookKeepError::DuplicateCheckIn(account.as_str().to_owned()));
+        }
+        let payee = entry
+            .payee
+            .as_deref()
+            .map(|payee| &*ctx.arena.alloc_str(payee));
+        self.checked_in.push(CheckIn {
+            account,
+            datetime: entry.datetime,
+            payee,
+            source,
+        });
+        Ok(())
+    }
+
+    /// Checks out the account, and returns the transaction of the time spent.
+    /// The account can be omitted only when exactly one account is checked in.
+    pub fn check_out(
+        &mut self,
+        ctx: &mut ReportContext<'ctx>,
+        bal: &mut Balance<'ctx>,
+        entry: &syntax::ClockOut<'_>,
+    ) -> Result<Transaction<'ctx>, BookKeepError> {
+        let index = match &entry.account {
+            Some(account) => {
+                let account = ctx.accounts.ensure_applied(account);
+                self.checked_in
+                    .iter()
+                    .position(|c| c.account == account)
+                    .ok_or_else(|| BookKeepError::UnmatchedCheckOut(account.as_str().to_owned()))?
+            }
+            None if self.checked_in.len() == 1 => 0,
+            None => return Err(BookKeepError::AmbiguousCheckOut(self.checked_in.len())),
+        };
+        // Keeps the check in on failure, so that the ledger stays intact with keep_going.
+        let spent = entry.datetime - self.checked_in[index].datetime;
+        if spent < chrono::TimeDelta::zero() {
+            return Err(BookKeepError::CheckOutBeforeCheckIn {
+                check_in: self.checked_in[index].datetime,
+                check_out: entry.datetime,
+            });
+        }
+        let check_in = self.checked_in.remove(index);
+        let hours =
+            (Decimal::from(spent.num_seconds()) / Decimal::from(SECONDS_PER_HOUR)).normalize();
+        let amount = Amount::from_value(ctx.commodities.ensure(HOUR_COMMODITY), hours);
+        bal.add_amount(check_in.account, amount.clone());
+        let payee = check_in
+            .payee
+            .or_else(|| {
+                entry
+                    .note
+                    .as_deref()
+                    .map(|note| &*ctx.arena.alloc_str(note))
+            })
+            .unwrap_or("");
+        let posting = Posting {
+            account: check_in.account,
+            kind: PostingKind::Virtual,
+            payee,
+            date: check_in.datetime.date(),
+            effective_date: None,
+            amount,
+            converted_amount: None,
+            metadata: Vec::new(),
+        };
+        Ok(Transaction {
+            date: check_in.datetime.date(),
+            effective_date: None,
+            postings: bcc::Vec::from_iter_in([posting], ctx.arena).into_boxed_slice(),
+            metadata: Vec::new(),
+        })
+    }
+
+    /// Returns `true` if no account is checked in.
+    pub fn is_empty(&self) -> bool {
+        self.checked_in.is_empty()
+    }
+
+    /// Ensures all the accounts are checked out.
+    pub fn finish(self) -> Result<(), ReportError> {
+        match self.checked_in.into_iter().next() {
+            None => Ok(()),
+            Some(check_in) => Err(ReportError::BookKeep(
+                BookKeepError::UnmatchedCheckIn(check_in.account.as_str().to_owned()),
+                check_in.source,
+            )),
+        }
+    }
+}
@@ -1,7 +1,13 @@
+use std::fmt::Display;
+
 use chrono::NaiveDate;
+use rust_decimal::Decimal;
+
+use crate::syntax::PostingKind;
 
 use super::{
     account::Account,
+    context::ReportContext,
     eval::{Amount, SingleAmount},
 };
 
@@ -11,11 +17,16 @@ use super::{
 #[non_exhaustive]
 pub struct Transaction<'ctx> {
     pub date: NaiveDate,
+    /// Effective date of the transaction, also known as the auxiliary date.
+    pub effective_date: Option<NaiveDate>,
     // Posting in the transaction.
     // Note this MUST be a Box instead of &[Posting],
     // as Posting is a [Drop] and we can't skip calling Drop,
     // otherwise we leave allocated memory for Amount HashMap.
     pub postings: bumpalo::boxed::Box<'ctx, [Posting<'ctx>]>,
+
+    /// Evaluated `::` metadata of the transaction, in the appearing order.
+    pub metadata: Vec<(&'ctx str, MetadataValue<'ctx>)>,
 }
 
 /// Evaluated posting of the transaction.
@@ -25,10 +36,21 @@ pub struct Posting<'ctx> {
     /// Account of the posting.
     pub account: Account<'ctx>,
 
+    /// Whether the posting is real or virtual.
+    pub kind: PostingKind,
+
     /// Payee of the posting. By default this is the enclosing transaction's
     /// payee; per-posting payee overrides via metadata are not implemented yet.
     pub payee: &'ctx str,
 
+    /// Date of the posting. By default this is the enclosing transaction's date,
+    /// while the posting can override it with `; [date]` metadata.
+    pub date: NaiveDate,
+
+    /// Effective date of the posting, which can be given with `; [=date]` metadata.
+    /// By default this is the enclosing transaction's effective date.
+    pub effective_date: Option<NaiveDate>,
+
     /// Amount of the posting described in the Ledger.
     // Note this is not PostingAmount,
     // as deduced posting may have non-single commodity amount.
@@ -38,4 +60,51 @@ pub struct Posting<'ctx> {
     /// Some time this is useful for a few use cases:
     /// - To balance within the transaction, we prefer this amount.
     pub converted_amount: Option<SingleAmount<'ctx>>,
+
+    /// Evaluated `::` metadata of the posting, in the appearing order.
+    pub metadata: Vec<(&'ctx str, MetadataValue<'ctx>)>,
+}
+
+impl Posting<'_> {
+    /// Returns the date used for the query,
+    /// which is the effective date if `effective` is set and the posting has it.
+    pub fn query_date(&self, effective: bool) -> NaiveDate {
+        match self.effective_date {
+            Some(effective_date) if effective => effective_date,
+            _ => self.date,
+        }
+    }
+}
+
+/// Evaluated value of the `::` metadata, such as `; Shares:: 10 AAPL`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataValue<'ctx> {
+    /// Number without commodity.
+    Number(Decimal),
+    /// Amount with commodities.
+    Amount(Amount<'ctx>),
+    /// Date written as `[2022/03/04]`.
+    Date(NaiveDate),
+}
+
+impl<'ctx> MetadataValue<'ctx> {
+    /// Returns [`Display`] implementation.
+    pub fn as_display<'a>(&'a self, ctx: &'a ReportContext<'ctx>) -> impl Display + 'a
+    where
+        'a: 'ctx,
+    {
+        MetadataValueDisplay(self, ctx)
+    }
+}
+
+struct MetadataValueDisplay<'a, 'ctx>(&'a MetadataValue<'ctx>, &'a ReportContext<'ctx>);
+
+impl Display for MetadataValueDisplay<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            MetadataValue::Number(x) => x.fmt(f),
+            MetadataValue::Amount(x) => x.as_inline_display(self.1).fmt(f),
+            MetadataValue::Date(x) => write!(f, "[{}]", x.format("%Y/%m/%d")),
+        }
+    }
 }
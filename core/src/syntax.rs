@@ -69,18 +69,45 @@ impl LedgerEntry<'_, plain::Ident> {
 pub enum LedgerStatement<'i, Deco: Decoration> {
     /// Transaction
     Txn(Transaction<'i, Deco>),
+    /// Automated transaction, "=" directive.
+    AutomatedTxn(AutomatedTransaction<'i, Deco>),
+    /// Periodic transaction, "~" directive.
+    PeriodicTxn(PeriodicTransaction<'i, Deco>),
     /// Comment, not limited to one-line oppose to `Metadata`.
     Comment(TopLevelComment<'i>),
+    /// "comment" or "test" block.
+    BlockComment(BlockComment<'i>),
     /// Apply tag directive.
     ApplyTag(ApplyTag<'i>),
     /// "end apply tag" directive.
     EndApplyTag,
+    /// "apply account" directive.
+    ApplyAccount(ApplyAccount<'i>),
+    /// "end apply account" directive.
+    EndApplyAccount,
+    /// Top-level "alias" directive.
+    Alias(AccountAlias<'i>),
     /// "include" directive.
     Include(IncludeFile<'i>),
+    /// "year" or "Y" directive, to set the year of the following dates without year.
+    Year(i32),
     /// "account" directive.
     Account(AccountDeclaration<'i>),
     /// "commodity" directive.
     Commodity(CommodityDeclaration<'i>),
+    /// "D" directive, to set the commodity of the following amounts without commodity.
+    /// The given amount also declares the display format of the commodity.
+    DefaultCommodity(expr::Amount<'i>),
+    /// "payee" directive.
+    Payee(PayeeDeclaration<'i>),
+    /// "tag" directive.
+    Tag(TagDeclaration<'i>),
+    /// "P" directive, market price of a commodity.
+    Price(PriceDBEntry<'i>),
+    /// "i" timeclock directive, to check in the account.
+    ClockIn(ClockIn<'i>),
+    /// "o" timeclock directive, to check out the account.
+    ClockOut(ClockOut<'i>),
 }
 
 impl LedgerStatement<'_, plain::Ident> {
@@ -88,12 +115,27 @@ impl LedgerStatement<'_, plain::Ident> {
     pub(crate) fn to_static(&self) -> LedgerStatement<'static, plain::Ident> {
         match self {
             LedgerStatement::Txn(v) => LedgerStatement::Txn(v.to_static()),
+            LedgerStatement::AutomatedTxn(v) => LedgerStatement::AutomatedTxn(v.to_static()),
+            LedgerStatement::PeriodicTxn(v) => LedgerStatement::PeriodicTxn(v.to_static()),
             LedgerStatement::Comment(v) => LedgerStatement::Comment(v.to_static()),
+            LedgerStatement::BlockComment(v) => LedgerStatement::BlockComment(v.to_static()),
             LedgerStatement::ApplyTag(v) => LedgerStatement::ApplyTag(v.to_static()),
             LedgerStatement::EndApplyTag => LedgerStatement::EndApplyTag,
+            LedgerStatement::ApplyAccount(v) => LedgerStatement::ApplyAccount(v.to_static()),
+            LedgerStatement::EndApplyAccount => LedgerStatement::EndApplyAccount,
+            LedgerStatement::Alias(v) => LedgerStatement::Alias(v.to_static()),
             LedgerStatement::Include(v) => LedgerStatement::Include(v.to_static()),
+            LedgerStatement::Year(v) => LedgerStatement::Year(*v),
             LedgerStatement::Account(v) => LedgerStatement::Account(v.to_static()),
             LedgerStatement::Commodity(v) => LedgerStatement::Commodity(v.to_static()),
+            LedgerStatement::DefaultCommodity(v) => {
+                LedgerStatement::DefaultCommodity(v.to_static())
+            }
+            LedgerStatement::Payee(v) => LedgerStatement::Payee(v.to_static()),
+            LedgerStatement::Tag(v) => LedgerStatement::Tag(v.to_static()),
+            LedgerStatement::Price(v) => LedgerStatement::Price(v.to_static()),
+            LedgerStatement::ClockIn(v) => LedgerStatement::ClockIn(v.to_static()),
+            LedgerStatement::ClockOut(v) => LedgerStatement::ClockOut(v.to_static()),
         }
     }
 }
@@ -102,6 +144,54 @@ impl LedgerStatement<'_, plain::Ident> {
 #[derive(Debug, PartialEq, Eq, ToStatic)]
 pub struct TopLevelComment<'i>(pub Cow<'i, str>);
 
+/// "comment" or "test" block, lasting until the matching "end comment" or "end test".
+/// Similar to `TopLevelComment`, it doesn't have any semantics.
+#[derive(Debug, PartialEq, Eq, ToStatic)]
+pub struct BlockComment<'i> {
+    pub kind: BlockCommentKind,
+    /// Rest of the opening line, such as the command of the "test" block.
+    pub header: Cow<'i, str>,
+    /// Enclosed lines as-is, including the line endings.
+    pub body: Cow<'i, str>,
+}
+
+impl BlockComment<'_> {
+    /// Returns the command arguments of the "test" block, such as `reg --flat`.
+    pub fn test_command(&self) -> Option<&str> {
+        match self.kind {
+            BlockCommentKind::Comment => None,
+            BlockCommentKind::Test => Some(&self.header),
+        }
+    }
+
+    /// Returns the expected output of the "test" block.
+    pub fn expected_output(&self) -> Option<&str> {
+        match self.kind {
+            BlockCommentKind::Comment => None,
+            BlockCommentKind::Test => Some(&self.body),
+        }
+    }
+}
+
+/// Kind of the [`BlockComment`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, ToStatic)]
+pub enum BlockCommentKind {
+    /// "comment" ... "end comment" block.
+    Comment,
+    /// "test" ... "end test" block, with the expected output of the command.
+    Test,
+}
+
+impl BlockCommentKind {
+    /// Returns the keyword of the block.
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            BlockCommentKind::Comment => "comment",
+            BlockCommentKind::Test => "test",
+        }
+    }
+}
+
 /// "apply tag" directive content.
 #[derive(Debug, PartialEq, Eq, ToStatic)]
 pub struct ApplyTag<'i> {
@@ -109,6 +199,17 @@ pub struct ApplyTag<'i> {
     pub value: Option<MetadataValue<'i>>,
 }
 
+/// "apply account" directive content, the account prefixed to the postings in the block.
+#[derive(Debug, PartialEq, Eq, ToStatic)]
+pub struct ApplyAccount<'i>(pub Cow<'i, str>);
+
+/// Top-level "alias" directive, declaring `alias` as an alias of `account`.
+#[derive(Debug, PartialEq, Eq, ToStatic)]
+pub struct AccountAlias<'i> {
+    pub alias: Cow<'i, str>,
+    pub account: Cow<'i, str>,
+}
+
 /// "include" directive, taking a path as an argument.
 /// Path can be a relative path or an absolute path.
 #[derive(Debug, PartialEq, Eq, ToStatic)]
@@ -157,6 +258,52 @@ pub enum CommodityDetail<'i> {
     Alias(Cow<'i, str>),
     /// Format describes how the comodity should be printed.
     Format(expr::Amount<'i>),
+    /// "default" sub-directive, to use the commodity for amounts without commodity,
+    /// same as "D" directive.
+    Default,
+    /// "nomarket" sub-directive, so that the commodity price isn't recorded
+    /// from the cost or the lot price of the postings.
+    NoMarket,
+}
+
+/// "payee" directive to declare payee information.
+#[derive(Debug, PartialEq, Eq, ToStatic)]
+pub struct PayeeDeclaration<'i> {
+    /// Canonical name of the payee.
+    pub name: Cow<'i, str>,
+    /// sub-directives for the payee.
+    pub details: Vec<PayeeDetail<'i>>,
+}
+
+/// Sub directives for "payee" directive.
+#[derive(Debug, PartialEq, Eq, ToStatic)]
+pub enum PayeeDetail<'i> {
+    /// Comment is a pure comment without any semantics, similar to `TopLevelComment`.
+    Comment(Cow<'i, str>),
+    /// Declare the given string is an alias for the declared payee.
+    Alias(Cow<'i, str>),
+}
+
+/// "tag" directive to declare metadata tag information.
+#[derive(Debug, PartialEq, Eq, ToStatic)]
+pub struct TagDeclaration<'i> {
+    /// Name of the tag.
+    pub name: Cow<'i, str>,
+    /// sub-directives for the tag.
+    pub details: Vec<TagDetail<'i>>,
+}
+
+/// Sub directives for "tag" directive.
+#[derive(Debug, PartialEq, Eq, ToStatic)]
+pub enum TagDetail<'i> {
+    /// Comment is a pure comment without any semantics, similar to `TopLevelComment`.
+    Comment(Cow<'i, str>),
+    /// "check" sub-directive, an expression expected to hold for the tag value.
+    /// Kept as the written text, as the expression isn't evaluated yet.
+    Check(Cow<'i, str>),
+    /// "assert" sub-directive, similar to "check" but meant to be an error.
+    /// Kept as the written text, as the expression isn't evaluated yet.
+    Assert(Cow<'i, str>),
 }
 
 /// Represents a transaction where the money transfered across the accounts.
@@ -215,11 +362,133 @@ impl<'i, Deco: Decoration> Transaction<'i, Deco> {
     }
 }
 
+/// Represents an automated transaction,
+/// which adds its postings to the transactions with the matching postings.
+#[derive_where(Debug, PartialEq, Eq)]
+pub struct AutomatedTransaction<'i, Deco: Decoration> {
+    /// Predicate to select the matching postings, such as `/^Expenses:Food/`.
+    pub predicate: Cow<'i, str>,
+    /// Postings added for each matching posting.
+    /// Amount without commodity is a multiplier of the matching posting amount.
+    pub posts: Vec<Deco::Decorated<Posting<'i, Deco>>>,
+    /// Automated transaction level metadata.
+    pub metadata: Vec<Metadata<'i>>,
+}
+
+impl AutomatedTransaction<'_, plain::Ident> {
+    #[cfg(test)]
+    fn to_static(&self) -> AutomatedTransaction<'static, plain::Ident> {
+        AutomatedTransaction {
+            predicate: self.predicate.to_static(),
+            posts: self.posts.iter().map(|p| p.to_static()).collect(),
+            metadata: self.metadata.to_static(),
+        }
+    }
+}
+
+impl<'i, Deco: Decoration> AutomatedTransaction<'i, Deco> {
+    /// Constructs automated transaction without postings.
+    pub fn new<T>(predicate: T) -> Self
+    where
+        T: Into<Cow<'i, str>>,
+    {
+        AutomatedTransaction {
+            predicate: predicate.into(),
+            posts: Vec::new(),
+            metadata: Vec::new(),
+        }
+    }
+}
+
+/// Represents a periodic transaction,
+/// which describes the postings expected to happen on every period, such as a budget.
+#[derive_where(Debug, PartialEq, Eq)]
+pub struct PeriodicTransaction<'i, Deco: Decoration> {
+    /// Period when the transaction recurs, such as `Monthly from 2025/01`.
+    pub period: PeriodExpr,
+    /// Postings expected on each period.
+    pub posts: Vec<Deco::Decorated<Posting<'i, Deco>>>,
+    /// Periodic transaction level metadata.
+    pub metadata: Vec<Metadata<'i>>,
+}
+
+impl PeriodicTransaction<'_, plain::Ident> {
+    #[cfg(test)]
+    fn to_static(&self) -> PeriodicTransaction<'static, plain::Ident> {
+        PeriodicTransaction {
+            period: self.period,
+            posts: self.posts.iter().map(|p| p.to_static()).collect(),
+            metadata: self.metadata.to_static(),
+        }
+    }
+}
+
+impl<Deco: Decoration> PeriodicTransaction<'_, Deco> {
+    /// Constructs periodic transaction without postings.
+    pub fn new(period: PeriodExpr) -> Self {
+        PeriodicTransaction {
+            period,
+            posts: Vec::new(),
+            metadata: Vec::new(),
+        }
+    }
+}
+
+/// Period expression, such as `Monthly from 2025/01 to 2025/07`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct PeriodExpr {
+    /// Length of each period.
+    pub interval: Interval,
+    /// Start of the first period (inclusive), if exists.
+    pub begin: Option<NaiveDate>,
+    /// End of the periods (exclusive), if exists.
+    pub end: Option<NaiveDate>,
+}
+
+impl PeriodExpr {
+    /// Constructs the period without begin nor end.
+    pub fn new(interval: Interval) -> Self {
+        Self {
+            interval,
+            begin: None,
+            end: None,
+        }
+    }
+}
+
+/// Length of the period.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Interval {
+    /// Every given number of days.
+    Days(u32),
+    /// Every given number of weeks.
+    Weeks(u32),
+    /// Every given number of months.
+    Months(u32),
+    /// Every given number of years.
+    Years(u32),
+}
+
+impl Interval {
+    /// Returns the start of the next period starting on `date`.
+    /// Returns `None` on overflow.
+    pub fn next_date(self, date: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Interval::Days(n) => date.checked_add_days(chrono::Days::new(n.into())),
+            Interval::Weeks(n) => date.checked_add_days(chrono::Days::new(u64::from(n) * 7)),
+            Interval::Months(n) => date.checked_add_months(chrono::Months::new(n)),
+            Interval::Years(n) => date.checked_add_months(chrono::Months::new(n.checked_mul(12)?)),
+        }
+    }
+}
+
 #[derive_where(Debug, PartialEq, Eq)]
 /// Posting in a transaction to represent a particular account amount increase / decrease.
 pub struct Posting<'i, Deco: Decoration> {
     /// Account of the post target.
     pub account: Deco::Decorated<Cow<'i, str>>,
+    /// Whether the posting is real or virtual.
+    pub kind: PostingKind,
     /// Posting specific ClearState.
     pub clear_state: ClearState,
     /// Amount of the posting.
@@ -235,6 +504,7 @@ impl Posting<'_, plain::Ident> {
     fn to_static(&self) -> Posting<'static, plain::Ident> {
         Posting {
             account: self.account.to_static(),
+            kind: self.kind,
             clear_state: self.clear_state,
             amount: self.amount.as_ref().map(|x| x.to_static()),
             balance: self.balance.to_static(),
@@ -250,6 +520,7 @@ impl<'i> Posting<'i, plain::Ident> {
     {
         Posting {
             account: account.into(),
+            kind: PostingKind::default(),
             clear_state: ClearState::default(),
             amount: None,
             balance: None,
@@ -262,6 +533,7 @@ impl<'i, Deco: Decoration> Posting<'i, Deco> {
     pub fn new(account: Deco::Decorated<Cow<'i, str>>) -> Self {
         Posting {
             account,
+            kind: PostingKind::default(),
             clear_state: ClearState::default(),
             amount: None,
             balance: None,
@@ -270,6 +542,19 @@ impl<'i, Deco: Decoration> Posting<'i, Deco> {
     }
 }
 
+/// Kind of the posting, which decides whether the posting must balance.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, ToStatic)]
+pub enum PostingKind {
+    /// Regular posting, which must balance with the other real postings.
+    #[default]
+    Real,
+    /// Virtual posting written as `(Account)`, which doesn't need to balance.
+    Virtual,
+    /// Virtual posting written as `[Account]`,
+    /// which must balance with the other balanced virtual postings.
+    BalancedVirtual,
+}
+
 /// Represents a clearing state, often combined with the ambiguity.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Default, ToStatic)]
 pub enum ClearState {
@@ -296,6 +581,12 @@ pub enum Metadata<'i> {
         key: Cow<'i, str>,
         value: MetadataValue<'i>,
     },
+    /// Dates overriding the enclosing item, in a format `[date=effective_date]`.
+    /// Either of the dates can be omitted, such as `[=2025/01/05]`.
+    Dates {
+        date: Option<NaiveDate>,
+        effective_date: Option<NaiveDate>,
+    },
 }
 
 /// MetadataValue represents the value in key-value pair used in `Metadata`.
@@ -304,8 +595,9 @@ pub enum MetadataValue<'i> {
     /// Regular string.
     Text(Cow<'i, str>),
     /// Expression parsed properly prefixed by `::` instead of `:`.
-    // TODO: Change htis type to Expr not Cow<'i, str>.
-    Expr(Cow<'i, str>),
+    Expr(expr::ValueExpr<'i>),
+    /// Date prefixed by `::`, written in brackets as `[2022/03/04]`.
+    Date(NaiveDate),
 }
 
 /// This is an amout for each posting.
@@ -387,6 +679,7 @@ pub enum Exchange<'i> {
 
 /// Price DB entry, which contains one commodity price
 /// in another commodity on a particular date time.
+/// Appears in the price DB file, or in the Ledger file as "P" directive.
 #[derive(Debug, PartialEq, Eq, ToStatic)]
 pub struct PriceDBEntry<'i> {
     pub datetime: NaiveDateTime,
@@ -395,4 +688,27 @@ pub struct PriceDBEntry<'i> {
     /// The rate of the target commodity.
     /// 1 target == rate.
     pub rate: expr::Amount<'i>,
+    /// Comment following `;` at the end of the line.
+    pub comment: Option<Cow<'i, str>>,
+}
+
+/// "i" timeclock directive, which starts the time spent on the account.
+#[derive(Debug, PartialEq, Eq, ToStatic)]
+pub struct ClockIn<'i> {
+    pub datetime: NaiveDateTime,
+    /// Account to check in.
+    pub account: Cow<'i, str>,
+    /// Payee of the time spent, following the account after two spaces.
+    pub payee: Option<Cow<'i, str>>,
+}
+
+/// "o" timeclock directive, which ends the time spent on the account.
+#[derive(Debug, PartialEq, Eq, ToStatic)]
+pub struct ClockOut<'i> {
+    pub datetime: NaiveDateTime,
+    /// Account to check out.
+    /// Can be omitted when only one account is checked in.
+    pub account: Option<Cow<'i, str>>,
+    /// Note of the check out, following the account after two spaces.
+    pub note: Option<Cow<'i, str>>,
 }
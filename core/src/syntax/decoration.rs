@@ -40,6 +40,8 @@ define_as_undecorated!(['i], &'i str);
 define_as_undecorated!(['i, Deco: Decoration], super::LedgerEntry<'i, Deco>);
 define_as_undecorated!(['i, Deco: Decoration], super::LedgerStatement<'i, Deco>);
 define_as_undecorated!(['i, Deco: Decoration], super::Transaction<'i, Deco>);
+define_as_undecorated!(['i, Deco: Decoration], super::AutomatedTransaction<'i, Deco>);
+define_as_undecorated!(['i, Deco: Decoration], super::PeriodicTransaction<'i, Deco>);
 define_as_undecorated!(['i, Deco: Decoration], super::Posting<'i, Deco>);
 define_as_undecorated!(['i, Deco: Decoration], super::PostingAmount<'i, Deco>);
 define_as_undecorated!(['i], super::Exchange<'i>);
@@ -15,6 +15,8 @@ use unicode_width::UnicodeWidthStr;
 pub struct DisplayContext {
     default_commodity: CommodityDisplayOption,
     commodity_overrides: HashMap<String, CommodityDisplayOption>,
+    /// Keeps the trailing zeros of the amounts beyond the minimum scale.
+    keep_scale: bool,
 }
 
 impl DisplayContext {
@@ -26,9 +28,30 @@ impl DisplayContext {
         Self {
             default_commodity,
             commodity_overrides,
+            keep_scale: false,
         }
     }
 
+    /// Returns the context which prints the amounts with at least their own scale,
+    /// instead of dropping the trailing zeros beyond the minimum scale,
+    /// so that computed amounts such as `26230.0000` keep the precision.
+    pub(crate) fn keeping_scale(self) -> Self {
+        Self {
+            keep_scale: true,
+            ..self
+        }
+    }
+
+    /// Returns the option applied to all commodities, unless overridden.
+    pub(crate) fn default_commodity(&self) -> &CommodityDisplayOption {
+        &self.default_commodity
+    }
+
+    /// Returns the options overridden for each commodity.
+    pub(crate) fn commodity_overrides(&self) -> &HashMap<String, CommodityDisplayOption> {
+        &self.commodity_overrides
+    }
+
     /// Returns given object reference wrapped with a context for `fmt::Display`.
     pub fn as_display<'a, T>(&'a self, value: &'a T) -> WithContext<'a, T>
     where
@@ -55,12 +78,83 @@ impl DisplayContext {
             .and_then(|o| o.min_scale)
             .or(self.default_commodity.min_scale)
     }
+
+    /// Returns the preferred [`expr::CommodityStyle`] for the `commodity`.
+    /// If `None`, the amount is printed in the style it was written.
+    pub fn commodity_style(&self, commodity: &str) -> Option<expr::CommodityStyle> {
+        self.commodity_overrides
+            .get(commodity)
+            .and_then(|o| o.style)
+            .or(self.default_commodity.style)
+    }
+
+    /// Records the `style` the `commodity` is written in, unless the style is already known,
+    /// so that the undeclared commodity is printed in the style it first appears.
+    pub(crate) fn observe_commodity_style(&mut self, commodity: &str, style: expr::CommodityStyle) {
+        if self.commodity_style(commodity).is_none() {
+            self.commodity_overrides
+                .entry(commodity.to_string())
+                .or_default()
+                .style = Some(style);
+        }
+    }
+
+    /// Updates the context with the declarations in the given `statement`,
+    /// namely "commodity" and "D" directives. Other statements are ignored.
+    pub fn apply_statement<Deco: Decoration>(&mut self, statement: &LedgerStatement<'_, Deco>) {
+        match statement {
+            LedgerStatement::Commodity(commodity) => self.apply_commodity_declaration(commodity),
+            LedgerStatement::DefaultCommodity(amount) => self.apply_default_commodity(amount),
+            _ => (),
+        }
+    }
+
+    /// Updates the context with the `format` sub-directive of the given "commodity" directive,
+    /// so that the following amounts of the commodity are printed in the declared
+    /// precision, thousands separator and style.
+    /// The format is also applied to the aliases of the commodity.
+    pub fn apply_commodity_declaration(&mut self, commodity: &CommodityDeclaration) {
+        let Some(format) = commodity.details.iter().rev().find_map(|d| match d {
+            CommodityDetail::Format(amount) => Some(amount),
+            _ => None,
+        }) else {
+            return;
+        };
+        let names = std::iter::once(&commodity.name).chain(commodity.details.iter().filter_map(
+            |d| match d {
+                CommodityDetail::Alias(alias) => Some(alias),
+                _ => None,
+            },
+        ));
+        for name in names {
+            self.set_commodity_format(name, format);
+        }
+    }
+
+    /// Updates the context with the amount given to "D" directive,
+    /// which also declares the format of the commodity.
+    pub fn apply_default_commodity(&mut self, amount: &expr::Amount) {
+        if !amount.commodity.is_empty() {
+            self.set_commodity_format(&amount.commodity, amount);
+        }
+    }
+
+    fn set_commodity_format(&mut self, commodity: &str, format: &expr::Amount) {
+        let option = self
+            .commodity_overrides
+            .entry(commodity.to_string())
+            .or_default();
+        option.format = format.value.format;
+        option.min_scale = u8::try_from(format.value.scale()).ok();
+        option.style = Some(format.style);
+    }
 }
 
 #[derive(Debug, Default, Clone)]
 pub struct CommodityDisplayOption {
     pub format: Option<pretty_decimal::Format>,
     pub min_scale: Option<u8>,
+    pub style: Option<expr::CommodityStyle>,
 }
 
 /// Object combined with the `DisplayContext`.
@@ -91,12 +185,25 @@ impl<Deco: Decoration> fmt::Display for WithContext<'_, LedgerStatement<'_, Deco
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self.value {
             LedgerStatement::Txn(txn) => self.pass_context(txn).fmt(f),
+            LedgerStatement::AutomatedTxn(txn) => self.pass_context(txn).fmt(f),
+            LedgerStatement::PeriodicTxn(txn) => self.pass_context(txn).fmt(f),
             LedgerStatement::Comment(v) => v.fmt(f),
+            LedgerStatement::BlockComment(v) => v.fmt(f),
             LedgerStatement::ApplyTag(v) => v.fmt(f),
             LedgerStatement::EndApplyTag => writeln!(f, "end apply tag"),
+            LedgerStatement::ApplyAccount(v) => v.fmt(f),
+            LedgerStatement::EndApplyAccount => writeln!(f, "end apply account"),
+            LedgerStatement::Alias(v) => v.fmt(f),
             LedgerStatement::Include(v) => v.fmt(f),
+            LedgerStatement::Year(v) => writeln!(f, "year {}", v),
             LedgerStatement::Account(v) => v.fmt(f),
             LedgerStatement::Commodity(v) => self.pass_context(v).fmt(f),
+            LedgerStatement::DefaultCommodity(v) => writeln!(f, "D {}", self.pass_context(v)),
+            LedgerStatement::Payee(v) => v.fmt(f),
+            LedgerStatement::Tag(v) => v.fmt(f),
+            LedgerStatement::Price(v) => self.pass_context(v).fmt(f),
+            LedgerStatement::ClockIn(v) => v.fmt(f),
+            LedgerStatement::ClockOut(v) => v.fmt(f),
         }
     }
 }
@@ -122,12 +229,49 @@ impl fmt::Display for LineWrapStr<'_> {
     }
 }
 
+/// Commodity name, double-quoted only if it can't be written as-is.
+struct CommodityName<'a>(&'a str);
+
+impl CommodityName<'_> {
+    fn quoted(&self) -> bool {
+        crate::parse::primitive::commodity_requires_quote(self.0)
+    }
+
+    /// Returns the display width of the name including quotes.
+    fn width(&self) -> usize {
+        self.0.width() + if self.quoted() { 2 } else { 0 }
+    }
+}
+
+impl fmt::Display for CommodityName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.quoted() {
+            write!(f, "\"{}\"", self.0)
+        } else {
+            f.write_str(self.0)
+        }
+    }
+}
+
 impl fmt::Display for TopLevelComment<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         LineWrapStr::wrap(";", &self.0).fmt(f)
     }
 }
 
+impl fmt::Display for BlockComment<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keyword = self.kind.keyword();
+        if self.header.is_empty() {
+            writeln!(f, "{}", keyword)?;
+        } else {
+            writeln!(f, "{} {}", keyword, self.header)?;
+        }
+        f.write_str(&self.body)?;
+        writeln!(f, "end {}", keyword)
+    }
+}
+
 impl fmt::Display for ApplyTag<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "apply tag {}", self.key)?;
@@ -138,12 +282,52 @@ impl fmt::Display for ApplyTag<'_> {
     }
 }
 
+impl fmt::Display for ApplyAccount<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "apply account {}", self.0)
+    }
+}
+
+impl fmt::Display for AccountAlias<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "alias {}={}", self.alias, self.account)
+    }
+}
+
 impl fmt::Display for IncludeFile<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "include {}", self.0)
     }
 }
 
+impl fmt::Display for ClockIn<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "i {} {}",
+            self.datetime.format("%Y/%m/%d %H:%M:%S"),
+            self.account
+        )?;
+        if let Some(payee) = &self.payee {
+            write!(f, "  {}", payee)?;
+        }
+        writeln!(f)
+    }
+}
+
+impl fmt::Display for ClockOut<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "o {}", self.datetime.format("%Y/%m/%d %H:%M:%S"))?;
+        if let Some(account) = &self.account {
+            write!(f, " {}", account)?;
+        }
+        if let Some(note) = &self.note {
+            write!(f, "  {}", note)?;
+        }
+        writeln!(f)
+    }
+}
+
 impl fmt::Display for AccountDeclaration<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "account {}", self.name)?;
@@ -165,7 +349,7 @@ impl fmt::Display for AccountDetail<'_> {
 
 impl fmt::Display for WithContext<'_, CommodityDeclaration<'_>> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "commodity {}", self.value.name)?;
+        writeln!(f, "commodity {}", CommodityName(&self.value.name))?;
         for detail in &self.value.details {
             self.pass_context(detail).fmt(f)?;
         }
@@ -177,11 +361,70 @@ impl fmt::Display for WithContext<'_, CommodityDetail<'_>> {
         match self.value {
             CommodityDetail::Comment(v) => LineWrapStr::wrap("    ; ", v).fmt(f),
             CommodityDetail::Note(v) => LineWrapStr::wrap("    note ", v).fmt(f),
-            CommodityDetail::Alias(v) => writeln!(f, "    alias {}", v),
+            CommodityDetail::Alias(v) => writeln!(f, "    alias {}", CommodityName(v)),
             CommodityDetail::Format(v) => writeln!(f, "    format {}", self.pass_context(v)),
+            CommodityDetail::Default => writeln!(f, "    default"),
+            CommodityDetail::NoMarket => writeln!(f, "    nomarket"),
+        }
+    }
+}
+impl fmt::Display for PayeeDeclaration<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "payee {}", self.name)?;
+        for detail in &self.details {
+            detail.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+impl fmt::Display for PayeeDetail<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PayeeDetail::Comment(v) => LineWrapStr::wrap("    ; ", v).fmt(f),
+            PayeeDetail::Alias(v) => writeln!(f, "    alias {}", v),
+        }
+    }
+}
+
+impl fmt::Display for TagDeclaration<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "tag {}", self.name)?;
+        for detail in &self.details {
+            detail.fmt(f)?;
         }
+        Ok(())
     }
 }
+impl fmt::Display for TagDetail<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TagDetail::Comment(v) => LineWrapStr::wrap("    ; ", v).fmt(f),
+            TagDetail::Check(v) => writeln!(f, "    check {}", v),
+            TagDetail::Assert(v) => writeln!(f, "    assert {}", v),
+        }
+    }
+}
+
+impl fmt::Display for WithContext<'_, PriceDBEntry<'_>> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entry = self.value;
+        write!(f, "P {}", entry.datetime.format("%Y/%m/%d"))?;
+        if entry.datetime.time() != chrono::NaiveTime::MIN {
+            write!(f, " {}", entry.datetime.format("%H:%M:%S"))?;
+        }
+        write!(
+            f,
+            " {} {}",
+            CommodityName(&entry.target),
+            self.pass_context(&entry.rate)
+        )?;
+        if let Some(comment) = &entry.comment {
+            write!(f, "  ; {}", comment)?;
+        }
+        writeln!(f)
+    }
+}
+
 impl<Deco: Decoration> fmt::Display for WithContext<'_, Transaction<'_, Deco>> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let xact = self.value;
@@ -204,6 +447,65 @@ impl<Deco: Decoration> fmt::Display for WithContext<'_, Transaction<'_, Deco>> {
     }
 }
 
+impl<Deco: Decoration> fmt::Display for WithContext<'_, AutomatedTransaction<'_, Deco>> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let xact = self.value;
+        writeln!(f, "= {}", xact.predicate)?;
+        for m in &xact.metadata {
+            m.fmt(f)?;
+        }
+        for post in &xact.posts {
+            write!(f, "{}", self.context.as_display(post.as_undecorated()))?;
+        }
+        Ok(())
+    }
+}
+
+impl<Deco: Decoration> fmt::Display for WithContext<'_, PeriodicTransaction<'_, Deco>> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let xact = self.value;
+        writeln!(f, "~ {}", xact.period)?;
+        for m in &xact.metadata {
+            m.fmt(f)?;
+        }
+        for post in &xact.posts {
+            write!(f, "{}", self.context.as_display(post.as_undecorated()))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for PeriodExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.interval.fmt(f)?;
+        if let Some(begin) = &self.begin {
+            write!(f, " from {}", begin.format("%Y/%m/%d"))?;
+        }
+        if let Some(end) = &self.end {
+            write!(f, " to {}", end.format("%Y/%m/%d"))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Interval::Days(1) => write!(f, "Daily"),
+            Interval::Weeks(1) => write!(f, "Weekly"),
+            Interval::Weeks(2) => write!(f, "Biweekly"),
+            Interval::Months(1) => write!(f, "Monthly"),
+            Interval::Months(2) => write!(f, "Bimonthly"),
+            Interval::Months(3) => write!(f, "Quarterly"),
+            Interval::Years(1) => write!(f, "Yearly"),
+            Interval::Days(n) => write!(f, "Every {} days", n),
+            Interval::Weeks(n) => write!(f, "Every {} weeks", n),
+            Interval::Months(n) => write!(f, "Every {} months", n),
+            Interval::Years(n) => write!(f, "Every {} years", n),
+        }
+    }
+}
+
 const METADATA_PREFIX: &str = "    ; ";
 
 impl fmt::Display for Metadata<'_> {
@@ -221,6 +523,20 @@ impl fmt::Display for Metadata<'_> {
                 f.write_str(METADATA_PREFIX)?;
                 writeln!(f, "{}{}", key, value)?
             }
+            Metadata::Dates {
+                date,
+                effective_date,
+            } => {
+                f.write_str(METADATA_PREFIX)?;
+                f.write_str("[")?;
+                if let Some(date) = date {
+                    write!(f, "{}", date.format("%Y/%m/%d"))?;
+                }
+                if let Some(effective_date) = effective_date {
+                    write!(f, "={}", effective_date.format("%Y/%m/%d"))?;
+                }
+                f.write_str("]\n")?
+            }
             Metadata::Comment(s) => LineWrapStr::wrap(METADATA_PREFIX, s).fmt(f)?,
         };
         Ok(())
@@ -230,7 +546,11 @@ impl fmt::Display for Metadata<'_> {
 impl fmt::Display for MetadataValue<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            MetadataValue::Expr(expr) => write!(f, ":: {}", expr),
+            // Metadata is written as-is, not following the commodity format.
+            MetadataValue::Expr(expr) => {
+                write!(f, ":: {}", DisplayContext::default().as_display(expr))
+            }
+            MetadataValue::Date(date) => write!(f, ":: [{}]", date.format("%Y/%m/%d")),
             MetadataValue::Text(text) => write!(f, ": {}", text),
         }
     }
@@ -240,9 +560,23 @@ impl<Deco: Decoration> fmt::Display for WithContext<'_, Posting<'_, Deco>> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let post = self.value;
         let post_clear = print_clear_state(post.clear_state);
-        write!(f, "    {}{}", post_clear, post.account.as_undecorated())?;
+        let (open, close) = match post.kind {
+            PostingKind::Real => ("", ""),
+            PostingKind::Virtual => ("(", ")"),
+            PostingKind::BalancedVirtual => ("[", "]"),
+        };
+        write!(
+            f,
+            "    {}{}{}{}",
+            post_clear,
+            open,
+            post.account.as_undecorated(),
+            close
+        )?;
         let account_width = UnicodeWidthStr::width_cjk(post.account.as_undecorated().as_ref())
-            + UnicodeWidthStr::width(post_clear);
+            + UnicodeWidthStr::width(post_clear)
+            + open.len()
+            + close.len();
         if let Some(amount) = &post.amount {
             let mut amount_str = String::new();
             let alignment = self
@@ -375,14 +709,34 @@ impl<'i, W: fmt::Write> expr::ExprVisitor<'i> for ExprFormatter<'_, W> {
 
     fn visit_amount(&mut self, amount: &expr::Amount<'i>) -> Result<Alignment, fmt::Error> {
         let amount_str = rescale(amount, self.context).to_string();
-        // TODO: Implement prefix-amount.
         if amount.commodity.is_empty() {
             write!(self.writer, "{}", amount_str)?;
             return Ok(Alignment::Partial(amount_str.as_str().len()));
         }
-        write!(self.writer, "{} {}", amount_str, amount.commodity)?;
-        // Given the amount is only [0-9.], it's ok to count bytes.
-        Ok(Alignment::Complete(amount_str.as_str().len()))
+        let style = self
+            .context
+            .commodity_style(&amount.commodity)
+            .unwrap_or(amount.style);
+        let sep = if style.spaced { " " } else { "" };
+        let commodity = CommodityName(&amount.commodity);
+        match style.position {
+            expr::CommodityPosition::Prefix => {
+                // Put the sign before the commodity, as `-$100`.
+                let (sign, amount_str) = match amount_str.strip_prefix('-') {
+                    Some(abs) => ("-", abs),
+                    None => ("", amount_str.as_str()),
+                };
+                write!(self.writer, "{}{}{}{}", sign, commodity, sep, amount_str)?;
+                Ok(Alignment::Complete(
+                    sign.len() + commodity.width() + sep.len() + amount_str.len(),
+                ))
+            }
+            expr::CommodityPosition::Suffix => {
+                write!(self.writer, "{}{}{}", amount_str, sep, commodity)?;
+                // Given the amount is only [0-9.], it's ok to count bytes.
+                Ok(Alignment::Complete(amount_str.as_str().len()))
+            }
+        }
     }
 
     fn visit_value_expr(&mut self, expr: &expr::ValueExpr<'i>) -> Result<Alignment, fmt::Error> {
@@ -445,7 +799,9 @@ fn get_column(colsize: usize, left: usize, padding: usize) -> usize {
 fn rescale(x: &expr::Amount, context: &DisplayContext) -> PrettyDecimal {
     let mut v = x.value;
     if let Some(min_scale) = context.min_scale(x.commodity.as_ref()) {
-        v.as_mut().normalize_assign();
+        if !context.keep_scale {
+            v.as_mut().normalize_assign();
+        }
         v.rescale(std::cmp::max(min_scale.into(), v.scale()));
     }
     match context.decimal_format(x.commodity.as_ref()) {
@@ -482,6 +838,7 @@ mod tests {
         expr::ValueExpr::Amount(expr::Amount {
             commodity: commodity.into(),
             value: PrettyDecimal::unformatted(value),
+            style: expr::CommodityStyle::default(),
         })
     }
 
@@ -528,7 +885,14 @@ mod tests {
                 "{}",
                 ctx.as_display(&tracked::LedgerStatement::ApplyTag(ApplyTag {
                     key: Cow::Borrowed("foo"),
-                    value: Some(MetadataValue::Expr(Cow::Borrowed("100")))
+                    value: Some(MetadataValue::Expr(
+                        expr::Amount {
+                            value: PrettyDecimal::unformatted(dec!(100)),
+                            commodity: Cow::Borrowed(""),
+                            style: expr::CommodityStyle::default(),
+                        }
+                        .into()
+                    ))
                 }))
             ),
         );
@@ -536,6 +900,22 @@ mod tests {
             "end apply tag\n",
             format!("{}", ctx.as_display(&plain::LedgerStatement::EndApplyTag))
         );
+        assert_eq!(
+            "P 2024/10/28 EUR 0.9367 CHF  ; from ECB\n",
+            format!(
+                "{}",
+                ctx.as_display(&plain::LedgerStatement::Price(PriceDBEntry {
+                    datetime: NaiveDate::from_ymd_opt(2024, 10, 28).unwrap().into(),
+                    target: Cow::Borrowed("EUR"),
+                    rate: expr::Amount {
+                        value: PrettyDecimal::unformatted(dec!(0.9367)),
+                        commodity: Cow::Borrowed("CHF"),
+                        style: expr::CommodityStyle::default(),
+                    },
+                    comment: Some(Cow::Borrowed("from ECB")),
+                }))
+            )
+        );
     }
 
     #[test]
@@ -567,6 +947,7 @@ mod tests {
                 payee: Cow::Borrowed("Example Grocery"),
                 posts: vec![Posting {
                     account: Cow::Borrowed("Assets"),
+                    kind: PostingKind::Real,
                     clear_state: ClearState::Uncleared,
                     amount: Some(PostingAmount {
                         amount: amount(dec!(123.45), "USD"),
@@ -673,7 +1054,7 @@ mod tests {
         // overrides only
         let ctx = DisplayContext::new(
             CommodityDisplayOption::default(),
-            hashmap! {"USD".to_string() => CommodityDisplayOption {format: Some(pretty_decimal::Format::Comma3Dot), min_scale: Some(4)}},
+            hashmap! {"USD".to_string() => CommodityDisplayOption {format: Some(pretty_decimal::Format::Comma3Dot), min_scale: Some(4), style: None}},
         );
         assert_eq!(
             concat!(
@@ -706,6 +1087,7 @@ mod tests {
                 expr::ValueExpr::Amount(expr::Amount {
                     commodity: Cow::Borrowed("JPY"),
                     value: PrettyDecimal::comma3dot(dec!(1_234_567)),
+                    style: expr::CommodityStyle::default(),
                 })
                 .into(),
             ),
@@ -716,6 +1098,7 @@ mod tests {
                 expr::ValueExpr::Amount(expr::Amount {
                     commodity: Cow::Borrowed("JPY"),
                     value: PrettyDecimal::comma3dot(dec!(0.0011)),
+                    style: expr::CommodityStyle::default(),
                 })
                 .into(),
             ),
@@ -762,6 +1145,7 @@ mod tests {
             CommodityDisplayOption {
                 format: None,
                 min_scale: Some(2),
+                style: None,
             },
             HashMap::new(),
         )
@@ -776,6 +1160,7 @@ mod tests {
             CommodityDisplayOption {
                 format: Some(pretty_decimal::Format::Comma3Dot),
                 min_scale: Some(2),
+                style: None,
             },
             HashMap::new(),
         )
@@ -12,6 +12,38 @@ use pretty_decimal::PrettyDecimal;
 pub struct Amount<'i> {
     pub value: PrettyDecimal,
     pub commodity: Cow<'i, str>,
+    /// How the commodity is written around the value.
+    pub style: CommodityStyle,
+}
+
+/// Describes how the commodity is written around the value,
+/// such as `$100`, `€ 12` or `100 CHF`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, ToStatic)]
+pub struct CommodityStyle {
+    /// Position of the commodity relative to the value.
+    pub position: CommodityPosition,
+    /// `true` if the commodity is separated from the value with a space.
+    pub spaced: bool,
+}
+
+impl Default for CommodityStyle {
+    /// Returns the suffix commodity with a space, such as `100 CHF`.
+    fn default() -> Self {
+        Self {
+            position: CommodityPosition::Suffix,
+            spaced: true,
+        }
+    }
+}
+
+/// Position of the commodity relative to the value.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, ToStatic)]
+pub enum CommodityPosition {
+    /// Commodity comes before the value, such as `$100`.
+    Prefix,
+    /// Commodity comes after the value, such as `100 CHF`.
+    #[default]
+    Suffix,
 }
 
 /// Defines value expression.
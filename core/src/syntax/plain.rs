@@ -28,6 +28,8 @@ impl Decoration for Ident {
 pub type LedgerEntry<'i> = super::LedgerEntry<'i, Ident>;
 pub type LedgerStatement<'i> = super::LedgerStatement<'i, Ident>;
 pub type Transaction<'i> = super::Transaction<'i, Ident>;
+pub type AutomatedTransaction<'i> = super::AutomatedTransaction<'i, Ident>;
+pub type PeriodicTransaction<'i> = super::PeriodicTransaction<'i, Ident>;
 pub type Posting<'i> = super::Posting<'i, Ident>;
 pub type PostingAmount<'i> = super::PostingAmount<'i, Ident>;
 pub type Lot<'i> = super::Lot<'i, Ident>;
@@ -31,6 +31,8 @@ impl Decoration for Tracking {
 pub type LedgerEntry<'i> = super::LedgerEntry<'i, Tracking>;
 pub type LedgerStatement<'i> = super::LedgerStatement<'i, Tracking>;
 pub type Transaction<'i> = super::Transaction<'i, Tracking>;
+pub type AutomatedTransaction<'i> = super::AutomatedTransaction<'i, Tracking>;
+pub type PeriodicTransaction<'i> = super::PeriodicTransaction<'i, Tracking>;
 pub type Posting<'i> = super::Posting<'i, Tracking>;
 pub type PostingAmount<'i> = super::PostingAmount<'i, Tracking>;
 pub type Lot<'i> = super::Lot<'i, Tracking>;
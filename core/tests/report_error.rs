@@ -5,9 +5,11 @@ use std::{
 
 use bumpalo::Bump;
 use maplit::hashmap;
+use pretty_assertions::assert_eq;
 use rstest::rstest;
 
 use okane_core::{
+    diagnostic::{Diagnostic, Label, Position, Severity},
     load::{self, FileSystem},
     report,
 };
@@ -67,3 +69,85 @@ fn report_error_string(
 
     golden.assert(&format!("{}\n", got_err));
 }
+
+#[rstest]
+fn report_warning_string(
+    #[base_dir = "../"]
+    #[files("testdata/warning/*.ledger")]
+    input: PathBuf,
+) {
+    let mut golden = input.clone();
+    assert!(
+        golden.set_extension("ledger.warning.txt"),
+        "failed to add extension to golden file {}",
+        golden.display()
+    );
+    let arena = Bump::new();
+    let mut ctx = report::ReportContext::new(&arena);
+    let golden = okane_golden::Golden::new(golden).unwrap();
+
+    let ledger = report::process(
+        &mut ctx,
+        new_loader(input).unwrap(),
+        &report::ProcessOptions {
+            strictness: report::Strictness::Warn,
+            ..report::ProcessOptions::default()
+        },
+    )
+    .unwrap();
+
+    let got: Vec<String> = ledger
+        .warnings()
+        .iter()
+        .map(|warning| format!("{}\n", warning))
+        .collect();
+    golden.assert(&got.join("\n"));
+}
+
+#[test]
+fn report_error_to_diagnostics() {
+    let input = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../testdata/error/imbalance_posting_amount.ledger");
+    let arena = Bump::new();
+    let mut ctx = report::ReportContext::new(&arena);
+
+    let got_err = report::process(
+        &mut ctx,
+        new_loader(input).unwrap(),
+        &report::ProcessOptions::default(),
+    )
+    .unwrap_err();
+
+    let label = |line, start, end, message: &str| Label {
+        start: Position {
+            line,
+            column: start,
+        },
+        end: Position { line, column: end },
+        message: message.to_string(),
+        primary: true,
+    };
+    assert_eq!(
+        vec![Diagnostic {
+            severity: Severity::Error,
+            message: "transaction cannot have unbalanced postings: -1000 JPY".to_string(),
+            path: Some("okane/testdata/error/imbalance_posting_amount.ledger".to_string()),
+            labels: vec![
+                label(
+                    4,
+                    5,
+                    57,
+                    "adds -10000 JPY, while -9000 JPY balances the transaction"
+                ),
+                label(
+                    5,
+                    5,
+                    57,
+                    "adds 9000 JPY, while 10000 JPY balances the transaction"
+                ),
+            ],
+            notes: Vec::new(),
+        }],
+        got_err.to_diagnostics()
+    );
+}